@@ -45,6 +45,7 @@ struct TestPoseidonCircuit<F: PrimeFieldBits> {
 
 impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPoseidonCircuit<F> {
     type Config = TestPoseidonCircuitConfig;
+    type StepWitness = ();
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let pconfig = MainGate::configure(meta);
@@ -56,6 +57,7 @@ impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPos
         config: Self::Config,
         layouter: &mut impl Layouter<F>,
         z_in: &[AssignedCell<F, F>; ARITY],
+        _witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
         let spec = Spec::<F, T1, RATE1>::new(R_F1, R_P1);
         let mut pchip = PoseidonChip::new(config.pconfig, spec);
@@ -100,7 +102,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         get_or_create_commitment_key::<grumpkin::G1Affine>(COMMITMENT_KEY_SIZE, "grumpkin")
             .expect("Failed to get primary key");
 
-    let mut pp = PublicParams::new(
+    let mut pp = PublicParams::<ARITY, bn256::G1Affine, grumpkin::G1Affine, _>::new(
         &sc1,
         primary_commitment_key,
         secondary_commitment_key,
@@ -1,113 +1,26 @@
 //! Benchmark for comparing the dependency of sangria::IVC and cyclefold::IVC on the GATES_COUNT parameter.
 //!
-//! This benchmark implements a multi-step circuit where the number of gates (i.e. circuit duplications)
-//! is hardcoded per benchmark run. Separate sets of GATES_COUNT values are used for Sangria and Cyclefold.
+//! This benchmark folds a step circuit that applies the poseidon step circuit GATES_COUNT times by
+//! [`Repeat`], so the number of gates is hardcoded per benchmark run. Separate sets of GATES_COUNT
+//! values are used for Sangria and Cyclefold.
 
-use std::{array, convert::TryInto, marker::PhantomData, path::Path};
+use std::{array, path::Path};
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use itertools::multizip;
 use sirius::{
     commitment::CommitmentKey,
-    ff::{FromUniformBytes, PrimeField},
+    ff::FromUniformBytes,
     gadgets::poseidon_step_circuit::TestPoseidonCircuit,
-    halo2_proofs::{
-        circuit::{AssignedCell, Layouter},
-        plonk::{self, ConstraintSystem},
-    },
     halo2curves::{bn256, grumpkin, CurveAffine},
-    ivc::{self, cyclefold, sangria, StepCircuit, SynthesisError},
+    ivc::{self, cyclefold, sangria, step_circuit::Repeat},
 };
 
-/// Base arity for the step circuit.
-const BASE_ARITY: usize = 1;
+/// Arity of the step circuit.
+const ARITY: usize = 1;
 
 /// Number of fold steps to perform in the IVC.
 const FOLD_STEP_COUNT: usize = 5;
 
-/// A multi-step circuit that composes an array of sub-circuits.
-/// It is parameterized by:
-///  - BASE_ARITY: the arity of each step circuit,
-///  - REPEATS: the number of times the step circuit is duplicated,
-///  - OVERALL_ARITY: must equal BASE_ARITY * REPEATS.
-#[derive(Clone, Debug)]
-pub struct MultiStepCircuit<
-    CIRCUIT,
-    const BASE_ARITY: usize,
-    const REPEATS: usize,
-    const OVERALL_ARITY: usize,
-    F: PrimeField,
-> {
-    pub circuits: [CIRCUIT; REPEATS],
-    _marker: PhantomData<F>,
-}
-
-impl<T, const BASE_ARITY: usize, const REPEATS: usize, const OVERALL_ARITY: usize, F>
-    MultiStepCircuit<T, BASE_ARITY, REPEATS, OVERALL_ARITY, F>
-where
-    F: PrimeField,
-{
-    pub fn new(circuits: [T; REPEATS]) -> Self {
-        assert_eq!(
-            OVERALL_ARITY,
-            BASE_ARITY * REPEATS,
-            "OVERALL_ARITY must equal BASE_ARITY * REPEATS"
-        );
-        Self {
-            circuits,
-            _marker: PhantomData,
-        }
-    }
-}
-
-impl<T, const BASE_ARITY: usize, const REPEATS: usize, const OVERALL_ARITY: usize, F>
-    StepCircuit<OVERALL_ARITY, F> for MultiStepCircuit<T, BASE_ARITY, REPEATS, OVERALL_ARITY, F>
-where
-    F: PrimeField,
-    T: StepCircuit<BASE_ARITY, F> + Clone,
-    T::Config: Clone,
-{
-    type Config = [T::Config; REPEATS];
-
-    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
-        assert_eq!(
-            OVERALL_ARITY,
-            BASE_ARITY * REPEATS,
-            "OVERALL_ARITY must equal BASE_ARITY * REPEATS"
-        );
-        array::from_fn(|_i| T::configure(cs))
-    }
-
-    fn synthesize_step(
-        &self,
-        configs: Self::Config,
-        layouter: &mut impl Layouter<F>,
-        z_in: &[AssignedCell<F, F>; OVERALL_ARITY],
-    ) -> Result<[AssignedCell<F, F>; OVERALL_ARITY], SynthesisError> {
-        assert_eq!(
-            OVERALL_ARITY,
-            BASE_ARITY * REPEATS,
-            "OVERALL_ARITY must equal BASE_ARITY * REPEATS"
-        );
-        let mut out_cells: Vec<AssignedCell<F, F>> = Vec::with_capacity(OVERALL_ARITY);
-        for (circuit, cfg, z_chunk) in multizip((
-            self.circuits.iter(),
-            configs.into_iter(),
-            z_in.chunks_exact(BASE_ARITY),
-        )) {
-            let z_chunk_arr: [AssignedCell<F, F>; BASE_ARITY] = z_chunk
-                .to_vec()
-                .try_into()
-                .map_err(|_| SynthesisError::Halo2(plonk::Error::Synthesis))?;
-            let out_chunk = circuit.synthesize_step(cfg, layouter, &z_chunk_arr)?;
-            out_cells.extend_from_slice(&out_chunk);
-        }
-        out_cells
-            .try_into()
-            .map_err(|_| SynthesisError::Halo2(plonk::Error::Synthesis))
-    }
-}
-
 /// IVC mode used in the benchmark.
 #[derive(Clone, Copy, Debug)]
 pub enum Mode {
@@ -127,19 +40,14 @@ fn get_or_create_commitment_key<C: CurveAffine>(k: usize, label: &'static str) -
 
 /// Runs an IVC instance using the given gate count and mode.
 /// The gate count is provided as a const generic parameter.
-fn run_ivc_with_gate_count<const GATES_COUNT: usize, const OVERALL_ARITY: usize>(mode: Mode)
+fn run_ivc_with_gate_count<const GATES_COUNT: usize>(mode: Mode)
 where
     bn256::Fr: FromUniformBytes<64>,
 {
-    // OVERALL_ARITY = BASE_ARITY * GATES_COUNT.
-    assert_eq!(OVERALL_ARITY, BASE_ARITY * GATES_COUNT);
-
-    // Build an array of step circuits.
-    let circuits = array::from_fn(|_| TestPoseidonCircuit::<bn256::Fr>::default());
-    let multi_circuit =
-        MultiStepCircuit::<_, BASE_ARITY, GATES_COUNT, OVERALL_ARITY, bn256::Fr>::new(circuits);
+    // The step circuit applied GATES_COUNT times per fold step.
+    let step_circuit = Repeat::<_, GATES_COUNT>::new(TestPoseidonCircuit::<bn256::Fr>::default());
 
-    // Create an input for the multi-step circuit.
+    // Create an input for the step circuit.
     let z_in = array::from_fn(|i| bn256::Fr::from(i as u64));
 
     // Get commitment keys.
@@ -149,10 +57,10 @@ where
 
     match mode {
         Mode::Sangria => {
-            let pp = sirius::sangria_prelude::bn256::new_default_pp::<OVERALL_ARITY, _, 1, _>(
+            let pp = sirius::sangria_prelude::bn256::new_default_pp::<ARITY, _, 1, _>(
                 17,
                 &primary_commitment_key,
-                &multi_circuit,
+                &step_circuit,
                 17,
                 &secondary_commitment_key,
                 &ivc::step_circuit::trivial::Circuit::default(),
@@ -162,7 +70,7 @@ where
 
             let mut ivc = sangria::IVC::new(
                 &pp,
-                &multi_circuit,
+                &step_circuit,
                 primary_input,
                 &ivc::step_circuit::trivial::Circuit::default(),
                 secondary_input,
@@ -172,7 +80,7 @@ where
             for _ in 0..FOLD_STEP_COUNT {
                 ivc.fold_step(
                     &pp,
-                    &multi_circuit,
+                    &step_circuit,
                     &ivc::step_circuit::trivial::Circuit::default(),
                 )
                 .expect("Sangria fold step failed");
@@ -180,19 +88,20 @@ where
             ivc.verify(&pp).expect("Sangria IVC verification failed");
         }
         Mode::Cyclefold => {
-            let mut pp = cyclefold::PublicParams::new(
-                &multi_circuit,
-                primary_commitment_key,
-                secondary_commitment_key,
-                20,
-            )
-            .expect("Failed to create Cyclefold public params");
+            let mut pp =
+                cyclefold::PublicParams::<ARITY, bn256::G1Affine, grumpkin::G1Affine, _>::new(
+                    &step_circuit,
+                    primary_commitment_key,
+                    secondary_commitment_key,
+                    20,
+                )
+                .expect("Failed to create Cyclefold public params");
             let primary_input = z_in;
-            let mut ivc = cyclefold::IVC::new(&mut pp, &multi_circuit, primary_input)
+            let mut ivc = cyclefold::IVC::new(&mut pp, &step_circuit, primary_input)
                 .expect("Failed to create Cyclefold IVC");
             for _ in 0..FOLD_STEP_COUNT {
                 ivc = ivc
-                    .next(&pp, &multi_circuit)
+                    .next(&pp, &step_circuit)
                     .expect("Cyclefold next step failed");
             }
             ivc.verify(&pp).expect("Cyclefold IVC verification failed");
@@ -206,7 +115,7 @@ macro_rules! bench_ivc {
         let bench_name = format!("{:?}_gates_{}", $mode, $gates);
         $group.bench_function(&bench_name, |b| {
             b.iter(|| {
-                run_ivc_with_gate_count::<$gates, { $gates * BASE_ARITY }>($mode);
+                run_ivc_with_gate_count::<$gates>($mode);
             });
         });
     }};
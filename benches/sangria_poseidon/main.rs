@@ -47,6 +47,7 @@ struct TestPoseidonCircuit<F: PrimeFieldBits> {
 
 impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPoseidonCircuit<F> {
     type Config = TestPoseidonCircuitConfig;
+    type StepWitness = ();
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let pconfig = MainGate::configure(meta);
@@ -58,6 +59,7 @@ impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPos
         config: Self::Config,
         layouter: &mut impl Layouter<F>,
         z_in: &[AssignedCell<F, F>; ARITY],
+        _witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
         let spec = Spec::<F, T1, RATE1>::new(R_F1, R_P1);
         let mut pchip = PoseidonChip::new(config.pconfig, spec);
@@ -15,7 +15,7 @@ use halo2_proofs::halo2curves;
 use sirius::{
     ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
     gadgets::poseidon_step_circuit::TestPoseidonCircuit,
-    ivc::{cyclefold, sangria, step_circuit::trivial, StepCircuit},
+    ivc::{cyclefold, sangria, step_circuit::trivial, IvcRunner, StepCircuit},
     poseidon::ROPair,
 };
 use tracing::*;
@@ -28,7 +28,7 @@ use sangria_poseidon as poseidon;
 #[allow(dead_code)]
 mod merkle;
 
-use merkle::{MerkleTreeUpdateCircuit, Tree};
+use merkle::{new_tree, MerkleTreeUpdateCircuit, ProofBatch};
 
 #[derive(Parser, Debug)]
 #[command(name = "sirius", version, about, long_about = None)]
@@ -276,8 +276,6 @@ trait TestCircuitHelpers<F: PrimeField> {
     fn get_default_input() -> F {
         F::ZERO
     }
-
-    fn update_between_step(&mut self) {}
 }
 
 impl<F: PrimeField, const ARITY: usize> TestCircuitHelpers<F> for trivial::Circuit<ARITY, F> {
@@ -293,13 +291,26 @@ where
     const NAME: &'static str = "MerkleTree";
 
     fn get_default_input() -> F {
-        *Tree::default().get_root()
+        *new_tree::<F>().get_root()
     }
+}
 
-    #[instrument("update_leaves", skip_all)]
-    fn update_between_step(&mut self) {
-        assert!(self.pop_front_proof_batch())
-    }
+/// Merkle tree circuit with `batches_count` batches of random leaves updates, one per fold step
+#[instrument("update_leaves", skip_all)]
+fn merkle_circuit<F>(
+    rng: &mut impl rand::Rng,
+    batch_size: usize,
+    batches_count: usize,
+) -> (MerkleTreeUpdateCircuit<F>, Vec<ProofBatch<F>>)
+where
+    F: PrimeFieldBits + serde::Serialize + FromUniformBytes<64>,
+{
+    MerkleTreeUpdateCircuit::new_with_random_updates(
+        &mut new_tree(),
+        rng,
+        batch_size,
+        batches_count,
+    )
 }
 
 fn fold<
@@ -307,11 +318,18 @@ fn fold<
     SC2: StepCircuit<1, C2Scalar> + TestCircuitHelpers<C2Scalar>,
 >(
     args: &Args,
-    mut primary: SC1,
-    mut secondary: SC2,
-) {
+    (primary, primary_witnesses): (SC1, Vec<SC1::StepWitness>),
+    (secondary, secondary_witnesses): (SC2, Vec<SC2::StepWitness>),
+) where
+    SC1::StepWitness: Default,
+    SC2::StepWitness: Default,
+{
     let _span = info_span!("cli", primary = SC1::NAME, secondary = SC2::NAME).entered();
 
+    // Steps without a witness of their own are folded with the default one
+    let mut primary_witnesses = primary_witnesses.into_iter();
+    let mut secondary_witnesses = secondary_witnesses.into_iter();
+
     let primary_commitment_key = poseidon::get_or_create_commitment_key::<C1Affine>(
         args.primary_commitment_key_size(),
         "bn256",
@@ -369,7 +387,7 @@ fn fold<
 
             let prove_span = info_span!("prove", steps = args.fold_step_count.get()).entered();
 
-            let mut ivc = sangria::IVC::new(
+            let ivc = sangria::IVC::new(
                 &pp,
                 &primary,
                 [primary_input],
@@ -379,18 +397,28 @@ fn fold<
             )
             .unwrap();
 
-            for _step in 1..args.fold_step_count.into() {
-                primary.update_between_step();
-                secondary.update_between_step();
-
-                ivc.fold_step(&pp, &primary, &secondary).unwrap();
-            }
+            let output = IvcRunner::new()
+                .run(ivc, 1..args.fold_step_count.into(), |mut ivc, _step| {
+                    let primary_witness = primary_witnesses.next().unwrap_or_default();
+                    let secondary_witness = secondary_witnesses.next().unwrap_or_default();
+
+                    ivc.fold_step_with_witness(
+                        &pp,
+                        &primary,
+                        &primary_witness,
+                        &secondary,
+                        &secondary_witness,
+                    )
+                    .map(|()| ivc)
+                })
+                .unwrap_or_else(|err| panic!("{err}"));
             prove_span.exit();
+            info!("fold steps stats: {:?}", output.stats);
 
-            ivc.verify(&pp).unwrap()
+            output.state.verify(&pp).unwrap()
         }
         Mode::Cyclefold(cyclefold_args) => {
-            let mut pp = cyclefold::PublicParams::new(
+            let mut pp = cyclefold::PublicParams::<1, C1Affine, C2Affine, SC1>::new(
                 &primary,
                 primary_commitment_key,
                 secondary_commitment_key,
@@ -402,20 +430,21 @@ fn fold<
 
             let prove_span = info_span!("prove", steps = args.fold_step_count.get()).entered();
 
-            let mut ivc =
+            let ivc =
                 cyclefold::IVC::new(&mut pp, &primary, [primary_input]).expect("while step=0");
 
-            for step in 1..args.fold_step_count.into() {
-                primary.update_between_step();
+            let output = IvcRunner::new()
+                .run(ivc, 1..args.fold_step_count.into(), |ivc, _step| {
+                    let primary_witness = primary_witnesses.next().unwrap_or_default();
 
-                ivc = ivc
-                    .next(&pp, &primary)
-                    .unwrap_or_else(|err| panic!("while step={step}: {err:?}"));
-            }
+                    ivc.next_with_witness(&pp, &primary, &primary_witness)
+                })
+                .unwrap_or_else(|err| panic!("{err}"));
 
             prove_span.exit();
+            info!("fold steps stats: {:?}", output.stats);
 
-            ivc.verify(&pp).expect("while verify");
+            output.state.verify(&pp).expect("while verify");
         }
     }
 }
@@ -436,46 +465,64 @@ fn main() {
     match (args.primary_circuit(), args.secondary_circuit()) {
         (Circuits::Poseidon, Circuits::Trivial) => fold(
             &args,
-            TestPoseidonCircuit::new(args.primary_repeat_count()),
-            trivial::Circuit::default(),
+            (
+                TestPoseidonCircuit::new(args.primary_repeat_count()),
+                vec![],
+            ),
+            (trivial::Circuit::default(), vec![]),
         ),
         (Circuits::Poseidon, Circuits::Poseidon) => fold(
             &args,
-            TestPoseidonCircuit::new(args.primary_repeat_count()),
-            TestPoseidonCircuit::new(args.secondary_repeat_count()),
+            (
+                TestPoseidonCircuit::new(args.primary_repeat_count()),
+                vec![],
+            ),
+            (
+                TestPoseidonCircuit::new(args.secondary_repeat_count()),
+                vec![],
+            ),
         ),
         (Circuits::Trivial, Circuits::Poseidon) => fold(
             &args,
-            trivial::Circuit::default(),
-            TestPoseidonCircuit::new(args.secondary_repeat_count()),
+            (trivial::Circuit::default(), vec![]),
+            (
+                TestPoseidonCircuit::new(args.secondary_repeat_count()),
+                vec![],
+            ),
         ),
         (Circuits::Trivial, Circuits::Trivial) => fold(
             &args,
-            trivial::Circuit::default(),
-            trivial::Circuit::default(),
+            (trivial::Circuit::default(), vec![]),
+            (trivial::Circuit::default(), vec![]),
         ),
         (Circuits::MerkleTree, Circuits::Trivial) => fold(
             &args,
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            merkle_circuit(
                 &mut rng,
                 args.primary_repeat_count(),
                 args.fold_step_count.get(),
             ),
-            trivial::Circuit::default(),
+            (trivial::Circuit::default(), vec![]),
         ),
         (Circuits::MerkleTree, Circuits::Poseidon) => fold(
             &args,
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            merkle_circuit(
                 &mut rng,
                 args.primary_repeat_count(),
                 args.fold_step_count.get(),
             ),
-            TestPoseidonCircuit::new(args.secondary_repeat_count()),
+            (
+                TestPoseidonCircuit::new(args.secondary_repeat_count()),
+                vec![],
+            ),
         ),
         (Circuits::Poseidon, Circuits::MerkleTree) => fold(
             &args,
-            TestPoseidonCircuit::new(args.primary_repeat_count()),
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            (
+                TestPoseidonCircuit::new(args.primary_repeat_count()),
+                vec![],
+            ),
+            merkle_circuit(
                 &mut rng,
                 args.secondary_repeat_count(),
                 args.fold_step_count.get(),
@@ -483,8 +530,8 @@ fn main() {
         ),
         (Circuits::Trivial, Circuits::MerkleTree) => fold(
             &args,
-            trivial::Circuit::default(),
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            (trivial::Circuit::default(), vec![]),
+            merkle_circuit(
                 &mut rng,
                 args.secondary_repeat_count(),
                 args.fold_step_count.get(),
@@ -492,12 +539,12 @@ fn main() {
         ),
         (Circuits::MerkleTree, Circuits::MerkleTree) => fold(
             &args,
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            merkle_circuit(
                 &mut rng,
                 args.primary_repeat_count(),
                 args.fold_step_count.get(),
             ),
-            merkle::MerkleTreeUpdateCircuit::new_with_random_updates(
+            merkle_circuit(
                 &mut rng,
                 args.secondary_repeat_count(),
                 args.fold_step_count.get(),
@@ -70,7 +70,12 @@ fn main() {
         get_or_create_commitment_key::<C2Affine>(COMMITMENT_KEY_SIZE, "grumpkin")
             .expect("Failed to get secondary key");
 
-    let mut pp = cyclefold::PublicParams::new(
+    let mut pp = cyclefold::PublicParams::<
+        { sirius::gadgets::poseidon_step_circuit::ARITY },
+        C1Affine,
+        C2Affine,
+        _,
+    >::new(
         &primary,
         primary_commitment_key,
         secondary_commitment_key,
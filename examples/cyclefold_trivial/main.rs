@@ -69,7 +69,7 @@ fn main() {
         .unwrap()
     };
 
-    let mut pp = PublicParams::new(
+    let mut pp = PublicParams::<A1, C1Affine, C2Affine, _>::new(
         &sc,
         primary_commitment_key,
         secondary_commitment_key,
@@ -59,6 +59,7 @@ impl<const A: usize, F: PrimeField, const FAIL: bool> StepCircuit<A, F>
     for InstancesCircuit<A, FAIL>
 {
     type Config = InstancesConfig<A>;
+    type StepWitness = ();
 
     fn instances(&self) -> Vec<Vec<F>> {
         (0..A).map(|val| vec![F::from_u128(val as u128)]).collect()
@@ -86,6 +87,7 @@ impl<const A: usize, F: PrimeField, const FAIL: bool> StepCircuit<A, F>
         config: Self::Config,
         layouter: &mut impl Layouter<F>,
         z_i: &[AssignedCell<F, F>; A],
+        _witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; A], SynthesisError> {
         for (input, instance) in z_i.iter().zip(config.instances) {
             layouter.constrain_instance(input.cell(), instance, 0)?;
@@ -165,5 +167,23 @@ fn main() {
     )
     .unwrap();
 
+    // Same folding, but only the instances of the last step are kept for verification
+    let mut ivc = SangriaIVC::new(
+        &pp,
+        &sc1,
+        array::from_fn(|i| C1Scalar::from(i as u64)),
+        &sc2,
+        array::from_fn(|i| C2Scalar::from(i as u64)),
+        false,
+    )
+    .unwrap()
+    .with_constant_size_pub_instances();
+
+    for _ in 0..FOLD_STEP_COUNT {
+        ivc.fold_step(&pp, &sc1, &sc2).unwrap();
+    }
+
+    ivc.verify(&pp).unwrap();
+
     println!("success");
 }
@@ -22,7 +22,7 @@ use sirius::{
 };
 use tracing::*;
 
-use crate::circuit::MerkleTreeUpdateCircuit;
+use crate::circuit::{new_tree, MerkleTreeUpdateCircuit};
 
 type C1Affine = <C1 as PrimeCurve>::Affine;
 type C1Scalar = <C1 as Group>::Scalar;
@@ -34,11 +34,13 @@ const ROWS: usize = 20838;
 pub fn run(repeat_count: usize) {
     let _s = info_span!("halo2-ipa").entered();
 
-    let circuit = MerkleTreeUpdateCircuit::<C1Scalar>::new_with_random_updates(
+    let (step_circuit, batches) = MerkleTreeUpdateCircuit::<C1Scalar>::new_with_random_updates(
+        &mut new_tree(),
         &mut rand::thread_rng(),
         repeat_count,
         1,
     );
+    let circuit = step_circuit.batch_circuit(&batches[0]);
 
     info!("circuit created");
 
@@ -19,7 +19,7 @@ use rand_core::OsRng;
 use sirius::group::{prime::PrimeCurve, Group};
 use tracing::*;
 
-use crate::circuit::MerkleTreeUpdateCircuit;
+use crate::circuit::{new_tree, MerkleTreeUpdateBatchCircuit, MerkleTreeUpdateCircuit};
 
 type C1Scalar = <C1 as Group>::Scalar;
 type C1Affine = <C1 as PrimeCurve>::Affine;
@@ -69,13 +69,13 @@ fn get_or_create_pk(
     path_pk: &Path,
     clean_cache: bool,
     params: &ParamsKZG<Bn256>,
-    circuit: &MerkleTreeUpdateCircuit<C1Scalar>,
+    circuit: &MerkleTreeUpdateBatchCircuit<'_, C1Scalar>,
 ) -> plonk::ProvingKey<C1Affine> {
     if path_pk.exists() && !clean_cache {
         info!("load pk from file");
         // Read the file and parse `pk` from it
         let mut file = fs::File::open(path_pk).expect("failed to open the pk file");
-        plonk::ProvingKey::read::<_, MerkleTreeUpdateCircuit<C1Scalar>>(
+        plonk::ProvingKey::read::<_, MerkleTreeUpdateBatchCircuit<'_, C1Scalar>>(
             &mut file,
             SerdeFormat::Processed,
         )
@@ -107,11 +107,13 @@ fn get_or_create_pk(
 pub fn run(repeat_count: usize, clean_cache: bool) {
     let _s = info_span!("halo2-ipa").entered();
 
-    let circuit = MerkleTreeUpdateCircuit::<C1Scalar>::new_with_random_updates(
+    let (step_circuit, batches) = MerkleTreeUpdateCircuit::<C1Scalar>::new_with_random_updates(
+        &mut new_tree(),
         &mut rand::thread_rng(),
         repeat_count,
         1,
     );
+    let circuit = step_circuit.batch_circuit(&batches[0]);
 
     info!("circuit created");
 
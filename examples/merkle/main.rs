@@ -14,13 +14,12 @@ use tracing::{info, info_span};
 use tracing_subscriber::{filter::LevelFilter, fmt::format::FmtSpan, EnvFilter};
 
 pub mod circuit;
-mod merkle_tree_gadget;
 
 mod ipa;
 mod kzg;
 
 mod sirius_mod {
-    use std::{array, io, num::NonZeroUsize, path::Path};
+    use std::{io, num::NonZeroUsize, path::Path};
 
     use halo2_proofs::halo2curves::{bn256, grumpkin, CurveAffine};
     use sirius::{
@@ -31,13 +30,11 @@ mod sirius_mod {
             sangria::{CircuitPublicParamsInput, PublicParams, IVC},
             step_circuit::trivial,
         },
+        poseidon::ROPair,
     };
     use tracing::info_span;
 
-    use crate::{
-        circuit::MerkleTreeUpdateCircuit,
-        merkle_tree_gadget::{off_circuit::Tree, *},
-    };
+    use crate::circuit::{new_tree, MerkleTreeUpdateCircuit, RandomOracle, T};
 
     const ARITY: usize = 1;
 
@@ -53,7 +50,6 @@ mod sirius_mod {
     type C2Affine = <C2 as PrimeCurve>::Affine;
     type C2Scalar = <C2 as Group>::Scalar;
 
-    type RandomOracle = sirius::poseidon::PoseidonRO<T, RATE>;
     type RandomOracleConstant<F> = <RandomOracle as ROPair<F>>::Args;
 
     fn get_or_create_commitment_key<C: CurveAffine>(
@@ -75,8 +71,12 @@ mod sirius_mod {
 
         let _span = info_span!("merkle_example").entered();
 
-        let mut sc1 =
-            MerkleTreeUpdateCircuit::new_with_random_updates(&mut rng, 1, fold_step_count);
+        let (sc1, batches) = MerkleTreeUpdateCircuit::new_with_random_updates(
+            &mut new_tree(),
+            &mut rng,
+            1,
+            fold_step_count,
+        );
 
         let primary_commitment_key =
             get_or_create_commitment_key::<C1Affine>(COMMITMENT_KEY_SIZE, "bn256")
@@ -93,14 +93,13 @@ mod sirius_mod {
         )
         .unwrap();
 
-        let primary_input = array::from_fn(|i| C1Scalar::from(i as u64));
+        let primary_input = [*new_tree::<C1Scalar>().get_root()];
 
         let mut ivc = IVC::new(&mut pp, &sc1, primary_input).expect("while step=0");
 
-        for step in 0..fold_step_count {
-            sc1.pop_front_proof_batch();
+        for (step, batch) in batches.iter().enumerate() {
             ivc = ivc
-                .next(&pp, &sc1)
+                .next_with_witness(&pp, &sc1, batch)
                 .unwrap_or_else(|err| panic!("while step={step}: {err:?}"));
         }
 
@@ -118,8 +117,12 @@ mod sirius_mod {
         let _span = info_span!("merkle_example").entered();
         let prepare_span = info_span!("prepare").entered();
 
-        let mut sc1 =
-            MerkleTreeUpdateCircuit::new_with_random_updates(&mut rng, 1, fold_step_count);
+        let (sc1, batches) = MerkleTreeUpdateCircuit::new_with_random_updates(
+            &mut new_tree(),
+            &mut rng,
+            1,
+            fold_step_count,
+        );
 
         let sc2 = trivial::Circuit::<ARITY, _>::default();
 
@@ -167,16 +170,16 @@ mod sirius_mod {
         let mut ivc = IVC::new(
             &pp,
             &sc1,
-            [*Tree::default().get_root()],
+            [*new_tree().get_root()],
             &sc2,
             [C2Scalar::ZERO],
             false,
         )
         .unwrap();
 
-        for _ in 0..fold_step_count {
-            sc1.pop_front_proof_batch();
-            ivc.fold_step(&pp, &sc1, &sc2).unwrap();
+        for batch in batches.iter() {
+            ivc.fold_step_with_witness(&pp, &sc1, batch, &sc2, &())
+                .unwrap();
         }
 
         ivc.verify(&pp).unwrap();
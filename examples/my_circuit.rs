@@ -46,6 +46,7 @@ struct MyStepCircuit {}
 impl<const A: usize, F: PrimeField> StepCircuit<A, F> for MyStepCircuit {
     /// This is a configuration object that stores things like columns.
     type Config = MyConfig;
+    type StepWitness = ();
 
     /// Configure the step circuit. This method initializes necessary
     /// fixed columns and advice columns
@@ -63,6 +64,7 @@ impl<const A: usize, F: PrimeField> StepCircuit<A, F> for MyStepCircuit {
         _config: Self::Config,
         _layouter: &mut impl Layouter<F>,
         _z_i: &[AssignedCell<F, F>; A],
+        _witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; A], SynthesisError> {
         todo!()
     }
@@ -18,7 +18,7 @@ use sha3::Shake256;
 use some_to_err::*;
 use tracing::*;
 
-use crate::{group::Curve, util::parallelize};
+use crate::{ff::PrimeField, group::Curve, plonk::permutation, util::parallelize};
 
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum Error {
@@ -170,20 +170,41 @@ impl<C: CurveAffine> CommitmentKey<C> {
     }
 }
 
-pub fn setup_smallest_key<C: CurveAffine>(
+/// Size (`k` for [`CommitmentKey::setup`]) of the smallest commitment key suitable for the
+/// circuit with this constraint system, see [`setup_smallest_key`]
+///
+/// `permutation` is the folded copy constraints argument of the circuit, if any, see
+/// [`crate::table::CircuitRunner::with_folded_copy_constraints`]
+pub fn smallest_key_size<F: PrimeField>(
     k_table_size: u32,
-    cs: &ConstraintSystem<C::ScalarExt>,
-    tag: &'static [u8],
-) -> CommitmentKey<C> {
+    cs: &ConstraintSystem<F>,
+    permutation: Option<&permutation::Arguments>,
+) -> usize {
     /// calculate smallest w such that 2^w >= n*(2^K)
     pub fn smallest_power(n: usize, K: u32) -> usize {
         ((n * 2usize.pow(K)) as f64).log2().ceil() as usize
     }
 
     let num_lookup = cs.lookups().len();
-    let p1 = smallest_power(cs.num_advice_columns() + 5 * num_lookup, k_table_size);
+    // each advice column of permutation adds helpers (h, g), see [`permutation::Arguments`]
+    let num_permutation = permutation
+        .map(permutation::Arguments::num_columns)
+        .unwrap_or_default();
+    let p1 = smallest_power(
+        cs.num_advice_columns() + 5 * num_lookup + 2 * num_permutation,
+        k_table_size,
+    );
     let p2 = smallest_power(cs.num_selectors + cs.num_fixed_columns(), k_table_size);
-    CommitmentKey::<C>::setup(p1.max(p2), tag)
+    p1.max(p2)
+}
+
+pub fn setup_smallest_key<C: CurveAffine>(
+    k_table_size: u32,
+    cs: &ConstraintSystem<C::ScalarExt>,
+    permutation: Option<&permutation::Arguments>,
+    tag: &'static [u8],
+) -> CommitmentKey<C> {
+    CommitmentKey::<C>::setup(smallest_key_size(k_table_size, cs, permutation), tag)
 }
 
 #[cfg(test)]
@@ -0,0 +1,150 @@
+//! Cycles of curves over which IVC is built
+//!
+//! Both IVC schemes require two curves, where the scalar field of one is the base field of the
+//! other. [`CurveCycle`] bundles such a pair together with everything that is usually chosen for
+//! it by default: limbs of Sangria on-circuit big uint math, poseidon rounds and commitment key
+//! labels.
+//! So the preludes take a single cycle type instead of a pair of curves
+
+use std::{io, marker::PhantomData, num::NonZeroUsize, path::Path};
+
+use crate::{
+    commitment::CommitmentKey,
+    ff::{FromUniformBytes, PrimeField},
+    group::prime::PrimeCurveAffine,
+    halo2curves::{bn256, grumpkin, pasta, secp256k1, secq256k1, CurveAffine},
+    poseidon::Spec,
+    sangria_prelude,
+};
+
+pub trait CurveCycle {
+    /// Curve of the primary circuit, its scalar field is the native field of the primary circuit
+    type C1: CurveAffine<Base = <Self::C2 as PrimeCurveAffine>::Scalar>;
+    /// Curve of the secondary (support) circuit
+    type C2: CurveAffine<Base = <Self::C1 as PrimeCurveAffine>::Scalar>;
+
+    /// Label of [`CommitmentKey`] of [`CurveCycle::C1`], see [`CommitmentKey::load_or_setup_cache`]
+    const C1_LABEL: &'static str;
+    /// Label of [`CommitmentKey`] of [`CurveCycle::C2`], see [`CommitmentKey::load_or_setup_cache`]
+    const C2_LABEL: &'static str;
+
+    /// Recommended limb width of on-circuit big uint math of Sangria IVC
+    ///
+    /// Cyclefold IVC doesn't use it, its limbs are fixed by
+    /// [`crate::ivc::cyclefold::DEFAULT_LIMB_WIDTH`], since they define sizes of its inputs
+    const SANGRIA_LIMB_WIDTH: NonZeroUsize = sangria_prelude::DEFAULT_LIMB_WIDTH;
+
+    /// Recommended maximum number of limbs of on-circuit big uint math of Sangria IVC
+    ///
+    /// Cyclefold IVC doesn't use it, see [`crate::ivc::cyclefold::DEFAULT_LIMBS_COUNT`]
+    const SANGRIA_LIMBS_COUNT: NonZeroUsize = sangria_prelude::DEFAULT_LIMBS_COUNT_LIMIT;
+
+    /// Number of complete rounds of poseidon
+    const POSEIDON_R_F: usize = sangria_prelude::POSEIDON_DEFAULT_R_F;
+    /// Number of partial rounds of poseidon
+    const POSEIDON_R_P: usize = sangria_prelude::POSEIDON_DEFAULT_R_P;
+
+    /// Poseidon spec of random oracle over any of the cycle fields
+    fn poseidon_spec<F, const T: usize, const RATE: usize>() -> Spec<F, T, RATE>
+    where
+        F: PrimeField + FromUniformBytes<64>,
+    {
+        Spec::new(Self::POSEIDON_R_F, Self::POSEIDON_R_P)
+    }
+
+    /// Load or if missing setup and store commitment keys of both curves in `cache_folder`
+    ///
+    /// # Safety
+    /// Same as [`CommitmentKey::load_or_setup_cache`]
+    unsafe fn load_or_setup_commitment_keys(
+        cache_folder: &Path,
+        c1_k: usize,
+        c2_k: usize,
+    ) -> io::Result<(CommitmentKey<Self::C1>, CommitmentKey<Self::C2>)> {
+        unsafe {
+            Ok((
+                CommitmentKey::load_or_setup_cache(cache_folder, Self::C1_LABEL, c1_k)?,
+                CommitmentKey::load_or_setup_cache(cache_folder, Self::C2_LABEL, c2_k)?,
+            ))
+        }
+    }
+}
+
+/// bn256 as primary & grumpkin as secondary curve
+#[derive(Debug, Clone, Copy)]
+pub struct Bn256Grumpkin;
+
+impl CurveCycle for Bn256Grumpkin {
+    type C1 = bn256::G1Affine;
+    type C2 = grumpkin::G1Affine;
+
+    const C1_LABEL: &'static str = "bn256";
+    const C2_LABEL: &'static str = "grumpkin";
+}
+
+/// pallas as primary & vesta as secondary curve
+#[derive(Debug, Clone, Copy)]
+pub struct PallasVesta;
+
+impl CurveCycle for PallasVesta {
+    type C1 = pasta::pallas::Affine;
+    type C2 = pasta::vesta::Affine;
+
+    const C1_LABEL: &'static str = "pallas";
+    const C2_LABEL: &'static str = "vesta";
+}
+
+/// secp256k1 as primary & secq256k1 as secondary curve
+#[derive(Debug, Clone, Copy)]
+pub struct Secp256k1Secq256k1;
+
+impl CurveCycle for Secp256k1Secq256k1 {
+    type C1 = secp256k1::Secp256k1Affine;
+    type C2 = secq256k1::Secq256k1Affine;
+
+    const C1_LABEL: &'static str = "secp256k1";
+    const C2_LABEL: &'static str = "secq256k1";
+}
+
+/// Same cycle with swapped primary & secondary curves
+#[derive(Debug, Clone, Copy)]
+pub struct Reversed<Cycle>(PhantomData<Cycle>);
+
+impl<Cycle: CurveCycle> CurveCycle for Reversed<Cycle> {
+    type C1 = Cycle::C2;
+    type C2 = Cycle::C1;
+
+    const C1_LABEL: &'static str = Cycle::C2_LABEL;
+    const C2_LABEL: &'static str = Cycle::C1_LABEL;
+
+    const SANGRIA_LIMB_WIDTH: NonZeroUsize = Cycle::SANGRIA_LIMB_WIDTH;
+    const SANGRIA_LIMBS_COUNT: NonZeroUsize = Cycle::SANGRIA_LIMBS_COUNT;
+
+    const POSEIDON_R_F: usize = Cycle::POSEIDON_R_F;
+    const POSEIDON_R_P: usize = Cycle::POSEIDON_R_P;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitment_keys_cached_by_labels<Cycle: CurveCycle>() {
+        let cache = tempfile::tempdir().unwrap();
+
+        let (ck1, ck2) =
+            unsafe { Cycle::load_or_setup_commitment_keys(cache.path(), 4, 5) }.unwrap();
+
+        assert_eq!(ck1.len(), 1 << 4);
+        assert_eq!(ck2.len(), 1 << 5);
+        assert!(cache.path().join(Cycle::C1_LABEL).join("4.bin").exists());
+        assert!(cache.path().join(Cycle::C2_LABEL).join("5.bin").exists());
+    }
+
+    #[test]
+    fn commitment_keys() {
+        commitment_keys_cached_by_labels::<Bn256Grumpkin>();
+        commitment_keys_cached_by_labels::<PallasVesta>();
+        commitment_keys_cached_by_labels::<Secp256k1Secq256k1>();
+        commitment_keys_cached_by_labels::<Reversed<PallasVesta>>();
+    }
+}
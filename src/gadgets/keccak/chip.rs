@@ -0,0 +1,572 @@
+use std::{array, iter};
+
+use halo2_proofs::{
+    circuit::{Chip, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, TableColumn},
+    poly::Rotation,
+};
+
+use super::{
+    lane_index, DIGEST_LANES, LANE_BITS, RATE_LANES, ROTATIONS, ROUND_CONSTANTS, STATE_LANES,
+};
+use crate::{
+    ff::PrimeFieldBits,
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx},
+};
+
+/// Max count of bits in one xor: five lanes of the θ step
+const XOR_INPUTS: usize = 5;
+/// Keys of χ entries of the table are shifted by this value, so they don't overlap with the
+/// xor ones (`0..=XOR_INPUTS + 1`)
+const CHI_OFFSET: u64 = 8;
+
+/// Lane of the state, little-endian bits
+type AssignedLane<F> = Vec<AssignedBit<F>>;
+
+#[derive(Clone, Copy, Debug)]
+enum LookupGate {
+    /// `out = in[0] ^ ... ^ in[4] ^ rc`
+    Xor,
+    /// `out = in[0] ^ (!in[1] & in[2]) ^ rc`
+    Chi,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeccakConfig<const T: usize> {
+    main_gate: MainGateConfig<T>,
+    inputs: [Column<Advice>; XOR_INPUTS],
+    out: Column<Advice>,
+    q_xor: Column<Fixed>,
+    q_chi: Column<Fixed>,
+    /// Bit of the round constant for ι step, or of the constant for padding
+    rc: Column<Fixed>,
+    table_key: TableColumn,
+    table_value: TableColumn,
+}
+
+/// Keccak-256 over [`MainGate`] & one lookup table
+///
+/// Each row of the chip is one lookup of `(key, out)`, where `key` is a linear combination of
+/// input bits & the fixed constant bit:
+/// - xor: `key = Σ in[i] + rc`, the table holds its parity
+/// - χ & ι: `key = 8 + in[0] + 2 * in[1] + 4 * in[2] + 8 * rc`
+///
+/// Inputs are always boolean: message bits are decomposed by [`MainGate::decompose_bits`] and
+/// the table has only boolean values, so outputs of lookups are boolean too. Inputs of xor that
+/// are not used are copies of the zero constant, so they can't change the parity
+///
+/// Table must be loaded once per circuit with [`KeccakChip::load`]
+#[derive(Debug)]
+pub struct KeccakChip<F: PrimeFieldBits, const T: usize> {
+    config: KeccakConfig<T>,
+    main_gate: MainGate<F, T>,
+}
+
+impl<F: PrimeFieldBits, const T: usize> Chip<F> for KeccakChip<F, T> {
+    type Config = KeccakConfig<T>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeFieldBits, const T: usize> KeccakChip<F, T> {
+    pub fn new(config: KeccakConfig<T>) -> Self {
+        Self {
+            main_gate: MainGate::new(config.main_gate.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> KeccakConfig<T> {
+        let main_gate = MainGate::configure(meta);
+
+        let inputs = array::from_fn(|_| meta.advice_column());
+        let out = meta.advice_column();
+        inputs.iter().chain(iter::once(&out)).for_each(|column| {
+            meta.enable_equality(*column);
+        });
+
+        let q_xor = meta.fixed_column();
+        let q_chi = meta.fixed_column();
+        let rc = meta.fixed_column();
+
+        let table_key = meta.lookup_table_column();
+        let table_value = meta.lookup_table_column();
+
+        meta.lookup("keccak bitwise", |meta| {
+            let inputs = inputs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let out = meta.query_advice(out, Rotation::cur());
+            let q_xor = meta.query_fixed(q_xor, Rotation::cur());
+            let q_chi = meta.query_fixed(q_chi, Rotation::cur());
+            let rc = meta.query_fixed(rc, Rotation::cur());
+
+            let xor_key = inputs
+                .iter()
+                .fold(rc.clone(), |acc, input| acc + input.clone());
+            let chi_key = Expression::Constant(F::from(CHI_OFFSET))
+                + inputs[0].clone()
+                + inputs[1].clone() * F::from(2)
+                + inputs[2].clone() * F::from(4)
+                + rc * F::from(8);
+
+            vec![
+                (q_xor.clone() * xor_key + q_chi.clone() * chi_key, table_key),
+                ((q_xor + q_chi) * out, table_value),
+            ]
+        });
+
+        KeccakConfig {
+            main_gate,
+            inputs,
+            out,
+            q_xor,
+            q_chi,
+            rc,
+            table_key,
+            table_value,
+        }
+    }
+
+    /// Entries of the table, `(0, 0)` is among them, so rows without lookup are valid
+    fn table() -> impl Iterator<Item = (u64, u64)> {
+        let xor = (0..=XOR_INPUTS as u64 + 1).map(|key| (key, key % 2));
+        let chi = (0..16).map(|key| {
+            let [a, b, c, rc] = array::from_fn(|i| (key >> i) & 1);
+            (CHI_OFFSET + key, a ^ ((1 ^ b) & c) ^ rc)
+        });
+
+        xor.chain(chi)
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "keccak table",
+            |mut table| {
+                for (index, (key, value)) in Self::table().enumerate() {
+                    table.assign_cell(
+                        || "key",
+                        self.config.table_key,
+                        index,
+                        || Value::known(F::from(key)),
+                    )?;
+                    table.assign_cell(
+                        || "value",
+                        self.config.table_value,
+                        index,
+                        || Value::known(F::from(value)),
+                    )?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign one lookup row, input columns after `inputs` are filled by copies of `padding`
+    fn assign_lookup(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        gate: LookupGate,
+        inputs: &[&AssignedBit<F>],
+        padding: Option<&AssignedBit<F>>,
+        rc: bool,
+    ) -> Result<AssignedBit<F>, Error> {
+        let KeccakConfig {
+            inputs: input_columns,
+            out,
+            q_xor,
+            q_chi,
+            rc: rc_column,
+            ..
+        } = &self.config;
+
+        let bits = inputs
+            .iter()
+            .map(|cell| cell.value().map(|value| *value == F::ONE))
+            .collect::<Value<Vec<bool>>>();
+
+        let out_value = bits.map(|bits| {
+            let bit = match gate {
+                LookupGate::Xor => bits.iter().fold(rc, |acc, bit| acc ^ bit),
+                LookupGate::Chi => bits[0] ^ (!bits[1] & bits[2]) ^ rc,
+            };
+            F::from(bit as u64)
+        });
+
+        match gate {
+            LookupGate::Xor => ctx.assign_fixed(|| "q_xor", *q_xor, F::ONE)?,
+            LookupGate::Chi => ctx.assign_fixed(|| "q_chi", *q_chi, F::ONE)?,
+        };
+        if rc {
+            ctx.assign_fixed(|| "rc", *rc_column, F::ONE)?;
+        }
+
+        for (column, input) in input_columns
+            .iter()
+            .zip(inputs.iter().copied().chain(padding.into_iter().cycle()))
+        {
+            ctx.assign_advice_from(|| "input", *column, input)?;
+        }
+        let out = ctx.assign_advice(|| "out", *out, out_value)?;
+
+        ctx.next();
+        Ok(out)
+    }
+
+    /// Xor of up to [`XOR_INPUTS`] bits, `zero` must be constrained to zero
+    fn xor(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        inputs: &[&AssignedBit<F>],
+        zero: &AssignedBit<F>,
+    ) -> Result<AssignedBit<F>, Error> {
+        assert!(inputs.len() <= XOR_INPUTS);
+        self.assign_lookup(ctx, LookupGate::Xor, inputs, Some(zero), false)
+    }
+
+    /// Assign bit equal to the constant, the value is fixed by the main gate
+    fn assign_constant_bit(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bit: bool,
+    ) -> Result<AssignedBit<F>, Error> {
+        let value = F::from(bit as u64);
+
+        let assigned = self.main_gate.assign_value(ctx, Value::known(value))?;
+        self.main_gate
+            .assert_equal_const(ctx, assigned.clone(), value)?;
+
+        Ok(assigned)
+    }
+
+    /// Keccak-f\[1600\] permutation of the state
+    fn keccak_f(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        mut state: Vec<AssignedLane<F>>,
+        zero: &AssignedBit<F>,
+    ) -> Result<Vec<AssignedLane<F>>, Error> {
+        let rotate_left = |lane: &AssignedLane<F>, offset: usize| -> AssignedLane<F> {
+            (0..LANE_BITS)
+                .map(|z| lane[(z + LANE_BITS - offset) % LANE_BITS].clone())
+                .collect()
+        };
+
+        for round_constant in ROUND_CONSTANTS {
+            // θ
+            let c = (0..5)
+                .map(|x| {
+                    (0..LANE_BITS)
+                        .map(|z| {
+                            let column = (0..5)
+                                .map(|y| &state[lane_index(x, y)][z])
+                                .collect::<Vec<_>>();
+                            self.xor(ctx, &column, zero)
+                        })
+                        .collect::<Result<AssignedLane<F>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let c_rotated = c
+                .iter()
+                .map(|lane| rotate_left(lane, 1))
+                .collect::<Vec<_>>();
+
+            let mut theta = Vec::with_capacity(STATE_LANES);
+            for y in 0..5 {
+                for x in 0..5 {
+                    let lane = (0..LANE_BITS)
+                        .map(|z| {
+                            self.xor(
+                                ctx,
+                                &[
+                                    &state[lane_index(x, y)][z],
+                                    &c[(x + 4) % 5][z],
+                                    &c_rotated[(x + 1) % 5][z],
+                                ],
+                                zero,
+                            )
+                        })
+                        .collect::<Result<AssignedLane<F>, _>>()?;
+                    theta.push(lane);
+                }
+            }
+
+            // ρ & π
+            let mut b = vec![Vec::new(); STATE_LANES];
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[lane_index(y, 2 * x + 3 * y)] =
+                        rotate_left(&theta[lane_index(x, y)], ROTATIONS[x][y] as usize);
+                }
+            }
+
+            // χ & ι
+            state = (0..STATE_LANES)
+                .map(|index| {
+                    let (x, y) = (index % 5, index / 5);
+                    (0..LANE_BITS)
+                        .map(|z| {
+                            let rc = index == 0 && (round_constant >> z) & 1 == 1;
+                            self.assign_lookup(
+                                ctx,
+                                LookupGate::Chi,
+                                &[
+                                    &b[lane_index(x, y)][z],
+                                    &b[lane_index(x + 1, y)][z],
+                                    &b[lane_index(x + 2, y)][z],
+                                ],
+                                None,
+                                rc,
+                            )
+                        })
+                        .collect::<Result<AssignedLane<F>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        Ok(state)
+    }
+
+    /// Sponge over the message bits: `pad10*1` padding with Keccak domain & absorption of
+    /// all blocks
+    fn digest_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        mut bits: Vec<AssignedBit<F>>,
+    ) -> Result<[AssignedValue<F>; DIGEST_LANES], Error> {
+        const RATE_BITS: usize = RATE_LANES * LANE_BITS;
+        assert_eq!(bits.len() % 8, 0, "message is a sequence of bytes");
+
+        let zero = self.assign_constant_bit(ctx, false)?;
+        let one = self.assign_constant_bit(ctx, true)?;
+
+        bits.push(one.clone());
+        let padded_len = bits.len().div_ceil(RATE_BITS) * RATE_BITS;
+        bits.resize(padded_len, zero.clone());
+        *bits.last_mut().expect("not empty") = one;
+
+        let mut state = Option::<Vec<AssignedLane<F>>>::None;
+        for block in bits.chunks_exact(RATE_BITS) {
+            let block = block.chunks_exact(LANE_BITS);
+
+            let absorbed = match state {
+                None => block
+                    .map(|lane| lane.to_vec())
+                    .chain(iter::repeat(vec![zero.clone(); LANE_BITS]))
+                    .take(STATE_LANES)
+                    .collect::<Vec<_>>(),
+                Some(mut state) => {
+                    for (lane, block_lane) in state.iter_mut().zip(block) {
+                        *lane = lane
+                            .iter()
+                            .zip(block_lane)
+                            .map(|(bit, block_bit)| self.xor(ctx, &[bit, block_bit], &zero))
+                            .collect::<Result<_, _>>()?;
+                    }
+                    state
+                }
+            };
+
+            state = Some(self.keccak_f(ctx, absorbed, &zero)?);
+        }
+
+        let state = state.expect("padding is at least one block");
+        let digest = state
+            .iter()
+            .take(DIGEST_LANES)
+            .map(|lane| self.main_gate.compose_bits(ctx, lane))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(digest.try_into().expect("exactly `DIGEST_LANES`"))
+    }
+
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        words: &[AssignedValue<F>],
+        bit_len: usize,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        Ok(words
+            .iter()
+            .map(|word| self.main_gate.decompose_bits(ctx, word, bit_len))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Keccak-256 of the message of bytes, each byte is range-checked
+    ///
+    /// Digest is returned as little-endian lanes, see [`super::off_circuit::keccak256_lanes`]
+    pub fn digest_bytes(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Result<[AssignedValue<F>; DIGEST_LANES], Error> {
+        let bits = self.decompose(ctx, bytes, 8)?;
+        self.digest_bits(ctx, bits)
+    }
+
+    /// Keccak-256 of the message of little-endian 64-bit lanes, each lane is range-checked
+    ///
+    /// On-circuit version of [`super::off_circuit::keccak256_lanes`]
+    pub fn digest_lanes(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        lanes: &[AssignedValue<F>],
+    ) -> Result<[AssignedValue<F>; DIGEST_LANES], Error> {
+        let bits = self.decompose(ctx, lanes, LANE_BITS)?;
+        self.digest_bits(ctx, bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use tracing_test::traced_test;
+
+    use super::{super::off_circuit, *};
+    use crate::{ff::Field, halo2curves::bn256::Fr};
+
+    const T: usize = 2;
+
+    struct TestCircuit {
+        message: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = KeccakConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            todo!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            KeccakChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = KeccakChip::<Fr, T>::new(config);
+            chip.load(&mut layouter)?;
+
+            let digest = layouter.assign_region(
+                || "keccak",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+
+                    let bytes = self
+                        .message
+                        .iter()
+                        .map(|byte| {
+                            chip.main_gate
+                                .assign_value(ctx, Value::known(Fr::from(*byte as u64)))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    chip.digest_bytes(ctx, &bytes)
+                },
+            )?;
+
+            let expected = off_circuit::keccak256(&self.message);
+            for (cell, chunk) in digest.iter().zip(expected.chunks_exact(8)) {
+                let lane = u64::from_le_bytes(chunk.try_into().unwrap());
+                assert_eq!(cell.value().unwrap().copied(), Some(Fr::from(lane)));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn keccak_chip() {
+        MockProver::run(
+            17,
+            &TestCircuit {
+                message: b"abc".to_vec(),
+            },
+            vec![],
+        )
+        .unwrap()
+        .verify()
+        .unwrap();
+    }
+
+    /// Xor of two zero bits, where the prover may put `1` into the first unused input & flip
+    /// the output, so the lookup is still satisfied
+    struct ForgedXorCircuit {
+        forge: bool,
+    }
+
+    impl Circuit<Fr> for ForgedXorCircuit {
+        type Config = KeccakConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { forge: self.forge }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            KeccakChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = KeccakChip::<Fr, T>::new(config.clone());
+            chip.load(&mut layouter)?;
+
+            layouter.assign_region(
+                || "xor",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+
+                    let zero = chip.assign_constant_bit(ctx, false)?;
+
+                    let row = ctx.offset();
+                    chip.xor(ctx, &[&zero, &zero], &zero)?;
+
+                    if self.forge {
+                        let next = ctx.offset();
+                        ctx.reset(row);
+                        ctx.assign_advice(
+                            || "forged input",
+                            config.inputs[2],
+                            Value::known(Fr::ONE),
+                        )?;
+                        ctx.assign_advice(|| "forged out", config.out, Value::known(Fr::ONE))?;
+                        ctx.reset(next);
+                    }
+
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn unused_xor_input_is_zero() {
+        let run = |forge| {
+            MockProver::run(10, &ForgedXorCircuit { forge }, vec![])
+                .unwrap()
+                .verify()
+        };
+
+        run(false).unwrap();
+        assert!(run(true).is_err());
+    }
+}
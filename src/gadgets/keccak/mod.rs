@@ -0,0 +1,75 @@
+//! The [Keccak-256] hash function, as used by Ethereum
+//!
+//! - [`KeccakChip`] implements Keccak-f\[1600\] over lanes decomposed into bits
+//! - [`KeccakStepCircuit`] hashes the previous digest per step, for hash-chain proofs
+//! - [`off_circuit`] is the reference implementation, used to calculate the step output
+//!
+//! All bitwise operations (θ & χ/ι steps) are checked with a single small lookup table, so
+//! the chip adds no custom gates: lookup expressions are linear in advice cells, which keeps
+//! [`crate::table::ConstraintSystemMetainfo::folding_degree`] of the step circuit equal to the
+//! one of [`crate::main_gate::MainGate`]. ρ & π steps are permutations of bits and cost
+//! nothing.
+//!
+//! [Keccak-256]: https://keccak.team/keccak_specs_summary.html
+
+mod chip;
+pub mod off_circuit;
+mod step_circuit;
+
+pub use chip::{KeccakChip, KeccakConfig};
+pub use step_circuit::KeccakStepCircuit;
+
+/// Count of rounds of Keccak-f\[1600\]
+pub const ROUNDS: usize = 24;
+/// Size of the lane, in bits
+pub const LANE_BITS: usize = 64;
+/// Count of lanes of the state
+pub const STATE_LANES: usize = 25;
+/// Size of the Keccak-256 rate (block), in lanes
+pub const RATE_LANES: usize = 17;
+/// Size of the Keccak-256 rate (block), in bytes
+pub const RATE_BYTES: usize = RATE_LANES * 8;
+/// Size of the Keccak-256 digest, in lanes
+pub const DIGEST_LANES: usize = 4;
+
+/// Constants of the ι step
+pub(crate) const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808A,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808B,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008A,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000A,
+    0x0000_0000_8000_808B,
+    0x8000_0000_0000_008B,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800A,
+    0x8000_0000_8000_000A,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// Offsets of the ρ step, indexed by `[x][y]`
+pub(crate) const ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// Index of the lane `(x, y)` in the state
+pub(crate) const fn lane_index(x: usize, y: usize) -> usize {
+    (x % 5) + 5 * (y % 5)
+}
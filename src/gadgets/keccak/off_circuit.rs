@@ -0,0 +1,129 @@
+//! Reference Keccak-256, matches [`super::KeccakChip`]
+
+use super::{
+    lane_index, DIGEST_LANES, RATE_BYTES, RATE_LANES, ROTATIONS, ROUND_CONSTANTS, STATE_LANES,
+};
+
+/// Keccak-f\[1600\] permutation
+pub fn keccak_f(state: &mut [u64; STATE_LANES]) {
+    for rc in ROUND_CONSTANTS {
+        // θ
+        let c: [u64; 5] =
+            std::array::from_fn(|x| (0..5).fold(0, |acc, y| acc ^ state[lane_index(x, y)]));
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[lane_index(x, y)] ^= d;
+            }
+        }
+
+        // ρ & π
+        let mut b = [0u64; STATE_LANES];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[lane_index(y, 2 * x + 3 * y)] =
+                    state[lane_index(x, y)].rotate_left(ROTATIONS[x][y]);
+            }
+        }
+
+        // χ
+        for x in 0..5 {
+            for y in 0..5 {
+                state[lane_index(x, y)] =
+                    b[lane_index(x, y)] ^ (!b[lane_index(x + 1, y)] & b[lane_index(x + 2, y)]);
+            }
+        }
+
+        // ι
+        state[0] ^= rc;
+    }
+}
+
+/// Message with appended `pad10*1` padding with Keccak domain (`0x01`), as lanes
+fn pad(bytes: &[u8]) -> Vec<u64> {
+    let mut padded = bytes.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(RATE_BYTES) * RATE_BYTES, 0);
+    *padded.last_mut().expect("not empty") |= 0x80;
+
+    padded
+        .chunks_exact(8)
+        .map(|lane| u64::from_le_bytes(lane.try_into().expect("chunks are exact")))
+        .collect()
+}
+
+fn digest_padded(padded: &[u64]) -> [u64; DIGEST_LANES] {
+    let mut state = [0u64; STATE_LANES];
+    for block in padded.chunks_exact(RATE_LANES) {
+        for (lane, word) in state.iter_mut().zip(block) {
+            *lane ^= word;
+        }
+        keccak_f(&mut state);
+    }
+
+    std::array::from_fn(|i| state[i])
+}
+
+/// Keccak-256 digest of the message
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    for (chunk, lane) in digest.chunks_exact_mut(8).zip(digest_padded(&pad(bytes))) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+/// Keccak-256 digest of the message of little-endian lanes, returned as little-endian lanes
+pub fn keccak256_lanes(lanes: &[u64]) -> [u64; DIGEST_LANES] {
+    let bytes = lanes
+        .iter()
+        .flat_map(|lane| lane.to_le_bytes())
+        .collect::<Vec<_>>();
+    digest_padded(&pad(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(
+            hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(&keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn multi_block() {
+        // Padding takes the whole last block
+        let bytes = vec![0xab; RATE_BYTES];
+        assert_eq!(pad(&bytes).len(), 2 * RATE_LANES);
+        // `0x01` & `0x80` in one byte
+        assert_eq!(pad(&bytes[1..]).len(), RATE_LANES);
+    }
+
+    #[test]
+    fn lanes() {
+        let lanes = [1u64, 2, 3, 4];
+        let bytes = lanes
+            .iter()
+            .flat_map(|lane| lane.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let digest = keccak256(&bytes);
+        let digest_lanes = keccak256_lanes(&lanes);
+
+        for (chunk, lane) in digest.chunks_exact(8).zip(digest_lanes) {
+            assert_eq!(chunk, lane.to_le_bytes());
+        }
+    }
+}
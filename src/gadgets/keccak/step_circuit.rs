@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error as Halo2PlonkError},
+};
+use num_traits::ToPrimitive;
+use tracing::*;
+
+use super::{off_circuit, KeccakChip, KeccakConfig, DIGEST_LANES};
+use crate::{
+    ff::PrimeFieldBits,
+    ivc::{StepCircuit, SynthesisError},
+    main_gate::RegionCtx,
+    util::fe_to_big,
+};
+
+/// Size of [`crate::main_gate::MainGate`] used by [`KeccakStepCircuit`], only decomposition of
+/// lanes into bits is done by it
+const T: usize = 2;
+
+/// Step circuit of the Keccak-256 hash chain: `z_{i+1} = keccak256(z_i)`
+///
+/// `z_i` is the digest of the previous step as [`DIGEST_LANES`] little-endian 64-bit lanes, so
+/// the hashed message is exactly the 32 bytes of the digest. The initial `z_0` must also
+/// consist of 64-bit values
+///
+/// One permutation takes about `85_000` rows, so the step requires `k >= 17`
+#[derive(Debug)]
+pub struct KeccakStepCircuit<F: PrimeFieldBits> {
+    _p: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> Default for KeccakStepCircuit<F> {
+    fn default() -> Self {
+        Self { _p: PhantomData }
+    }
+}
+
+impl<F: PrimeFieldBits> StepCircuit<DIGEST_LANES, F> for KeccakStepCircuit<F> {
+    type Config = KeccakConfig<T>;
+    type StepWitness = ();
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        KeccakChip::configure(cs)
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; DIGEST_LANES],
+        _witness: &Self::StepWitness,
+        _k_table_size: u32,
+    ) -> Result<[F; DIGEST_LANES], SynthesisError> {
+        let lanes = z_i
+            .iter()
+            .map(|fe| {
+                fe_to_big(fe).to_u64().ok_or_else(|| {
+                    error!("`z_i` element {fe:?} is not a 64-bit lane");
+                    SynthesisError::Halo2(Halo2PlonkError::Synthesis)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(off_circuit::keccak256_lanes(&lanes).map(F::from))
+    }
+
+    fn synthesize_step(
+        &self,
+        config: Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; DIGEST_LANES],
+        _witness: &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; DIGEST_LANES], SynthesisError> {
+        let chip = KeccakChip::new(config);
+        chip.load(layouter).map_err(SynthesisError::Halo2)?;
+
+        layouter
+            .assign_region(
+                || "keccak256",
+                |region| {
+                    let mut region = RegionCtx::new(region, 0);
+                    chip.digest_lanes(&mut region, z_i)
+                },
+            )
+            .map_err(SynthesisError::Halo2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::array;
+
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{halo2curves::bn256::Fr, util::MockProver};
+
+    #[traced_test]
+    #[test]
+    fn hash_chain() {
+        let circuit = KeccakStepCircuit::<Fr>::default();
+
+        let z_0 = array::from_fn(|i| Fr::from(u64::MAX - i as u64));
+        let z_1 = circuit.process_step(&z_0, &(), 17).unwrap();
+
+        MockProver::run(17, &circuit, vec![], z_0)
+            .unwrap()
+            .verify(z_1)
+            .unwrap();
+    }
+
+    #[test]
+    fn not_a_lane() {
+        let z_i = [Fr::from(u64::MAX) * Fr::from(2); DIGEST_LANES];
+        assert!(KeccakStepCircuit::default()
+            .process_step(&z_i, &(), 17)
+            .is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use halo2_proofs::{
+    circuit::{Chip, Value},
+    plonk::Error,
+};
+use tracing::*;
+
+use super::off_circuit::{Proof, EMPTY_LEAF};
+use crate::{
+    ff::{FromUniformBytes, PrimeFieldBits},
+    main_gate::{AssignedValue, MainGate, MainGateConfig, RegionCtx},
+    poseidon::{ROCircuitTrait, ROPair},
+};
+
+/// Assigned result of [`MerkleTreeChip::verify_update`]
+#[derive(Debug, Clone)]
+pub struct AssignedUpdate<F: PrimeFieldBits> {
+    /// Index of the leaf, bound to positions of nodes in the path
+    pub index: AssignedValue<F>,
+    pub old_leaf: AssignedValue<F>,
+    pub new_leaf: AssignedValue<F>,
+    pub old_root: AssignedValue<F>,
+    pub new_root: AssignedValue<F>,
+}
+
+/// Assigned result of [`MerkleTreeChip::verify_membership`] &
+/// [`MerkleTreeChip::verify_non_membership`]
+#[derive(Debug, Clone)]
+pub struct AssignedMembership<F: PrimeFieldBits> {
+    /// Index of the leaf, bound to positions of nodes in the path
+    pub index: AssignedValue<F>,
+    pub leaf: AssignedValue<F>,
+    pub root: AssignedValue<F>,
+}
+
+/// On-circuit verification of [`Proof`]
+///
+/// Position of each node among its siblings is constrained by one-hot flags, so the leaf index
+/// is part of the statement, not only of the witness
+///
+/// Requires `T >= 4` because of [`MainGate::conditional_select`]
+pub struct MerkleTreeChip<F, RP, const T: usize>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    main_gate: MainGate<F, T>,
+    args: RP::Args,
+    arity: usize,
+}
+
+impl<F, RP, const T: usize> MerkleTreeChip<F, RP, T>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    pub fn new(config: MainGateConfig<T>, args: RP::Args, arity: usize) -> Self {
+        assert!(arity >= 2, "arity of merkle tree must be at least two");
+
+        Self {
+            main_gate: MainGate::new(config),
+            args,
+            arity,
+        }
+    }
+
+    fn hasher(&self) -> RP::OnCircuit {
+        RP::OnCircuit::new(self.main_gate.config().clone(), self.args.clone())
+    }
+
+    /// On-circuit version of [`super::hash_leaf`]
+    pub fn hash_leaf(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.hash_node(ctx, std::slice::from_ref(value))
+    }
+
+    /// On-circuit version of [`super::hash_node`]
+    pub fn hash_node(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        children: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        self.hasher().absorb_iter(children.iter()).squeeze(ctx)
+    }
+
+    /// Verify both `old` & `new` paths of the proof
+    pub fn verify_update(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &Proof<F>,
+    ) -> Result<AssignedUpdate<F>, Error> {
+        let leaf = proof.leaf();
+        let (index, leaves, roots) = self.assign_paths(ctx, proof, &[leaf.old, leaf.new])?;
+
+        let [old_leaf, new_leaf]: [_; 2] = leaves.try_into().unwrap();
+        let [old_root, new_root]: [_; 2] = roots.try_into().unwrap();
+
+        Ok(AssignedUpdate {
+            index,
+            old_leaf,
+            new_leaf,
+            old_root,
+            new_root,
+        })
+    }
+
+    /// Verify `old` path of the proof, i.e. that the leaf is placed in tree with this root
+    pub fn verify_membership(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &Proof<F>,
+    ) -> Result<AssignedMembership<F>, Error> {
+        let (index, mut leaves, mut roots) = self.assign_paths(ctx, proof, &[proof.leaf().old])?;
+
+        Ok(AssignedMembership {
+            index,
+            leaf: leaves.pop().unwrap(),
+            root: roots.pop().unwrap(),
+        })
+    }
+
+    /// Verify `old` path of the proof & that the leaf is [`EMPTY_LEAF`]
+    pub fn verify_non_membership(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &Proof<F>,
+    ) -> Result<AssignedMembership<F>, Error> {
+        let membership = self.verify_membership(ctx, proof)?;
+        self.main_gate
+            .assert_equal_const(ctx, membership.leaf.clone(), F::from(EMPTY_LEAF))?;
+        Ok(membership)
+    }
+
+    /// Assign `leaves` & climb with each of them to the root, using siblings from `proof`
+    ///
+    /// Return the assigned leaf index, leaves & roots
+    #[allow(clippy::type_complexity)]
+    fn assign_paths(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &Proof<F>,
+        leaves: &[F],
+    ) -> Result<
+        (
+            AssignedValue<F>,
+            Vec<AssignedValue<F>>,
+            Vec<AssignedValue<F>>,
+        ),
+        Error,
+    > {
+        if proof.arity() != self.arity {
+            error!(
+                "proof arity {} not match chip arity {}",
+                proof.arity(),
+                self.arity
+            );
+            return Err(Error::Synthesis);
+        }
+
+        let mg = &self.main_gate;
+
+        let assigned_leaves = leaves
+            .iter()
+            .map(|leaf| mg.assign_value(ctx, Value::known(*leaf)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut current = assigned_leaves.clone();
+        let mut index = Option::<AssignedValue<F>>::None;
+        let mut weight = F::ONE;
+
+        for (node_index, update) in proof.iter().take(proof.depth()) {
+            let position = node_index.position(self.arity);
+
+            // One-hot flags of the node position & their prefix sums
+            let flags = (0..self.arity)
+                .map(|j| mg.assign_bit(ctx, Value::known(F::from((j == position) as u64))))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut prefix = vec![flags[0].clone()];
+            for flag in flags.iter().skip(1) {
+                let next = mg.add(ctx, prefix.last().unwrap(), flag)?;
+                prefix.push(next);
+            }
+            mg.assert_equal_const(ctx, prefix.last().unwrap().clone(), F::ONE)?;
+
+            let assigned_position =
+                flags
+                    .iter()
+                    .enumerate()
+                    .skip(2)
+                    .try_fold(flags[1].clone(), |acc, (j, flag)| {
+                        let term = mg.mul_by_const(ctx, flag, F::from(j as u64))?;
+                        mg.add(ctx, &acc, &term)
+                    })?;
+            index = Some(match index {
+                None => assigned_position,
+                Some(index) => {
+                    let term = mg.mul_by_const(ctx, &assigned_position, weight)?;
+                    mg.add(ctx, &index, &term)?
+                }
+            });
+            weight *= F::from(self.arity as u64);
+
+            let siblings = update
+                .siblings
+                .iter()
+                .map(|sibling| mg.assign_value(ctx, Value::known(*sibling)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Sibling placed in slot `j` if the node is not there: `siblings[j - 1]` if the
+            // node is to the left of `j`, otherwise `siblings[j]`
+            let slot_siblings = (0..self.arity)
+                .map(|j| match j {
+                    0 => Ok(siblings[0].clone()),
+                    j if j == self.arity - 1 => Ok(siblings[j - 1].clone()),
+                    j => mg.conditional_select(ctx, &siblings[j - 1], &siblings[j], &prefix[j - 1]),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            current = current
+                .iter()
+                .map(|node| {
+                    let children = slot_siblings
+                        .iter()
+                        .zip(flags.iter())
+                        .map(|(sibling, flag)| mg.conditional_select(ctx, node, sibling, flag))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    self.hash_node(ctx, &children)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        let root = proof.root();
+        for (assigned, expected) in current.iter().zip([root.old, root.new]) {
+            if assigned
+                .value()
+                .unwrap()
+                .is_some_and(|value| value != &expected)
+            {
+                warn!("assigned root doesn't match the proof: {assigned:?} != {expected:?}");
+            }
+        }
+
+        Ok((index.expect("depth is not zero"), assigned_leaves, current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem},
+    };
+    use rand::Rng;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        ff::Field,
+        gadgets::merkle::Tree,
+        halo2curves::bn256::Fr,
+        poseidon::{PoseidonRO, Spec},
+    };
+
+    const T: usize = 5;
+    type RP = PoseidonRO<T, 4>;
+
+    struct TestCircuit {
+        arity: usize,
+        updates: Box<[Proof<Fr>]>,
+        membership: Proof<Fr>,
+        non_membership: Proof<Fr>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = MainGateConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            todo!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MainGate::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "merkle",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let chip = MerkleTreeChip::<Fr, RP, T>::new(
+                        config.clone(),
+                        Spec::new(10, 10),
+                        self.arity,
+                    );
+
+                    let mut prev = Option::<AssignedValue<Fr>>::None;
+                    for proof in self.updates.iter() {
+                        let update = chip.verify_update(ctx, proof)?;
+
+                        assert_eq!(
+                            update.index.value().unwrap().copied(),
+                            Some(Fr::from(proof.index()))
+                        );
+                        if let Some(prev) = prev {
+                            ctx.constrain_equal(prev.cell(), update.old_root.cell())?;
+                        }
+                        prev = Some(update.new_root);
+                    }
+                    let last_root = prev.unwrap();
+
+                    let membership = chip.verify_membership(ctx, &self.membership)?;
+                    ctx.constrain_equal(last_root.cell(), membership.root.cell())?;
+
+                    let non_membership = chip.verify_non_membership(ctx, &self.non_membership)?;
+                    ctx.constrain_equal(last_root.cell(), non_membership.root.cell())?;
+
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn merkle_chip() {
+        const K: u32 = 17;
+        let mut rng = rand::thread_rng();
+
+        for arity in [2, 3] {
+            let mut tree =
+                Tree::<Fr, RP>::new(NonZeroUsize::new(4).unwrap(), arity, Spec::new(10, 10))
+                    .unwrap();
+
+            let updates = (0..3)
+                .map(|_| (rng.gen::<u64>() % tree.leaves_count(), Fr::random(&mut rng)))
+                .collect::<Vec<_>>();
+            let proofs = tree.update_leaves(updates.iter().copied()).unwrap();
+
+            let (index, value) = *updates.last().unwrap();
+            let free = (0..tree.leaves_count())
+                .find(|index| updates.iter().all(|(used, _)| used != index))
+                .unwrap();
+
+            let circuit = TestCircuit {
+                arity,
+                updates: proofs,
+                membership: tree.prove_membership(index, value).unwrap(),
+                non_membership: tree.prove_non_membership(free).unwrap(),
+            };
+
+            MockProver::run(K, &circuit, vec![])
+                .unwrap()
+                .verify()
+                .unwrap();
+        }
+    }
+}
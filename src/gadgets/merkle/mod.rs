@@ -0,0 +1,34 @@
+//! Sparse merkle tree with configurable depth & arity
+//!
+//! - [`Tree`] is the off-circuit tree, which produces [`Proof`] for updates, membership &
+//!   non-membership
+//! - [`MerkleTreeChip`] verifies these proofs on-circuit
+//! - [`MerkleTreeUpdateCircuit`] is a [`crate::ivc::StepCircuit`] applying a batch of updates
+//!   per step, the batch ([`ProofBatch`]) is the witness of the step
+//!
+//! The hash function is any [`crate::poseidon::ROPair`], so the same tree can be used with
+//! different random oracles on- & off-circuit
+
+mod chip;
+mod off_circuit;
+mod step_circuit;
+
+pub use chip::{AssignedMembership, AssignedUpdate, MerkleTreeChip};
+pub use off_circuit::{hash_leaf, hash_node, Index, NodeUpdate, Proof, Tree, EMPTY_LEAF};
+pub use step_circuit::{MerkleTreeUpdateBatchCircuit, MerkleTreeUpdateCircuit, ProofBatch, ARITY};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("Arity of the tree must be at least two, but {arity} provided")]
+    WrongArity { arity: usize },
+    #[error("Tree with depth {depth} & arity {arity} has more leaves than fit into `u64`")]
+    TooDeep { depth: usize, arity: usize },
+    #[error("Leaf index {index} out of range, tree has {limit} leaves")]
+    IndexOutOfRange { index: u64, limit: u64 },
+    #[error("Leaf {index} doesn't contain the value")]
+    NotMember { index: u64 },
+    #[error("Leaf {index} is not empty")]
+    AlreadyMember { index: u64 },
+    #[error("Batch requires {expected} updates, but only {actual} provided")]
+    NotEnoughUpdates { expected: usize, actual: usize },
+}
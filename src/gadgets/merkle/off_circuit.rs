@@ -0,0 +1,595 @@
+use std::{collections::HashMap, fmt, num::NonZeroUsize};
+
+use tracing::*;
+
+use super::Error;
+use crate::{
+    ff::{FromUniformBytes, PrimeFieldBits},
+    poseidon::{ROPair, ROTrait},
+};
+
+/// Value of a leaf that was never updated
+///
+/// Non-membership of the index is proven by a path to this value
+pub const EMPTY_LEAF: u64 = 0;
+
+/// Position of the node in the tree
+///
+/// Level `0` are leaves, level `depth` is the root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Index {
+    pub level: usize,
+    pub index: u64,
+}
+
+impl Index {
+    /// Index of the parent node, for any arity the node is placed in parent
+    /// at position `index % arity`
+    pub fn parent(&self, arity: usize) -> Self {
+        Self {
+            level: self.level + 1,
+            index: self.index / arity as u64,
+        }
+    }
+
+    /// Position of this node among the children of its parent
+    pub fn position(&self, arity: usize) -> usize {
+        (self.index % arity as u64) as usize
+    }
+
+    /// Indexes of all nodes with the same parent (including this one) in order
+    pub fn children_of_parent(&self, arity: usize) -> impl Iterator<Item = Self> {
+        let first = self.index - self.position(arity) as u64;
+        let level = self.level;
+
+        (first..first + arity as u64).map(move |index| Self { level, index })
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}][{}]", self.level, self.index)
+    }
+}
+
+/// Change of one node in the path from leaf to root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeUpdate<F> {
+    /// Index of node in a level
+    pub index: u64,
+    /// Old value, before update
+    pub old: F,
+    /// New value, after update
+    pub new: F,
+    /// Other children of the parent node in order, without this node itself,
+    /// so `arity - 1` elements. Empty for root
+    pub siblings: Box<[F]>,
+}
+
+impl<F> NodeUpdate<F> {
+    pub fn map<T>(self, mut f: impl FnMut(F) -> T) -> NodeUpdate<T> {
+        NodeUpdate {
+            index: self.index,
+            old: f(self.old),
+            new: f(self.new),
+            siblings: self.siblings.into_vec().into_iter().map(f).collect(),
+        }
+    }
+
+    pub fn try_map<T, E>(self, mut f: impl FnMut(F) -> Result<T, E>) -> Result<NodeUpdate<T>, E> {
+        Ok(NodeUpdate {
+            index: self.index,
+            old: f(self.old)?,
+            new: f(self.new)?,
+            siblings: self
+                .siblings
+                .into_vec()
+                .into_iter()
+                .map(f)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<F: Copy> NodeUpdate<F> {
+    /// All children of the parent node in order, with `value` placed at position of this node
+    pub fn children(&self, arity: usize, value: F) -> impl '_ + Iterator<Item = F> {
+        let position = (self.index % arity as u64) as usize;
+
+        self.siblings[..position]
+            .iter()
+            .copied()
+            .chain([value])
+            .chain(self.siblings[position..].iter().copied())
+    }
+}
+
+/// Path from the leaf to the root
+///
+/// Proves the update of the leaf from `old` to `new` value. If these values are equal, then the
+/// proof is a membership proof (see [`Proof::is_read_only`]), if `old` leaf is
+/// [`EMPTY_LEAF`] it also proves non-membership of the index before the update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof<F> {
+    arity: usize,
+    /// `depth + 1` elements, first is leaf, last is root
+    path: Box<[NodeUpdate<F>]>,
+}
+
+impl<F> Proof<F> {
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// Index of the leaf
+    pub fn index(&self) -> u64 {
+        self.leaf().index
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &NodeUpdate<F>)> {
+        self.path.iter().enumerate().map(|(level, update)| {
+            (
+                Index {
+                    level,
+                    index: update.index,
+                },
+                update,
+            )
+        })
+    }
+
+    pub fn leaf(&self) -> &NodeUpdate<F> {
+        self.path.first().unwrap()
+    }
+
+    pub fn root(&self) -> &NodeUpdate<F> {
+        self.path.last().unwrap()
+    }
+
+    pub fn map<T>(self, mut f: impl FnMut(F) -> T) -> Proof<T> {
+        Proof {
+            arity: self.arity,
+            path: self
+                .path
+                .into_vec()
+                .into_iter()
+                .map(|update| update.map(&mut f))
+                .collect(),
+        }
+    }
+}
+
+impl<F: PartialEq> Proof<F> {
+    /// The proof does not change the tree
+    pub fn is_read_only(&self) -> bool {
+        self.path.iter().all(|update| update.old == update.new)
+    }
+}
+
+impl<F: PrimeFieldBits + FromUniformBytes<64>> Proof<F> {
+    /// Is the `old` leaf an [`EMPTY_LEAF`], i.e. proof of non-membership before update
+    pub fn is_old_leaf_empty(&self) -> bool {
+        self.leaf().old == F::from(EMPTY_LEAF)
+    }
+
+    /// Check that both `old` & `new` paths lead to their roots
+    pub fn verify<RP: ROPair<F>>(&self, args: &RP::Args) -> bool {
+        if self.root().index != 0 {
+            error!("root index is not zero: {}", self.root().index);
+            return false;
+        }
+
+        self.iter()
+            .zip(self.path.iter().skip(1))
+            .all(|((index, update), next)| {
+                let expected_index = index.parent(self.arity).index;
+                if next.index != expected_index {
+                    error!(
+                        "index of {index} parent not match {expected_index} != {}",
+                        next.index
+                    );
+                    return false;
+                }
+
+                if update.siblings.len() != self.arity - 1 {
+                    error!("siblings count at {index} not match arity");
+                    return false;
+                }
+
+                let old_next = hash_node::<F, RP>(args, update.children(self.arity, update.old));
+                if next.old != old_next {
+                    error!("`old` not match at {index}: {:?} != {old_next:?}", next.old);
+                    return false;
+                }
+
+                let new_next = hash_node::<F, RP>(args, update.children(self.arity, update.new));
+                if next.new != new_next {
+                    error!("`new` not match at {index}: {:?} != {new_next:?}", next.new);
+                    return false;
+                }
+
+                true
+            })
+    }
+}
+
+/// Hash of the node, by its children in order
+pub fn hash_node<F, RP>(args: &RP::Args, children: impl Iterator<Item = F>) -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F>,
+{
+    RP::OffCircuit::new(args.clone())
+        .absorb_field_iter(children)
+        .squeeze::<F>(NonZeroUsize::new(F::NUM_BITS as usize).unwrap())
+}
+
+/// Hash of the user data, which is placed into leaf
+pub fn hash_leaf<F, RP>(args: &RP::Args, value: F) -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F>,
+{
+    hash_node::<F, RP>(args, [value].into_iter())
+}
+
+/// Sparse merkle tree with configurable depth & arity
+///
+/// Only updated nodes are stored, all others have the default value of their level, which is
+/// computed from [`EMPTY_LEAF`]
+pub struct Tree<F, RP>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F>,
+{
+    arity: usize,
+    args: RP::Args,
+    filled_nodes: HashMap<Index, F>,
+    /// `depth + 1` elements, default value for each level
+    default_values: Box<[F]>,
+}
+
+impl<F, RP> Tree<F, RP>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F>,
+{
+    /// Create an empty tree with `arity.pow(depth)` leaves
+    pub fn new(depth: NonZeroUsize, arity: usize, args: RP::Args) -> Result<Self, Error> {
+        if arity < 2 {
+            return Err(Error::WrongArity { arity });
+        }
+
+        u32::try_from(depth.get())
+            .ok()
+            .and_then(|depth| (arity as u64).checked_pow(depth))
+            .ok_or(Error::TooDeep {
+                depth: depth.get(),
+                arity,
+            })?;
+
+        let mut default_values = vec![F::from(EMPTY_LEAF)];
+        for _ in 0..depth.get() {
+            let prev = *default_values.last().unwrap();
+            default_values.push(hash_node::<F, RP>(
+                &args,
+                std::iter::repeat(prev).take(arity),
+            ));
+        }
+
+        Ok(Self {
+            arity,
+            args,
+            filled_nodes: HashMap::new(),
+            default_values: default_values.into_boxed_slice(),
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.default_values.len() - 1
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn args(&self) -> &RP::Args {
+        &self.args
+    }
+
+    /// Count of leaves, all leaves indexes less than it
+    pub fn leaves_count(&self) -> u64 {
+        (self.arity as u64).pow(self.depth() as u32)
+    }
+
+    pub fn get_root(&self) -> &F {
+        self.get_node(Index {
+            level: self.depth(),
+            index: 0,
+        })
+    }
+
+    pub fn get_leaf(&self, index: u64) -> Result<&F, Error> {
+        self.check_index(index)?;
+        Ok(self.get_node(Index { level: 0, index }))
+    }
+
+    fn check_index(&self, index: u64) -> Result<(), Error> {
+        let limit = self.leaves_count();
+        if index < limit {
+            Ok(())
+        } else {
+            Err(Error::IndexOutOfRange { index, limit })
+        }
+    }
+
+    fn get_default_value(&self, level: usize) -> &F {
+        &self.default_values[level]
+    }
+
+    fn get_node(&self, index: Index) -> &F {
+        self.filled_nodes
+            .get(&index)
+            .unwrap_or_else(|| self.get_default_value(index.level))
+    }
+
+    fn siblings(&self, index: Index) -> Box<[F]> {
+        index
+            .children_of_parent(self.arity)
+            .filter(|sibling| sibling != &index)
+            .map(|sibling| *self.get_node(sibling))
+            .collect()
+    }
+
+    /// Set the leaf to `hash_leaf(value)` and return the proof of this update
+    pub fn update_leaf(&mut self, index: u64, value: F) -> Result<Proof<F>, Error> {
+        let leaf = hash_leaf::<F, RP>(&self.args, value);
+        self.set_leaf(index, leaf)
+    }
+
+    /// Set the leaf to [`EMPTY_LEAF`] and return the proof of this update
+    pub fn remove_leaf(&mut self, index: u64) -> Result<Proof<F>, Error> {
+        self.set_leaf(index, F::from(EMPTY_LEAF))
+    }
+
+    /// Apply updates one by one
+    ///
+    /// The proofs are chained: `new` root of each proof is the `old` root of the next one. All
+    /// indexes are checked before the first update, so the tree is unchanged on error
+    pub fn update_leaves(
+        &mut self,
+        updates: impl IntoIterator<Item = (u64, F)>,
+    ) -> Result<Box<[Proof<F>]>, Error> {
+        let updates = updates.into_iter().collect::<Vec<_>>();
+        updates
+            .iter()
+            .try_for_each(|(index, _)| self.check_index(*index))?;
+
+        updates
+            .into_iter()
+            .map(|(index, value)| self.update_leaf(index, value))
+            .collect()
+    }
+
+    /// Proof of the current value of the leaf, without changes in tree
+    ///
+    /// If the leaf is [`EMPTY_LEAF`] it's a non-membership proof
+    pub fn prove(&self, index: u64) -> Result<Proof<F>, Error> {
+        self.check_index(index)?;
+
+        let path = (0..=self.depth())
+            .scan(Index { level: 0, index }, |current, _| {
+                let value = *self.get_node(*current);
+                let update = NodeUpdate {
+                    index: current.index,
+                    old: value,
+                    new: value,
+                    siblings: if current.level == self.depth() {
+                        Box::new([])
+                    } else {
+                        self.siblings(*current)
+                    },
+                };
+                *current = current.parent(self.arity);
+                Some(update)
+            })
+            .collect();
+
+        Ok(Proof {
+            arity: self.arity,
+            path,
+        })
+    }
+
+    /// Proof that `value` is placed at `index`
+    pub fn prove_membership(&self, index: u64, value: F) -> Result<Proof<F>, Error> {
+        let proof = self.prove(index)?;
+
+        if proof.leaf().old == hash_leaf::<F, RP>(&self.args, value) {
+            Ok(proof)
+        } else {
+            Err(Error::NotMember { index })
+        }
+    }
+
+    /// Proof that nothing is placed at `index`
+    pub fn prove_non_membership(&self, index: u64) -> Result<Proof<F>, Error> {
+        let proof = self.prove(index)?;
+
+        if proof.is_old_leaf_empty() {
+            Ok(proof)
+        } else {
+            Err(Error::AlreadyMember { index })
+        }
+    }
+
+    #[instrument(skip(self, leaf))]
+    fn set_leaf(&mut self, index: u64, leaf: F) -> Result<Proof<F>, Error> {
+        self.check_index(index)?;
+
+        let mut current = Index { level: 0, index };
+        let mut new_value = leaf;
+        let mut path = Vec::with_capacity(self.depth() + 1);
+
+        loop {
+            let siblings = if current.level == self.depth() {
+                Box::new([]) as Box<[F]>
+            } else {
+                self.siblings(current)
+            };
+
+            let old_value = self
+                .filled_nodes
+                .insert(current, new_value)
+                .unwrap_or_else(|| *self.get_default_value(current.level));
+
+            debug!("update {current}: {old_value:?} -> {new_value:?}");
+
+            let update = NodeUpdate {
+                index: current.index,
+                old: old_value,
+                new: new_value,
+                siblings,
+            };
+
+            if current.level == self.depth() {
+                path.push(update);
+                break;
+            }
+
+            new_value = hash_node::<F, RP>(&self.args, update.children(self.arity, new_value));
+            path.push(update);
+            current = current.parent(self.arity);
+        }
+
+        Ok(Proof {
+            arity: self.arity,
+            path: path.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        ff::Field,
+        halo2curves::bn256::Fr,
+        poseidon::{PoseidonRO, Spec},
+    };
+
+    type RP = PoseidonRO<5, 4>;
+
+    fn tree(depth: usize, arity: usize) -> Tree<Fr, RP> {
+        Tree::new(NonZeroUsize::new(depth).unwrap(), arity, Spec::new(10, 10)).unwrap()
+    }
+
+    #[traced_test]
+    #[test]
+    fn update() {
+        let mut rng = rand::thread_rng();
+
+        for arity in [2, 3, 4] {
+            let mut tr = tree(8, arity);
+            let empty_root = *tr.get_root();
+
+            let pr1 = tr.update_leaf(3, Fr::random(&mut rng)).unwrap();
+            assert!(pr1.verify::<RP>(tr.args()));
+            assert!(pr1.is_old_leaf_empty());
+            assert_eq!(pr1.root().old, empty_root);
+            assert_eq!(&pr1.root().new, tr.get_root());
+
+            let pr2 = tr.update_leaf(3, Fr::random(&mut rng)).unwrap();
+            assert!(pr2.verify::<RP>(tr.args()));
+
+            pr1.path
+                .iter()
+                .zip(pr2.path.iter())
+                .for_each(|(upd1, upd2)| {
+                    assert_eq!(upd1.index, upd2.index);
+                    assert_eq!(upd1.new, upd2.old);
+                    assert_eq!(upd1.siblings, upd2.siblings);
+                });
+
+            let last = tr.leaves_count() - 1;
+            let pr3 = tr.update_leaf(last, Fr::random(&mut rng)).unwrap();
+            assert!(pr3.verify::<RP>(tr.args()));
+
+            let pr4 = tr.remove_leaf(last).unwrap();
+            assert!(pr4.verify::<RP>(tr.args()));
+            assert_eq!(&pr4.root().new, &pr3.root().old);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn batch_and_membership() {
+        let mut rng = rand::thread_rng();
+        let mut tr = tree(10, 4);
+
+        let updates = (0..5)
+            .map(|_| (rng.gen::<u64>() % tr.leaves_count(), Fr::random(&mut rng)))
+            .collect::<Vec<_>>();
+
+        let proofs = tr.update_leaves(updates.iter().copied()).unwrap();
+        for (prev, next) in proofs.iter().zip(proofs.iter().skip(1)) {
+            assert_eq!(prev.root().new, next.root().old);
+        }
+        assert_eq!(&proofs.last().unwrap().root().new, tr.get_root());
+
+        let (index, value) = *updates.last().unwrap();
+        let membership = tr.prove_membership(index, value).unwrap();
+        assert!(membership.is_read_only());
+        assert!(membership.verify::<RP>(tr.args()));
+        assert_eq!(&membership.root().old, tr.get_root());
+
+        assert_eq!(
+            tr.prove_non_membership(index),
+            Err(Error::AlreadyMember { index })
+        );
+
+        let free = (0..tr.leaves_count())
+            .find(|index| updates.iter().all(|(used, _)| used != index))
+            .unwrap();
+        let non_membership = tr.prove_non_membership(free).unwrap();
+        assert!(non_membership.verify::<RP>(tr.args()));
+        assert_eq!(
+            tr.prove_membership(free, value),
+            Err(Error::NotMember { index: free })
+        );
+    }
+
+    #[test]
+    fn wrong_params() {
+        assert_eq!(
+            Tree::<Fr, RP>::new(NonZeroUsize::new(4).unwrap(), 1, Spec::new(10, 10)).err(),
+            Some(Error::WrongArity { arity: 1 })
+        );
+        assert_eq!(
+            Tree::<Fr, RP>::new(NonZeroUsize::new(64).unwrap(), 2, Spec::new(10, 10)).err(),
+            Some(Error::TooDeep {
+                depth: 64,
+                arity: 2
+            })
+        );
+        assert_eq!(
+            tree(2, 2).update_leaf(4, Fr::ONE).err(),
+            Some(Error::IndexOutOfRange { index: 4, limit: 4 })
+        );
+
+        let mut tr = tree(2, 2);
+        let root = *tr.get_root();
+        assert_eq!(
+            tr.update_leaves([(0, Fr::ONE), (4, Fr::ONE)]).err(),
+            Some(Error::IndexOutOfRange { index: 4, limit: 4 })
+        );
+        assert_eq!(tr.get_root(), &root, "tree must be unchanged on error");
+    }
+}
@@ -0,0 +1,382 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error as Halo2PlonkError},
+};
+use rand::Rng;
+use tracing::*;
+
+use super::{chip::MerkleTreeChip, off_circuit::Proof, Error, Tree};
+use crate::{
+    ff::{FromUniformBytes, PrimeFieldBits},
+    ivc::{StepCircuit, SynthesisError},
+    main_gate::{AssignedValue, MainGate, MainGateConfig, RegionCtx},
+    poseidon::ROPair,
+};
+
+/// Input and output size of [`MerkleTreeUpdateCircuit`], the root of the tree
+pub const ARITY: usize = 1;
+
+/// Proofs of one batch of updates, chained by roots, see [`Tree::update_leaves`]
+///
+/// [`StepCircuit::StepWitness`] of [`MerkleTreeUpdateCircuit`]
+pub type ProofBatch<F> = Box<[Proof<F>]>;
+
+/// Step circuit applying `batch_size` updates of the [`Tree`] per step
+///
+/// `z_i` is the root of the tree before the step, `z_{i+1}` is the root after all updates of
+/// the step. The circuit itself only knows params of the tree, the updates of each step are
+/// passed as [`ProofBatch`] witness, prepared off-circuit by
+/// [`MerkleTreeUpdateCircuit::update_leaves`] on the caller's tree.
+///
+/// The default (empty) witness is replaced by read-only proofs of the initial tree, so the step
+/// keeps the initial root & has the same shape as any other step
+pub struct MerkleTreeUpdateCircuit<F, RP, const T: usize>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    args: RP::Args,
+    arity: usize,
+    batch_size: usize,
+    default_batch: ProofBatch<F>,
+}
+
+impl<F, RP, const T: usize> MerkleTreeUpdateCircuit<F, RP, T>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    /// `tree` is the initial tree, its root is `z_0` of IVC
+    pub fn new(tree: &Tree<F, RP>, batch_size: usize) -> Self {
+        let leaves_count = tree.leaves_count();
+
+        Self {
+            args: tree.args().clone(),
+            arity: tree.arity(),
+            batch_size,
+            default_batch: (0..batch_size as u64)
+                .map(|index| tree.prove(index % leaves_count))
+                .collect::<Result<_, _>>()
+                .expect("indexes are less than limit"),
+        }
+    }
+
+    /// Create circuit for `tree` & `batches_count` batches of random updates of it, one per step
+    pub fn new_with_random_updates(
+        tree: &mut Tree<F, RP>,
+        rng: &mut impl Rng,
+        batch_size: usize,
+        batches_count: usize,
+    ) -> (Self, Vec<ProofBatch<F>>) {
+        let self_ = Self::new(tree, batch_size);
+
+        let batches = (0..batches_count)
+            .map(|_| self_.random_update_leaves(tree, &mut *rng))
+            .collect();
+
+        (self_, batches)
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn random_update_leaves(
+        &self,
+        tree: &mut Tree<F, RP>,
+        mut rng: &mut impl Rng,
+    ) -> ProofBatch<F> {
+        let limit = tree.leaves_count();
+
+        // 'allow' is necessary, because otherwise the closure captures rnd and we have to copy it
+        #[allow(clippy::needless_borrows_for_generic_args)]
+        self.update_leaves(
+            tree,
+            std::iter::repeat_with(move || (rng.gen::<u64>() % limit, F::random(&mut rng)))
+                .take(self.batch_size),
+        )
+        .expect("indexes are less than limit")
+    }
+
+    /// Apply the next batch of updates to `tree`, `batch_size` updates are taken from the
+    /// iterator
+    ///
+    /// Return the witness of the step proving this batch. The tree is unchanged on error
+    pub fn update_leaves(
+        &self,
+        tree: &mut Tree<F, RP>,
+        updates: impl IntoIterator<Item = (u64, F)>,
+    ) -> Result<ProofBatch<F>, Error> {
+        let updates = updates
+            .into_iter()
+            .take(self.batch_size)
+            .collect::<Vec<_>>();
+
+        if updates.len() != self.batch_size {
+            return Err(Error::NotEnoughUpdates {
+                expected: self.batch_size,
+                actual: updates.len(),
+            });
+        }
+
+        tree.update_leaves(updates)
+    }
+
+    /// Batch proven by the step with `witness`, see [`MerkleTreeUpdateCircuit`] about the empty
+    /// one
+    fn batch<'w>(&'w self, witness: &'w [Proof<F>]) -> &'w [Proof<F>] {
+        if witness.is_empty() {
+            &self.default_batch
+        } else {
+            witness
+        }
+    }
+
+    fn synthesize_batch(
+        &self,
+        config: MainGateConfig<T>,
+        region: &mut RegionCtx<'_, F>,
+        z_i: Option<&AssignedValue<F>>,
+        witness: &[Proof<F>],
+    ) -> Result<Option<AssignedValue<F>>, Halo2PlonkError> {
+        let batch = self.batch(witness);
+        if batch.len() != self.batch_size {
+            error!(
+                "batch of {} proofs, but {} expected",
+                batch.len(),
+                self.batch_size
+            );
+            return Err(Halo2PlonkError::Synthesis);
+        }
+
+        let chip = MerkleTreeChip::<F, RP, T>::new(config, self.args.clone(), self.arity);
+
+        let mut prev = z_i.cloned();
+        for proof in batch.iter() {
+            let update = chip.verify_update(region, proof)?;
+
+            if let Some(prev) = prev {
+                region.constrain_equal(prev.cell(), update.old_root.cell())?;
+            }
+            prev = Some(update.new_root);
+        }
+        debug!("offset = {}", region.offset());
+
+        Ok(prev)
+    }
+
+    /// Standalone circuit, proving `batch` without IVC
+    pub fn batch_circuit<'c>(
+        &'c self,
+        batch: &'c [Proof<F>],
+    ) -> MerkleTreeUpdateBatchCircuit<'c, F, RP, T> {
+        MerkleTreeUpdateBatchCircuit {
+            circuit: self,
+            batch,
+        }
+    }
+}
+
+impl<F, RP, const T: usize> StepCircuit<ARITY, F> for MerkleTreeUpdateCircuit<F, RP, T>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    type Config = MainGateConfig<T>;
+    type StepWitness = ProofBatch<F>;
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        MainGate::configure(cs)
+    }
+
+    fn process_step(
+        &self,
+        _z_i: &[F; ARITY],
+        witness: &Self::StepWitness,
+        _k_table_size: u32,
+    ) -> Result<[F; ARITY], SynthesisError> {
+        self.batch(witness)
+            .last()
+            .map(|proof| [proof.root().new])
+            .ok_or(SynthesisError::Halo2(Halo2PlonkError::Synthesis))
+    }
+
+    fn synthesize_step(
+        &self,
+        config: Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; ARITY],
+        witness: &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
+        layouter
+            .assign_region(
+                || "merkle_tree_update",
+                |region| {
+                    let mut region = RegionCtx::new(region, 0);
+
+                    let z_out = self
+                        .synthesize_batch(config.clone(), &mut region, Some(&z_i[0]), witness)?
+                        .expect("`z_i` is always present");
+
+                    Ok([z_out])
+                },
+            )
+            .map_err(SynthesisError::Halo2)
+    }
+}
+
+/// [`MerkleTreeUpdateCircuit`] with one batch to prove, see
+/// [`MerkleTreeUpdateCircuit::batch_circuit`]
+pub struct MerkleTreeUpdateBatchCircuit<'c, F, RP, const T: usize>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    circuit: &'c MerkleTreeUpdateCircuit<F, RP, T>,
+    batch: &'c [Proof<F>],
+}
+
+impl<F, RP, const T: usize> Circuit<F> for MerkleTreeUpdateBatchCircuit<'_, F, RP, T>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    RP: ROPair<F, Config = MainGateConfig<T>>,
+{
+    type Config = MainGateConfig<T>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    /// Circuit of the same shape: read-only proofs of the initial tree
+    fn without_witnesses(&self) -> Self {
+        Self {
+            circuit: self.circuit,
+            batch: &[],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MainGate::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Halo2PlonkError> {
+        layouter.assign_region(
+            || "merkle_tree_update",
+            |region| {
+                let mut region = RegionCtx::new(region, 0);
+                self.circuit
+                    .synthesize_batch(config.clone(), &mut region, None, self.batch)
+                    .map(|_| ())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use halo2_proofs::dev::MockProver;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        ff::Field,
+        halo2curves::bn256::Fr,
+        poseidon::{PoseidonRO, Spec},
+    };
+
+    const T: usize = 5;
+    type RP = PoseidonRO<T, 4>;
+
+    fn new_tree(depth: usize, arity: usize) -> Tree<Fr, RP> {
+        Tree::new(NonZeroUsize::new(depth).unwrap(), arity, Spec::new(10, 10)).unwrap()
+    }
+
+    #[traced_test]
+    #[test]
+    fn process_step_matches_synthesis() {
+        let mut tree = new_tree(8, 4);
+        let initial_root = *tree.get_root();
+
+        let (circuit, batches) = MerkleTreeUpdateCircuit::new_with_random_updates(
+            &mut tree,
+            &mut rand::thread_rng(),
+            2,
+            2,
+        );
+
+        let mut z_i = [initial_root];
+        for batch in batches.iter() {
+            assert_eq!(batch.first().unwrap().root().old, z_i[0]);
+
+            let z_out = circuit.process_step(&z_i, batch, 17).unwrap();
+            assert_eq!(z_out, [batch.last().unwrap().root().new]);
+
+            MockProver::run(17, &circuit.batch_circuit(batch), vec![])
+                .unwrap()
+                .verify()
+                .unwrap();
+
+            z_i = z_out;
+        }
+
+        assert_eq!(&z_i[0], tree.get_root());
+    }
+
+    #[traced_test]
+    #[test]
+    fn default_witness() {
+        let mut tree = new_tree(8, 4);
+        tree.update_leaf(1, Fr::ONE).unwrap();
+        let root = *tree.get_root();
+
+        let circuit = MerkleTreeUpdateCircuit::new(&tree, 2);
+        let witness = ProofBatch::<Fr>::default();
+
+        assert_eq!(circuit.process_step(&[root], &witness, 17).unwrap(), [root]);
+
+        MockProver::run(17, &circuit.batch_circuit(&witness), vec![])
+            .unwrap()
+            .verify()
+            .unwrap();
+    }
+
+    #[test]
+    fn not_enough_updates() {
+        let mut tree = new_tree(4, 2);
+        let root = *tree.get_root();
+
+        let circuit = MerkleTreeUpdateCircuit::new(&tree, 3);
+        assert_eq!(
+            circuit.update_leaves(&mut tree, [(0, Fr::ONE), (1, Fr::ONE)]),
+            Err(Error::NotEnoughUpdates {
+                expected: 3,
+                actual: 2
+            })
+        );
+
+        assert_eq!(tree.get_root(), &root, "tree must be unchanged");
+    }
+
+    #[traced_test]
+    #[test]
+    fn without_witnesses() {
+        let mut tree = new_tree(8, 4);
+        let (circuit, batches) = MerkleTreeUpdateCircuit::new_with_random_updates(
+            &mut tree,
+            &mut rand::thread_rng(),
+            2,
+            1,
+        );
+
+        let empty = circuit.batch_circuit(&batches[0]).without_witnesses();
+        assert!(empty.batch.is_empty());
+
+        MockProver::run(17, &empty, vec![])
+            .unwrap()
+            .verify()
+            .unwrap();
+    }
+}
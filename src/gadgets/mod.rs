@@ -1,5 +1,10 @@
 pub mod ecc;
+pub mod keccak;
+pub mod merkle;
 pub mod nonnative;
+pub mod range;
+pub mod sha256;
+pub mod signature;
 pub(crate) mod util;
 
 pub mod poseidon_step_circuit;
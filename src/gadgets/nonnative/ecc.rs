@@ -0,0 +1,475 @@
+//! Arithmetic of points of the curve `C`, whose base field is foreign for the circuit field
+//! `F`, e.g. secp256k1 inside of the BN254 scalar field
+//!
+//! Coordinates are [`AssignedNonNative`] elements of [`NonNativeField`]. All formulas are
+//! checked by witnessing the result and checking the defining equation, so each operation
+//! costs a few nonnative multiplications & comparisons.
+//!
+//! The infinity point is not representable, formulas are incomplete:
+//! - [`NonNativeEccChip::add_unequal`] requires `p.x != q.x`, which is enforced
+//! - [`NonNativeEccChip::double`] requires `p.y != 0`, which always holds for curves of odd
+//!   order, such as secp256k1
+//!
+//! [`NonNativeEccChip::scalar_mul`] starts from the auxiliary point with unknown discrete log,
+//! so these exceptional cases occur only with negligible probability.
+
+use std::num::NonZeroUsize;
+
+use halo2_proofs::circuit::Value;
+
+use super::{
+    bn::big_uint_mul_mod_chip::{Error, MAIN_GATE_T},
+    field::{AssignedNonNative, NonNativeField},
+};
+use crate::{
+    ff::{Field, PrimeField, PrimeFieldBits},
+    gadgets::range::RangeConfig,
+    group::{prime::PrimeCurveAffine, Curve},
+    halo2curves::CurveAffine,
+    main_gate::{AssignedBit, MainGateConfig, RegionCtx},
+};
+
+/// Point of `C` with nonnative coordinates, always on the curve & not the infinity
+#[derive(Clone, Debug)]
+pub struct AssignedNonNativePoint<F: PrimeField, C: CurveAffine> {
+    x: AssignedNonNative<F, C::Base>,
+    y: AssignedNonNative<F, C::Base>,
+}
+
+impl<F: PrimeField, C: CurveAffine> AssignedNonNativePoint<F, C> {
+    pub fn x(&self) -> &AssignedNonNative<F, C::Base> {
+        &self.x
+    }
+
+    pub fn y(&self) -> &AssignedNonNative<F, C::Base> {
+        &self.y
+    }
+
+    pub fn value(&self) -> Value<C> {
+        self.x
+            .value()
+            .zip(self.y.value())
+            .map(|(x, y)| C::from_xy(x, y).unwrap_or(C::identity()))
+    }
+}
+
+/// Point with unknown discrete log, used as the initial accumulator of
+/// [`NonNativeEccChip::scalar_mul`]: the first point with the smallest positive `x`
+pub fn aux_point<C: CurveAffine>() -> C {
+    let mut x = C::Base::ONE;
+    loop {
+        let y_square = x.square() * x + C::a() * x + C::b();
+        if let Some(y) = Option::<C::Base>::from(y_square.sqrt()) {
+            return C::from_xy(x, y).unwrap();
+        }
+        x += C::Base::ONE;
+    }
+}
+
+/// Chip of the nonnative curve `C` arithmetic, see [module-level](self) docs
+#[derive(Debug)]
+pub struct NonNativeEccChip<F: PrimeFieldBits, C: CurveAffine> {
+    base: NonNativeField<F, C::Base>,
+}
+
+impl<F: PrimeFieldBits, C: CurveAffine> NonNativeEccChip<F, C> {
+    pub fn new(
+        config: MainGateConfig<MAIN_GATE_T>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: NonNativeField::new(config, limb_width, limbs_count)?,
+        })
+    }
+
+    /// Same as [`NonNativeEccChip::new`], but limbs are range-checked by lookups, see
+    /// [`NonNativeField::new_with_range`]
+    pub fn new_with_range(
+        config: RangeConfig<MAIN_GATE_T>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: NonNativeField::new_with_range(config, limb_width, limbs_count)?,
+        })
+    }
+
+    /// Chip of the base field of `C`
+    pub fn base_field(&self) -> &NonNativeField<F, C::Base> {
+        &self.base
+    }
+
+    /// Assign `point` & check it's on the curve
+    pub fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: Value<C>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let coordinates = point.map(|point| {
+            let coordinates = point.coordinates().unwrap();
+            (*coordinates.x(), *coordinates.y())
+        });
+
+        let point = AssignedNonNativePoint {
+            x: self.base.assign(ctx, coordinates.map(|(x, _)| x))?,
+            y: self.base.assign(ctx, coordinates.map(|(_, y)| y))?,
+        };
+        self.assert_on_curve(ctx, &point)?;
+
+        Ok(point)
+    }
+
+    /// Assign `point` with coordinates fixed by constraints
+    ///
+    /// # Panics
+    /// If `point` is the infinity
+    pub fn assign_constant_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: C,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let coordinates = point.coordinates().unwrap();
+
+        Ok(AssignedNonNativePoint {
+            x: self.base.assign_constant(ctx, *coordinates.x())?,
+            y: self.base.assign_constant(ctx, *coordinates.y())?,
+        })
+    }
+
+    /// Check `y^2 = x^3 + a * x + b`
+    pub fn assert_on_curve(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+    ) -> Result<(), Error> {
+        let y_square = self.base.square(ctx, &p.y)?;
+
+        let x_square = self.base.square(ctx, &p.x)?;
+        let x_cube = self.base.mul(ctx, &x_square, &p.x)?;
+        let b = self.base.assign_constant(ctx, C::b())?;
+        let mut rhs = self.base.add(ctx, &x_cube, &b)?;
+
+        if !C::a().is_zero_vartime() {
+            let a = self.base.assign_constant(ctx, C::a())?;
+            let ax = self.base.mul(ctx, &a, &p.x)?;
+            rhs = self.base.add(ctx, &rhs, &ax)?;
+        }
+
+        self.base.assert_equal(ctx, &y_square, &rhs)
+    }
+
+    pub fn assert_equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+        q: &AssignedNonNativePoint<F, C>,
+    ) -> Result<(), Error> {
+        self.base.assert_equal(ctx, &p.x, &q.x)?;
+        self.base.assert_equal(ctx, &p.y, &q.y)
+    }
+
+    pub fn negate(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        Ok(AssignedNonNativePoint {
+            x: p.x.clone(),
+            y: self.base.neg(ctx, &p.y)?,
+        })
+    }
+
+    /// Returns `p` if `cond` is `1`, `q` otherwise
+    pub fn conditional_select(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+        q: &AssignedNonNativePoint<F, C>,
+        cond: &AssignedBit<F>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        Ok(AssignedNonNativePoint {
+            x: self.base.conditional_select(ctx, &p.x, &q.x, cond)?,
+            y: self.base.conditional_select(ctx, &p.y, &q.y, cond)?,
+        })
+    }
+
+    /// Given `lambda` & `x` of the result, check & calculate `y` of the result
+    ///
+    /// Checks `lambda^2 = p.x + q.x + r.x` & `lambda * (p.x - r.x) = p.y + r.y`
+    fn finish_with_lambda(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        lambda: &AssignedNonNative<F, C::Base>,
+        p: &AssignedNonNativePoint<F, C>,
+        q_x: &AssignedNonNative<F, C::Base>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let x = self.base.assign(
+            ctx,
+            lambda
+                .value()
+                .zip(p.x.value())
+                .zip(q_x.value())
+                .map(|((lambda, p_x), q_x)| lambda.square() - p_x - q_x),
+        )?;
+        let y = self.base.assign(
+            ctx,
+            lambda
+                .value()
+                .zip(p.x.value())
+                .zip(p.y.value())
+                .zip(x.value())
+                .map(|(((lambda, p_x), p_y), x)| lambda * (p_x - x) - p_y),
+        )?;
+
+        let lambda_square = self.base.square(ctx, lambda)?;
+        let x_sum = self.base.add(ctx, &p.x, q_x)?;
+        let x_sum = self.base.add(ctx, &x_sum, &x)?;
+        self.base.assert_equal(ctx, &lambda_square, &x_sum)?;
+
+        let lambda_p_x = self.base.mul(ctx, lambda, &p.x)?;
+        let lambda_x = self.base.mul(ctx, lambda, &x)?;
+        let rhs = self.base.add(ctx, &lambda_x, &p.y)?;
+        let rhs = self.base.add(ctx, &rhs, &y)?;
+        self.base.assert_equal(ctx, &lambda_p_x, &rhs)?;
+
+        Ok(AssignedNonNativePoint { x, y })
+    }
+
+    /// `p + q`, if `p.x == q.x` the circuit is unsatisfied
+    pub fn add_unequal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+        q: &AssignedNonNativePoint<F, C>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let x_diff = q.x.value() - p.x.value();
+        let y_diff = q.y.value() - p.y.value();
+
+        // `t * (q.x - p.x) = 1`, so `p.x != q.x`
+        let t = self.base.assign(
+            ctx,
+            x_diff.map(|x_diff| x_diff.invert().unwrap_or(C::Base::ZERO)),
+        )?;
+        let t_q_x = self.base.mul(ctx, &t, &q.x)?;
+        let t_p_x = self.base.mul(ctx, &t, &p.x)?;
+        let one = self.base.assign_constant(ctx, C::Base::ONE)?;
+        let t_p_x_one = self.base.add(ctx, &t_p_x, &one)?;
+        self.base.assert_equal(ctx, &t_q_x, &t_p_x_one)?;
+
+        // `lambda * (q.x - p.x) = q.y - p.y`
+        let lambda = self.base.assign(
+            ctx,
+            y_diff
+                .zip(x_diff)
+                .map(|(y_diff, x_diff)| y_diff * x_diff.invert().unwrap_or(C::Base::ZERO)),
+        )?;
+        let lambda_q_x = self.base.mul(ctx, &lambda, &q.x)?;
+        let lambda_p_x = self.base.mul(ctx, &lambda, &p.x)?;
+        let lhs = self.base.add(ctx, &lambda_q_x, &p.y)?;
+        let rhs = self.base.add(ctx, &lambda_p_x, &q.y)?;
+        self.base.assert_equal(ctx, &lhs, &rhs)?;
+
+        self.finish_with_lambda(ctx, &lambda, p, &q.x)
+    }
+
+    /// `2 * p`
+    pub fn double(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        // `lambda * 2 * p.y = 3 * p.x^2 + a`
+        let lambda = self.base.assign(
+            ctx,
+            p.x.value().zip(p.y.value()).map(|(x, y)| {
+                (x.square() * C::Base::from(3) + C::a())
+                    * y.double().invert().unwrap_or(C::Base::ZERO)
+            }),
+        )?;
+
+        let lambda_y = self.base.mul(ctx, &lambda, &p.y)?;
+        let lhs = self.base.add(ctx, &lambda_y, &lambda_y)?;
+
+        let x_square = self.base.square(ctx, &p.x)?;
+        let x_square_double = self.base.add(ctx, &x_square, &x_square)?;
+        let mut rhs = self.base.add(ctx, &x_square_double, &x_square)?;
+        if !C::a().is_zero_vartime() {
+            let a = self.base.assign_constant(ctx, C::a())?;
+            rhs = self.base.add(ctx, &rhs, &a)?;
+        }
+        self.base.assert_equal(ctx, &lhs, &rhs)?;
+
+        self.finish_with_lambda(ctx, &lambda, p, &p.x)
+    }
+
+    /// `scalar * p`, where `scalar` is given by little-endian bits
+    ///
+    /// Double-and-add from the most significant bit, starting from [`aux_point`] `A`, which
+    /// is subtracted in the end as `2^n * A`. The scalar must not be zero (or a multiple of the
+    /// order of `p`), since the infinity is not representable
+    pub fn scalar_mul(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+        scalar_bits: &[AssignedBit<F>],
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let aux = aux_point::<C>();
+        let mut acc = self.assign_constant_point(ctx, aux)?;
+
+        for bit in scalar_bits.iter().rev() {
+            acc = self.double(ctx, &acc)?;
+            let sum = self.add_unequal(ctx, &acc, p)?;
+            acc = self.conditional_select(ctx, &sum, &acc, bit)?;
+        }
+
+        self.remove_aux(ctx, &acc, aux, scalar_bits.len())
+    }
+
+    /// `p_scalar * p + q_scalar * q`, where scalars are given by little-endian bits of the
+    /// same length
+    ///
+    /// Same as [`NonNativeEccChip::scalar_mul`], but doublings are shared between both
+    /// scalars (Shamir's trick), so it's almost twice cheaper than two multiplications.
+    /// Additionally requires `p != ±q`
+    pub fn double_scalar_mul(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        p: &AssignedNonNativePoint<F, C>,
+        p_scalar_bits: &[AssignedBit<F>],
+        q: &AssignedNonNativePoint<F, C>,
+        q_scalar_bits: &[AssignedBit<F>],
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        assert_eq!(p_scalar_bits.len(), q_scalar_bits.len());
+        let main_gate = self.base.main_gate();
+
+        let p_plus_q = self.add_unequal(ctx, p, q)?;
+
+        let aux = aux_point::<C>();
+        let mut acc = self.assign_constant_point(ctx, aux)?;
+
+        for (p_bit, q_bit) in p_scalar_bits.iter().zip(q_scalar_bits).rev() {
+            acc = self.double(ctx, &acc)?;
+
+            // `p + q`, `p` or `q`, the last one is also used if both bits are zero, but then
+            // the sum is dropped
+            let with_p = self.conditional_select(ctx, &p_plus_q, p, q_bit)?;
+            let addend = self.conditional_select(ctx, &with_p, q, p_bit)?;
+            let sum = self.add_unequal(ctx, &acc, &addend)?;
+
+            // `p_bit | q_bit = p_bit + q_bit - p_bit * q_bit`
+            let bits_sum = main_gate.add(ctx, p_bit, q_bit)?;
+            let bits_product = main_gate.mul(ctx, p_bit, q_bit)?;
+            let any_bit = main_gate.sub(ctx, &bits_sum, &bits_product)?;
+
+            acc = self.conditional_select(ctx, &sum, &acc, &any_bit)?;
+        }
+
+        self.remove_aux(ctx, &acc, aux, p_scalar_bits.len())
+    }
+
+    /// `acc - 2^doublings * aux`
+    fn remove_aux(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        acc: &AssignedNonNativePoint<F, C>,
+        aux: C,
+        doublings: usize,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        let offset = (aux * C::ScalarExt::from(2).pow_vartime([doublings as u64])).to_affine();
+        let neg_offset = self.assign_constant_point(ctx, -offset)?;
+
+        self.add_unequal(ctx, acc, &neg_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error as Halo2Error},
+    };
+
+    use super::*;
+    use crate::{
+        halo2curves::{bn256::Fr, secp256k1::Secp256k1Affine},
+        main_gate::MainGate,
+        run_mock_prover_test,
+    };
+
+    const LIMB_WIDTH: NonZeroUsize = match NonZeroUsize::new(64) {
+        Some(width) => width,
+        None => unreachable!(),
+    };
+    const LIMBS_COUNT: NonZeroUsize = match NonZeroUsize::new(4) {
+        Some(count) => count,
+        None => unreachable!(),
+    };
+
+    type Chip = NonNativeEccChip<Fr, Secp256k1Affine>;
+
+    struct TestCircuit {
+        p: Secp256k1Affine,
+        q: Secp256k1Affine,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = MainGateConfig<MAIN_GATE_T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MainGate::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Halo2Error> {
+            let chip = Chip::new(config, LIMB_WIDTH, LIMBS_COUNT).unwrap();
+
+            layouter.assign_region(
+                || "nonnative ecc",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+                    let p = chip.assign_point(&mut ctx, Value::known(self.p)).unwrap();
+                    let q = chip.assign_point(&mut ctx, Value::known(self.q)).unwrap();
+
+                    let sum = chip.add_unequal(&mut ctx, &p, &q).unwrap();
+                    sum.value()
+                        .assert_if_known(|sum| *sum == (self.p + self.q).to_affine());
+                    let expected = chip
+                        .assign_constant_point(&mut ctx, (self.p + self.q).to_affine())
+                        .unwrap();
+                    chip.assert_equal(&mut ctx, &sum, &expected).unwrap();
+
+                    let doubled = chip.double(&mut ctx, &p).unwrap();
+                    doubled
+                        .value()
+                        .assert_if_known(|doubled| *doubled == (self.p + self.p).to_affine());
+                    chip.assert_on_curve(&mut ctx, &doubled).unwrap();
+
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn add_double() {
+        let p = Secp256k1Affine::generator();
+        let q = (p * <Secp256k1Affine as CurveAffine>::ScalarExt::from(7)).to_affine();
+
+        run_mock_prover_test!(17, TestCircuit { p, q }, Vec::<Vec<Fr>>::new());
+    }
+
+    #[test]
+    fn aux_point_on_curve() {
+        let aux = aux_point::<Secp256k1Affine>();
+        assert!(bool::from(aux.is_on_curve()));
+    }
+}
@@ -0,0 +1,761 @@
+//! Arithmetic of the foreign field `FF` inside of the circuit over `F`
+//!
+//! Elements are represented by `limbs_count` limbs of `limb_width` bits, see
+//! [`AssignedNonNative`]. [`NonNativeField`] is built on top of [`BigUintMulModChip`], which
+//! checks `lhs * rhs = q * m + r` & `val = q * m + r` over integers, and adds what makes it a
+//! field:
+//! - all witnessed limbs (input, quotient & remainder) are range-checked to `limb_width` bits,
+//!   by bits decomposition or, if the chip is created by [`NonNativeField::new_with_range`],
+//!   by lookups of [`RangeChip`]
+//! - [`NonNativeField::reduce`] also checks the remainder is less than the modulus, so reduced
+//!   elements have unique representation and can be compared limb by limb
+//!
+//! Reduction is lazy: [`NonNativeField::add`] just adds limbs, tracking the maximum value of
+//! the limb. Elements are reduced only when required, e.g. before multiplication or
+//! comparison, or when limbs grow too big. [`NonNativeField::assert_equal`] doesn't need the
+//! unique representation: limbs of any representatives being equal means congruence, so only
+//! [`NonNativeField::is_equal`] pays for the comparison with the modulus.
+
+use std::{marker::PhantomData, num::NonZeroUsize};
+
+use halo2_proofs::circuit::{Chip, Value};
+use num_bigint::BigUint as BigUintRaw;
+use num_traits::Zero;
+
+use super::bn::{
+    big_uint::{self, BigUint},
+    big_uint_mul_mod_chip::{BigUintMulModChip, Error, OverflowingBigUint, MAIN_GATE_T},
+};
+use crate::{
+    ff::{Field, PrimeField, PrimeFieldBits},
+    gadgets::range::{RangeChip, RangeConfig},
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx},
+    util::modulus,
+};
+
+/// How many bits of additions are allowed on top of `limb_width` before the element is
+/// reduced by [`NonNativeField::add`], i.e. up to `2^8` additions in a row
+const MAX_OVERFLOW_BITS: usize = 8;
+
+/// Element of `FF` assigned as limbs in the circuit over `F`
+///
+/// Limbs may overflow `limb_width` after lazy additions, `max_word` is the upper bound of each
+/// limb. `reduced` elements are checked to be less than the modulus
+#[derive(Clone, Debug)]
+pub struct AssignedNonNative<F: PrimeField, FF: PrimeField> {
+    limbs: OverflowingBigUint<F>,
+    limb_width: NonZeroUsize,
+    reduced: bool,
+    _p: PhantomData<FF>,
+}
+
+impl<F: PrimeField, FF: PrimeField> AssignedNonNative<F, FF> {
+    pub fn limbs(&self) -> &[AssignedValue<F>] {
+        &self.limbs.cells
+    }
+
+    /// Value of the element, reduced by the modulus of `FF`
+    pub fn value(&self) -> Value<FF> {
+        self.limbs
+            .cells
+            .iter()
+            .rev()
+            .fold(Value::known(BigUintRaw::zero()), |acc, limb| {
+                acc.zip(limb.value())
+                    .map(|(acc, limb)| (acc << self.limb_width.get()) + big_uint::f_to_nat(limb))
+            })
+            .map(|value| {
+                big_uint::nat_to_f(&(value % modulus::<FF>())).expect("reduced by modulus")
+            })
+    }
+
+    fn is_normalized(&self) -> bool {
+        self.limbs.max_word == max_limb::<F>(self.limb_width)
+    }
+}
+
+fn max_limb<F: PrimeField>(limb_width: NonZeroUsize) -> F {
+    big_uint::nat_to_f(&big_uint::get_big_int_with_n_ones(limb_width.get())).unwrap_or_default()
+}
+
+/// Chip of the nonnative field `FF` arithmetic, see [module-level](self) docs
+#[derive(Debug)]
+pub struct NonNativeField<F: PrimeFieldBits, FF: PrimeField> {
+    bn_chip: BigUintMulModChip<F>,
+    main_gate: MainGate<F, MAIN_GATE_T>,
+    range: Option<RangeChip<F, MAIN_GATE_T>>,
+    modulus: BigUint<F>,
+    limb_width: NonZeroUsize,
+    limbs_count: NonZeroUsize,
+    _p: PhantomData<FF>,
+}
+
+impl<F: PrimeFieldBits, FF: PrimeField> NonNativeField<F, FF> {
+    /// # Panics
+    /// - if `limb_width * limbs_count` is not enough to represent `FF`
+    /// - if `limb_width` is too big to multiply limbs without overflow of `F`
+    pub fn new(
+        config: MainGateConfig<MAIN_GATE_T>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<Self, Error> {
+        assert!(limb_width.get() * limbs_count.get() >= FF::NUM_BITS as usize);
+        assert!(2 * limb_width.get() + MAX_OVERFLOW_BITS < F::CAPACITY as usize);
+
+        Ok(Self {
+            modulus: BigUint::from_biguint(&modulus::<FF>(), limb_width, limbs_count)?,
+            bn_chip: BigUintMulModChip::new(config.clone(), limb_width, limbs_count),
+            main_gate: MainGate::new(config),
+            range: None,
+            limb_width,
+            limbs_count,
+            _p: PhantomData,
+        })
+    }
+
+    /// Same as [`NonNativeField::new`], but limbs are range-checked by lookups, which is
+    /// several times cheaper for wide limbs. The table must be loaded by [`RangeChip::load`]
+    pub fn new_with_range(
+        config: RangeConfig<MAIN_GATE_T>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<Self, Error> {
+        let range = RangeChip::new(config);
+        let main_gate_config = range.main_gate().config().clone();
+
+        Ok(Self {
+            range: Some(range),
+            ..Self::new(main_gate_config, limb_width, limbs_count)?
+        })
+    }
+
+    pub fn main_gate(&self) -> &MainGate<F, MAIN_GATE_T> {
+        &self.main_gate
+    }
+
+    fn limbs_of(&self, value: &FF) -> Vec<F> {
+        BigUint::from_different_field(value, self.limb_width, self.limbs_count)
+            .expect("limbs are enough for `FF`, checked by `new`")
+            .limbs()
+            .to_vec()
+    }
+
+    fn normalized(&self, limbs: Vec<AssignedValue<F>>) -> AssignedNonNative<F, FF> {
+        AssignedNonNative {
+            limbs: OverflowingBigUint::new(limbs, self.limb_width),
+            limb_width: self.limb_width,
+            reduced: false,
+            _p: PhantomData,
+        }
+    }
+
+    fn reduced(&self, limbs: Vec<AssignedValue<F>>) -> AssignedNonNative<F, FF> {
+        AssignedNonNative {
+            reduced: true,
+            ..self.normalized(limbs)
+        }
+    }
+
+    fn range_check_limbs(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        limbs: &[AssignedValue<F>],
+    ) -> Result<(), Error> {
+        for limb in limbs {
+            match &self.range {
+                Some(range) => {
+                    range.range_check(ctx, limb, self.limb_width.get())?;
+                }
+                None => {
+                    self.main_gate
+                        .decompose_bits(ctx, limb, self.limb_width.get())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check limbs represent an integer less than the modulus, limbs are expected to be
+    /// range-checked
+    ///
+    /// Calculates `m - 1 - value` with borrows, where each limb of the difference is
+    /// range-checked and the last borrow is zero
+    fn assert_less_than_modulus(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        limbs: &[AssignedValue<F>],
+    ) -> Result<(), Error> {
+        let bound = BigUint::<F>::from_biguint(
+            &(modulus::<FF>() - 1u8),
+            self.limb_width,
+            self.limbs_count,
+        )?;
+        let shift = F::from(2).pow_vartime([self.limb_width.get() as u64]);
+
+        let mut borrow = Option::<AssignedBit<F>>::None;
+        for (limb, bound_limb) in limbs.iter().zip(bound.limbs()) {
+            let borrow_in = borrow
+                .as_ref()
+                .map(|borrow| borrow.value().copied())
+                .unwrap_or(Value::known(F::ZERO));
+
+            let borrow_out_value = limb.value().zip(borrow_in).map(|(limb, borrow_in)| {
+                let subtrahend = big_uint::f_to_nat(limb) + big_uint::f_to_nat(&borrow_in);
+                F::from((big_uint::f_to_nat(bound_limb) < subtrahend) as u64)
+            });
+            let borrow_out = self.main_gate.assign_bit(ctx, borrow_out_value)?;
+
+            // diff = bound_limb - limb - borrow_in + borrow_out * 2^limb_width
+            let mut terms = vec![(limb.clone(), -F::ONE), (borrow_out.clone(), shift)];
+            if let Some(borrow_in) = borrow {
+                terms.push((borrow_in, -F::ONE));
+            }
+            let diff = self
+                .main_gate
+                .linear_combination(ctx, &terms, *bound_limb)?;
+            self.range_check_limbs(ctx, &[diff])?;
+
+            borrow = Some(borrow_out);
+        }
+
+        if let Some(borrow) = borrow {
+            self.main_gate.assert_equal_const(ctx, borrow, F::ZERO)?;
+        }
+
+        Ok(())
+    }
+
+    /// Assign `value` as a reduced element
+    pub fn assign(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: Value<FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let limbs = value.map(|value| self.limbs_of(&value));
+
+        let limbs = (0..self.limbs_count.get())
+            .map(|i| {
+                self.main_gate
+                    .assign_value(ctx, limbs.as_ref().map(|limbs| limbs[i]))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.range_check_limbs(ctx, &limbs)?;
+        self.assert_less_than_modulus(ctx, &limbs)?;
+
+        Ok(self.reduced(limbs))
+    }
+
+    /// Assign `value` as a reduced element, limbs are fixed by constraints
+    pub fn assign_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: FF,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let limbs = self
+            .limbs_of(&value)
+            .into_iter()
+            .map(|limb| {
+                let cell = self.main_gate.assign_value(ctx, Value::known(limb))?;
+                self.main_gate.assert_equal_const(ctx, cell.clone(), limb)?;
+                Ok(cell)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(self.reduced(limbs))
+    }
+
+    /// Interpret already assigned `limbs` as an element, e.g. limbs of an element of another
+    /// field with the same limbs layout
+    ///
+    /// Limbs are range-checked, but the value may be greater than the modulus
+    pub fn assign_from_limbs(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        limbs: &[AssignedValue<F>],
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        if limbs.len() > self.limbs_count.get() {
+            return Err(big_uint::Error::LimbLimitReached {
+                limit: self.limbs_count,
+                actual: limbs.len(),
+            }
+            .into());
+        }
+
+        self.range_check_limbs(ctx, limbs)?;
+
+        Ok(self.normalized(limbs.to_vec()))
+    }
+
+    /// Little-endian bits of the reduced `a`, [`PrimeField::NUM_BITS`] of `FF` in total
+    pub fn to_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        let reduced = self.reduce(ctx, a)?;
+
+        let mut bits = Vec::with_capacity(self.limb_width.get() * self.limbs_count.get());
+        for limb in reduced.limbs() {
+            bits.extend(
+                self.main_gate
+                    .decompose_bits(ctx, limb, self.limb_width.get())?,
+            );
+        }
+        // Bits above `NUM_BITS` are zero, since reduced value is less than the modulus
+        bits.truncate(FF::NUM_BITS as usize);
+
+        Ok(bits)
+    }
+
+    /// Returns `a` if `cond` is `1`, `b` otherwise
+    pub fn conditional_select(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+        cond: &AssignedBit<F>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let a = self.normalize(ctx, a)?;
+        let b = self.normalize(ctx, b)?;
+
+        let limbs = a
+            .limbs()
+            .iter()
+            .zip(b.limbs())
+            .map(|(a, b)| self.main_gate.conditional_select(ctx, a, b, cond))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(AssignedNonNative {
+            reduced: a.reduced && b.reduced,
+            ..self.normalized(limbs)
+        })
+    }
+
+    /// Reduce `a` by the modulus without checking the result is less than the modulus, so the
+    /// result is only normalized: all limbs fit into `limb_width`
+    fn normalize(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        if a.is_normalized() {
+            return Ok(a.clone());
+        }
+
+        self.representative(ctx, a)
+    }
+
+    /// Reduce `a` into the unique representation, less than the modulus
+    pub fn reduce(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        if a.reduced {
+            return Ok(a.clone());
+        }
+
+        let representative = self.representative(ctx, a)?;
+        self.assert_less_than_modulus(ctx, representative.limbs())?;
+
+        Ok(self.reduced(representative.limbs.cells))
+    }
+
+    /// Normalized element congruent to `a`, limbs of representatives of congruent elements
+    /// are equal for the honest prover
+    ///
+    /// It's `a` itself, if `a` is reduced, and `a mod m` otherwise, but without the check
+    /// that the remainder is less than the modulus
+    fn representative(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        if a.reduced {
+            return Ok(a.clone());
+        }
+
+        let result = self.bn_chip.red_mod(ctx, a.limbs.clone(), &self.modulus)?;
+        self.range_check_limbs(ctx, &result.quotient)?;
+        self.range_check_limbs(ctx, &result.remainder)?;
+
+        Ok(self.normalized(result.remainder))
+    }
+
+    /// `a + b` without reduction
+    pub fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let limit = big_uint::get_big_int_with_n_ones(self.limb_width.get() + MAX_OVERFLOW_BITS);
+        let fits = |a: &AssignedNonNative<F, FF>, b: &AssignedNonNative<F, FF>| {
+            big_uint::f_to_nat(&(a.limbs.max_word + b.limbs.max_word)) <= limit
+        };
+
+        let (a, b) = if fits(a, b) {
+            (a.clone(), b.clone())
+        } else {
+            let a = self.normalize(ctx, a)?;
+            let b = self.normalize(ctx, b)?;
+            (a, b)
+        };
+
+        let max_word = a.limbs.max_word + b.limbs.max_word;
+        let sum = self.bn_chip.assign_sum(ctx, &a.limbs, &b.limbs.cells)?;
+
+        Ok(AssignedNonNative {
+            limbs: OverflowingBigUint {
+                cells: sum.res.cells,
+                max_word,
+            },
+            limb_width: self.limb_width,
+            reduced: false,
+            _p: PhantomData,
+        })
+    }
+
+    /// `a - b`, the result is witnessed as reduced element `d` with `d + b == a` check
+    pub fn sub(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let diff = self.assign(ctx, a.value() - b.value())?;
+
+        let sum = self.add(ctx, &diff, b)?;
+        self.assert_equal(ctx, &sum, a)?;
+
+        Ok(diff)
+    }
+
+    pub fn neg(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let zero = self.assign_constant(ctx, FF::ZERO)?;
+        self.sub(ctx, &zero, a)
+    }
+
+    /// `a * b`, the result is normalized, but not necessarily reduced
+    pub fn mul(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let a = self.normalize(ctx, a)?;
+        let b = self.normalize(ctx, b)?;
+
+        let result = self
+            .bn_chip
+            .mult_mod(ctx, a.limbs(), b.limbs(), &self.modulus)?;
+        self.range_check_limbs(ctx, &result.quotient)?;
+        self.range_check_limbs(ctx, &result.remainder)?;
+
+        Ok(self.normalized(result.remainder))
+    }
+
+    pub fn square(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        self.mul(ctx, a, a)
+    }
+
+    /// `a / b`, the result is witnessed as reduced element `c` with `b * c == a` check
+    ///
+    /// If `b` is zero, the circuit is unsatisfied
+    pub fn div(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let quotient = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| a * b.invert().unwrap_or(FF::ZERO));
+        let quotient = self.assign(ctx, quotient)?;
+
+        let product = self.mul(ctx, b, &quotient)?;
+        self.assert_equal(ctx, &product, a)?;
+
+        Ok(quotient)
+    }
+
+    /// `a^-1`, if `a` is zero, the circuit is unsatisfied
+    pub fn invert(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedNonNative<F, FF>, Error> {
+        let one = self.assign_constant(ctx, FF::ONE)?;
+        self.div(ctx, &one, a)
+    }
+
+    /// Check `a == b` in `FF`, see [module-level](self) docs
+    pub fn assert_equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<(), Error> {
+        let a = self.representative(ctx, a)?;
+        let b = self.representative(ctx, b)?;
+
+        for (lhs, rhs) in a.limbs().iter().zip(b.limbs()) {
+            ctx.constrain_equal(lhs.cell(), rhs.cell())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `1` if `a == b` in `FF`, `0` otherwise
+    pub fn is_equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedNonNative<F, FF>,
+        b: &AssignedNonNative<F, FF>,
+    ) -> Result<AssignedBit<F>, Error> {
+        let a = self.reduce(ctx, a)?;
+        let b = self.reduce(ctx, b)?;
+
+        let mut result = Option::<AssignedBit<F>>::None;
+        for (lhs, rhs) in a.limbs().iter().zip(b.limbs()) {
+            let is_equal = self.main_gate.is_equal_term(ctx, lhs, rhs)?;
+            result = Some(match result {
+                Some(result) => self.main_gate.mul(ctx, &result, &is_equal)?,
+                None => is_equal,
+            });
+        }
+
+        Ok(result.expect("`limbs_count` is non zero"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error as Halo2Error},
+    };
+
+    use super::*;
+    use crate::{
+        halo2curves::{bn256::Fr, secp256k1::Fp},
+        run_mock_prover_test,
+    };
+
+    const K: u32 = 16;
+    /// Limbs are range-checked by lookups in all tests, except [`not_reduced_assign`]
+    const RANGE_LIMB_BITS: usize = 8;
+    const LIMB_WIDTH: NonZeroUsize = match NonZeroUsize::new(64) {
+        Some(width) => width,
+        None => unreachable!(),
+    };
+    const LIMBS_COUNT: NonZeroUsize = match NonZeroUsize::new(4) {
+        Some(count) => count,
+        None => unreachable!(),
+    };
+
+    type Chip = NonNativeField<Fr, Fp>;
+
+    /// Check over the assigned `lhs` & `rhs`
+    type Case = fn(
+        &Chip,
+        &mut RegionCtx<'_, Fr>,
+        AssignedNonNative<Fr, Fp>,
+        AssignedNonNative<Fr, Fp>,
+    ) -> Result<(), Error>;
+
+    struct TestCircuit {
+        lhs: Fp,
+        rhs: Fp,
+        case: Case,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = RangeConfig<MAIN_GATE_T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let main_gate = MainGate::configure(meta);
+            RangeChip::configure(meta, main_gate, RANGE_LIMB_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Halo2Error> {
+            RangeChip::new(config.clone()).load(&mut layouter)?;
+            let chip = Chip::new_with_range(config, LIMB_WIDTH, LIMBS_COUNT).unwrap();
+
+            layouter.assign_region(
+                || "nonnative field",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+                    let lhs = chip.assign(&mut ctx, Value::known(self.lhs)).unwrap();
+                    let rhs = chip.assign(&mut ctx, Value::known(self.rhs)).unwrap();
+                    (self.case)(&chip, &mut ctx, lhs, rhs).unwrap();
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn run(lhs: Fp, rhs: Fp, case: Case) {
+        run_mock_prover_test!(K, TestCircuit { lhs, rhs, case }, Vec::<Vec<Fr>>::new());
+    }
+
+    fn check(
+        chip: &Chip,
+        ctx: &mut RegionCtx<'_, Fr>,
+        actual: &AssignedNonNative<Fr, Fp>,
+        expected: Fp,
+    ) -> Result<(), Error> {
+        actual.value().assert_if_known(|actual| *actual == expected);
+        let expected = chip.assign_constant(ctx, expected)?;
+        chip.assert_equal(ctx, actual, &expected)
+    }
+
+    fn operands() -> (Fp, Fp) {
+        // Both close to the modulus, so results overflow it
+        (-Fp::from(3), -Fp::from(u64::MAX))
+    }
+
+    #[test]
+    fn add() {
+        let (lhs, rhs) = operands();
+        run(lhs, rhs, |chip, ctx, lhs, rhs| {
+            let sum = chip.add(ctx, &lhs, &rhs)?;
+            let sum = chip.add(ctx, &sum, &sum)?;
+            let (a, b) = operands();
+            check(chip, ctx, &sum, (a + b).double())
+        });
+    }
+
+    #[test]
+    fn sub_neg() {
+        let (lhs, rhs) = operands();
+        run(rhs, lhs, |chip, ctx, lhs, rhs| {
+            let diff = chip.sub(ctx, &lhs, &rhs)?;
+            check(chip, ctx, &diff, Fp::from(3) - Fp::from(u64::MAX))?;
+
+            let neg = chip.neg(ctx, &rhs)?;
+            check(chip, ctx, &neg, Fp::from(3))
+        });
+    }
+
+    #[test]
+    fn mul_after_add() {
+        let (lhs, rhs) = operands();
+        run(lhs, rhs, |chip, ctx, lhs, rhs| {
+            let sum = chip.add(ctx, &lhs, &rhs)?;
+            let product = chip.mul(ctx, &sum, &rhs)?;
+            let (a, b) = operands();
+            check(chip, ctx, &product, (a + b) * b)?;
+
+            let square = chip.square(ctx, &lhs)?;
+            check(chip, ctx, &square, Fp::from(9))
+        });
+    }
+
+    #[test]
+    fn invert_div() {
+        let (lhs, rhs) = operands();
+        run(lhs, rhs, |chip, ctx, lhs, rhs| {
+            let (a, b) = operands();
+
+            let inv = chip.invert(ctx, &lhs)?;
+            check(chip, ctx, &inv, a.invert().unwrap())?;
+
+            let quotient = chip.div(ctx, &lhs, &rhs)?;
+            check(chip, ctx, &quotient, a * b.invert().unwrap())
+        });
+    }
+
+    #[test]
+    fn is_equal() {
+        let (lhs, rhs) = operands();
+        run(lhs, rhs, |chip, ctx, lhs, rhs| {
+            let not_equal = chip.is_equal(ctx, &lhs, &rhs)?;
+            not_equal.value().assert_if_known(|flag| **flag == Fr::ZERO);
+            chip.main_gate()
+                .assert_equal_const(ctx, not_equal, Fr::ZERO)?;
+
+            // Not reduced sum overflows the modulus, so limbs differ, but equal in the field
+            let sum = chip.add(ctx, &lhs, &rhs)?;
+            let (a, b) = operands();
+            let expected = chip.assign_constant(ctx, a + b)?;
+            let equal = chip.is_equal(ctx, &sum, &expected)?;
+            equal.value().assert_if_known(|flag| **flag == Fr::ONE);
+            chip.main_gate().assert_equal_const(ctx, equal, Fr::ONE)?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn not_reduced_assign() {
+        // Limbs of the modulus itself must be rejected by `assert_less_than_modulus`, limbs
+        // are range-checked by bits here
+        let modulus = BigUint::<Fr>::from_biguint(&modulus::<Fp>(), LIMB_WIDTH, LIMBS_COUNT)
+            .unwrap()
+            .limbs()
+            .to_vec();
+
+        let circuit = NotReduced { limbs: modulus };
+        let prover = halo2_proofs::dev::MockProver::run(K, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    struct NotReduced {
+        limbs: Vec<Fr>,
+    }
+
+    impl Circuit<Fr> for NotReduced {
+        type Config = MainGateConfig<MAIN_GATE_T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MainGate::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Halo2Error> {
+            let chip = Chip::new(config, LIMB_WIDTH, LIMBS_COUNT).unwrap();
+
+            layouter.assign_region(
+                || "not reduced",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+                    let limbs = self
+                        .limbs
+                        .iter()
+                        .map(|limb| chip.main_gate().assign_value(&mut ctx, Value::known(*limb)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    chip.range_check_limbs(&mut ctx, &limbs).unwrap();
+                    chip.assert_less_than_modulus(&mut ctx, &limbs).unwrap();
+                    Ok(())
+                },
+            )
+        }
+    }
+}
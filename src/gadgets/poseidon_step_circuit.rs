@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, num::NonZeroUsize};
 
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter},
@@ -8,9 +8,9 @@ use tracing::*;
 
 use crate::{
     ff::{FromUniformBytes, PrimeFieldBits},
-    ivc::{StepCircuit, SynthesisError},
+    ivc::{step_circuit::NativeStep, StepCircuit, SynthesisError},
     main_gate::{MainGate, MainGateConfig, RegionCtx, WrapValue},
-    poseidon::{poseidon_circuit::PoseidonChip, Spec},
+    poseidon::{poseidon_circuit::PoseidonChip, PoseidonHash, Spec},
 };
 
 /// Input and output size for `StepCircuit` within each step
@@ -56,6 +56,7 @@ impl<F: PrimeFieldBits> TestPoseidonCircuit<F> {
 
 impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPoseidonCircuit<F> {
     type Config = TestPoseidonCircuitConfig;
+    type StepWitness = ();
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let pconfig = MainGate::configure(meta);
@@ -67,6 +68,7 @@ impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPos
         config: Self::Config,
         layouter: &mut impl Layouter<F>,
         z_in: &[AssignedCell<F, F>; ARITY],
+        _witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
         let spec = CircuitPoseidonSpec::<F>::new(R_F1, R_P1);
 
@@ -115,3 +117,43 @@ impl<F: PrimeFieldBits + FromUniformBytes<64>> StepCircuit<ARITY, F> for TestPos
             })
     }
 }
+
+impl<F: PrimeFieldBits + FromUniformBytes<64>> NativeStep<ARITY, F, ()> for TestPoseidonCircuit<F> {
+    fn native_step(&self, z_i: &[F; ARITY], _witness: &()) -> [F; ARITY] {
+        let num_bits = NonZeroUsize::new(F::NUM_BITS as usize).unwrap();
+
+        (0..=self.repeat_count).fold(*z_i, |z_i, _| {
+            [PoseidonHash::digest(
+                CircuitPoseidonSpec::<F>::new(R_F1, R_P1),
+                &z_i,
+                num_bits,
+            )]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use rand_core::OsRng;
+
+    use super::*;
+    use crate::util::mock_prover::check_native_step;
+
+    const K: u32 = 12;
+
+    #[test]
+    fn native() {
+        for repeat_count in [0, 2] {
+            check_native_step(
+                K,
+                &TestPoseidonCircuit::<Fr>::new(repeat_count),
+                &(),
+                vec![],
+                2,
+                OsRng,
+            )
+            .unwrap();
+        }
+    }
+}
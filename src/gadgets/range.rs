@@ -0,0 +1,452 @@
+//! Range checks & comparisons on top of [`MainGate`]
+//!
+//! - [`RangeChip::range_check`] decomposes value into limbs of `limb_bits`, each checked by
+//!   lookup into the `0..2^limb_bits` table
+//! - [`RangeChip::range_check_bits`] decomposes value into bits, without lookups
+//! - [`RangeChip::less_than`] & [`RangeChip::is_in_range`] return comparison flags
+//! - [`RangeChip::to_canonical_bits`] decomposes value into all
+//!   [`crate::ff::PrimeField::NUM_BITS`] bits and enforces that they represent an integer less
+//!   than the modulus
+
+use halo2_proofs::{
+    circuit::{Chip, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, TableColumn},
+    poly::Rotation,
+};
+
+use crate::{
+    ff::{Field, PrimeFieldBits},
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx},
+};
+
+#[derive(Clone, Debug)]
+pub struct RangeConfig<const T: usize> {
+    main_gate: MainGateConfig<T>,
+    limb: Column<Advice>,
+    q_lookup: Column<Fixed>,
+    table: TableColumn,
+    limb_bits: usize,
+}
+
+impl<const T: usize> RangeConfig<T> {
+    pub fn limb_bits(&self) -> usize {
+        self.limb_bits
+    }
+}
+
+/// Range checks & comparisons, see [module-level](self) docs
+///
+/// Table must be loaded once per circuit with [`RangeChip::load`], it takes `2^limb_bits` rows
+#[derive(Debug)]
+pub struct RangeChip<F: PrimeFieldBits, const T: usize> {
+    config: RangeConfig<T>,
+    main_gate: MainGate<F, T>,
+}
+
+impl<F: PrimeFieldBits, const T: usize> Chip<F> for RangeChip<F, T> {
+    type Config = RangeConfig<T>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeFieldBits, const T: usize> RangeChip<F, T> {
+    pub fn new(config: RangeConfig<T>) -> Self {
+        Self {
+            main_gate: MainGate::new(config.main_gate.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        main_gate: MainGateConfig<T>,
+        limb_bits: usize,
+    ) -> RangeConfig<T> {
+        assert!(limb_bits > 0 && limb_bits < F::NUM_BITS as usize);
+
+        let limb = meta.advice_column();
+        meta.enable_equality(limb);
+        let q_lookup = meta.fixed_column();
+        let table = meta.lookup_table_column();
+
+        meta.lookup("range limb", |meta| {
+            let limb = meta.query_advice(limb, Rotation::cur());
+            let q_lookup = meta.query_fixed(q_lookup, Rotation::cur());
+
+            vec![(q_lookup * limb, table)]
+        });
+
+        RangeConfig {
+            main_gate,
+            limb,
+            q_lookup,
+            table,
+            limb_bits,
+        }
+    }
+
+    pub fn main_gate(&self) -> &MainGate<F, T> {
+        &self.main_gate
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range table",
+            |mut table| {
+                for value in 0..(1u64 << self.config.limb_bits) {
+                    table.assign_cell(
+                        || "limb",
+                        self.config.table,
+                        value as usize,
+                        || Value::known(F::from(value)),
+                    )?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the copy of `value` on the row with lookup
+    fn lookup(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        ctx.assign_fixed(|| "q_lookup", self.config.q_lookup, F::ONE)?;
+        let limb = ctx.assign_advice_from(|| "limb", self.config.limb, value)?;
+        ctx.next();
+        Ok(limb)
+    }
+
+    /// Check that `value < 2^bits` by lookups of limbs
+    ///
+    /// The last limb, if it's shorter than `limb_bits`, is also looked up shifted to the top
+    /// of the limb. Return the little-endian limbs
+    pub fn range_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert!(
+            bits < F::NUM_BITS as usize,
+            "range must not wrap around modulus"
+        );
+        let limb_bits = self.config.limb_bits;
+        let mg = &self.main_gate;
+
+        let le_bits = value.value().map(|value| {
+            value
+                .to_le_bits()
+                .into_iter()
+                .take(bits)
+                .collect::<Vec<_>>()
+        });
+
+        let limbs = (0..bits)
+            .step_by(limb_bits)
+            .map(|from| {
+                let limb = le_bits.as_ref().map(|le_bits| {
+                    le_bits[from..bits.min(from + limb_bits)]
+                        .iter()
+                        .rev()
+                        .fold(F::ZERO, |acc, bit| acc.double() + F::from(*bit as u64))
+                });
+
+                let limb = mg.assign_value(ctx, limb)?;
+                self.lookup(ctx, &limb)?;
+                Ok(limb)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if let Some(last) = limbs.last().filter(|_| bits % limb_bits != 0) {
+            let shift = F::from(2).pow([(limb_bits - bits % limb_bits) as u64]);
+            let shifted = mg.mul_by_const(ctx, last, shift)?;
+            self.lookup(ctx, &shifted)?;
+        }
+
+        let base = F::from(2).pow([limb_bits as u64]);
+        let terms = limbs
+            .iter()
+            .cloned()
+            .scan(F::ONE, |weight, limb| {
+                let term = (limb, *weight);
+                *weight *= base;
+                Some(term)
+            })
+            .collect::<Vec<_>>();
+
+        let num = mg.linear_combination(ctx, &terms, F::ZERO)?;
+        ctx.constrain_equal(num.cell(), value.cell())?;
+
+        Ok(limbs)
+    }
+
+    /// Check that `value < 2^bits` by decomposition into bits, return little-endian bits
+    pub fn range_check_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        assert!(
+            bits < F::NUM_BITS as usize,
+            "range must not wrap around modulus"
+        );
+        self.main_gate.decompose_bits(ctx, value, bits)
+    }
+
+    /// Return `1 - top`, where `d = low + 2^bits * top` with `low < 2^bits` & boolean `top`,
+    /// i.e. the flag of `d < 2^bits` for `d < 2^(bits + 1)`
+    fn is_below_power_of_two(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        d: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedBit<F>, Error> {
+        let mg = &self.main_gate;
+        let power = F::from(2).pow([bits as u64]);
+
+        let top_value = d
+            .value()
+            .map(|d| F::from(d.to_le_bits().get(bits).is_some_and(|bit| *bit) as u64));
+        let top = mg.assign_bit(ctx, top_value)?;
+        let low = mg.assign_value(ctx, d.value().copied() - top_value * Value::known(power))?;
+        self.range_check(ctx, &low, bits)?;
+
+        let composed =
+            mg.linear_combination(ctx, &[(low, F::ONE), (top.clone(), power)], F::ZERO)?;
+        ctx.constrain_equal(composed.cell(), d.cell())?;
+
+        mg.linear_combination(ctx, &[(top, -F::ONE)], F::ONE)
+    }
+
+    /// Return the flag of `a < b`
+    ///
+    /// Both `a` & `b` must be less than `2^bits`, use [`RangeChip::range_check`] if they are
+    /// not constrained already
+    pub fn less_than(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedBit<F>, Error> {
+        assert!(bits + 1 < F::NUM_BITS as usize);
+
+        // `a - b + 2^bits` is in `[0, 2^(bits + 1))` & less than `2^bits` iff `a < b`
+        let d = self.main_gate.linear_combination(
+            ctx,
+            &[(a.clone(), F::ONE), (b.clone(), -F::ONE)],
+            F::from(2).pow([bits as u64]),
+        )?;
+
+        self.is_below_power_of_two(ctx, &d, bits)
+    }
+
+    /// Return the flag of `value < 2^bits`
+    ///
+    /// `value` must be less than `2^value_bits`
+    pub fn is_in_range(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        bits: usize,
+        value_bits: usize,
+    ) -> Result<AssignedBit<F>, Error> {
+        assert!(bits <= value_bits && value_bits + 1 < F::NUM_BITS as usize);
+
+        // `value + 2^value_bits - 2^bits` is less than `2^value_bits` iff `value < 2^bits`
+        let d = self.main_gate.linear_combination(
+            ctx,
+            &[(value.clone(), F::ONE)],
+            F::from(2).pow([value_bits as u64]) - F::from(2).pow([bits as u64]),
+        )?;
+
+        self.is_below_power_of_two(ctx, &d, value_bits)
+    }
+
+    /// Decompose `value` into [`crate::ff::PrimeField::NUM_BITS`] little-endian bits, which
+    /// represent an integer less than the modulus
+    ///
+    /// Bits are compared with `modulus - 1` from the most significant one: `eq` is the flag of
+    /// all higher bits being equal, `gt` is the flag of some higher bit being greater, at the
+    /// end `gt` must be zero
+    pub fn to_canonical_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        let mg = &self.main_gate;
+        let bits = mg.decompose_bits(ctx, value, F::NUM_BITS as usize)?;
+        let max = (-F::ONE).to_le_bits();
+
+        // `None` is used for constants: `eq = 1` & `gt = 0`
+        let mut eq = Option::<AssignedBit<F>>::None;
+        let mut gt = Option::<AssignedBit<F>>::None;
+
+        for (bit, max_bit) in bits.iter().zip(max.iter()).rev() {
+            let eq_and_bit = match &eq {
+                None => bit.clone(),
+                Some(eq) => mg.mul(ctx, eq, bit)?,
+            };
+
+            if *max_bit {
+                eq = Some(eq_and_bit);
+            } else {
+                gt = Some(match &gt {
+                    None => eq_and_bit.clone(),
+                    Some(gt) => mg.add(ctx, gt, &eq_and_bit)?,
+                });
+                eq = Some(match &eq {
+                    None => mg.linear_combination(ctx, &[(eq_and_bit, -F::ONE)], F::ONE)?,
+                    Some(eq) => mg.sub(ctx, eq, &eq_and_bit)?,
+                });
+            }
+        }
+
+        let gt = gt.expect("`modulus - 1` has zero bits");
+        mg.assert_equal_const(ctx, gt, F::ZERO)?;
+
+        Ok(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{ff::PrimeField, halo2curves::bn256::Fr};
+
+    const T: usize = 2;
+    const K: u32 = 14;
+    const LIMB_BITS: usize = 8;
+
+    type Case = fn(&RangeChip<Fr, T>, &mut RegionCtx<'_, Fr>) -> Result<(), Error>;
+
+    struct TestCircuit {
+        case: Case,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = RangeConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            todo!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let main_gate = MainGate::configure(meta);
+            RangeChip::configure(meta, main_gate, LIMB_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = RangeChip::new(config);
+            chip.load(&mut layouter)?;
+
+            layouter.assign_region(
+                || "range",
+                |region| (self.case)(&chip, &mut RegionCtx::new(region, 0)),
+            )
+        }
+    }
+
+    fn run(case: Case) -> bool {
+        MockProver::run(K, &TestCircuit { case }, vec![])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    fn assign(
+        chip: &RangeChip<Fr, T>,
+        ctx: &mut RegionCtx<'_, Fr>,
+        value: u64,
+    ) -> AssignedValue<Fr> {
+        chip.main_gate()
+            .assign_value(ctx, Value::known(Fr::from(value)))
+            .unwrap()
+    }
+
+    fn assert_flag(flag: &AssignedBit<Fr>, expected: bool) {
+        assert_eq!(
+            flag.value().unwrap().copied(),
+            Some(Fr::from(expected as u64))
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn range_check() {
+        assert!(run(|chip, ctx| {
+            let value = assign(chip, ctx, 0xabcd);
+            assert_eq!(chip.range_check(ctx, &value, 16)?.len(), 2);
+            chip.range_check(ctx, &value, 20)?;
+            chip.range_check_bits(ctx, &value, 16)?;
+
+            let value = assign(chip, ctx, 0x1fff);
+            chip.range_check(ctx, &value, 13)?;
+            chip.range_check_bits(ctx, &value, 13)?;
+            Ok(())
+        }));
+
+        assert!(!run(|chip, ctx| {
+            let value = assign(chip, ctx, 256);
+            chip.range_check(ctx, &value, 8).map(|_| ())
+        }));
+        assert!(!run(|chip, ctx| {
+            let value = assign(chip, ctx, 0x2000);
+            chip.range_check(ctx, &value, 13).map(|_| ())
+        }));
+        assert!(!run(|chip, ctx| {
+            let value = assign(chip, ctx, 0x2000);
+            chip.range_check_bits(ctx, &value, 13).map(|_| ())
+        }));
+    }
+
+    #[traced_test]
+    #[test]
+    fn comparison() {
+        assert!(run(|chip, ctx| {
+            for (a, b) in [(3, 5), (5, 3), (5, 5), (0, 0xffff), (0xffff, 0)] {
+                let (a_cell, b_cell) = (assign(chip, ctx, a), assign(chip, ctx, b));
+                assert_flag(&chip.less_than(ctx, &a_cell, &b_cell, 16)?, a < b);
+            }
+
+            for value in [0, 255, 256, 300, 0xffff] {
+                let cell = assign(chip, ctx, value);
+                assert_flag(&chip.is_in_range(ctx, &cell, 8, 16)?, value < 256);
+            }
+
+            Ok(())
+        }));
+    }
+
+    #[traced_test]
+    #[test]
+    fn canonical_bits() {
+        assert!(run(|chip, ctx| {
+            for value in [Fr::ZERO, Fr::ONE, -Fr::ONE, Fr::from(u64::MAX)] {
+                let cell = chip.main_gate().assign_value(ctx, Value::known(value))?;
+                let bits = chip.to_canonical_bits(ctx, &cell)?;
+                assert_eq!(bits.len(), Fr::NUM_BITS as usize);
+            }
+            Ok(())
+        }));
+    }
+}
@@ -0,0 +1,365 @@
+//! The [SHA-256] hash function.
+//!
+//! - [`Table16Chip`] implements compression & message schedule with a `2^16` spread lookup table,
+//!   so it requires `k >= 17`
+//! - [`Sha256`] is a gadget over any [`Sha256Instructions`], with standard padding & any
+//!   count of blocks
+//! - [`Sha256StepCircuit`] hashes one block per step, for hash-chain proofs
+//! - [`off_circuit`] is the reference implementation, used to calculate the step output
+//!
+//! [SHA-256]: https://tools.ietf.org/html/rfc6234
+
+use std::{convert::TryInto, fmt, mem};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Chip, Layouter},
+    plonk::Error,
+};
+
+pub mod off_circuit;
+mod step_circuit;
+mod table16;
+
+pub use step_circuit::Sha256StepCircuit;
+pub use table16::{BlockWord, Table16Chip, Table16Config};
+
+/// The size of a SHA-256 block, in 32-bit words.
+pub const BLOCK_SIZE: usize = 16;
+/// The size of a SHA-256 digest, in 32-bit words.
+pub const DIGEST_SIZE: usize = 8;
+
+/// The set of circuit instructions required to use the [`Sha256`] gadget.
+pub trait Sha256Instructions<F: Field>: Chip<F> {
+    /// Variable representing the SHA-256 internal state.
+    type State: Clone + fmt::Debug;
+    /// Variable representing a 32-bit word of the input block to the SHA-256 compression
+    /// function.
+    type BlockWord: Copy + fmt::Debug + Default + From<u32>;
+
+    /// Places the SHA-256 IV in the circuit, returning the initial state variable.
+    fn initialization_vector(&self, layouter: &mut impl Layouter<F>) -> Result<Self::State, Error>;
+
+    /// Creates an initial state from the output state of a previous block
+    fn initialization(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        init_state: &Self::State,
+    ) -> Result<Self::State, Error>;
+
+    /// Starting from the given initialized state, processes a block of input and returns the
+    /// final state.
+    fn compress(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        initialized_state: &Self::State,
+        input: [Self::BlockWord; BLOCK_SIZE],
+        input_cells: [AssignedCell<F, F>; BLOCK_SIZE],
+    ) -> Result<Self::State, Error>;
+
+    /// Assigns a word constrained to be equal to the constant, used for padding
+    fn assign_constant_word(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: u32,
+    ) -> Result<AssignedCell<F, F>, Error>;
+
+    /// Converts the given state into a message digest.
+    fn digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: &Self::State,
+    ) -> Result<[Self::BlockWord; DIGEST_SIZE], Error>;
+
+    /// Converts the given state into a message digest.
+    fn digest_cells(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: &Self::State,
+    ) -> Result<[AssignedCell<F, F>; DIGEST_SIZE], Error>;
+}
+
+/// The output of a SHA-256 circuit invocation.
+#[derive(Debug)]
+pub struct Sha256Digest<BlockWord>(pub [BlockWord; DIGEST_SIZE]);
+
+/// A gadget that constrains a SHA-256 invocation. It supports input at a granularity of
+/// 32 bits.
+#[derive(Debug)]
+pub struct Sha256<F: Field, CS: Sha256Instructions<F>> {
+    chip: CS,
+    state: CS::State,
+    /// Count of already compressed blocks, the state must be re-initialized before each block
+    /// except the first one
+    blocks_count: usize,
+    cur_block: Vec<CS::BlockWord>,
+    cur_block_src_cell: Vec<AssignedCell<F, F>>,
+    /// Length of the message in bits
+    length: u64,
+}
+
+impl<F: Field, Sha256Chip: Sha256Instructions<F>> Sha256<F, Sha256Chip> {
+    /// Create a new hasher instance.
+    pub fn new(chip: Sha256Chip, mut layouter: impl Layouter<F>) -> Result<Self, Error> {
+        let state = chip.initialization_vector(&mut layouter)?;
+        Ok(Sha256 {
+            chip,
+            state,
+            blocks_count: 0,
+            cur_block: Vec::with_capacity(BLOCK_SIZE),
+            cur_block_src_cell: Vec::with_capacity(BLOCK_SIZE),
+            length: 0,
+        })
+    }
+
+    fn compress_full_blocks(&mut self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        while self.cur_block.len() >= BLOCK_SIZE {
+            let rest_words = self.cur_block.split_off(BLOCK_SIZE);
+            let rest_cells = self.cur_block_src_cell.split_off(BLOCK_SIZE);
+
+            let words = mem::replace(&mut self.cur_block, rest_words);
+            let cells = mem::replace(&mut self.cur_block_src_cell, rest_cells);
+
+            if self.blocks_count != 0 {
+                self.state = self.chip.initialization(layouter, &self.state)?;
+            }
+
+            self.state = self.chip.compress(
+                layouter,
+                &self.state,
+                words.try_into().expect("cur_block.len() == BLOCK_SIZE"),
+                cells.try_into().expect("cur_block.len() == BLOCK_SIZE"),
+            )?;
+            self.blocks_count += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Digest data, updating the internal state.
+    ///
+    /// `input_cells` are constrained to be equal to the words of the message schedule
+    pub fn update(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        input: &[Sha256Chip::BlockWord],
+        input_cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        assert_eq!(input.len(), input_cells.len());
+
+        self.length += input.len() as u64 * 32;
+
+        self.cur_block.extend_from_slice(input);
+        self.cur_block_src_cell.extend_from_slice(input_cells);
+
+        self.compress_full_blocks(&mut layouter)
+    }
+
+    /// Append the standard padding: `1` bit, zeros & 64-bit length of the message, and
+    /// compress all remaining blocks
+    fn pad_and_compress(&mut self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let padding = off_circuit::padding(self.length);
+
+        for word in padding {
+            let cell = self.chip.assign_constant_word(layouter, word)?;
+            self.cur_block.push(Sha256Chip::BlockWord::from(word));
+            self.cur_block_src_cell.push(cell);
+        }
+
+        self.compress_full_blocks(layouter)?;
+        assert!(self.cur_block.is_empty(), "padding fills the last block");
+
+        Ok(())
+    }
+
+    /// Retrieve result and consume hasher instance.
+    pub fn finalize(
+        mut self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<Sha256Digest<Sha256Chip::BlockWord>, Error> {
+        self.pad_and_compress(&mut layouter)?;
+        self.chip
+            .digest(&mut layouter, &self.state)
+            .map(Sha256Digest)
+    }
+
+    /// Retrieve result and consume hasher instance.
+    pub fn finalize_cells(
+        mut self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<[AssignedCell<F, F>; DIGEST_SIZE], Error> {
+        self.pad_and_compress(&mut layouter)?;
+        self.chip.digest_cells(&mut layouter, &self.state)
+    }
+
+    /// Convenience function to compute hash of the data. It will handle hasher creation,
+    /// data feeding and finalization.
+    pub fn digest(
+        chip: Sha256Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha256Chip::BlockWord],
+        input_cells: &[AssignedCell<F, F>],
+    ) -> Result<Sha256Digest<Sha256Chip::BlockWord>, Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update(layouter.namespace(|| "update"), data, input_cells)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of the data. It will handle hasher creation,
+    /// data feeding and finalization.
+    pub fn digest_cells(
+        chip: Sha256Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha256Chip::BlockWord],
+        input_cells: &[AssignedCell<F, F>],
+    ) -> Result<[AssignedCell<F, F>; DIGEST_SIZE], Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update(layouter.namespace(|| "update"), data, input_cells)?;
+        hasher.finalize_cells(layouter.namespace(|| "finalize"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Instance},
+    };
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{ff::PrimeField, halo2curves::pasta::Fp, run_mock_prover_test};
+
+    /// Constrains the digest of `message` to be equal to the instance column
+    struct TestCircuit {
+        message: Vec<u32>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for TestCircuit {
+        type Config = (Table16Config, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            todo!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            let digest = meta.instance_column();
+            meta.enable_equality(digest);
+            (Table16Chip::configure(meta), input, digest)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, digest_column): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let input_cells = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    self.message
+                        .iter()
+                        .enumerate()
+                        .map(|(row, word)| {
+                            region.assign_advice(
+                                || "word",
+                                input,
+                                row,
+                                || Value::known(F::from(*word as u64)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let words = self
+                .message
+                .iter()
+                .copied()
+                .map(BlockWord::from)
+                .collect::<Vec<_>>();
+
+            let digest = Sha256::digest_cells(
+                Table16Chip::construct(config),
+                layouter.namespace(|| "sha256"),
+                &words,
+                &input_cells,
+            )?;
+
+            for (row, cell) in digest.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), digest_column, row)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn digest_instance(digest: [u32; DIGEST_SIZE]) -> Vec<Vec<Fp>> {
+        vec![digest.iter().map(|word| Fp::from(*word as u64)).collect()]
+    }
+
+    #[traced_test]
+    #[test]
+    fn multi_block() {
+        // Empty message, last block without space for length & exactly one block of data
+        for len in [0, 15, BLOCK_SIZE] {
+            let message = (0..len as u32)
+                .map(|i| i.wrapping_mul(0x9e37_79b9))
+                .collect::<Vec<_>>();
+            let digest = digest_instance(off_circuit::digest(&message));
+            run_mock_prover_test!(17, TestCircuit { message }, digest);
+        }
+    }
+
+    /// FIPS 180-2 test vectors
+    #[traced_test]
+    #[test]
+    fn known_answer() {
+        let vectors = [
+            (
+                // empty message
+                vec![],
+                [
+                    0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c,
+                    0xa495991b, 0x7852b855,
+                ],
+            ),
+            (
+                // "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", two blocks
+                vec![
+                    0x61626364, 0x62636465, 0x63646566, 0x64656667, 0x65666768, 0x66676869,
+                    0x6768696a, 0x68696a6b, 0x696a6b6c, 0x6a6b6c6d, 0x6b6c6d6e, 0x6c6d6e6f,
+                    0x6d6e6f70, 0x6e6f7071,
+                ],
+                [
+                    0x248d6a61, 0xd20638b8, 0xe5c02693, 0x0c3e6039, 0xa33ce459, 0x64ff2167,
+                    0xf6ecedd4, 0x19db06c1,
+                ],
+            ),
+        ];
+
+        for (message, digest) in vectors {
+            run_mock_prover_test!(17, TestCircuit { message }, digest_instance(digest));
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn wrong_digest() {
+        let message = vec![0x61626364];
+        let mut digest = off_circuit::digest(&message);
+        digest[0] = digest[0].wrapping_add(1);
+
+        let prover = halo2_proofs::dev::MockProver::run(
+            17,
+            &TestCircuit { message },
+            digest_instance(digest),
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
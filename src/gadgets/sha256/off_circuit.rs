@@ -0,0 +1,127 @@
+//! Reference SHA-256 over 32-bit words, matches [`super::Sha256`] with [`super::Table16Chip`]
+
+use std::iter;
+
+use super::{
+    table16::{IV, ROUND_CONSTANTS},
+    BLOCK_SIZE, DIGEST_SIZE,
+};
+
+/// SHA-256 compression function, return the next state
+pub fn compress(state: [u32; DIGEST_SIZE], block: &[u32; BLOCK_SIZE]) -> [u32; DIGEST_SIZE] {
+    let mut w = [0u32; 64];
+    w[..BLOCK_SIZE].copy_from_slice(block);
+    for i in BLOCK_SIZE..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for (k, w) in ROUND_CONSTANTS.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    let mut next = state;
+    for (next, word) in next.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *next = next.wrapping_add(word);
+    }
+    next
+}
+
+/// Words appended to a message of `length` bits: `1` bit, zeros & the 64-bit length, so the
+/// padded message is a multiple of [`BLOCK_SIZE`]
+///
+/// `length` must be a multiple of 32
+pub fn padding(length: u64) -> Vec<u32> {
+    assert_eq!(length % 32, 0, "message is a sequence of 32-bit words");
+
+    let words = (length / 32) as usize;
+    // `0x80000000` word & two words of length
+    let zeros = (2 * BLOCK_SIZE - (words + 3) % BLOCK_SIZE) % BLOCK_SIZE;
+
+    iter::once(0x8000_0000)
+        .chain(iter::repeat(0).take(zeros))
+        .chain([(length >> 32) as u32, length as u32])
+        .collect()
+}
+
+/// Message with appended [`padding`]
+pub fn pad(words: &[u32]) -> Vec<u32> {
+    words
+        .iter()
+        .copied()
+        .chain(padding(words.len() as u64 * 32))
+        .collect()
+}
+
+/// SHA-256 digest of the message
+pub fn digest(words: &[u32]) -> [u32; DIGEST_SIZE] {
+    pad(words)
+        .chunks_exact(BLOCK_SIZE)
+        .fold(IV, |state, block| {
+            compress(state, block.try_into().expect("chunks are exact"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(
+            digest(&[]),
+            [
+                0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b,
+                0x7852b855,
+            ]
+        );
+    }
+
+    #[test]
+    fn abc() {
+        let mut block = [0u32; BLOCK_SIZE];
+        block[0] = 0x6162_6380;
+        block[BLOCK_SIZE - 1] = 24;
+
+        assert_eq!(
+            compress(IV, &block),
+            [
+                0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+                0xf20015ad,
+            ]
+        );
+    }
+
+    #[test]
+    fn padding_len() {
+        for words in 0..3 * BLOCK_SIZE {
+            assert_eq!(pad(&vec![0; words]).len() % BLOCK_SIZE, 0);
+            assert!(padding(words as u64 * 32).len() >= 3);
+        }
+        // Digest of the step circuit is exactly one block
+        assert_eq!(pad(&[0; DIGEST_SIZE]).len(), BLOCK_SIZE);
+    }
+}
@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{ConstraintSystem, Error as Halo2PlonkError},
+};
+use num_traits::ToPrimitive;
+use tracing::*;
+
+use super::{off_circuit, BlockWord, Sha256, Table16Chip, Table16Config, DIGEST_SIZE};
+use crate::{
+    ff::PrimeField,
+    ivc::{StepCircuit, SynthesisError},
+    util::fe_to_big,
+};
+
+/// Step circuit of the SHA-256 hash chain: `z_{i+1} = SHA256(z_i)`
+///
+/// `z_i` is the digest of the previous step, [`DIGEST_SIZE`] field elements each holding one
+/// 32-bit word. Digest with the standard padding is exactly one block, so each step is one
+/// compression of [`Table16Chip`], which requires `k >= 17`
+///
+/// Words of `z_i` are range-checked by the message schedule, so the initial `z_0` must also
+/// consist of 32-bit words
+#[derive(Debug)]
+pub struct Sha256StepCircuit<F: PrimeField> {
+    _p: PhantomData<F>,
+}
+
+impl<F: PrimeField> Default for Sha256StepCircuit<F> {
+    fn default() -> Self {
+        Self { _p: PhantomData }
+    }
+}
+
+fn fe_to_word<F: PrimeField>(fe: &F) -> Option<u32> {
+    fe_to_big(fe).to_u32()
+}
+
+impl<F: PrimeField> StepCircuit<DIGEST_SIZE, F> for Sha256StepCircuit<F> {
+    type Config = Table16Config;
+    type StepWitness = ();
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        Table16Chip::configure(cs)
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; DIGEST_SIZE],
+        _witness: &Self::StepWitness,
+        _k_table_size: u32,
+    ) -> Result<[F; DIGEST_SIZE], SynthesisError> {
+        let words = z_i
+            .iter()
+            .map(|fe| {
+                fe_to_word(fe).ok_or_else(|| {
+                    error!("`z_i` element {fe:?} is not a 32-bit word");
+                    SynthesisError::Halo2(Halo2PlonkError::Synthesis)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(off_circuit::digest(&words).map(|word| F::from(word as u64)))
+    }
+
+    fn synthesize_step(
+        &self,
+        config: Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; DIGEST_SIZE],
+        _witness: &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; DIGEST_SIZE], SynthesisError> {
+        Table16Chip::load(config.clone(), layouter).map_err(SynthesisError::Halo2)?;
+
+        let words = z_i
+            .iter()
+            .map(|cell| match cell.value().unwrap().map(fe_to_word) {
+                Some(Some(word)) => Ok(BlockWord(Value::known(word))),
+                Some(None) => {
+                    error!("`z_i` cell {cell:?} is not a 32-bit word");
+                    Err(SynthesisError::Halo2(Halo2PlonkError::Synthesis))
+                }
+                None => Ok(BlockWord(Value::unknown())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Sha256::digest_cells(
+            Table16Chip::construct(config),
+            layouter.namespace(|| "sha256"),
+            &words,
+            z_i,
+        )
+        .map_err(SynthesisError::Halo2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::array;
+
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{halo2curves::bn256::Fr, util::MockProver};
+
+    #[traced_test]
+    #[test]
+    fn hash_chain() {
+        let circuit = Sha256StepCircuit::<Fr>::default();
+
+        let mut z_i = array::from_fn(|i| Fr::from(i as u64));
+        for _ in 0..2 {
+            let z_out = circuit.process_step(&z_i, &(), 17).unwrap();
+
+            MockProver::run(17, &circuit, vec![], z_i)
+                .unwrap()
+                .verify(z_out)
+                .unwrap();
+
+            z_i = z_out;
+        }
+    }
+
+    #[test]
+    fn not_a_word() {
+        let z_i = [Fr::from(u32::MAX as u64 + 1); DIGEST_SIZE];
+        assert!(Sha256StepCircuit::default()
+            .process_step(&z_i, &(), 17)
+            .is_err());
+    }
+}
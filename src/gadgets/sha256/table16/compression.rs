@@ -1,21 +1,22 @@
-use std::{convert::TryInto, ops::Range};
+use std::convert::TryInto;
 
 use halo2_proofs::{
-    circuit::{Layouter, Value},
+    circuit::{AssignedCell, Layouter, Region, Value},
     plonk::{Advice, Column, ConstraintSystem, Error, Selector},
     poly::Rotation,
 };
-use sirius::ff::PrimeField;
 
 use super::{
+    super::DIGEST_SIZE,
     util::{i2lebsp, lebs2ip},
     AssignedBits, BlockWord, SpreadInputs, SpreadVar, Table16Assignment, ROUNDS, STATE,
 };
-use crate::{AssignedCell, DIGEST_SIZE};
+use crate::ff::PrimeField;
 
 mod compression_gates;
 mod compression_util;
 mod subregion_digest;
+mod subregion_feed_forward;
 mod subregion_initial;
 mod subregion_main;
 
@@ -90,44 +91,6 @@ pub struct AbcdVar<F: PrimeField> {
     d: SpreadVar<F, 10, 20>,
 }
 
-impl<F: PrimeField> AbcdVar<F> {
-    fn a_range() -> Range<usize> {
-        0..2
-    }
-
-    fn b_range() -> Range<usize> {
-        2..13
-    }
-
-    fn c_lo_range() -> Range<usize> {
-        13..16
-    }
-
-    fn c_mid_range() -> Range<usize> {
-        16..19
-    }
-
-    fn c_hi_range() -> Range<usize> {
-        19..22
-    }
-
-    fn d_range() -> Range<usize> {
-        22..32
-    }
-
-    fn pieces(val: u32) -> Vec<Vec<bool>> {
-        let val: [bool; 32] = i2lebsp(val.into());
-        vec![
-            val[Self::a_range()].to_vec(),
-            val[Self::b_range()].to_vec(),
-            val[Self::c_lo_range()].to_vec(),
-            val[Self::c_mid_range()].to_vec(),
-            val[Self::c_hi_range()].to_vec(),
-            val[Self::d_range()].to_vec(),
-        ]
-    }
-}
-
 impl<F: PrimeField> UpperSigmaVar<4, 22, 18, 20> for AbcdVar<F> {
     fn spread_a(&self) -> Value<[bool; 4]> {
         self.a.spread.value().map(|v| v.0)
@@ -179,44 +142,6 @@ pub struct EfghVar<F: PrimeField> {
     d: SpreadVar<F, 7, 14>,
 }
 
-impl<F: PrimeField> EfghVar<F> {
-    fn a_lo_range() -> Range<usize> {
-        0..3
-    }
-
-    fn a_hi_range() -> Range<usize> {
-        3..6
-    }
-
-    fn b_lo_range() -> Range<usize> {
-        6..8
-    }
-
-    fn b_hi_range() -> Range<usize> {
-        8..11
-    }
-
-    fn c_range() -> Range<usize> {
-        11..25
-    }
-
-    fn d_range() -> Range<usize> {
-        25..32
-    }
-
-    fn pieces(val: u32) -> Vec<Vec<bool>> {
-        let val: [bool; 32] = i2lebsp(val.into());
-        vec![
-            val[Self::a_lo_range()].to_vec(),
-            val[Self::a_hi_range()].to_vec(),
-            val[Self::b_lo_range()].to_vec(),
-            val[Self::b_hi_range()].to_vec(),
-            val[Self::c_range()].to_vec(),
-            val[Self::d_range()].to_vec(),
-        ]
-    }
-}
-
 impl<F: PrimeField> UpperSigmaVar<12, 10, 28, 14> for EfghVar<F> {
     fn spread_a(&self) -> Value<[bool; 12]> {
         self.a_lo
@@ -273,6 +198,12 @@ impl<F: PrimeField> RoundWordDense<F> {
             .zip(self.1.value_u16())
             .map(|(lo, hi)| lo as u32 + (1 << 16) * hi as u32)
     }
+
+    /// Constrain both halves of this word to be equal to the halves of `other`
+    pub fn constrain_equal(&self, region: &mut Region<'_, F>, other: &Self) -> Result<(), Error> {
+        region.constrain_equal(self.0.cell(), other.0.cell())?;
+        region.constrain_equal(self.1.cell(), other.1.cell())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -284,15 +215,6 @@ impl<F: PrimeField> From<(AssignedBits<F, 32>, AssignedBits<F, 32>)> for RoundWo
     }
 }
 
-impl<F: PrimeField> RoundWordSpread<F> {
-    pub fn value(&self) -> Value<u64> {
-        self.0
-            .value_u32()
-            .zip(self.1.value_u32())
-            .map(|(lo, hi)| lo as u64 + (1 << 32) * hi as u64)
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct RoundWordA<F: PrimeField> {
     pieces: Option<AbcdVar<F>>,
@@ -451,6 +373,7 @@ pub(super) struct CompressionConfig {
     // Decomposition gate for EfghVar
     s_decompose_efgh: Selector,
 
+    s_feed_forward: Selector,
     s_digest: Selector,
 }
 
@@ -478,6 +401,7 @@ impl CompressionConfig {
         // Decomposition gate for EfghVar
         let s_decompose_efgh = meta.selector();
 
+        let s_feed_forward = meta.selector();
         let s_digest = meta.selector();
 
         // Rename these here for ease of matching the gates to the specification.
@@ -820,6 +744,29 @@ impl CompressionConfig {
             )
         });
 
+        // s_feed_forward to add the initialized state to the state after the final round
+        meta.create_gate("s_feed_forward", |meta| {
+            let s_feed_forward = meta.query_selector(s_feed_forward);
+            let out_lo = meta.query_advice(a_1, Rotation::cur());
+            let out_hi = meta.query_advice(a_1, Rotation::next());
+            let carry = meta.query_advice(a_7, Rotation::cur());
+            let init_lo = meta.query_advice(a_3, Rotation::cur());
+            let init_hi = meta.query_advice(a_4, Rotation::cur());
+            let word_lo = meta.query_advice(a_5, Rotation::cur());
+            let word_hi = meta.query_advice(a_6, Rotation::cur());
+
+            CompressionGate::s_feed_forward(
+                s_feed_forward,
+                out_lo,
+                out_hi,
+                carry,
+                init_lo,
+                init_hi,
+                word_lo,
+                word_hi,
+            )
+        });
+
         // s_digest for final round
         meta.create_gate("s_digest", |meta| {
             let s_digest = meta.query_selector(s_digest);
@@ -856,6 +803,7 @@ impl CompressionConfig {
             s_upper_sigma_1,
             s_decompose_abcd,
             s_decompose_efgh,
+            s_feed_forward,
             s_digest,
         }
     }
@@ -896,7 +844,8 @@ impl CompressionConfig {
         Ok(new_state)
     }
 
-    /// Given an initialized state and a message schedule, perform 64 compression rounds.
+    /// Given an initialized state `H_i` and a message schedule, perform 64 compression rounds
+    /// and add `H_i` to the result, returning the next state `H_{i+1}`.
     pub(super) fn compress<F: PrimeField>(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -914,7 +863,13 @@ impl CompressionConfig {
                 Ok(())
             },
         )?;
-        Ok(state)
+
+        layouter.assign_region(
+            || "feed_forward",
+            |mut region| {
+                self.assign_feed_forward(&mut region, initialized_state.clone(), state.clone())
+            },
+        )
     }
 
     /// After the final round, convert the state into the final digest.
@@ -952,18 +907,16 @@ impl CompressionConfig {
 mod tests {
     use std::marker::PhantomData;
 
-    use ff::PrimeField;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
         plonk::{Circuit, ConstraintSystem, Error},
     };
-    use halo2curves::pasta::{pallas, Fp};
-    use sirius::run_mock_prover_test;
+    use tracing_test::traced_test;
 
     use super::super::{
         super::BLOCK_SIZE, msg_schedule_test_input, BlockWord, Table16Chip, Table16Config, IV,
     };
+    use crate::{ff::PrimeField, halo2curves::pasta::Fp, run_mock_prover_test};
 
     #[traced_test]
     #[test]
@@ -974,8 +927,6 @@ mod tests {
         impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
             type Config = Table16Config;
             type FloorPlanner = SimpleFloorPlanner;
-            #[cfg(feature = "circuit-params")]
-            type Params = ();
 
             fn without_witnesses(&self) -> Self {
                 MyCircuit {
@@ -1009,8 +960,7 @@ mod tests {
                 let digest = config.compression.digest(&mut layouter, state)?;
                 for (idx, digest_word) in digest.iter().enumerate() {
                     digest_word.0.assert_if_known(|digest_word| {
-                        (*digest_word as u64 + IV[idx] as u64) as u32
-                            == super::compression_util::COMPRESSION_OUTPUT[idx]
+                        *digest_word == super::compression_util::COMPRESSION_OUTPUT[idx]
                     });
                 }
 
@@ -3,9 +3,9 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::plonk::{Constraint, Constraints, Expression};
-use sirius::ff::PrimeField;
 
 use super::super::{util::*, Gate};
+use crate::ff::PrimeField;
 
 pub struct CompressionGate<F: PrimeField>(PhantomData<F>);
 
@@ -412,6 +412,32 @@ impl<F: PrimeField> CompressionGate<F> {
         Some(("s_e_new", s_e_new * check))
     }
 
+    // s_feed_forward to get H_{i+1} = H_i + state after the final round, modulo 2^32
+    #[allow(clippy::too_many_arguments)]
+    pub fn s_feed_forward(
+        s_feed_forward: Expression<F>,
+        out_lo: Expression<F>,
+        out_hi: Expression<F>,
+        carry: Expression<F>,
+        init_lo: Expression<F>,
+        init_hi: Expression<F>,
+        word_lo: Expression<F>,
+        word_hi: Expression<F>,
+    ) -> impl IntoIterator<Item = Constraint<F>> {
+        let lo = init_lo + word_lo;
+        let hi = init_hi + word_hi;
+        let sum = lo + hi * F::from(1 << 16);
+        let out = out_lo + out_hi * F::from(1 << 16);
+
+        let check = sum - (carry.clone() * F::from(1 << 32)) - out;
+        let carry_check = carry.clone() * (Self::ones() - carry);
+
+        Constraints::with_selector(
+            s_feed_forward,
+            [("check_sum", check), ("carry_is_bit", carry_check)],
+        )
+    }
+
     // s_digest on final round
     #[allow(clippy::too_many_arguments)]
     pub fn s_digest(
@@ -4,13 +4,17 @@ use halo2_proofs::{
     circuit::{Region, Value},
     plonk::{Advice, Column, Error},
 };
-use sirius::ff::PrimeField;
 
 use super::{
     AbcdVar, CompressionConfig, EfghVar, RoundWord, RoundWordA, RoundWordDense, RoundWordE,
     RoundWordSpread, State, UpperSigmaVar,
 };
-use crate::table16::{util::*, AssignedBits, SpreadVar, SpreadWord, StateWord, Table16Assignment};
+use crate::{
+    ff::PrimeField,
+    gadgets::sha256::table16::{
+        util::*, AssignedBits, SpreadVar, SpreadWord, StateWord, Table16Assignment,
+    },
+};
 
 // Test vector 'abc'
 #[cfg(test)]
@@ -1,15 +1,14 @@
 use halo2_proofs::{
-    circuit::{Region, Value},
+    circuit::{AssignedCell, Region, Value},
     plonk::{Advice, Column, Error},
 };
-use sirius::ff::PrimeField;
 
 use super::{
     super::{BlockWord, RoundWordDense},
     compression_util::*,
     CompressionConfig, State,
 };
-use crate::{AssignedCell, DIGEST_SIZE};
+use crate::{ff::PrimeField, gadgets::sha256::DIGEST_SIZE};
 
 impl CompressionConfig {
     #[allow(clippy::many_single_char_names)]
@@ -0,0 +1,105 @@
+use std::convert::TryInto;
+
+use halo2_proofs::{
+    circuit::{Region, Value},
+    plonk::Error,
+};
+
+use super::{
+    super::{util::*, RoundWord, RoundWordA, RoundWordDense, RoundWordE, SpreadVar, SpreadWord},
+    compression_util::*,
+    CompressionConfig, State, StateWord,
+};
+use crate::ff::PrimeField;
+
+// Rows needed for each word of the feed-forward
+pub const FEED_FORWARD_ROWS: usize = 2;
+
+impl CompressionConfig {
+    /// Add the initialized state `H_i` to the state after the final round, word by word modulo
+    /// `2^32`
+    ///
+    /// Returns `H_{i+1}`, halves of its words are range-checked by the spread table
+    #[allow(clippy::many_single_char_names)]
+    pub fn assign_feed_forward<F: PrimeField>(
+        &self,
+        region: &mut Region<'_, F>,
+        initialized_state: State<F>,
+        state: State<F>,
+    ) -> Result<State<F>, Error> {
+        let (a_i, b_i, c_i, d_i, e_i, f_i, g_i, h_i) = match_state(initialized_state);
+        let (a, b, c, d, e, f, g, h) = match_state(state);
+
+        let [a, b, c, d, e, f, g, h]: [RoundWord<F>; 8] = [
+            (a_i.dense_halves, a.dense_halves),
+            (b_i.dense_halves, b.dense_halves),
+            (c_i.dense_halves, c.dense_halves),
+            (d_i, d),
+            (e_i.dense_halves, e.dense_halves),
+            (f_i.dense_halves, f.dense_halves),
+            (g_i.dense_halves, g.dense_halves),
+            (h_i, h),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, (init, word))| {
+            self.assign_word_sum(region, idx * FEED_FORWARD_ROWS, init, word)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("state consists of 8 words"));
+
+        Ok(State::new(
+            StateWord::A(RoundWordA::new_dense(a.dense_halves)),
+            StateWord::B(b),
+            StateWord::C(c),
+            StateWord::D(d.dense_halves),
+            StateWord::E(RoundWordE::new_dense(e.dense_halves)),
+            StateWord::F(f),
+            StateWord::G(g),
+            StateWord::H(h.dense_halves),
+        ))
+    }
+
+    // s_feed_forward to get `init + word` modulo 2^32
+    fn assign_word_sum<F: PrimeField>(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        init: &RoundWordDense<F>,
+        word: &RoundWordDense<F>,
+    ) -> Result<RoundWord<F>, Error> {
+        self.s_feed_forward.enable(region, row)?;
+
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+
+        // Assign and copy init_lo, init_hi, word_lo, word_hi
+        init.0.copy_advice(|| "init_lo", region, a_3, row)?;
+        init.1.copy_advice(|| "init_hi", region, a_4, row)?;
+        word.0.copy_advice(|| "word_lo", region, a_5, row)?;
+        word.1.copy_advice(|| "word_hi", region, a_6, row)?;
+
+        // Assign sum, carry
+        let (sum, carry) = sum_with_carry(vec![
+            (init.0.value_u16(), init.1.value_u16()),
+            (word.0.value_u16(), word.1.value_u16()),
+        ]);
+        region.assign_advice(|| "carry", a_7, row, || carry.map(F::from))?;
+
+        let sum: Value<[bool; 32]> = sum.map(|w| i2lebsp(w.into()));
+        let lo: Value<[bool; 16]> = sum.map(|w| w[..16].try_into().unwrap());
+        let hi: Value<[bool; 16]> = sum.map(|w| w[16..].try_into().unwrap());
+
+        let lo = SpreadVar::with_lookup(region, &self.lookup, row, lo.map(SpreadWord::new))?;
+        let hi = SpreadVar::with_lookup(region, &self.lookup, row + 1, hi.map(SpreadWord::new))?;
+
+        Ok(RoundWord::new(
+            (lo.dense, hi.dense).into(),
+            (lo.spread, hi.spread).into(),
+        ))
+    }
+}
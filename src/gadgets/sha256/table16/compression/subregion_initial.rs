@@ -2,13 +2,13 @@ use halo2_proofs::{
     circuit::{Region, Value},
     plonk::Error,
 };
-use sirius::ff::PrimeField;
 
 use super::{
     super::{RoundWord, StateWord, STATE},
     compression_util::*,
     CompressionConfig, State,
 };
+use crate::ff::PrimeField;
 
 impl CompressionConfig {
     #[allow(clippy::many_single_char_names)]
@@ -55,6 +55,8 @@ impl CompressionConfig {
         ))
     }
 
+    /// Initialize the state from the state of a previous block: words are decomposed again &
+    /// their dense halves are constrained to be equal to the halves of `state`
     #[allow(clippy::many_single_char_names)]
     pub fn initialize_state<F: PrimeField>(
         &self,
@@ -65,44 +67,56 @@ impl CompressionConfig {
         let (a, b, c, d, e, f, g, h) = match_state(state);
 
         // Decompose E into (6, 5, 14, 7)-bit chunks
-        let e = e.dense_halves.value();
-        let e = self.decompose_e(region, RoundIdx::Init, e)?;
+        let e_new = self.decompose_e(region, RoundIdx::Init, e.dense_halves.value())?;
+        e_new
+            .dense_halves
+            .constrain_equal(region, &e.dense_halves)?;
 
         // Decompose F, G
-        let f = f.dense_halves.value();
-        let f = self.decompose_f(region, InitialRound, f)?;
-        let g = g.dense_halves.value();
-        let g = self.decompose_g(region, InitialRound, g)?;
+        let f_new = self.decompose_f(region, InitialRound, f.dense_halves.value())?;
+        f_new
+            .dense_halves
+            .constrain_equal(region, &f.dense_halves)?;
+        let g_new = self.decompose_g(region, InitialRound, g.dense_halves.value())?;
+        g_new
+            .dense_halves
+            .constrain_equal(region, &g.dense_halves)?;
 
         // Assign H
-        let h = h.value();
         let h_row = get_h_row(RoundIdx::Init);
-        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h)?;
+        let h_new = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h.value())?;
+        h_new.constrain_equal(region, &h)?;
 
         // Decompose A into (2, 11, 9, 10)-bit chunks
-        let a = a.dense_halves.value();
-        let a = self.decompose_a(region, RoundIdx::Init, a)?;
+        let a_new = self.decompose_a(region, RoundIdx::Init, a.dense_halves.value())?;
+        a_new
+            .dense_halves
+            .constrain_equal(region, &a.dense_halves)?;
 
         // Decompose B, C
-        let b = b.dense_halves.value();
-        let b = self.decompose_b(region, InitialRound, b)?;
-        let c = c.dense_halves.value();
-        let c = self.decompose_c(region, InitialRound, c)?;
+        let b_new = self.decompose_b(region, InitialRound, b.dense_halves.value())?;
+        b_new
+            .dense_halves
+            .constrain_equal(region, &b.dense_halves)?;
+        let c_new = self.decompose_c(region, InitialRound, c.dense_halves.value())?;
+        c_new
+            .dense_halves
+            .constrain_equal(region, &c.dense_halves)?;
 
         // Assign D
-        let d = d.value();
         let d_row = get_d_row(RoundIdx::Init);
-        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d)?;
+        let d_new = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d.value())?;
+        d_new.constrain_equal(region, &d)?;
 
         Ok(State::new(
-            StateWord::A(a),
-            StateWord::B(b),
-            StateWord::C(c),
-            StateWord::D(d),
-            StateWord::E(e),
-            StateWord::F(f),
-            StateWord::G(g),
-            StateWord::H(h),
+            StateWord::A(a_new),
+            StateWord::B(b_new),
+            StateWord::C(c_new),
+            StateWord::D(d_new),
+            StateWord::E(e_new),
+            StateWord::F(f_new),
+            StateWord::G(g_new),
+            StateWord::H(h_new),
         ))
     }
 
@@ -1,11 +1,11 @@
 use halo2_proofs::{circuit::Region, plonk::Error};
-use sirius::ff::PrimeField;
 
 use super::{
     super::{AssignedBits, RoundWord, RoundWordA, RoundWordE, StateWord, ROUND_CONSTANTS},
     compression_util::*,
     CompressionConfig, State,
 };
+use crate::ff::PrimeField;
 
 impl CompressionConfig {
     #[allow(clippy::many_single_char_names)]
@@ -1,5 +1,6 @@
 use halo2_proofs::{arithmetic::Field, plonk::Expression};
-use sirius::ff::PrimeField;
+
+use crate::ff::PrimeField;
 
 pub struct Gate<F: Field>(pub Expression<F>);
 
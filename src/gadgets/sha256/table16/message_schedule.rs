@@ -5,10 +5,9 @@ use halo2_proofs::{
     plonk::{Advice, Column, ConstraintSystem, Error, Selector},
     poly::Rotation,
 };
-use sirius::ff::PrimeField;
 
-use super::{super::BLOCK_SIZE, AssignedBits, SpreadInputs, Table16Assignment, ROUNDS};
-use crate::BlockWord;
+use super::{super::BLOCK_SIZE, AssignedBits, BlockWord, SpreadInputs, Table16Assignment, ROUNDS};
+use crate::ff::PrimeField;
 
 mod schedule_gates;
 mod schedule_util;
@@ -396,22 +395,17 @@ impl MessageScheduleConfig {
 mod tests {
     use std::marker::PhantomData;
 
-    use ff::PrimeField;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
         plonk::{Circuit, ConstraintSystem, Error},
     };
-    use halo2curves::pasta::pallas;
-    use sirius::run_mock_prover_test;
+    use tracing_test::traced_test;
 
     use super::{
-        super::{
-            super::BLOCK_SIZE, util::lebs2ip, BlockWord, SpreadTableChip, Table16Chip,
-            Table16Config,
-        },
+        super::{super::BLOCK_SIZE, util::lebs2ip, BlockWord, Table16Chip, Table16Config},
         schedule_util::*,
     };
+    use crate::{ff::PrimeField, halo2curves::pasta::pallas, run_mock_prover_test};
 
     #[traced_test]
     #[test]
@@ -422,8 +416,6 @@ mod tests {
         impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
             type Config = Table16Config;
             type FloorPlanner = SimpleFloorPlanner;
-            #[cfg(feature = "circuit-params")]
-            type Params = ();
 
             fn without_witnesses(&self) -> Self {
                 MyCircuit {
@@ -441,7 +433,7 @@ mod tests {
                 mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
                 // Load lookup table
-                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+                config.lookup.load(&mut layouter)?;
 
                 // Provide input
                 // Test vector: "abc"
@@ -1,9 +1,9 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::plonk::Expression;
-use sirius::ff::PrimeField;
 
 use super::super::Gate;
+use crate::ff::PrimeField;
 
 pub struct ScheduleGate<F: PrimeField>(PhantomData<F>);
 
@@ -1,10 +1,10 @@
 use super::super::AssignedBits;
 use super::MessageScheduleConfig;
+use crate::ff::PrimeField;
 use halo2_proofs::{
     circuit::{Region, Value},
     plonk::Error,
 };
-use sirius::ff::PrimeField;
 
 #[cfg(test)]
 use super::super::{super::BLOCK_SIZE, BlockWord, ROUNDS};
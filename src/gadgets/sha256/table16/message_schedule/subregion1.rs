@@ -4,13 +4,13 @@ use halo2_proofs::{
     circuit::{Region, Value},
     plonk::Error,
 };
-use sirius::ff::PrimeField;
 
 use super::{
     super::{util::*, AssignedBits, BlockWord, SpreadVar, SpreadWord, Table16Assignment},
     schedule_util::*,
     MessageScheduleConfig,
 };
+use crate::ff::PrimeField;
 
 // A word in subregion 1
 // (3, 4, 11, 14)-bit chunks
@@ -19,8 +19,6 @@ pub struct Subregion1Word<F: PrimeField> {
     index: usize,
     a: AssignedBits<F, 3>,
     b: AssignedBits<F, 4>,
-    c: AssignedBits<F, 11>,
-    d: AssignedBits<F, 14>,
     spread_c: AssignedBits<F, 22>,
     spread_d: AssignedBits<F, 28>,
 }
@@ -158,8 +156,6 @@ impl MessageScheduleConfig {
             index,
             a,
             b,
-            c: spread_c.dense,
-            d: spread_d.dense,
             spread_c: spread_c.spread,
             spread_d: spread_d.spread,
         })
@@ -4,13 +4,13 @@ use halo2_proofs::{
     circuit::{Region, Value},
     plonk::Error,
 };
-use sirius::ff::PrimeField;
 
 use super::{
     super::{util::*, AssignedBits, Bits, SpreadVar, SpreadWord, Table16Assignment},
     schedule_util::*,
     MessageScheduleConfig, MessageWord,
 };
+use crate::ff::PrimeField;
 
 /// A word in subregion 2
 /// (3, 4, 3, 7, 1, 1, 13)-bit chunks
@@ -20,10 +20,8 @@ pub struct Subregion2Word<F: PrimeField> {
     a: AssignedBits<F, 3>,
     b: AssignedBits<F, 4>,
     c: AssignedBits<F, 3>,
-    d: AssignedBits<F, 7>,
     e: AssignedBits<F, 1>,
     f: AssignedBits<F, 1>,
-    g: AssignedBits<F, 13>,
     spread_d: AssignedBits<F, 14>,
     spread_g: AssignedBits<F, 26>,
 }
@@ -350,10 +348,8 @@ impl MessageScheduleConfig {
             a,
             b: spread_b.dense,
             c,
-            d: spread_d.dense,
             e,
             f,
-            g: spread_g.dense,
             spread_d: spread_d.spread,
             spread_g: spread_g.spread,
         })
@@ -4,24 +4,20 @@ use halo2_proofs::{
     circuit::{Region, Value},
     plonk::Error,
 };
-use sirius::ff::PrimeField;
 
 use super::{
     super::{util::*, AssignedBits, Bits, SpreadVar, SpreadWord, Table16Assignment},
     schedule_util::*,
     MessageScheduleConfig, MessageWord,
 };
+use crate::ff::PrimeField;
 
 // A word in subregion 3
 // (10, 7, 2, 13)-bit chunks
 pub struct Subregion3Word<F: PrimeField> {
     index: usize,
-    #[allow(dead_code)]
-    a: AssignedBits<F, 10>,
     b: AssignedBits<F, 7>,
     c: AssignedBits<F, 2>,
-    #[allow(dead_code)]
-    d: AssignedBits<F, 13>,
     spread_a: AssignedBits<F, 20>,
     spread_d: AssignedBits<F, 26>,
 }
@@ -237,10 +233,8 @@ impl MessageScheduleConfig {
 
         Ok(Subregion3Word {
             index,
-            a: spread_a.dense,
             b,
             c,
-            d: spread_d.dense,
             spread_a: spread_a.spread,
             spread_d: spread_d.spread,
         })
@@ -1,12 +1,14 @@
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
-use super::sha256::Sha256Instructions;
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, Region, Value},
-    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error},
+    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
 };
-use sirius::ff::PrimeField;
+
+use super::Sha256Instructions;
+use crate::ff::PrimeField;
 
 mod compression;
 mod gates;
@@ -35,7 +37,7 @@ pub(crate) const ROUND_CONSTANTS: [u32; ROUNDS] = [
     0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
 ];
 
-const IV: [u32; STATE] = [
+pub(crate) const IV: [u32; STATE] = [
     0x6a09_e667,
     0xbb67_ae85,
     0x3c6e_f372,
@@ -51,6 +53,12 @@ const IV: [u32; STATE] = [
 // TODO: Make the internals of this struct private.
 pub struct BlockWord(pub Value<u32>);
 
+impl From<u32> for BlockWord {
+    fn from(word: u32) -> Self {
+        Self(Value::known(word))
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Little-endian bits (up to 64 bits)
 pub struct Bits<const LEN: usize>([bool; LEN]);
@@ -193,10 +201,6 @@ impl<F: PrimeField> AssignedBits<F, 16> {
 }
 
 impl<F: PrimeField> AssignedBits<F, 32> {
-    fn value_u32(&self) -> Value<u32> {
-        self.value().map(|v| v.into())
-    }
-
     fn assign<A, AR>(
         region: &mut Region<'_, F>,
         annotation: A,
@@ -233,6 +237,15 @@ pub struct Table16Config {
     lookup: SpreadTableConfig,
     message_schedule: MessageScheduleConfig,
     compression: CompressionConfig,
+    constant_word: ConstantWordConfig,
+}
+
+/// Gate `word == constant` for words of message padding
+#[derive(Clone, Debug)]
+struct ConstantWordConfig {
+    q_constant: Selector,
+    constant: Column<Fixed>,
+    word: Column<Advice>,
 }
 
 /// A chip that implements SHA-256 with a maximum lookup table size of $2^16$.
@@ -282,7 +295,7 @@ impl<F: PrimeField> Table16Chip<F> {
         let input_dense = meta.advice_column();
         let input_spread = meta.advice_column();
 
-        let lookup = SpreadTableChip::configure(meta, input_tag, input_dense, input_spread);
+        let lookup = SpreadTableConfig::configure(meta, input_tag, input_dense, input_spread);
         let lookup_inputs = lookup.input.clone();
 
         // Rename these here for ease of matching the gates to the specification.
@@ -302,6 +315,19 @@ impl<F: PrimeField> Table16Chip<F> {
             meta.enable_equality(*column);
         }
 
+        let constant_word = ConstantWordConfig {
+            q_constant: meta.selector(),
+            constant: meta.fixed_column(),
+            word: a_5,
+        };
+        meta.create_gate("constant word", |meta| {
+            let q_constant = meta.query_selector(constant_word.q_constant);
+            let constant = meta.query_fixed(constant_word.constant, Rotation::cur());
+            let word = meta.query_advice(constant_word.word, Rotation::cur());
+
+            vec![q_constant * (word - constant)]
+        });
+
         let compression =
             CompressionConfig::configure(meta, lookup_inputs.clone(), message_schedule, extras);
 
@@ -312,12 +338,13 @@ impl<F: PrimeField> Table16Chip<F> {
             lookup,
             message_schedule,
             compression,
+            constant_word,
         }
     }
 
     /// Loads the lookup table required by this chip into the circuit.
     pub fn load(config: Table16Config, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        SpreadTableChip::load(config.lookup, layouter)
+        config.lookup.load(layouter)
     }
 }
 
@@ -367,6 +394,28 @@ impl<F: PrimeField> Sha256Instructions<F> for Table16Chip<F> {
             .compress(layouter, initialized_state.clone(), w_halves)
     }
 
+    fn assign_constant_word(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: u32,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let ConstantWordConfig {
+            q_constant,
+            constant,
+            word: word_column,
+        } = self.config().constant_word.clone();
+        let value = Value::known(F::from(word as u64));
+
+        layouter.assign_region(
+            || "constant word",
+            |mut region| {
+                q_constant.enable(&mut region, 0)?;
+                region.assign_fixed(|| "constant", constant, 0, || value)?;
+                region.assign_advice(|| "word", word_column, 0, || value)
+            },
+        )
+    }
+
     fn digest(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -465,71 +514,3 @@ trait Table16Assignment<F: PrimeField> {
         Ok(even)
     }
 }
-
-#[cfg(test)]
-#[cfg(feature = "test-dev-graph")]
-mod tests {
-    use super::super::{Sha256, BLOCK_SIZE};
-    use super::{message_schedule::msg_schedule_test_input, Table16Chip, Table16Config};
-    use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        plonk::{Circuit, ConstraintSystem, Error},
-    };
-    use halo2curves::pasta::pallas;
-
-    #[traced_test]
-    #[test]
-    fn print_sha256_circuit() {
-        use plotters::prelude::*;
-        struct MyCircuit {}
-
-        impl Circuit<F> for MyCircuit {
-            type Config = Table16Config;
-            type FloorPlanner = SimpleFloorPlanner;
-            #[cfg(feature = "circuit-params")]
-            type Params = ();
-
-            fn without_witnesses(&self) -> Self {
-                MyCircuit {}
-            }
-
-            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-                Table16Chip::configure(meta)
-            }
-
-            fn synthesize(
-                &self,
-                config: Self::Config,
-                mut layouter: impl Layouter<F>,
-            ) -> Result<(), Error> {
-                let table16_chip = Table16Chip::construct(config.clone());
-                Table16Chip::load(config, &mut layouter)?;
-
-                // Test vector: "abc"
-                let test_input = msg_schedule_test_input();
-
-                // Create a message of length 31 blocks
-                let mut input = Vec::with_capacity(31 * BLOCK_SIZE);
-                for _ in 0..31 {
-                    input.extend_from_slice(&test_input);
-                }
-
-                Sha256::digest(table16_chip, layouter.namespace(|| "'abc' * 31"), &input)?;
-
-                Ok(())
-            }
-        }
-
-        let root =
-            BitMapBackend::new("sha-256-table16-chip-layout.png", (1024, 3480)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("16-bit Table SHA-256 Chip", ("sans-serif", 60))
-            .unwrap();
-
-        let circuit = MyCircuit {};
-        halo2_proofs::dev::CircuitLayout::default()
-            .render::<F, _, _>(17, &circuit, &root)
-            .unwrap();
-    }
-}
@@ -1,14 +1,13 @@
-use std::{convert::TryInto, marker::PhantomData};
+use std::convert::TryInto;
 
 use halo2_proofs::{
-    arithmetic::Field,
-    circuit::{Chip, Layouter, Region, Value},
+    circuit::{Layouter, Region, Value},
     plonk::{Advice, Column, ConstraintSystem, Error, TableColumn},
     poly::Rotation,
 };
-use sirius::ff::PrimeField;
 
 use super::{util::*, AssignedBits};
+use crate::ff::PrimeField;
 
 const BITS_7: usize = 1 << 7;
 const BITS_10: usize = 1 << 10;
@@ -69,7 +68,6 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
 /// A variable stored in advice columns corresponding to a row of [`SpreadTableConfig`].
 #[derive(Clone, Debug)]
 pub(super) struct SpreadVar<F: PrimeField, const DENSE: usize, const SPREAD: usize> {
-    pub tag: Value<u8>,
     pub dense: AssignedBits<F, DENSE>,
     pub spread: AssignedBits<F, SPREAD>,
 }
@@ -103,7 +101,7 @@ impl<F: PrimeField, const DENSE: usize, const SPREAD: usize> SpreadVar<F, DENSE,
             spread_val,
         )?;
 
-        Ok(SpreadVar { tag, dense, spread })
+        Ok(SpreadVar { dense, spread })
     }
 
     pub(super) fn without_lookup(
@@ -114,7 +112,6 @@ impl<F: PrimeField, const DENSE: usize, const SPREAD: usize> SpreadVar<F, DENSE,
         spread_row: usize,
         word: Value<SpreadWord<DENSE, SPREAD>>,
     ) -> Result<Self, Error> {
-        let tag = word.map(|word| word.tag);
         let dense_val = word.map(|word| word.dense);
         let spread_val = word.map(|word| word.spread);
 
@@ -134,7 +131,7 @@ impl<F: PrimeField, const DENSE: usize, const SPREAD: usize> SpreadVar<F, DENSE,
             spread_val,
         )?;
 
-        Ok(SpreadVar { tag, dense, spread })
+        Ok(SpreadVar { dense, spread })
     }
 }
 
@@ -158,32 +155,13 @@ pub(super) struct SpreadTableConfig {
     pub table: SpreadTable,
 }
 
-#[derive(Clone, Debug)]
-pub(super) struct SpreadTableChip<F: Field> {
-    config: SpreadTableConfig,
-    _marker: PhantomData<F>,
-}
-
-impl<F: Field> Chip<F> for SpreadTableChip<F> {
-    type Config = SpreadTableConfig;
-    type Loaded = ();
-
-    fn config(&self) -> &Self::Config {
-        &self.config
-    }
-
-    fn loaded(&self) -> &Self::Loaded {
-        &()
-    }
-}
-
-impl<F: PrimeField> SpreadTableChip<F> {
-    pub fn configure(
+impl SpreadTableConfig {
+    pub fn configure<F: PrimeField>(
         meta: &mut ConstraintSystem<F>,
         input_tag: Column<Advice>,
         input_dense: Column<Advice>,
         input_spread: Column<Advice>,
-    ) -> <Self as Chip<F>>::Config {
+    ) -> Self {
         let table_tag = meta.lookup_table_column();
         let table_dense = meta.lookup_table_column();
         let table_spread = meta.lookup_table_column();
@@ -214,21 +192,18 @@ impl<F: PrimeField> SpreadTableChip<F> {
         }
     }
 
-    pub fn load(
-        config: SpreadTableConfig,
-        layouter: &mut impl Layouter<F>,
-    ) -> Result<<Self as Chip<F>>::Loaded, Error> {
+    pub fn load<F: PrimeField>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         layouter.assign_table(
             || "spread table",
             |mut table| {
                 // We generate the row values lazily (we only need them during keygen).
-                let mut rows = SpreadTableConfig::generate::<F>();
+                let mut rows = Self::generate::<F>();
 
                 for index in 0..(1 << 16) {
                     let mut row = None;
                     table.assign_cell(
                         || "tag",
-                        config.table.tag,
+                        self.table.tag,
                         index,
                         || {
                             row = rows.next();
@@ -237,13 +212,13 @@ impl<F: PrimeField> SpreadTableChip<F> {
                     )?;
                     table.assign_cell(
                         || "dense",
-                        config.table.dense,
+                        self.table.dense,
                         index,
                         || Value::known(row.map(|(_, dense, _)| dense).unwrap()),
                     )?;
                     table.assign_cell(
                         || "spread",
-                        config.table.spread,
+                        self.table.spread,
                         index,
                         || Value::known(row.map(|(_, _, spread)| spread).unwrap()),
                     )?;
@@ -253,9 +228,7 @@ impl<F: PrimeField> SpreadTableChip<F> {
             },
         )
     }
-}
 
-impl SpreadTableConfig {
     fn generate<F: PrimeField>() -> impl Iterator<Item = (F, F, F)> {
         (1..=(1 << 16)).scan((F::ZERO, F::ZERO, F::ZERO), |(tag, dense, spread), i| {
             // We computed this table row in the previous iteration.
@@ -289,17 +262,15 @@ impl SpreadTableConfig {
 mod tests {
     use std::marker::PhantomData;
 
-    use ff::PrimeField;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
-        dev::MockProver,
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
     };
-    use halo2curves::pasta::Fp;
     use rand::Rng;
-    use sirius::run_mock_prover_test;
+    use tracing_test::traced_test;
 
-    use super::{get_tag, SpreadTableChip, SpreadTableConfig};
+    use super::{get_tag, SpreadTableConfig};
+    use crate::{ff::PrimeField, halo2curves::pasta::Fp, run_mock_prover_test};
 
     #[traced_test]
     #[test]
@@ -315,8 +286,6 @@ mod tests {
         impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
             type Config = SpreadTableConfig;
             type FloorPlanner = SimpleFloorPlanner;
-            #[cfg(feature = "circuit-params")]
-            type Params = ();
 
             fn without_witnesses(&self) -> Self {
                 MyCircuit {
@@ -329,7 +298,7 @@ mod tests {
                 let input_dense = meta.advice_column();
                 let input_spread = meta.advice_column();
 
-                SpreadTableChip::configure(meta, input_tag, input_dense, input_spread)
+                SpreadTableConfig::configure(meta, input_tag, input_dense, input_spread)
             }
 
             fn synthesize(
@@ -337,7 +306,7 @@ mod tests {
                 config: Self::Config,
                 mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
-                SpreadTableChip::load(config.clone(), &mut layouter)?;
+                config.load(&mut layouter)?;
 
                 layouter.assign_region(
                     || "spread_test",
@@ -0,0 +1,416 @@
+//! ECDSA signatures over the curve `C`, whose base & scalar fields are both foreign for the
+//! circuit field `F`, e.g. secp256k1 inside of the BN254 scalar field
+//!
+//! For the secret key `sk`, the public key `pk = sk * G` & the message hash `z` (reduced into
+//! the scalar field by [`message_hash_to_scalar`]), the signature is `(r, s)`, where:
+//! - `r = (k * G).x mod n` for the random nonce `k`
+//! - `s = (z + r * sk) / k mod n`
+//!
+//! The signature is valid if `r, s != 0` & `(z / s * G + r / s * pk).x mod n = r`.
+//!
+//! In the circuit both scalar multiplications are done at once by
+//! [`NonNativeEccChip::double_scalar_mul`]. The verification of one signature with limbs
+//! range-checked by 16-bit lookups takes about `2^21` rows.
+
+use std::num::NonZeroUsize;
+
+use halo2_proofs::circuit::Value;
+use num_bigint::BigUint;
+use rand_core::RngCore;
+
+use crate::{
+    ff::{Field, PrimeField, PrimeFieldBits},
+    gadgets::{
+        nonnative::{
+            bn::big_uint_mul_mod_chip::{Error, MAIN_GATE_T},
+            ecc::{AssignedNonNativePoint, NonNativeEccChip},
+            field::{AssignedNonNative, NonNativeField},
+        },
+        range::RangeConfig,
+    },
+    group::{prime::PrimeCurveAffine, Curve},
+    halo2curves::CurveAffine,
+    main_gate::RegionCtx,
+    util,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature<C: CurveAffine> {
+    pub r: C::ScalarExt,
+    pub s: C::ScalarExt,
+}
+
+/// Message hash as the scalar: the leftmost [`PrimeField::NUM_BITS`] bits of the big-endian
+/// `digest`, reduced by the scalar field modulus
+pub fn message_hash_to_scalar<C: CurveAffine>(digest: &[u8]) -> C::ScalarExt {
+    let num_bits = C::ScalarExt::NUM_BITS as usize;
+    let digest_bits = digest.len() * 8;
+
+    let mut hash = BigUint::from_bytes_be(digest);
+    if digest_bits > num_bits {
+        hash >>= digest_bits - num_bits;
+    }
+
+    util::fe_from_big(hash % util::modulus::<C::ScalarExt>()).expect("reduced by modulus")
+}
+
+/// `x mod n`
+fn x_to_scalar<C: CurveAffine>(point: &C) -> C::ScalarExt {
+    let x = *point.coordinates().unwrap().x();
+    util::fe_to_fe(&x).expect("reduced by modulus")
+}
+
+pub fn public_key<C: CurveAffine>(sk: &C::ScalarExt) -> C {
+    (C::generator() * sk).to_affine()
+}
+
+pub fn sign<C: CurveAffine>(
+    sk: &C::ScalarExt,
+    msg_hash: &C::ScalarExt,
+    mut rng: impl RngCore,
+) -> Signature<C> {
+    loop {
+        let k = C::ScalarExt::random(&mut rng);
+        let r = x_to_scalar(&public_key::<C>(&k));
+        if r.is_zero_vartime() {
+            continue;
+        }
+
+        let s = (*msg_hash + r * sk) * k.invert().unwrap();
+        if s.is_zero_vartime() {
+            continue;
+        }
+
+        return Signature { r, s };
+    }
+}
+
+pub fn verify<C: CurveAffine>(pk: &C, msg_hash: &C::ScalarExt, signature: &Signature<C>) -> bool {
+    let Signature { r, s } = signature;
+    let Some(s_inv) = Option::<C::ScalarExt>::from(s.invert()) else {
+        return false;
+    };
+    if r.is_zero_vartime() {
+        return false;
+    }
+
+    let point = (C::generator() * (*msg_hash * s_inv) + *pk * (*r * s_inv)).to_affine();
+    if bool::from(point.is_identity()) {
+        return false;
+    }
+
+    x_to_scalar(&point) == *r
+}
+
+/// Assigned [`Signature`]
+#[derive(Clone, Debug)]
+pub struct AssignedSignature<F: PrimeField, C: CurveAffine> {
+    pub r: AssignedNonNative<F, C::ScalarExt>,
+    pub s: AssignedNonNative<F, C::ScalarExt>,
+}
+
+/// Verification of [`Signature`] in the circuit, see [module-level](self) docs
+///
+/// Both fields of `C` use the same limbs layout, so the `x` coordinate can be reinterpreted as
+/// the scalar
+#[derive(Debug)]
+pub struct EcdsaChip<F: PrimeFieldBits, C: CurveAffine> {
+    ecc: NonNativeEccChip<F, C>,
+    scalar: NonNativeField<F, C::ScalarExt>,
+}
+
+impl<F: PrimeFieldBits, C: CurveAffine> EcdsaChip<F, C> {
+    /// Limbs are range-checked by lookups, the table must be loaded by
+    /// [`crate::gadgets::range::RangeChip::load`]
+    pub fn new(
+        config: RangeConfig<MAIN_GATE_T>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            ecc: NonNativeEccChip::new_with_range(config.clone(), limb_width, limbs_count)?,
+            scalar: NonNativeField::new_with_range(config, limb_width, limbs_count)?,
+        })
+    }
+
+    pub fn ecc(&self) -> &NonNativeEccChip<F, C> {
+        &self.ecc
+    }
+
+    /// Chip of the scalar field of `C`, e.g. to assign the message hash
+    pub fn scalar_field(&self) -> &NonNativeField<F, C::ScalarExt> {
+        &self.scalar
+    }
+
+    /// Assign `pk` & check it's on the curve
+    pub fn assign_public_key(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        pk: Value<C>,
+    ) -> Result<AssignedNonNativePoint<F, C>, Error> {
+        self.ecc.assign_point(ctx, pk)
+    }
+
+    pub fn assign_signature(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        signature: Value<Signature<C>>,
+    ) -> Result<AssignedSignature<F, C>, Error> {
+        Ok(AssignedSignature {
+            r: self
+                .scalar
+                .assign(ctx, signature.as_ref().map(|signature| signature.r))?,
+            s: self
+                .scalar
+                .assign(ctx, signature.as_ref().map(|signature| signature.s))?,
+        })
+    }
+
+    /// Check the `signature` of `msg_hash` by `pk`
+    ///
+    /// `pk` is expected to be assigned by [`EcdsaChip::assign_public_key`]. Besides `r, s != 0`
+    /// requires `pk != ±G`, see [`NonNativeEccChip::double_scalar_mul`]
+    pub fn verify(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        pk: &AssignedNonNativePoint<F, C>,
+        msg_hash: &AssignedNonNative<F, C::ScalarExt>,
+        signature: &AssignedSignature<F, C>,
+    ) -> Result<(), Error> {
+        let AssignedSignature { r, s } = signature;
+        let main_gate = self.scalar.main_gate();
+
+        let zero = self.scalar.assign_constant(ctx, C::ScalarExt::ZERO)?;
+        let is_r_zero = self.scalar.is_equal(ctx, r, &zero)?;
+        main_gate.assert_equal_const(ctx, is_r_zero, F::ZERO)?;
+
+        // Unsatisfied for `s = 0`
+        let s_inv = self.scalar.invert(ctx, s)?;
+        let u1 = self.scalar.mul(ctx, msg_hash, &s_inv)?;
+        let u2 = self.scalar.mul(ctx, r, &s_inv)?;
+        let u1_bits = self.scalar.to_bits(ctx, &u1)?;
+        let u2_bits = self.scalar.to_bits(ctx, &u2)?;
+
+        let g = self.ecc.assign_constant_point(ctx, C::generator())?;
+        let point = self
+            .ecc
+            .double_scalar_mul(ctx, &g, &u1_bits, pk, &u2_bits)?;
+
+        let x = self.x_to_scalar(ctx, &point)?;
+        self.scalar.assert_equal(ctx, &x, r)
+    }
+
+    /// `x mod n`, the same as the off-circuit [`x_to_scalar`]
+    ///
+    /// `x < p`, so it fits into limbs of the scalar & is reduced by the next operation on it
+    fn x_to_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedNonNativePoint<F, C>,
+    ) -> Result<AssignedNonNative<F, C::ScalarExt>, Error> {
+        let x = self.ecc.base_field().reduce(ctx, point.x())?;
+        self.scalar.assign_from_limbs(ctx, x.limbs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error as Halo2Error},
+    };
+    use rand_core::OsRng;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        gadgets::{keccak::off_circuit::keccak256, range::RangeChip},
+        halo2curves::{bn256::Fr, secp256k1::Secp256k1Affine},
+        main_gate::MainGate,
+        run_mock_prover_test,
+    };
+
+    type C = Secp256k1Affine;
+    type Scalar = <C as CurveAffine>::ScalarExt;
+
+    const K: u32 = 21;
+    const RANGE_LIMB_BITS: usize = 16;
+    const LIMB_WIDTH: NonZeroUsize = match NonZeroUsize::new(64) {
+        Some(width) => width,
+        None => unreachable!(),
+    };
+    const LIMBS_COUNT: NonZeroUsize = match NonZeroUsize::new(4) {
+        Some(count) => count,
+        None => unreachable!(),
+    };
+
+    struct TestCircuit {
+        pk: C,
+        msg_hash: Scalar,
+        signature: Signature<C>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = RangeConfig<MAIN_GATE_T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let main_gate = MainGate::configure(meta);
+            RangeChip::configure(meta, main_gate, RANGE_LIMB_BITS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Halo2Error> {
+            RangeChip::new(config.clone()).load(&mut layouter)?;
+            let chip = EcdsaChip::<Fr, C>::new(config, LIMB_WIDTH, LIMBS_COUNT).unwrap();
+
+            layouter.assign_region(
+                || "ecdsa",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+
+                    let pk = chip
+                        .assign_public_key(&mut ctx, Value::known(self.pk))
+                        .unwrap();
+                    let msg_hash = chip
+                        .scalar_field()
+                        .assign(&mut ctx, Value::known(self.msg_hash))
+                        .unwrap();
+                    let signature = chip
+                        .assign_signature(&mut ctx, Value::known(self.signature.clone()))
+                        .unwrap();
+
+                    chip.verify(&mut ctx, &pk, &msg_hash, &signature).unwrap();
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// Only the reinterpretation of `x` as the scalar, cheap enough to run with every test
+    struct XToScalarCircuit {
+        point: C,
+    }
+
+    impl Circuit<Fr> for XToScalarCircuit {
+        type Config = RangeConfig<MAIN_GATE_T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            TestCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Halo2Error> {
+            RangeChip::new(config.clone()).load(&mut layouter)?;
+            let chip = EcdsaChip::<Fr, C>::new(config, LIMB_WIDTH, LIMBS_COUNT).unwrap();
+
+            layouter.assign_region(
+                || "x to scalar",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+
+                    let point = chip
+                        .assign_public_key(&mut ctx, Value::known(self.point))
+                        .unwrap();
+                    let x = chip.x_to_scalar(&mut ctx, &point).unwrap();
+                    let expected = chip
+                        .scalar_field()
+                        .assign(&mut ctx, Value::known(x_to_scalar(&self.point)))
+                        .unwrap();
+
+                    chip.scalar_field()
+                        .assert_equal(&mut ctx, &x, &expected)
+                        .unwrap();
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn signed() -> TestCircuit {
+        let sk = Scalar::random(OsRng);
+        let msg_hash = message_hash_to_scalar::<C>(&keccak256(b"transfer 100 to bob"));
+
+        TestCircuit {
+            pk: public_key(&sk),
+            msg_hash,
+            signature: sign::<C>(&sk, &msg_hash, OsRng),
+        }
+    }
+
+    #[test]
+    fn off_circuit() {
+        let TestCircuit {
+            pk,
+            msg_hash,
+            signature,
+        } = signed();
+        assert!(verify(&pk, &msg_hash, &signature));
+
+        assert!(!verify(&pk, &(msg_hash + Scalar::ONE), &signature));
+        assert!(!verify(
+            &pk,
+            &msg_hash,
+            &Signature {
+                r: signature.r,
+                s: signature.s.double(),
+            }
+        ));
+        assert!(!verify(
+            &pk,
+            &msg_hash,
+            &Signature {
+                r: Scalar::ZERO,
+                s: signature.s,
+            }
+        ));
+    }
+
+    #[test]
+    fn message_hash_truncation() {
+        // 512-bit digest is truncated to the leftmost 256 bits
+        let mut digest = [0u8; 64];
+        digest[31] = 1;
+        assert_eq!(message_hash_to_scalar::<C>(&digest), Scalar::ONE);
+    }
+
+    #[traced_test]
+    #[test]
+    fn x_as_scalar() {
+        let point = public_key::<C>(&Scalar::random(OsRng));
+        run_mock_prover_test!(17, XToScalarCircuit { point }, Vec::<Vec<Fr>>::new());
+    }
+
+    #[test]
+    #[ignore = "cause it takes a few minutes to run"]
+    fn valid() {
+        let circuit = signed();
+        let prover = halo2_proofs::dev::MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    #[ignore = "cause it takes a few minutes to run"]
+    fn wrong_message() {
+        let mut circuit = signed();
+        circuit.msg_hash += Scalar::ONE;
+
+        let prover = halo2_proofs::dev::MockProver::run(K, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
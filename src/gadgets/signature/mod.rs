@@ -0,0 +1,11 @@
+//! Signature verification gadgets for step circuits
+//!
+//! - [`schnorr`] verifies Schnorr signatures over the curve, whose base field is the circuit
+//!   field, e.g. Grumpkin inside of BN254, on top of [`crate::gadgets::ecc::EccChip`]
+//! - [`ecdsa`] verifies ECDSA signatures over the foreign curve, e.g. secp256k1 inside of
+//!   BN254, on top of [`crate::gadgets::nonnative::ecc::NonNativeEccChip`]
+//!
+//! Each module also provides off-circuit signing & verification.
+
+pub mod ecdsa;
+pub mod schnorr;
@@ -0,0 +1,462 @@
+//! Schnorr signatures over the curve `C`, whose base field is the circuit field
+//!
+//! For the secret key `sk` & the public key `pk = sk * G`, the signature of the message
+//! `msg` (elements of `C::Base`) is `(r, s)`, where:
+//! - `r = k * G` for the random nonce `k`
+//! - `e = H(r, pk, msg)` is the challenge of [`NUM_CHALLENGE_BITS`] bits
+//! - `s = k + e * sk`
+//!
+//! The signature is valid if `s * G = r + e * pk`. The hash `H` is any [`ROPair`], so the
+//! same challenge is calculated off-circuit & on-circuit.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::Value, plonk::Error};
+use rand_core::RngCore;
+
+use crate::{
+    constants::NUM_CHALLENGE_BITS,
+    ff::{Field, FromUniformBytes, PrimeField, PrimeFieldBits},
+    gadgets::ecc::{AssignedPoint, EccChip},
+    group::{prime::PrimeCurveAffine, Curve},
+    halo2curves::CurveAffine,
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx},
+    poseidon::{ROCircuitTrait, ROPair, ROTrait},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature<C: CurveAffine> {
+    pub r: C,
+    pub s: C::ScalarExt,
+}
+
+/// Challenge `e = H(r, pk, msg)`
+pub fn challenge<C, RO>(ro_args: RO::Args, r: &C, pk: &C, msg: &[C::Base]) -> C::ScalarExt
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    RO: ROPair<C::Base>,
+{
+    RO::OffCircuit::new(ro_args)
+        .absorb_point(r)
+        .absorb_point(pk)
+        .absorb_field_iter(msg.iter().copied())
+        .squeeze::<C::ScalarExt>(NUM_CHALLENGE_BITS)
+}
+
+pub fn public_key<C: CurveAffine>(sk: &C::ScalarExt) -> C {
+    (C::generator() * sk).to_affine()
+}
+
+pub fn sign<C, RO>(
+    ro_args: RO::Args,
+    sk: &C::ScalarExt,
+    msg: &[C::Base],
+    rng: impl RngCore,
+) -> Signature<C>
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    RO: ROPair<C::Base>,
+{
+    let k = C::ScalarExt::random(rng);
+    let r = public_key::<C>(&k);
+    let e = challenge::<C, RO>(ro_args, &r, &public_key::<C>(sk), msg);
+
+    Signature { r, s: k + e * sk }
+}
+
+pub fn verify<C, RO>(ro_args: RO::Args, pk: &C, msg: &[C::Base], signature: &Signature<C>) -> bool
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    RO: ROPair<C::Base>,
+{
+    let e = challenge::<C, RO>(ro_args, &signature.r, pk, msg);
+    public_key::<C>(&signature.s) == (signature.r + (*pk * e).to_affine()).to_affine()
+}
+
+/// Assigned [`Signature`], `s` is decomposed into bits for the scalar multiplication
+#[derive(Clone, Debug)]
+pub struct AssignedSignature<C: CurveAffine> {
+    pub r: AssignedPoint<C>,
+    pub s_bits: Vec<AssignedBit<C::Base>>,
+}
+
+/// Verification of [`Signature`] in the circuit, see [module-level](self) docs
+///
+/// Requires `T >= 4`, because of [`EccChip`]
+pub struct SchnorrChip<C, RO, const T: usize>
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    RO: ROPair<C::Base>,
+{
+    ecc: EccChip<C, MainGate<C::Base, T>>,
+    main_gate: MainGate<C::Base, T>,
+    ro_config: RO::Config,
+    ro_args: RO::Args,
+    _p: PhantomData<RO>,
+}
+
+impl<C, RO, const T: usize> SchnorrChip<C, RO, T>
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    C::ScalarExt: PrimeFieldBits,
+    RO: ROPair<C::Base>,
+    RO::Config: Clone,
+{
+    pub fn new(config: MainGateConfig<T>, ro_config: RO::Config, ro_args: RO::Args) -> Self {
+        Self {
+            ecc: EccChip::new(config.clone()),
+            main_gate: MainGate::new(config),
+            ro_config,
+            ro_args,
+            _p: PhantomData,
+        }
+    }
+
+    /// Check `y^2 = x^3 + a * x + b`, the infinity `(0, 0)` is rejected too
+    pub fn assert_on_curve(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        p: &AssignedPoint<C>,
+    ) -> Result<(), Error> {
+        let (x, y) = p.coordinates();
+
+        let y_square = self.main_gate.mul(ctx, y, y)?;
+        let x_square = self.main_gate.mul(ctx, x, x)?;
+        let x_cube = self.main_gate.mul(ctx, &x_square, x)?;
+
+        let diff = self.main_gate.linear_combination(
+            ctx,
+            &[
+                (y_square, C::Base::ONE),
+                (x_cube, -C::Base::ONE),
+                (x.clone(), -C::a()),
+            ],
+            -C::b(),
+        )?;
+
+        self.main_gate.assert_equal_const(ctx, diff, C::Base::ZERO)
+    }
+
+    /// Assign `point` by coordinates & check it's on the curve
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        point: Value<C>,
+    ) -> Result<AssignedPoint<C>, Error> {
+        let coordinates = point.map(|point| {
+            Option::<_>::from(point.coordinates())
+                .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                .unwrap_or((C::Base::ZERO, C::Base::ZERO))
+        });
+
+        let point = AssignedPoint {
+            x: self
+                .main_gate
+                .assign_value(ctx, coordinates.map(|(x, _)| x))?,
+            y: self
+                .main_gate
+                .assign_value(ctx, coordinates.map(|(_, y)| y))?,
+        };
+        self.assert_on_curve(ctx, &point)?;
+
+        Ok(point)
+    }
+
+    /// Assign `pk` & check it's on the curve
+    pub fn assign_public_key(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        pk: Value<C>,
+    ) -> Result<AssignedPoint<C>, Error> {
+        self.assign_point(ctx, pk)
+    }
+
+    /// Assign `signature`, bits of `s` are constrained to be less than the order of `C`, so
+    /// `s` is canonical & the signature is not malleable
+    pub fn assign_signature(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        signature: Value<Signature<C>>,
+    ) -> Result<AssignedSignature<C>, Error> {
+        let num_bits = C::ScalarExt::NUM_BITS as usize;
+        let s_bits = signature.as_ref().map(|signature| {
+            signature
+                .s
+                .to_le_bits()
+                .into_iter()
+                .take(num_bits)
+                .collect::<Vec<_>>()
+        });
+
+        self.assign_signature_bits(ctx, signature.map(|signature| signature.r), s_bits)
+    }
+
+    fn assign_signature_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        r: Value<C>,
+        s_bits: Value<Vec<bool>>,
+    ) -> Result<AssignedSignature<C>, Error> {
+        let r = self.assign_point(ctx, r)?;
+
+        let s_bits = s_bits
+            .transpose_vec(C::ScalarExt::NUM_BITS as usize)
+            .into_iter()
+            .map(|bit| {
+                self.main_gate
+                    .assign_bit(ctx, bit.map(|bit| C::Base::from(bit as u64)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.assert_less_than_order(ctx, &s_bits)?;
+
+        Ok(AssignedSignature { r, s_bits })
+    }
+
+    /// Check little-endian `bits` represent an integer less than the order of `C`
+    ///
+    /// Goes from the most significant bit: `eq` is `1` while the bits are equal to the bits of the
+    /// order, `lt` becomes `1` at the first bit, which is zero where the bit of the order is one
+    fn assert_less_than_order(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        bits: &[AssignedBit<C::Base>],
+    ) -> Result<(), Error> {
+        let order_bits = C::ScalarExt::char_le_bits()
+            .into_iter()
+            .take(bits.len())
+            .collect::<Vec<_>>();
+
+        let mut lt = Option::<AssignedValue<C::Base>>::None;
+        let mut eq = Option::<AssignedValue<C::Base>>::None;
+
+        for (bit, order_bit) in bits.iter().zip(order_bits).rev() {
+            let not_bit = self.main_gate.linear_combination(
+                ctx,
+                &[(bit.clone(), -C::Base::ONE)],
+                C::Base::ONE,
+            )?;
+
+            if order_bit {
+                // lt = lt + eq * (1 - bit), eq = eq * bit
+                let eq_and_not_bit = match &eq {
+                    Some(eq) => self.main_gate.mul(ctx, eq, &not_bit)?,
+                    None => not_bit,
+                };
+                lt = Some(match lt {
+                    Some(lt) => self.main_gate.add(ctx, &lt, &eq_and_not_bit)?,
+                    None => eq_and_not_bit,
+                });
+                eq = Some(match eq {
+                    Some(eq) => self.main_gate.mul(ctx, &eq, bit)?,
+                    None => bit.clone(),
+                });
+            } else {
+                // eq = eq * (1 - bit)
+                eq = Some(match eq {
+                    Some(eq) => self.main_gate.mul(ctx, &eq, &not_bit)?,
+                    None => not_bit,
+                });
+            }
+        }
+
+        let lt = lt.expect("the most significant bit of the order is one");
+        self.main_gate.assert_equal_const(ctx, lt, C::Base::ONE)
+    }
+
+    /// Check `s * G = r + e * pk`, where `e = H(r, pk, msg)`
+    ///
+    /// `pk` & `signature` are expected to be assigned by [`SchnorrChip::assign_public_key`] &
+    /// [`SchnorrChip::assign_signature`]
+    pub fn verify(
+        &self,
+        ctx: &mut RegionCtx<'_, C::Base>,
+        pk: &AssignedPoint<C>,
+        msg: &[AssignedValue<C::Base>],
+        signature: &AssignedSignature<C>,
+    ) -> Result<(), Error> {
+        let generator = C::generator();
+        let generator_coordinates = generator.coordinates().unwrap();
+        let g = self.ecc.assign_from_curve(ctx, || "G", &generator)?;
+        let (g_x, g_y) = g.coordinates();
+        self.main_gate
+            .assert_equal_const(ctx, g_x.clone(), *generator_coordinates.x())?;
+        self.main_gate
+            .assert_equal_const(ctx, g_y.clone(), *generator_coordinates.y())?;
+
+        let (r_x, r_y) = signature.r.coordinates();
+        let (pk_x, pk_y) = pk.coordinates();
+        let e_bits = RO::OnCircuit::new(self.ro_config.clone(), self.ro_args.clone())
+            .absorb_point([r_x.into(), r_y.into()])
+            .absorb_point([pk_x.into(), pk_y.into()])
+            .absorb_iter(msg.iter())
+            .squeeze_n_bits(ctx, NUM_CHALLENGE_BITS)?;
+
+        let lhs = self.ecc.scalar_mul(ctx, &g, &signature.s_bits)?;
+
+        let e_pk = self.ecc.scalar_mul(ctx, pk, &e_bits)?;
+        let rhs = self.ecc.add(ctx, &signature.r, &e_pk)?;
+
+        let (lhs_x, lhs_y) = lhs.coordinates();
+        let (rhs_x, rhs_y) = rhs.coordinates();
+        ctx.constrain_equal(lhs_x.cell(), rhs_x.cell())?;
+        ctx.constrain_equal(lhs_y.cell(), rhs_y.cell())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem},
+    };
+    use rand_core::OsRng;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        halo2curves::{
+            bn256::{Fq, Fr},
+            grumpkin,
+        },
+        poseidon::{PoseidonRO, Spec},
+        run_mock_prover_test, util,
+    };
+
+    const T: usize = 5;
+    const RATE: usize = 4;
+    const R_F: usize = 10;
+    const R_P: usize = 10;
+
+    type C = grumpkin::G1Affine;
+    type RO = PoseidonRO<T, RATE>;
+
+    fn spec() -> Spec<Fr, T, RATE> {
+        Spec::new(R_F, R_P)
+    }
+
+    struct TestCircuit {
+        pk: C,
+        msg: Vec<Fr>,
+        signature: Signature<C>,
+        /// Assign `s + n` instead of `s`, where `n` is the order of `C`
+        non_canonical_s: bool,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = MainGateConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MainGate::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = SchnorrChip::<C, RO, T>::new(config.clone(), config, spec());
+
+            layouter.assign_region(
+                || "schnorr",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+
+                    let pk = chip.assign_public_key(&mut ctx, Value::known(self.pk))?;
+                    let signature = if self.non_canonical_s {
+                        let s = util::fe_to_big(&self.signature.s) + util::modulus::<Fq>();
+                        let s_bits = (0..Fq::NUM_BITS as u64).map(|i| s.bit(i)).collect();
+
+                        chip.assign_signature_bits(
+                            &mut ctx,
+                            Value::known(self.signature.r),
+                            Value::known(s_bits),
+                        )?
+                    } else {
+                        chip.assign_signature(&mut ctx, Value::known(self.signature.clone()))?
+                    };
+                    let msg = self
+                        .msg
+                        .iter()
+                        .map(|m| chip.main_gate.assign_value(&mut ctx, Value::known(*m)))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    chip.verify(&mut ctx, &pk, &msg, &signature)
+                },
+            )
+        }
+    }
+
+    fn signed() -> TestCircuit {
+        let sk = <C as CurveAffine>::ScalarExt::random(OsRng);
+        let msg = vec![Fr::from(1), Fr::from(2), Fr::from(3)];
+        let signature = sign::<C, RO>(spec(), &sk, &msg, OsRng);
+
+        TestCircuit {
+            pk: public_key(&sk),
+            msg,
+            signature,
+            non_canonical_s: false,
+        }
+    }
+
+    #[test]
+    fn off_circuit() {
+        let TestCircuit {
+            pk, msg, signature, ..
+        } = signed();
+        assert!(verify::<C, RO>(spec(), &pk, &msg, &signature));
+
+        let mut wrong_msg = msg.clone();
+        wrong_msg[0] += Fr::ONE;
+        assert!(!verify::<C, RO>(spec(), &pk, &wrong_msg, &signature));
+
+        let wrong_signature = Signature {
+            s: signature.s + <C as CurveAffine>::ScalarExt::ONE,
+            ..signature
+        };
+        assert!(!verify::<C, RO>(spec(), &pk, &msg, &wrong_signature));
+    }
+
+    #[traced_test]
+    #[test]
+    fn valid() {
+        run_mock_prover_test!(16, signed(), Vec::<Vec<Fr>>::new());
+    }
+
+    #[test]
+    fn wrong_message() {
+        let mut circuit = signed();
+        circuit.msg[1] += Fr::ONE;
+
+        let prover = halo2_proofs::dev::MockProver::run(16, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// `s + n` is the same scalar, but must be rejected: otherwise the signature is malleable
+    #[test]
+    fn non_canonical_s() {
+        // `s + n` fits into `NUM_BITS` only for small enough `s`
+        let bound = num_bigint::BigUint::from(1u8) << Fq::NUM_BITS;
+        let mut circuit = std::iter::repeat_with(signed)
+            .find(|circuit| util::fe_to_big(&circuit.signature.s) + util::modulus::<Fq>() < bound)
+            .unwrap();
+
+        run_mock_prover_test!(16, circuit, Vec::<Vec<Fr>>::new());
+
+        circuit.non_canonical_s = true;
+        let prover = halo2_proofs::dev::MockProver::run(16, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -4,8 +4,9 @@ use halo2_proofs::{
 };
 
 use crate::{
-    ff::PrimeField,
-    main_gate::{AssignedValue, MainGate, MainGateConfig, RegionCtx},
+    ff::{PrimeField, PrimeFieldBits},
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx, WrapValue},
+    util,
 };
 
 impl<F: PrimeField, const T: usize> MainGate<F, T> {
@@ -19,6 +20,20 @@ impl<F: PrimeField, const T: usize> MainGate<F, T> {
         Ok(out)
     }
 
+    /// Assign `c` fixed by the `rc` column
+    pub fn assign_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        c: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.apply(
+            ctx,
+            (None, None, None),
+            Some(c),
+            (-F::ONE, Value::known(c).into()),
+        )
+    }
+
     pub fn assign_bit(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -282,4 +297,104 @@ impl<F: PrimeField, const T: usize> MainGate<F, T> {
         let (_, b_inv) = self.invert_with_flag(ctx, b.clone())?;
         self.mul(ctx, a, &b_inv)
     }
+
+    /// Calculate `Σ coeff_i * term_i + constant`, by `T` terms per row
+    ///
+    /// Unlike [`MainGate::le_bits_to_num`], the accumulator is not a free witness: the first
+    /// row has no input and the `constant` is fixed by the `rc` column
+    pub fn linear_combination(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[(AssignedValue<F>, F)],
+        constant: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        let mut acc = Option::<AssignedValue<F>>::None;
+
+        for chunk in terms.chunks(T) {
+            let chunk_value = chunk
+                .iter()
+                .fold(Value::known(F::ZERO), |acc, (term, coeff)| {
+                    acc + term.value().copied() * Value::known(*coeff)
+                });
+            let (state, q_1): (Vec<WrapValue<F>>, Vec<F>) = chunk
+                .iter()
+                .map(|(term, coeff)| (term.into(), *coeff))
+                .unzip();
+
+            acc = Some(match acc {
+                None => self.apply(
+                    ctx,
+                    (Some(q_1), None, Some(state)),
+                    Some(constant),
+                    (-F::ONE, (chunk_value + Value::known(constant)).into()),
+                )?,
+                Some(acc) => {
+                    let out_value = acc.value().copied() + chunk_value;
+                    self.apply_with_input(
+                        ctx,
+                        (Some(q_1), None, Some(state)),
+                        (Some(F::ONE), Some(acc.into())),
+                        (-F::ONE, out_value.into()),
+                    )?
+                }
+            });
+        }
+
+        match acc {
+            Some(acc) => Ok(acc),
+            None => {
+                let assigned = self.assign_value(ctx, Value::known(constant))?;
+                self.assert_equal_const(ctx, assigned.clone(), constant)?;
+                Ok(assigned)
+            }
+        }
+    }
+}
+
+impl<F: PrimeFieldBits, const T: usize> MainGate<F, T> {
+    /// Decompose `value` into `bit_len` little-endian bits
+    ///
+    /// If `value` doesn't fit into `bit_len` bits, the circuit is unsatisfied. With `bit_len`
+    /// equal to [`PrimeField::NUM_BITS`] the decomposition is not unique, use
+    /// [`crate::gadgets::range::RangeChip::to_canonical_bits`] to also enforce `< modulus`
+    pub fn decompose_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        bit_len: usize,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        assert!(bit_len <= F::NUM_BITS as usize);
+
+        let le_bits = value.value().map(|value| {
+            value
+                .to_le_bits()
+                .into_iter()
+                .take(bit_len)
+                .collect::<Vec<_>>()
+        });
+
+        let bits = (0..bit_len)
+            .map(|i| self.assign_bit(ctx, le_bits.as_ref().map(|bits| F::from(bits[i] as u64))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let num = self.compose_bits(ctx, &bits)?;
+        ctx.constrain_equal(num.cell(), value.cell())?;
+
+        Ok(bits)
+    }
+
+    /// Compose little-endian bits into the number, bits are expected to be already constrained
+    pub fn compose_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bits: &[AssignedBit<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let terms = bits
+            .iter()
+            .cloned()
+            .zip(util::get_power_of_two_iter::<F>())
+            .collect::<Vec<_>>();
+
+        self.linear_combination(ctx, &terms, F::ZERO)
+    }
 }
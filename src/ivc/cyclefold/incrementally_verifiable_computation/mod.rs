@@ -1,10 +1,10 @@
-use std::{marker::PhantomData, num::NonZeroUsize};
+use std::{iter, marker::PhantomData, num::NonZeroUsize};
 
 use itertools::Itertools;
 use tracing::{error, info_span, trace};
 
 use super::{
-    ro,
+    ro, ro_acc, ro_nark,
     support_circuit::{self, SupportCircuit},
 };
 use crate::{
@@ -19,14 +19,15 @@ use crate::{
     },
     ivc::{
         cyclefold::sfc::{self, StepFoldingCircuit},
-        step_circuit, StepCircuit,
+        sangria::instances_accumulator_computation,
+        step_circuit, IvcState, StepCircuit,
     },
     nifs::{
         self,
         protogalaxy::{AccumulatorArgs, ProtoGalaxy},
         sangria::VanillaFS,
     },
-    plonk::{eval, PlonkTrace},
+    plonk::{eval, PlonkInstance, PlonkTrace},
     polynomial::lagrange,
     poseidon::random_oracle::ROTrait,
     sangria_prelude::CommitmentKey,
@@ -34,19 +35,26 @@ use crate::{
     util,
 };
 
-type SangriaFS<C> = VanillaFS<C, { support_circuit::INSTANCES_LEN }>;
+type SangriaFS<C, const SUPPORT_INSTANCES_LEN: usize> = VanillaFS<C, SUPPORT_INSTANCES_LEN>;
 
-type SangriaRelaxedPlonkTrace<C> =
-    nifs::sangria::RelaxedPlonkTrace<C, { support_circuit::INSTANCES_LEN }>;
+type SangriaRelaxedPlonkTrace<C, const SUPPORT_INSTANCES_LEN: usize> =
+    nifs::sangria::RelaxedPlonkTrace<C, SUPPORT_INSTANCES_LEN>;
 
-type SangriaFoldablePlonkInstance<C> =
-    nifs::sangria::accumulator::FoldablePlonkInstance<C, { support_circuit::INSTANCES_LEN }>;
+type SangriaFoldablePlonkInstance<C, const SUPPORT_INSTANCES_LEN: usize> =
+    nifs::sangria::accumulator::FoldablePlonkInstance<C, SUPPORT_INSTANCES_LEN>;
 
-mod public_params;
-pub use public_params::PublicParams;
+pub(super) mod public_params;
+pub use public_params::{Error as PublicParamsError, PublicParams, PublicParamsStats};
 
-pub struct IVC<const ARITY: usize, CMain, CSup, SC>
-where
+/// `SUPPORT_INSTANCES_LEN` sets the capacity of the support circuit, i.e. how many EC operations
+/// are delegated to one of its traces, see [`super::support_instances_len`]
+pub struct IVC<
+    const ARITY: usize,
+    CMain,
+    CSup,
+    SC,
+    const SUPPORT_INSTANCES_LEN: usize = { support_circuit::DEFAULT_INSTANCES_LEN },
+> where
     CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
     CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
     SC: StepCircuit<ARITY, CMain::Scalar>,
@@ -60,12 +68,35 @@ where
     primary_z_current: [CMain::Scalar; ARITY],
     primary_z_0: [CMain::Scalar; ARITY],
 
-    support_acc: nifs::sangria::RelaxedPlonkTrace<CSup, { support_circuit::INSTANCES_LEN }>,
+    /// Hash chain of the step circuit instances of all steps, except the last one
+    primary_sc_instances_hash_acc: CMain::Scalar,
+    /// Step circuit instances of all steps, including the last one
+    primary_pub_instances: Vec<Vec<Vec<CMain::Scalar>>>,
+
+    support_acc: SangriaRelaxedPlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
+
+    /// Instances folded at the last step, `None` before the first non-formal step
+    last_step: Option<LastStep<CMain, CSup, SUPPORT_INSTANCES_LEN>>,
 
     _p: PhantomData<(CMain, CSup, SC)>,
 }
 
-impl<const ARITY: usize, CMain, CSup, SC> IVC<ARITY, CMain, CSup, SC>
+/// Instance part of the last [`IVC::next_with_witness`], enough to repeat the verifier side of
+/// its folding, see [`IVC::check_last_step`]
+struct LastStep<CMain: CurveAffine, CSup: CurveAffine, const SUPPORT_INSTANCES_LEN: usize> {
+    primary_input_acc: nifs::protogalaxy::AccumulatorInstance<CMain>,
+    primary_incoming: PlonkInstance<CMain>,
+    primary_proof: nifs::protogalaxy::Proof<CMain::ScalarExt>,
+
+    support_input_acc: nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    support_incoming: Vec<(
+        SangriaFoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        nifs::sangria::CrossTermCommits<CSup>,
+    )>,
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize>
+    IVC<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
 where
     CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
     CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
@@ -73,24 +104,29 @@ where
     CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
     CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
 {
+    /// Zero step is formal, its output is `z_0`, so the step circuit is synthesized on the
+    /// default [`StepCircuit::StepWitness`]
     pub fn new(
-        pp: &mut PublicParams<ARITY, CMain, CSup, SC>,
+        pp: &mut PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
         sc: &SC,
         z_0: [CMain::ScalarExt; ARITY],
-    ) -> Result<Self, Error<CMain>> {
+    ) -> Result<Self, Error<CMain>>
+    where
+        SC::StepWitness: Default,
+    {
         let _span = info_span!("ivc_new", step = 0).entered();
 
         let primary_initial_acc = ProtoGalaxy::<CMain, 1>::new_accumulator(
             AccumulatorArgs::from(&pp.primary_S),
             &pp.protogalaxy_prover_params(),
-            &mut ro(),
+            &mut ro_acc(),
             pp.primary_initial_trace.clone(),
         )
         .map_err(Error::WhileProtoGalaxyAccCreation)?;
 
         // At zero step cyclefold ivc - output protogalaxy-accumulator is input
         // protogalaxy-accumulator. Bug proof still should be valid.
-        let mut random_oracle = ro();
+        let mut random_oracle = ro_acc();
         let (_new_acc, self_proof) = ProtoGalaxy::prove(
             &pp.primary_ck,
             &pp.protogalaxy_prover_params(),
@@ -107,8 +143,8 @@ where
             assert_eq!(
                 ProtoGalaxy::verify(
                     &pp.protogalaxy_verifier_params(),
-                    &mut ro(),
-                    &mut ro(),
+                    &mut ro_nark(),
+                    &mut ro_acc(),
                     &primary_initial_acc.clone().into(),
                     &[pp.primary_initial_trace.u.clone()],
                     &self_proof,
@@ -120,7 +156,7 @@ where
 
         let support_initial_acc = nifs::sangria::accumulator::RelaxedPlonkTrace::from_regular(
             pp.support_initial_trace.clone(),
-            SupportCircuit::<CMain>::MIN_K_TABLE_SIZE as usize,
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE as usize,
         );
 
         // At zero step cyclefold ivc - output sangria-accumulator is input
@@ -128,7 +164,7 @@ where
         let SupportCircuitFoldResult {
             new_accumulator: _new_new_accumulator,
             incoming: support_incoming,
-        } = fold_support_circuit::<CMain, CSup>(
+        } = fold_support_circuit::<CMain, CSup, SUPPORT_INSTANCES_LEN>(
             &pp.support_ck,
             &pp.sangria_prover_params(),
             &support_initial_acc,
@@ -137,13 +173,22 @@ where
                 .W_commitments
                 .iter()
                 .copied()
-                .zip_eq(pp.primary_initial_trace.u.W_commitments.iter().copied()),
-            CMain::Base::ZERO, // for zero step
-            CMain::Base::ZERO, // for zero step
+                .zip_eq(pp.primary_initial_trace.u.W_commitments.iter().copied())
+                .map(|(p0, p1)| support_circuit::InstanceInput {
+                    p0,
+                    l0: CMain::Base::ZERO, // for zero step
+                    p1,
+                    l1: CMain::Base::ZERO, // for zero step
+                }),
         )?;
 
+        let primary_initial_sc_instances_hash_acc =
+            instances_accumulator_computation::get_initial_native_sc_instances_accumulator();
+
+        let primary_step_witness = SC::StepWitness::default();
         let primary_sfc = StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC> {
             sc,
+            witness: &primary_step_witness,
             input: sfc::InputBuilder {
                 step: 0,
                 pp_digest: pp.pp_digest_coordinates(),
@@ -154,12 +199,14 @@ where
                 self_acc: &primary_initial_acc.clone().into(),
                 z_i: z_0,
                 z_0,
+                step_circuit_instances_hash_accumulator: primary_initial_sc_instances_hash_acc,
             }
             .build(),
             _p: PhantomData,
         };
 
         let primary_initial_instances = primary_sfc.initial_instances();
+        let primary_sc_instances_hash_acc = primary_sfc.step_circuit_instances_hash_accumulator();
 
         #[cfg(test)]
         {
@@ -191,7 +238,7 @@ where
             &primary_initial_instances,
             &primary_witness,
             &pp.protogalaxy_prover_params(),
-            &mut ro(),
+            &mut ro_nark(),
         )?;
 
         Ok(Self {
@@ -200,17 +247,39 @@ where
             // on-circuit) - we just take initial acc-s & z_0
             primary_z_current: z_0,
             primary_z_0: z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances: vec![sc.instances()],
             primary_trace: primary_post_initial_trace,
             primary_acc: primary_initial_acc,
             support_acc: support_initial_acc,
+            last_step: None,
             _p: PhantomData,
         })
     }
 
+    /// Fold the next step, synthesized on the default [`StepCircuit::StepWitness`]
+    ///
+    /// See [`IVC::next_with_witness`] for step circuits with non-deterministic advice
     pub fn next(
         self,
-        pp: &PublicParams<ARITY, CMain, CSup, SC>,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
         sc: &SC,
+    ) -> Result<Self, Error<CMain>>
+    where
+        SC::StepWitness: Default,
+    {
+        self.next_with_witness(pp, sc, &SC::StepWitness::default())
+    }
+
+    /// Fold the next step, the step circuit is synthesized on `witness`
+    ///
+    /// So `sc` itself stays the same for all steps & the witness of each step can be taken from
+    /// any external source
+    pub fn next_with_witness(
+        self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+        sc: &SC,
+        witness: &SC::StepWitness,
     ) -> Result<Self, Error<CMain>> {
         let _span = info_span!("ivc_next", step = self.step.get()).entered();
 
@@ -220,11 +289,14 @@ where
             primary_trace,
             primary_z_current,
             primary_z_0,
+            primary_sc_instances_hash_acc,
+            mut primary_pub_instances,
             support_acc,
+            last_step: _,
             _p,
         } = self;
 
-        let mut random_oracle = ro();
+        let mut random_oracle = ro_acc();
         let (primary_next_acc, primary_proof) = ProtoGalaxy::prove(
             &pp.primary_ck,
             &pp.protogalaxy_prover_params(),
@@ -252,7 +324,7 @@ where
         let SupportCircuitFoldResult {
             new_accumulator: support_next_acc,
             incoming: support_incoming,
-        } = fold_support_circuit::<CMain, CSup>(
+        } = fold_support_circuit::<CMain, CSup, SUPPORT_INSTANCES_LEN>(
             &pp.support_ck,
             &pp.sangria_prover_params(),
             &support_acc,
@@ -261,35 +333,45 @@ where
                 .W_commitments
                 .iter()
                 .copied()
-                .zip_eq(primary_trace.u.W_commitments.iter().copied()),
-            l0,
-            l1,
+                .zip_eq(primary_trace.u.W_commitments.iter().copied())
+                .map(|(p0, p1)| support_circuit::InstanceInput { p0, l0, p1, l1 }),
         )?;
 
-        let primary_z_next = sc.process_step(&primary_z_current, pp.primary_k_table_size)?;
+        let primary_input_acc = nifs::protogalaxy::AccumulatorInstance::from(primary_acc);
 
         let primary_sfc = StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC> {
             sc,
+            witness,
             input: sfc::InputBuilder {
                 step: step.get(),
                 pp_digest: pp.pp_digest_coordinates(),
                 z_i: primary_z_current,
                 z_0: primary_z_0,
                 self_incoming: &primary_trace.u,
-                self_proof: primary_proof,
+                self_proof: primary_proof.clone(),
                 support_acc: &support_acc.U,
                 support_incoming: support_incoming.as_slice(),
-                self_acc: &primary_acc.into(),
+                self_acc: &primary_input_acc,
+                step_circuit_instances_hash_accumulator: primary_sc_instances_hash_acc,
             }
             .build(),
             _p: PhantomData,
         };
 
+        // Step circuit is synthesized only here, its output is taken from the same synthesis
+        let (primary_witness, primary_z_next) = primary_sfc
+            .collect_witness_with_output(&pp.primary_layout)
+            .map_err(|err| Error::WhileCollectPrimaryWitness {
+                step: step.get(),
+                err,
+            })?;
+
         let primary_instances = primary_sfc.instances(
             &primary_next_acc.clone().into(),
             &support_next_acc.U,
             &primary_z_next,
         );
+        primary_pub_instances.push(primary_instances[1..].to_vec());
 
         #[cfg(test)]
         {
@@ -304,23 +386,12 @@ where
             .unwrap();
         }
 
-        let primary_witness = CircuitRunner::new(
-            pp.primary_k_table_size,
-            primary_sfc,
-            primary_instances.clone(),
-        )
-        .try_collect_witness()
-        .map_err(|err| Error::WhileCollectPrimaryWitness {
-            step: step.get(),
-            err,
-        })?;
-
         let primary_next_trace = ProtoGalaxy::<CMain, 1>::generate_plonk_trace(
             &pp.primary_ck,
             &primary_instances,
             &primary_witness,
             &pp.protogalaxy_prover_params(),
-            &mut ro(),
+            &mut ro_nark(),
         )?;
 
         Ok(Self {
@@ -329,12 +400,24 @@ where
             primary_trace: primary_next_trace,
             primary_z_current: primary_z_next,
             primary_z_0,
+            primary_sc_instances_hash_acc: primary_sfc.step_circuit_instances_hash_accumulator(),
+            primary_pub_instances,
             support_acc: support_next_acc,
+            last_step: Some(LastStep {
+                primary_input_acc,
+                primary_incoming: primary_trace.u,
+                primary_proof,
+                support_input_acc: support_acc.U,
+                support_incoming,
+            }),
             _p,
         })
     }
 
-    pub fn verify(self, pp: &PublicParams<ARITY, CMain, CSup, SC>) -> Result<Self, Error<CMain>> {
+    pub fn verify(
+        self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+    ) -> Result<Self, Error<CMain>> {
         let _span = info_span!("ivc_verify").entered();
         let Self {
             step,
@@ -342,12 +425,32 @@ where
             primary_trace,
             primary_z_current,
             primary_z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances,
             support_acc,
+            last_step: _,
             _p,
         } = &self;
 
         let mut errors: Vec<VerifyError<CMain>> = vec![];
 
+        let (last_pub_instances, previous_pub_instances) = primary_pub_instances
+            .split_last()
+            .expect("safe: instances are collected at each step");
+
+        let expected_sc_instances_hash_acc = sc_instances_hash_acc(previous_pub_instances);
+
+        if expected_sc_instances_hash_acc != *primary_sc_instances_hash_acc {
+            errors.push(VerifyError::MismatchStepCircuitInstancesHashAcc {
+                expected: expected_sc_instances_hash_acc,
+                actual: *primary_sc_instances_hash_acc,
+            });
+        }
+
+        if primary_trace.u.instances[1..] != last_pub_instances[..] {
+            errors.push(VerifyError::MismatchStepCircuitInstances { step: step.get() });
+        }
+
         if let Err(err) = VerifyError::is_mismatch_proto_galaxy_consistency_marker(
             ro().absorb(
                 &sfc::InputBuilder {
@@ -357,6 +460,7 @@ where
                     support_acc: &support_acc.U,
                     z_i: *primary_z_current,
                     z_0: *primary_z_0,
+                    step_circuit_instances_hash_accumulator: expected_sc_instances_hash_acc,
 
                     // next fields not used in absorb
                     self_incoming: &primary_trace.u,
@@ -380,8 +484,12 @@ where
             errors.push(VerifyError::WhileProtoGalaxyIsSat(err))
         }
 
-        if let Err(err) = SangriaFS::<CSup>::is_sat(&pp.support_ck, &pp.support_S, support_acc, &[])
-        {
+        if let Err(err) = SangriaFS::<CSup, SUPPORT_INSTANCES_LEN>::is_sat(
+            &pp.support_ck,
+            &pp.support_S,
+            support_acc,
+            &[],
+        ) {
             errors.push(VerifyError::WhileSangriaIsSat(err))
         }
 
@@ -391,24 +499,200 @@ where
             Err(Error::Verify(errors.into_boxed_slice()))
         }
     }
+
+    /// Lightweight check of the last step only
+    ///
+    /// Unlike [`IVC::verify`], accumulated witnesses are not touched, so the cost doesn't depend
+    /// on the circuit size. The consistency marker & the special soundness protocol of the last
+    /// incoming trace are checked, and the verifier side of the last folding of both accumulators
+    /// is repeated on instances. The hash chain of step circuit instances and satisfiability of
+    /// the accumulators are checked only by [`IVC::verify`]
+    pub fn check_last_step(
+        &self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+    ) -> Result<(), Error<CMain>> {
+        let _span = info_span!("ivc_check_last_step", step = self.step.get()).entered();
+        let Self {
+            step,
+            primary_acc,
+            primary_trace,
+            primary_z_current,
+            primary_z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances,
+            support_acc,
+            last_step,
+            _p,
+        } = self;
+
+        let mut errors: Vec<VerifyError<CMain>> = vec![];
+
+        let primary_acc_instance = primary_acc.instance();
+
+        if primary_pub_instances
+            .last()
+            .is_some_and(|last| primary_trace.u.instances[1..] != last[..])
+        {
+            errors.push(VerifyError::MismatchStepCircuitInstances { step: step.get() });
+        }
+
+        if let Err(err) = VerifyError::is_mismatch_proto_galaxy_consistency_marker(
+            ro().absorb(
+                &sfc::InputBuilder {
+                    step: step.get(),
+                    pp_digest: pp.pp_digest_coordinates(),
+                    self_acc: &primary_acc_instance,
+                    support_acc: &support_acc.U,
+                    z_i: *primary_z_current,
+                    z_0: *primary_z_0,
+                    step_circuit_instances_hash_accumulator: *primary_sc_instances_hash_acc,
+
+                    // next fields not used in absorb
+                    self_incoming: &primary_trace.u,
+                    self_proof: nifs::protogalaxy::Proof::default(),
+                    support_incoming: &[],
+                }
+                .build(),
+            )
+            .output(
+                NonZeroUsize::new(<CMain::ScalarExt as PrimeField>::NUM_BITS as usize).unwrap(),
+            ),
+            primary_trace.u.instances[0][0],
+        ) {
+            errors.push(err);
+        }
+
+        if let Err(err) =
+            ProtoGalaxy::<CMain, 1>::verify_sps(iter::once(&primary_trace.u), &mut ro_nark())
+        {
+            errors.push(VerifyError::WhileSpsVerify(err));
+        }
+
+        // After the zero step accumulators are the initial ones, so there is no folding to repeat
+        if let Some(LastStep {
+            primary_input_acc,
+            primary_incoming,
+            primary_proof,
+            support_input_acc,
+            support_incoming,
+        }) = last_step
+        {
+            match ProtoGalaxy::<CMain, 1>::verify(
+                &pp.protogalaxy_verifier_params(),
+                &mut ro_nark(),
+                &mut ro_acc(),
+                primary_input_acc,
+                &[primary_incoming.clone()],
+                primary_proof,
+            ) {
+                Ok(folded) if folded == primary_acc_instance => {}
+                Ok(_) => errors.push(VerifyError::MismatchProtoGalaxyFold),
+                Err(err) => errors.push(VerifyError::WhileProtoGalaxyVerify(err)),
+            }
+
+            match verify_support_fold(
+                pp.pp_digest_coordinates(),
+                support_input_acc,
+                support_incoming,
+            ) {
+                Ok(folded) if folded == support_acc.U => {}
+                Ok(_) => errors.push(VerifyError::MismatchSangriaFold),
+                Err((index, err)) => errors.push(VerifyError::WhileSangriaVerify { index, err }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Verify(errors.into_boxed_slice()))
+        }
+    }
+
+    /// Step circuit instances of each step, in the order of steps
+    ///
+    /// Their hash chain is a part of the consistency marker and is checked by
+    /// [`IVC::verify`]
+    pub fn pub_instances(&self) -> &[Vec<Vec<CMain::Scalar>>] {
+        &self.primary_pub_instances
+    }
+}
+
+/// Hash chain of step circuit instances of all steps, except the last one, as it's expected in
+/// the consistency marker of the last step
+pub(in crate::ivc::cyclefold) fn sc_instances_hash_acc<F>(
+    previous_pub_instances: &[Vec<Vec<F>>],
+) -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    previous_pub_instances.iter().fold(
+        instances_accumulator_computation::get_initial_native_sc_instances_accumulator(),
+        |acc, instances| {
+            instances_accumulator_computation::absorb_in_native_sc_instances_accumulator(
+                &acc, instances,
+            )
+        },
+    )
+}
+
+/// Verifier side of [`fold_support_circuit`]: fold `incoming` support traces one by one into
+/// `accumulator`, in case of error the index of the failed trace is returned with it
+pub(in crate::ivc::cyclefold) fn verify_support_fold<CSup, const SUPPORT_INSTANCES_LEN: usize>(
+    pp_digest: (CSup::Base, CSup::Base),
+    accumulator: &nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    incoming: &[(
+        SangriaFoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        nifs::sangria::CrossTermCommits<CSup>,
+    )],
+) -> Result<
+    nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    (usize, nifs::sangria::Error),
+>
+where
+    CSup: CurveAffine,
+    CSup::Base: PrimeFieldBits + FromUniformBytes<64>,
+{
+    let vp = nifs::sangria::VerifierParam::<CSup>::from(pp_digest);
+
+    incoming
+        .iter()
+        .enumerate()
+        .try_fold(accumulator.clone(), |acc, (index, (incoming, proof))| {
+            SangriaFS::<CSup, SUPPORT_INSTANCES_LEN>::verify(
+                &vp,
+                &mut ro_nark(),
+                &mut ro_acc(),
+                &acc,
+                &[incoming.clone()],
+                proof,
+            )
+            .map_err(|err| (index, err))
+        })
 }
 
-struct SupportCircuitFoldResult<C: CurveAffine> {
-    new_accumulator: SangriaRelaxedPlonkTrace<C>,
-    incoming: Vec<(
-        SangriaFoldablePlonkInstance<C>,
+pub(in crate::ivc::cyclefold) struct SupportCircuitFoldResult<
+    C: CurveAffine,
+    const SUPPORT_INSTANCES_LEN: usize,
+> {
+    pub new_accumulator: SangriaRelaxedPlonkTrace<C, SUPPORT_INSTANCES_LEN>,
+    pub incoming: Vec<(
+        SangriaFoldablePlonkInstance<C, SUPPORT_INSTANCES_LEN>,
         nifs::sangria::CrossTermCommits<C>,
     )>,
 }
 
-fn fold_support_circuit<CMain, CSup>(
+/// Batch `inputs` into [`SupportCircuit::traces_count`] support circuit traces and fold them one
+/// by one into `accumulator`
+pub(in crate::ivc::cyclefold) fn fold_support_circuit<
+    CMain,
+    CSup,
+    const SUPPORT_INSTANCES_LEN: usize,
+>(
     support_ck: &CommitmentKey<CSup>,
     prover_params: &nifs::sangria::ProverParam<CSup>,
-    accumulator: &SangriaRelaxedPlonkTrace<CSup>,
-    W_commitments_pairs: impl Iterator<Item = (CMain, CMain)>,
-    l0: CMain::Base,
-    l1: CMain::Base,
-) -> Result<SupportCircuitFoldResult<CSup>, nifs::sangria::Error>
+    accumulator: &SangriaRelaxedPlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
+    inputs: impl Iterator<Item = support_circuit::InstanceInput<CMain>>,
+) -> Result<SupportCircuitFoldResult<CSup, SUPPORT_INSTANCES_LEN>, nifs::sangria::Error>
 where
     CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
     CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
@@ -417,15 +701,15 @@ where
 {
     let _support = info_span!("support").entered();
 
-    let traces = W_commitments_pairs
-        .map(|(p0, p1)| support_circuit::InstanceInput::<CMain> { p0, p1, l0, l1 }.into_instance())
+    let traces = SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::instances(inputs)
+        .into_iter()
         .map(|instances| {
             #[cfg(test)]
             {
                 let _mock = info_span!("mock_debug").entered();
                 crate::halo2_proofs::dev::MockProver::run(
-                    SupportCircuit::<CMain>::MIN_K_TABLE_SIZE,
-                    &SupportCircuit::<CMain>::default(),
+                    SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+                    &SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::default(),
                     instances.clone(),
                 )
                 .unwrap()
@@ -434,18 +718,18 @@ where
             }
 
             let witness = CircuitRunner::<CMain::Base, _>::new(
-                SupportCircuit::<CMain>::MIN_K_TABLE_SIZE,
-                SupportCircuit::<CMain>::default(),
+                SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+                SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::default(),
                 instances.clone(),
             )
             .try_collect_witness()?;
 
-            SangriaFS::<CSup>::generate_plonk_trace(
+            SangriaFS::<CSup, SUPPORT_INSTANCES_LEN>::generate_plonk_trace(
                 support_ck,
                 &instances,
                 &witness,
                 prover_params,
-                &mut ro(),
+                &mut ro_nark(),
             )
         })
         .collect::<Result<Vec<_>, _>>()?;
@@ -453,10 +737,10 @@ where
     let mut new_accumulator = accumulator.clone();
     let mut paired_incoming = vec![];
     for trace in traces {
-        let (next_acc, proof) = SangriaFS::<CSup>::prove(
+        let (next_acc, proof) = SangriaFS::<CSup, SUPPORT_INSTANCES_LEN>::prove(
             support_ck,
             prover_params,
-            &mut ro(),
+            &mut ro_acc(),
             new_accumulator,
             &[trace.clone()],
         )?;
@@ -503,11 +787,56 @@ pub enum VerifyError<CMain: CurveAffine> {
         expected: CMain::ScalarExt,
         actual: CMain::ScalarExt,
     },
+    #[error("Mismatch step circuit instances hash accumulator: {expected:?} != {actual:?}")]
+    MismatchStepCircuitInstancesHashAcc {
+        expected: CMain::ScalarExt,
+        actual: CMain::ScalarExt,
+    },
+
+    #[error("Mismatch step circuit instances of the last step {step}")]
+    MismatchStepCircuitInstances { step: usize },
+
     #[error("While is sat protogalaxy acc: {0:?}")]
     WhileProtoGalaxyIsSat(Vec<nifs::protogalaxy::VerifyError<CMain::ScalarExt>>),
 
     #[error("While is sat protogalaxy acc: {0:?}")]
     WhileSangriaIsSat(Vec<nifs::sangria::VerifyError>),
+
+    #[error("While sps verify of the last incoming trace: {0:?}")]
+    WhileSpsVerify(nifs::protogalaxy::Error),
+
+    #[error("While protogalaxy verify of the last step: {0:?}")]
+    WhileProtoGalaxyVerify(nifs::protogalaxy::Error),
+
+    #[error("Mismatch protogalaxy acc with the one folded at the last step")]
+    MismatchProtoGalaxyFold,
+
+    #[error("While sangria verify of the last step, support trace {index}: {err:?}")]
+    WhileSangriaVerify {
+        index: usize,
+        err: nifs::sangria::Error,
+    },
+
+    #[error("Mismatch sangria acc with the one folded at the last step")]
+    MismatchSangriaFold,
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize> IvcState
+    for IVC<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
+where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    fn step(&self) -> usize {
+        self.step.get()
+    }
+
+    fn accumulator_size(&self) -> usize {
+        self.primary_acc.trace.w.size() + self.support_acc.W.size()
+    }
 }
 
 impl<CMain: CurveAffine> VerifyError<CMain> {
@@ -533,8 +862,12 @@ mod tests {
     use crate::{
         commitment::CommitmentKey,
         halo2_proofs::arithmetic::Field,
-        ivc::step_circuit::trivial,
-        sangria_prelude::bn256::{C1Affine, C1Scalar, C2Affine},
+        ivc::{
+            cyclefold::support_instances_len,
+            step_circuit::{lookup, trivial},
+            StepCircuit,
+        },
+        sangria_prelude::bn256::{C1Affine, C1Scalar, C2Affine, C2Scalar},
     };
 
     /// Arity : Input/output size per fold-step for primary step-circuit
@@ -549,11 +882,21 @@ mod tests {
 
     const FOLDER: &str = ".cache/examples";
 
-    #[traced_test]
-    #[test]
-    fn ivc() {
-        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+    type PublicParams<SC> = super::PublicParams<ARITY, C1Affine, C2Affine, SC>;
 
+    fn new_pp<SC: StepCircuit<ARITY, C1Scalar>>(sc: &SC) -> PublicParams<SC>
+    where
+        SC::StepWitness: Default,
+    {
+        new_pp_with_support(sc)
+    }
+
+    fn new_pp_with_support<SC: StepCircuit<ARITY, C1Scalar>, const SUPPORT_INSTANCES_LEN: usize>(
+        sc: &SC,
+    ) -> super::PublicParams<ARITY, C1Affine, C2Affine, SC, SUPPORT_INSTANCES_LEN>
+    where
+        SC::StepWitness: Default,
+    {
         let primary_commitment_key = unsafe {
             CommitmentKey::<C1Affine>::load_or_setup_cache(
                 Path::new(FOLDER),
@@ -574,8 +917,8 @@ mod tests {
 
         info!("ck generated");
 
-        let mut pp = super::PublicParams::new(
-            &sc,
+        let pp = super::PublicParams::new(
+            sc,
             primary_commitment_key,
             secondary_commitment_key,
             PRIMARY_CIRCUIT_TABLE_SIZE,
@@ -583,13 +926,208 @@ mod tests {
         .unwrap();
         info!("pp created");
 
-        super::IVC::new(&mut pp, &sc, array::from_fn(|_| C1Scalar::ZERO))
+        pp
+    }
+
+    fn run_ivc<SC: StepCircuit<ARITY, C1Scalar>, const SUPPORT_INSTANCES_LEN: usize>(
+        sc: &SC,
+        mut pp: super::PublicParams<ARITY, C1Affine, C2Affine, SC, SUPPORT_INSTANCES_LEN>,
+    ) where
+        SC::StepWitness: Default,
+    {
+        let ivc =
+            super::IVC::new(&mut pp, sc, array::from_fn(|_| C1Scalar::ZERO)).expect("while step=0");
+        ivc.check_last_step(&pp).expect("while check step=0");
+
+        let ivc = ivc
+            .next(&pp, sc)
+            .expect("while step=1")
+            .next(&pp, sc)
+            .expect("while step=2");
+        ivc.check_last_step(&pp).expect("while check step=2");
+
+        ivc.verify(&pp).expect("while verify");
+    }
+
+    #[traced_test]
+    #[test]
+    fn ivc() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        run_ivc(&sc, new_pp(&sc));
+    }
+
+    /// Public params are created by the cycle type only, without naming its curves
+    #[traced_test]
+    #[test]
+    fn ivc_pallas_vesta() {
+        use crate::{
+            cyclefold_prelude::{new_default_pp, CurveCycle, PallasVesta},
+            group::prime::PrimeCurveAffine,
+        };
+
+        type Scalar = <<PallasVesta as CurveCycle>::C1 as PrimeCurveAffine>::Scalar;
+
+        let sc = trivial::Circuit::<ARITY, Scalar>::default();
+
+        let (primary_commitment_key, secondary_commitment_key) = unsafe {
+            PallasVesta::load_or_setup_commitment_keys(
+                Path::new(FOLDER),
+                PRIMARY_COMMITMENT_KEY_SIZE,
+                SECONDARY_COMMITMENT_KEY_SIZE,
+            )
+            .unwrap()
+        };
+
+        let mut pp = new_default_pp::<PallasVesta, ARITY, _>(
+            &sc,
+            primary_commitment_key,
+            secondary_commitment_key,
+            PRIMARY_CIRCUIT_TABLE_SIZE,
+        )
+        .unwrap();
+
+        let ivc = super::IVC::new(&mut pp, &sc, array::from_fn(|_| Scalar::ZERO))
+            .expect("while step=0")
+            .next(&pp, &sc)
+            .expect("while step=1");
+        ivc.check_last_step(&pp).expect("while check step=1");
+
+        ivc.verify(&pp).expect("while verify");
+    }
+
+    /// Two folding steps over the secp256k1 & secq256k1 cycle
+    #[traced_test]
+    #[test]
+    fn ivc_secp256k1_secq256k1() {
+        use crate::{
+            cyclefold_prelude::{new_default_pp, CurveCycle, Secp256k1Secq256k1},
+            group::prime::PrimeCurveAffine,
+        };
+
+        type Scalar = <<Secp256k1Secq256k1 as CurveCycle>::C1 as PrimeCurveAffine>::Scalar;
+
+        let sc = trivial::Circuit::<ARITY, Scalar>::default();
+
+        let (primary_commitment_key, secondary_commitment_key) = unsafe {
+            Secp256k1Secq256k1::load_or_setup_commitment_keys(
+                Path::new(FOLDER),
+                PRIMARY_COMMITMENT_KEY_SIZE,
+                SECONDARY_COMMITMENT_KEY_SIZE,
+            )
+            .unwrap()
+        };
+
+        let mut pp = new_default_pp::<Secp256k1Secq256k1, ARITY, _>(
+            &sc,
+            primary_commitment_key,
+            secondary_commitment_key,
+            PRIMARY_CIRCUIT_TABLE_SIZE,
+        )
+        .unwrap();
+
+        let ivc = super::IVC::new(&mut pp, &sc, array::from_fn(|_| Scalar::ZERO))
             .expect("while step=0")
             .next(&pp, &sc)
             .expect("while step=1")
             .next(&pp, &sc)
-            .expect("while step=2")
-            .verify(&pp)
-            .expect("while verify");
+            .expect("while step=2");
+        ivc.check_last_step(&pp).expect("while check step=2");
+
+        ivc.verify(&pp).expect("while verify");
+    }
+
+    fn check_last_step_errors<SC: StepCircuit<ARITY, C1Scalar>>(
+        ivc: &super::IVC<ARITY, C1Affine, C2Affine, SC>,
+        pp: &PublicParams<SC>,
+    ) -> Box<[super::VerifyError<C1Affine>]> {
+        match ivc.check_last_step(pp) {
+            Err(super::Error::Verify(errors)) => errors,
+            Err(err) => panic!("unexpected error: {err:?}"),
+            Ok(()) => panic!("tampered IVC passed the last step check"),
+        }
+    }
+
+    /// Each part of the last step is tampered & restored in turn, so that only the expected
+    /// check rejects it
+    #[traced_test]
+    #[test]
+    fn check_last_step_tampered() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+
+        let mut ivc = super::IVC::new(&mut pp, &sc, array::from_fn(|_| C1Scalar::ZERO))
+            .expect("while step=0")
+            .next(&pp, &sc)
+            .expect("while step=1");
+        ivc.check_last_step(&pp).expect("while check step=1");
+
+        ivc.primary_trace.u.instances[0][0] += C1Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors.iter().any(|err| matches!(
+                err,
+                super::VerifyError::MismatchProtoGalaxyConsistencyMarker { .. }
+            )),
+            "{errors:?}"
+        );
+        ivc.primary_trace.u.instances[0][0] -= C1Scalar::ONE;
+
+        ivc.support_acc.U.u += C2Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, super::VerifyError::MismatchSangriaFold)),
+            "{errors:?}"
+        );
+        ivc.support_acc.U.u -= C2Scalar::ONE;
+
+        ivc.primary_acc.e += C1Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, super::VerifyError::MismatchProtoGalaxyFold)),
+            "{errors:?}"
+        );
+        ivc.primary_acc.e -= C1Scalar::ONE;
+
+        ivc.check_last_step(&pp)
+            .expect("while check restored step=1");
+    }
+
+    /// Lookup adds W-commitments to the primary trace, so the support circuit is folded more
+    /// than once per step
+    #[traced_test]
+    #[test]
+    fn ivc_multiple_W_commitments() {
+        let sc = lookup::Circuit::<ARITY, C1Scalar>::default();
+        let pp = new_pp(&sc);
+        assert!(pp.primary_initial_trace.u.W_commitments.len() > 1);
+
+        run_ivc(&sc, pp);
+    }
+
+    /// The trivial circuit has one W-commitment, so with capacity two the on-circuit verifier
+    /// pads the support traces up to the count needed for the max count of W-commitments
+    #[traced_test]
+    #[test]
+    fn ivc_support_capacity_two() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        run_ivc(
+            &sc,
+            new_pp_with_support::<_, { support_instances_len(2) }>(&sc),
+        );
+    }
+
+    /// With capacity one, each W-commitment is delegated to its own support trace
+    #[traced_test]
+    #[test]
+    fn ivc_multiple_W_commitments_support_capacity_one() {
+        let sc = lookup::Circuit::<ARITY, C1Scalar>::default();
+        let pp = new_pp_with_support::<_, { support_instances_len(1) }>(&sc);
+        assert!(pp.primary_initial_trace.u.W_commitments.len() > 1);
+
+        run_ivc(&sc, pp);
     }
 }
@@ -1,7 +1,7 @@
 use std::{io, iter, marker::PhantomData};
 
 use serde::Serialize;
-use tracing::info_span;
+use tracing::{info, info_span};
 
 use crate::{
     constants::NUM_HASH_BITS,
@@ -16,6 +16,7 @@ use crate::{
     },
     ivc::{
         cyclefold::{
+            ro_nark,
             sfc::{self, StepFoldingCircuit},
             support_circuit::{self, SupportCircuit},
         },
@@ -24,18 +25,22 @@ use crate::{
     nifs::{
         self,
         protogalaxy::ProtoGalaxy,
-        sangria::{FoldablePlonkTrace, VanillaFS},
+        sangria::{FoldablePlonkInstance, FoldablePlonkTrace, VanillaFS},
     },
     plonk::{PlonkStructure, PlonkTrace},
     polynomial::Expression,
-    poseidon::{PoseidonHash, ROTrait, Spec},
     sangria_prelude::CommitmentKey,
-    table::CircuitRunner,
+    table::{CircuitLayout, CircuitRunner, CircuitStats},
     util,
 };
 
-pub struct PublicParams<const ARITY: usize, CMain, CSup, SC>
-where
+pub struct PublicParams<
+    const ARITY: usize,
+    CMain,
+    CSup,
+    SC,
+    const SUPPORT_INSTANCES_LEN: usize = { support_circuit::DEFAULT_INSTANCES_LEN },
+> where
     CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
     CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
     SC: StepCircuit<ARITY, CMain::Scalar>,
@@ -45,24 +50,24 @@ where
     pub primary_ck: CommitmentKey<CMain>,
     pub primary_S: PlonkStructure<CMain::ScalarExt>,
     pub primary_k_table_size: u32,
+    /// Layout of the step folding circuit, reused to collect its witness on each step
+    pub primary_layout: CircuitLayout<CMain::ScalarExt, sfc::Config<SC::Config>>,
     pub primary_initial_trace: PlonkTrace<CMain>,
 
     pub support_ck: CommitmentKey<CSup>,
     pub support_S: PlonkStructure<CSup::ScalarExt>,
-    pub support_initial_trace: FoldablePlonkTrace<CSup, { support_circuit::INSTANCES_LEN }>,
+    pub support_initial_trace: FoldablePlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
 
     hash_bytes: CMain,
 
     _p: PhantomData<SC>,
 }
 
-const T: usize = 10;
-const RATE: usize = T - 1;
-const R_F: usize = 10;
-const R_P: usize = 10;
-
-fn ro<F: PrimeFieldBits + FromUniformBytes<64>>() -> PoseidonHash<F, T, RATE> {
-    PoseidonHash::<F, T, RATE>::new(Spec::<F, T, RATE>::new(R_F, R_P))
+/// Size & folding cost of the step folding & support circuits, see [`PublicParams::collect_stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicParamsStats {
+    pub primary: CircuitStats,
+    pub support: CircuitStats,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -73,6 +78,9 @@ pub enum Error {
     #[error("While collect witness: {0:?}")]
     WhileCollectWitness(Halo2PlonkError),
 
+    #[error("While collect stats: {0:?}")]
+    WhileCollectStats(Halo2PlonkError),
+
     #[error("While nifs::protogalaxy: {0:?}")]
     ProtoGalaxy(#[from] nifs::protogalaxy::Error),
 
@@ -83,7 +91,83 @@ pub enum Error {
     Digest(io::Error),
 }
 
-impl<const ARITY: usize, CMain, CSup, SC> PublicParams<ARITY, CMain, CSup, SC>
+/// Collect the plonk structure of the support circuit & its trace on the zero step input, which
+/// is the identity point multiplied by zero
+///
+/// Shared by all IVC variants, which delegate EC operations to the support circuit
+#[allow(clippy::type_complexity)]
+pub(in crate::ivc::cyclefold) fn setup_support<CMain, CSup, const SUPPORT_INSTANCES_LEN: usize>(
+    ck2: &CommitmentKey<CSup>,
+) -> Result<
+    (
+        PlonkStructure<CMain::Base>,
+        FoldablePlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
+    ),
+    Error,
+>
+where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    let _support = info_span!("support").entered();
+    // Since I want to scalar_multiply points for main::sfc, I take `CMain` as the main curve here
+    // CMain::Base or CSupport::Scalar (native for support_circuit)
+    //
+    // For step zero, cyclefold::sfc expects `C::identity` to be multiplied by zero
+    let support_circuit_instances: Vec<Vec<CMain::Base>> =
+        SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::instances([
+            support_circuit::InstanceInput::padding(),
+        ])
+        .pop()
+        .expect("one input always gives one trace");
+
+    #[cfg(test)]
+    {
+        let _mock = info_span!("mock-debug").entered();
+        crate::halo2_proofs::dev::MockProver::run(
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+            &SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::default(),
+            support_circuit_instances.clone(),
+        )
+        .unwrap()
+        .verify()
+        .unwrap();
+    }
+
+    let support_cr = CircuitRunner::<CMain::Base, _>::new(
+        SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+        SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::default(),
+        support_circuit_instances.clone(),
+    );
+    let S = support_cr
+        .try_collect_plonk_structure()
+        .map_err(Error::WhileCollectS)?;
+
+    // The trace is generated for `CSup`, since all result types use `C::ScalarExt` in our
+    // case it will be `CSup::ScalarExt` or `CMain::Base`
+    Ok((
+        S,
+        VanillaFS::<CSup, SUPPORT_INSTANCES_LEN>::generate_plonk_trace(
+            ck2,
+            &support_circuit_instances,
+            &support_cr
+                .try_collect_witness()
+                .map_err(Error::WhileCollectWitness)?,
+            &nifs::sangria::ProverParam {
+                S: support_cr
+                    .try_collect_plonk_structure()
+                    .map_err(Error::WhileCollectS)?,
+                pp_digest: (CSup::Base::ZERO, CSup::Base::ZERO),
+            },
+            &mut ro_nark(),
+        )?,
+    ))
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize>
+    PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
 where
     CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
     CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
@@ -91,6 +175,7 @@ where
     CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
     CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
 {
+    /// The step circuit is synthesized on the default [`StepCircuit::StepWitness`]
     pub fn new(
         primary_sc: &SC,
         ck1: CommitmentKey<CMain>,
@@ -100,95 +185,26 @@ where
     where
         CMain::ScalarExt: Serialize,
         CSup::ScalarExt: Serialize,
+        SC::StepWitness: Default,
     {
-        // Trace in C1::Base or C2::Scalar
-        let (support_S, support_initial_trace): (
-            PlonkStructure<CMain::Base>,
-            FoldablePlonkTrace<CSup, { support_circuit::INSTANCES_LEN }>,
-        ) = {
-            let _support = info_span!("support").entered();
-            // Since I want to scalar_multiply points for main::sfc, I take `CMain` as the main curve here
-            // CMain::Base or CSupport::Scalar (native for support_circuit)
-            //
-            // For step zero, cyclefold::sfc expects `C::identity` to be multiplied by zero
-            let support_circuit_instances: Vec<Vec<CMain::Base>> = support_circuit::InstanceInput {
-                p0: CMain::identity(),
-                l0: CMain::Base::ZERO,
-                p1: CMain::identity(),
-                l1: CMain::Base::ZERO,
-            }
-            .into_instance();
-
-            #[cfg(test)]
-            {
-                let _mock = info_span!("mock-debug").entered();
-                crate::halo2_proofs::dev::MockProver::run(
-                    SupportCircuit::<CMain>::MIN_K_TABLE_SIZE,
-                    &SupportCircuit::<CMain>::default(),
-                    support_circuit_instances.clone(),
-                )
-                .unwrap()
-                .verify()
-                .unwrap();
-            }
-
-            let support_cr = CircuitRunner::<CMain::Base, _>::new(
-                SupportCircuit::<CMain>::MIN_K_TABLE_SIZE,
-                SupportCircuit::<CMain>::default(),
-                support_circuit_instances.clone(),
-            );
-            let S = support_cr
-                .try_collect_plonk_structure()
-                .map_err(Error::WhileCollectS)?;
-
-            // The trace is generated for `CSup`, since all result types use `C::ScalarExt` in our
-            // case it will be `CSup::ScalarExt` or `CMain::Base`
-            (
-                S,
-                VanillaFS::<CSup, { support_circuit::INSTANCES_LEN }>::generate_plonk_trace(
-                    &ck2,
-                    &support_circuit_instances,
-                    &support_cr
-                        .try_collect_witness()
-                        .map_err(Error::WhileCollectWitness)?,
-                    &nifs::sangria::ProverParam {
-                        S: support_cr
-                            .try_collect_plonk_structure()
-                            .map_err(Error::WhileCollectS)?,
-                        pp_digest: (CSup::Base::ZERO, CSup::Base::ZERO),
-                    },
-                    &mut ro(),
-                )?,
-            )
-        };
+        let (support_S, support_initial_trace) =
+            setup_support::<CMain, CSup, SUPPORT_INSTANCES_LEN>(&ck2)?;
 
         let _primary = info_span!("primary").entered();
 
-        let (primary_S, primary_initial_trace) = {
+        let primary_step_witness = SC::StepWitness::default();
+
+        let (primary_S, primary_layout, primary_initial_trace) = {
             let mock_S = {
                 let _s = info_span!("pre_run_mock").entered();
 
-                let num_io = iter::once(1)
-                    .chain(primary_sc.instances().iter().map(|col| col.len()))
-                    .collect::<Box<[_]>>();
-
-                let mock_sfc = StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
-                    sc: primary_sc,
-                    input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<CMain, CSup>(
-                        &PlonkStructure {
-                            k: k_table_size as usize,
-                            num_io,
-                            // because with zero gates - calc count is zero - sfc panic
-                            gates: vec![Expression::Constant(CMain::ScalarExt::ZERO)],
-                            num_challenges: 3,
-                            ..Default::default()
-                        },
-                        &support_S,
-                        &support_initial_trace.u,
-                    ),
-                    _p: PhantomData,
-                };
-
+                let mock_sfc = Self::mock_primary_sfc(
+                    primary_sc,
+                    &primary_step_witness,
+                    k_table_size,
+                    &support_S,
+                    &support_initial_trace.u,
+                );
                 let mock_instances = mock_sfc.initial_instances();
 
                 #[cfg(test)]
@@ -211,11 +227,12 @@ where
 
             let sfc = StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
                 sc: primary_sc,
-                input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<CMain, CSup>(
-                    &mock_S,
-                    &support_S,
-                    &support_initial_trace.u,
-                ),
+                witness: &primary_step_witness,
+                input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<
+                    CMain,
+                    CSup,
+                    SUPPORT_INSTANCES_LEN,
+                >(&mock_S, &support_S, &support_initial_trace.u),
                 _p: PhantomData,
             };
 
@@ -240,6 +257,7 @@ where
                 primary_cr
                     .try_collect_plonk_structure()
                     .map_err(Error::WhileCollectS)?,
+                primary_cr.layout(),
                 ProtoGalaxy::<CMain, 1>::generate_plonk_trace(
                     &ck1,
                     &primary_instances,
@@ -252,7 +270,7 @@ where
                             .map_err(Error::WhileCollectS)?,
                         pp_digest: (CMain::Base::ZERO, CMain::Base::ZERO),
                     },
-                    &mut ro(),
+                    &mut ro_nark(),
                 )?,
             )
         };
@@ -286,6 +304,7 @@ where
             primary_ck: ck1,
             support_ck: ck2,
             primary_k_table_size: k_table_size,
+            primary_layout,
 
             primary_initial_trace,
             support_initial_trace,
@@ -299,6 +318,196 @@ where
         })
     }
 
+    /// Find the minimal `k` of the step folding circuit, create commitment keys of matching sizes
+    /// and build public params with them
+    ///
+    /// `setup_primary_ck` & `setup_support_ck` receive the required commitment key size, see
+    /// [`CircuitStats::commitment_key_size`]
+    pub fn new_with_min_k(
+        primary_sc: &SC,
+        setup_primary_ck: impl FnOnce(usize) -> CommitmentKey<CMain>,
+        setup_support_ck: impl FnOnce(usize) -> CommitmentKey<CSup>,
+    ) -> Result<Self, Error>
+    where
+        CMain::ScalarExt: Serialize,
+        CSup::ScalarExt: Serialize,
+        SC::StepWitness: Default,
+    {
+        let PublicParamsStats { primary, support } = Self::find_min_k_table_size(primary_sc, 1)?;
+
+        Self::new(
+            primary_sc,
+            setup_primary_ck(primary.commitment_key_size),
+            setup_support_ck(support.commitment_key_size),
+            primary.k_table_size,
+        )
+    }
+
+    /// The step folding circuit contains accumulators whose size depends on the primary plonk
+    /// structure itself, so we collect it in advance on a circuit with a minimal mock structure
+    fn mock_primary_sfc<'sc>(
+        primary_sc: &'sc SC,
+        primary_step_witness: &'sc SC::StepWitness,
+        k_table_size: u32,
+        support_S: &PlonkStructure<CMain::Base>,
+        support_initial_instance: &FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    ) -> StepFoldingCircuit<'sc, ARITY, CMain, CSup, SC> {
+        let num_io = iter::once(1)
+            .chain(primary_sc.instances().iter().map(|col| col.len()))
+            .collect::<Box<[_]>>();
+
+        StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
+            sc: primary_sc,
+            witness: primary_step_witness,
+            input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<
+                CMain,
+                CSup,
+                SUPPORT_INSTANCES_LEN,
+            >(
+                &PlonkStructure {
+                    k: k_table_size as usize,
+                    num_io,
+                    // because with zero gates - calc count is zero - sfc panic
+                    gates: vec![Expression::Constant(CMain::ScalarExt::ZERO)],
+                    num_challenges: 3,
+                    ..Default::default()
+                },
+                support_S,
+                support_initial_instance,
+            ),
+            _p: PhantomData,
+        }
+    }
+
+    /// Synthesize the step folding & support circuits without witness and report their size &
+    /// folding cost, see [`CircuitStats`]
+    ///
+    /// Does not need commitment keys, so it can be used to choose `k` and key sizes before
+    /// calling [`PublicParams::new`]
+    pub fn collect_stats(primary_sc: &SC, k_table_size: u32) -> Result<PublicParamsStats, Error>
+    where
+        SC::StepWitness: Default,
+    {
+        let (support_S, support_stats, support_initial_instance) = Self::support_stats()?;
+
+        Ok(PublicParamsStats {
+            primary: Self::primary_stats(
+                primary_sc,
+                k_table_size,
+                &support_S,
+                &support_initial_instance,
+            )?,
+            support: support_stats,
+        })
+    }
+
+    /// Find the minimal `k` of the step folding circuit and the matching commitment key sizes
+    ///
+    /// `lower_bound` is the `k` the search starts from, so passing `1` gives the minimal
+    /// possible `k`. The support circuit always uses its own fixed `MIN_K_TABLE_SIZE`
+    pub fn find_min_k_table_size(
+        primary_sc: &SC,
+        lower_bound: u32,
+    ) -> Result<PublicParamsStats, Error>
+    where
+        SC::StepWitness: Default,
+    {
+        let (support_S, support_stats, support_initial_instance) = Self::support_stats()?;
+
+        let primary_stats = CircuitStats::find_min_k(lower_bound, |k_table_size| {
+            Self::primary_stats(
+                primary_sc,
+                k_table_size,
+                &support_S,
+                &support_initial_instance,
+            )
+        })?;
+        info!("primary k table size is {}", primary_stats.k_table_size);
+
+        Ok(PublicParamsStats {
+            primary: primary_stats,
+            support: support_stats,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn support_stats() -> Result<
+        (
+            PlonkStructure<CMain::Base>,
+            CircuitStats,
+            FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        ),
+        Error,
+    > {
+        let _support = info_span!("support").entered();
+
+        let support_cr = CircuitRunner::<CMain::Base, _>::new(
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::default(),
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::instances([
+                support_circuit::InstanceInput::padding(),
+            ])
+            .pop()
+            .expect("one input always gives one trace"),
+        );
+        let S = support_cr
+            .try_collect_plonk_structure()
+            .map_err(Error::WhileCollectS)?;
+
+        // Only the shape of the instance is needed, so the SPS is not really run here
+        let initial_instance = FoldablePlonkInstance::new(S.dry_run_sps_protocol::<CSup>().u)
+            .expect("support circuit instances always start with markers");
+
+        let stats = support_cr
+            .try_collect_stats()
+            .map_err(Error::WhileCollectStats)?;
+
+        Ok((S, stats, initial_instance))
+    }
+
+    fn primary_stats(
+        primary_sc: &SC,
+        k_table_size: u32,
+        support_S: &PlonkStructure<CMain::Base>,
+        support_initial_instance: &FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    ) -> Result<CircuitStats, Error>
+    where
+        SC::StepWitness: Default,
+    {
+        let _primary = info_span!("primary").entered();
+
+        let primary_step_witness = SC::StepWitness::default();
+
+        let mock_sfc = Self::mock_primary_sfc(
+            primary_sc,
+            &primary_step_witness,
+            k_table_size,
+            support_S,
+            support_initial_instance,
+        );
+        let mock_instances = mock_sfc.initial_instances();
+        // Only sizes of the structure matter here, so it is collected without synthesis, which
+        // would fail on a too small `k`
+        let mock_S = CircuitRunner::new(k_table_size, mock_sfc, mock_instances)
+            .collect_plonk_structure_shape();
+
+        let sfc = StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
+            sc: primary_sc,
+            witness: &primary_step_witness,
+            input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<
+                CMain,
+                CSup,
+                SUPPORT_INSTANCES_LEN,
+            >(&mock_S, support_S, support_initial_instance),
+            _p: PhantomData,
+        };
+        let primary_instances = sfc.initial_instances();
+
+        CircuitRunner::new(k_table_size, sfc, primary_instances)
+            .try_collect_stats()
+            .map_err(Error::WhileCollectStats)
+    }
+
     pub fn pp_digest_coordinates<F: PrimeField>(&self) -> (F, F) {
         self.hash_bytes
             .coordinates()
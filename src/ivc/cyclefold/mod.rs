@@ -1,19 +1,21 @@
 use std::num::NonZeroUsize;
 
-use crate::{
-    halo2_proofs::halo2curves::ff::{FromUniformBytes, PrimeFieldBits},
-    main_gate::MainGateConfig,
-    poseidon::{poseidon_circuit::PoseidonChip, PoseidonHash, ROTrait, Spec},
-};
-
 mod support_circuit;
+pub use support_circuit::{
+    instances_len as support_instances_len, DEFAULT_INSTANCES_LEN as DEFAULT_SUPPORT_INSTANCES_LEN,
+};
 
 mod sfc;
 
 #[allow(clippy::upper_case_acronyms)]
 pub mod incrementally_verifiable_computation;
 
-pub use incrementally_verifiable_computation::{PublicParams, IVC};
+pub use incrementally_verifiable_computation::{
+    PublicParams, PublicParamsError, PublicParamsStats, IVC,
+};
+
+#[allow(clippy::upper_case_acronyms)]
+pub mod sangria_primary;
 
 pub const T: usize = 5;
 pub const T_MAIN_GATE: usize = 5;
@@ -22,22 +24,121 @@ pub const RATE: usize = T - 1;
 pub const R_F: usize = 10;
 pub const R_P: usize = 10;
 
+pub const POSEIDON2_T: usize = 4;
+pub const POSEIDON2_RATE: usize = POSEIDON2_T - 1;
+pub const POSEIDON2_R_F: usize = 8;
+pub const POSEIDON2_R_P: usize = 56;
+
 /// Safety: because 64 != 0
 pub const DEFAULT_LIMB_WIDTH: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(64) };
 
-/// Safety: because 10 != 0
+/// Safety: because 20 != 0
 pub const DEFAULT_LIMBS_COUNT: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(20) };
 
-pub fn ro_const<F: PrimeFieldBits + FromUniformBytes<64>>() -> Spec<F, T, RATE> {
-    Spec::<F, T, RATE>::new(R_F, R_P)
-}
+pub use random_oracle::{ro, ro_acc, ro_acc_chip, ro_chip, ro_const, ro_nark};
+
+/// Random oracle of the cyclefold IVC: Poseidon with [`T`] & [`RATE`]
+///
+/// [`ro_nark`] & [`ro_acc`] are the domain separated [`PoseidonSpongeHash`], the rest of hashes
+/// (consistency markers) use [`ro`]
+#[cfg(not(feature = "cyclefold-poseidon2"))]
+mod random_oracle {
+    use super::{RATE, R_F, R_P, T};
+    use crate::{
+        halo2_proofs::halo2curves::ff::{FromUniformBytes, PrimeFieldBits},
+        main_gate::MainGateConfig,
+        poseidon::{
+            poseidon_circuit::PoseidonChip, PoseidonHash, PoseidonSpongeHash,
+            PoseidonSpongeHashChip, ROCircuitTrait, ROTrait, Spec, RO_ACC_DOMAIN, RO_NARK_DOMAIN,
+        },
+    };
+
+    pub fn ro_const<F: PrimeFieldBits + FromUniformBytes<64>>() -> Spec<F, T, RATE> {
+        Spec::<F, T, RATE>::new(R_F, R_P)
+    }
+
+    pub fn ro<F: PrimeFieldBits + FromUniformBytes<64>>() -> PoseidonHash<F, T, RATE> {
+        PoseidonHash::<F, T, RATE>::new(ro_const())
+    }
 
-pub fn ro<F: PrimeFieldBits + FromUniformBytes<64>>() -> PoseidonHash<F, T, RATE> {
-    PoseidonHash::<F, T, RATE>::new(ro_const())
+    pub fn ro_chip<F: PrimeFieldBits + FromUniformBytes<64>>(
+        main_gate_config: MainGateConfig<T>,
+    ) -> PoseidonChip<F, T, RATE> {
+        PoseidonChip::new(main_gate_config, ro_const())
+    }
+
+    /// Random oracle of the NARK rounds: challenges of the special-sound protocol
+    pub fn ro_nark<F: PrimeFieldBits + FromUniformBytes<64>>() -> PoseidonSpongeHash<F, T, RATE> {
+        PoseidonSpongeHash::new(ro_const()).with_domain(RO_NARK_DOMAIN)
+    }
+
+    /// Random oracle of the accumulation: challenges of the folding
+    pub fn ro_acc<F: PrimeFieldBits + FromUniformBytes<64>>() -> PoseidonSpongeHash<F, T, RATE> {
+        PoseidonSpongeHash::new(ro_const()).with_domain(RO_ACC_DOMAIN)
+    }
+
+    /// On-circuit mirror of [`ro_acc`]
+    pub fn ro_acc_chip<F: PrimeFieldBits + FromUniformBytes<64>>(
+        main_gate_config: MainGateConfig<T>,
+    ) -> PoseidonSpongeHashChip<F, T, RATE> {
+        PoseidonSpongeHashChip::new(main_gate_config, ro_const()).with_domain(RO_ACC_DOMAIN)
+    }
 }
 
-pub fn ro_chip<F: PrimeFieldBits + FromUniformBytes<64>>(
-    main_gate_config: MainGateConfig<T>,
-) -> PoseidonChip<F, T, RATE> {
-    PoseidonChip::new(main_gate_config, ro_const())
+/// Random oracle of the cyclefold IVC: Poseidon2 with [`POSEIDON2_T`] & [`POSEIDON2_RATE`]
+///
+/// Poseidon2 doesn't support the width [`T`], so the chip uses the first columns of the main gate
+///
+/// [`ro_nark`] & [`ro_acc`] are bound to their domains by
+/// [`Poseidon2Spec::initial_state_with_domain`]
+#[cfg(feature = "cyclefold-poseidon2")]
+mod random_oracle {
+    use super::{POSEIDON2_RATE, POSEIDON2_R_F, POSEIDON2_R_P, POSEIDON2_T, T};
+    use crate::{
+        halo2_proofs::halo2curves::ff::{FromUniformBytes, PrimeFieldBits},
+        main_gate::MainGateConfig,
+        poseidon::{
+            poseidon2::{Poseidon2Chip, Poseidon2Hash, Poseidon2Spec},
+            ROTrait, RO_ACC_DOMAIN, RO_NARK_DOMAIN,
+        },
+    };
+
+    pub fn ro_const<F: PrimeFieldBits + FromUniformBytes<64>>(
+    ) -> Poseidon2Spec<F, POSEIDON2_T, POSEIDON2_RATE> {
+        Poseidon2Spec::new(POSEIDON2_R_F, POSEIDON2_R_P)
+    }
+
+    pub fn ro<F: PrimeFieldBits + FromUniformBytes<64>>(
+    ) -> Poseidon2Hash<F, POSEIDON2_T, POSEIDON2_RATE> {
+        Poseidon2Hash::new(ro_const())
+    }
+
+    pub fn ro_chip<F: PrimeFieldBits + FromUniformBytes<64>>(
+        main_gate_config: MainGateConfig<T>,
+    ) -> Poseidon2Chip<F, POSEIDON2_T, POSEIDON2_RATE> {
+        let main_gate_config = main_gate_config
+            .into_smaller_size()
+            .expect("Unreachable, because `POSEIDON2_T < T`");
+
+        Poseidon2Chip::new(main_gate_config, ro_const())
+    }
+
+    /// Random oracle of the NARK rounds: challenges of the special-sound protocol
+    pub fn ro_nark<F: PrimeFieldBits + FromUniformBytes<64>>(
+    ) -> Poseidon2Hash<F, POSEIDON2_T, POSEIDON2_RATE> {
+        ro().with_domain(RO_NARK_DOMAIN)
+    }
+
+    /// Random oracle of the accumulation: challenges of the folding
+    pub fn ro_acc<F: PrimeFieldBits + FromUniformBytes<64>>(
+    ) -> Poseidon2Hash<F, POSEIDON2_T, POSEIDON2_RATE> {
+        ro().with_domain(RO_ACC_DOMAIN)
+    }
+
+    /// On-circuit mirror of [`ro_acc`]
+    pub fn ro_acc_chip<F: PrimeFieldBits + FromUniformBytes<64>>(
+        main_gate_config: MainGateConfig<T>,
+    ) -> Poseidon2Chip<F, POSEIDON2_T, POSEIDON2_RATE> {
+        ro_chip(main_gate_config).with_domain(RO_ACC_DOMAIN)
+    }
 }
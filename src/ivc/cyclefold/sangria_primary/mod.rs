@@ -0,0 +1,957 @@
+//! Cyclefold IVC with the primary circuit folded by Sangria
+//!
+//! Same as [`crate::ivc::cyclefold::IVC`], but ProtoGalaxy of the primary circuit is replaced by
+//! [`nifs::sangria::VanillaFS`]. Unlike [`crate::ivc::SangriaIVC`], there is no secondary step
+//! circuit: all EC operations of the on-circuit verifier are delegated to the support circuit, so
+//! only the primary step circuit is defined by the user. This allows to compare Sangria &
+//! ProtoGalaxy folding of the primary circuit on equal footing
+
+use std::{iter, marker::PhantomData, num::NonZeroUsize};
+
+use itertools::Itertools;
+use tracing::{info_span, trace};
+
+use super::{
+    incrementally_verifiable_computation::{
+        fold_support_circuit, sc_instances_hash_acc, verify_support_fold, SupportCircuitFoldResult,
+    },
+    ro, ro_nark,
+    support_circuit::{self, SupportCircuit},
+};
+use crate::{
+    halo2_proofs::{
+        arithmetic::best_multiexp,
+        halo2curves::{
+            ff::{Field, FromUniformBytes, PrimeField, PrimeFieldBits},
+            group::prime::PrimeCurveAffine,
+            CurveAffine,
+        },
+        plonk::Error as Halo2PlonkError,
+    },
+    ivc::{sangria::instances_accumulator_computation, IvcState, StepCircuit},
+    nifs::{
+        self,
+        sangria::{
+            FoldablePlonkInstance, FoldablePlonkTrace, RelaxedPlonkInstance, RelaxedPlonkTrace,
+            VanillaFS,
+        },
+    },
+    plonk,
+    poseidon::random_oracle::ROTrait,
+    sangria_prelude::CommitmentKey,
+    sps::{self, SpecialSoundnessVerifier},
+    table::CircuitRunner,
+    util,
+};
+
+mod sfc;
+use sfc::{StepFoldingCircuit, MARKERS_LEN};
+
+mod public_params;
+pub use public_params::PublicParams;
+
+type PrimaryFS<C> = VanillaFS<C, MARKERS_LEN>;
+
+type SangriaFS<C, const SUPPORT_INSTANCES_LEN: usize> = VanillaFS<C, SUPPORT_INSTANCES_LEN>;
+
+/// `SUPPORT_INSTANCES_LEN` sets the capacity of the support circuit, same as for
+/// [`crate::ivc::cyclefold::IVC`]
+pub struct IVC<
+    const ARITY: usize,
+    CMain,
+    CSup,
+    SC,
+    const SUPPORT_INSTANCES_LEN: usize = { support_circuit::DEFAULT_INSTANCES_LEN },
+> where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    step: NonZeroUsize,
+
+    primary_acc: RelaxedPlonkTrace<CMain, MARKERS_LEN>,
+    primary_trace: FoldablePlonkTrace<CMain, MARKERS_LEN>,
+    primary_z_current: [CMain::Scalar; ARITY],
+    primary_z_0: [CMain::Scalar; ARITY],
+
+    /// Hash chain of the step circuit instances of all steps, except the last one
+    primary_sc_instances_hash_acc: CMain::Scalar,
+    /// Step circuit instances of all steps, including the last one
+    primary_pub_instances: Vec<Vec<Vec<CMain::Scalar>>>,
+
+    support_acc: RelaxedPlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
+
+    /// `None` after the zero step, since there was no folding
+    last_step: Option<LastStep<CMain, CSup, SUPPORT_INSTANCES_LEN>>,
+
+    _p: PhantomData<(CMain, CSup, SC)>,
+}
+
+/// Instance part of the last [`IVC::next_with_witness`], enough to repeat the verifier side of
+/// its folding, see [`IVC::check_last_step`]
+struct LastStep<CMain: CurveAffine, CSup: CurveAffine, const SUPPORT_INSTANCES_LEN: usize> {
+    primary_input_acc: RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+    primary_incoming: FoldablePlonkInstance<CMain, MARKERS_LEN>,
+    primary_proof: nifs::sangria::CrossTermCommits<CMain>,
+
+    support_input_acc: RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    support_incoming: Vec<(
+        FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        nifs::sangria::CrossTermCommits<CSup>,
+    )>,
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize>
+    IVC<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
+where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// Zero step is formal, its output is `z_0`, so the step circuit is synthesized on the
+    /// default [`StepCircuit::StepWitness`]
+    pub fn new(
+        pp: &mut PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+        sc: &SC,
+        z_0: [CMain::ScalarExt; ARITY],
+    ) -> Result<Self, Error<CMain>>
+    where
+        SC::StepWitness: Default,
+    {
+        let _span = info_span!("ivc_new", step = 0).entered();
+
+        let primary_initial_acc = RelaxedPlonkTrace::from_regular(
+            pp.primary_initial_trace.clone(),
+            pp.primary_k_table_size as usize,
+        );
+
+        // At zero step output sangria-accumulator is input sangria-accumulator. But proof still
+        // should be valid.
+        let PrimaryFoldResult {
+            new_accumulator: _new_acc,
+            cross_term_commits: self_proof,
+            r,
+        } = fold_primary(
+            &pp.primary_ck,
+            &pp.primary_S,
+            pp.pp_digest_coordinates(),
+            &primary_initial_acc,
+            &pp.primary_initial_trace,
+        )?;
+
+        #[cfg(test)]
+        {
+            PrimaryFS::<CMain>::is_sat_without_pub_instances(
+                &pp.primary_ck,
+                &pp.primary_S,
+                &_new_acc,
+            )
+            .expect("initial primary accumulator not corrent");
+        }
+
+        let support_initial_acc = RelaxedPlonkTrace::from_regular(
+            pp.support_initial_trace.clone(),
+            SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::MIN_K_TABLE_SIZE as usize,
+        );
+
+        // At zero step output sangria-accumulator of support circuit is input one too. But
+        // proofs still should be valid.
+        let SupportCircuitFoldResult {
+            new_accumulator: _new_support_acc,
+            incoming: support_incoming,
+        } = fold_support_circuit::<CMain, CSup, SUPPORT_INSTANCES_LEN>(
+            &pp.support_ck,
+            &pp.sangria_prover_params(),
+            &support_initial_acc,
+            support_circuit_inputs(
+                &primary_initial_acc.U,
+                &pp.primary_initial_trace.u,
+                &self_proof,
+                r,
+                true,
+            )
+            .into_iter(),
+        )?;
+
+        let primary_initial_sc_instances_hash_acc =
+            instances_accumulator_computation::get_initial_native_sc_instances_accumulator();
+
+        let primary_step_witness = SC::StepWitness::default();
+        let primary_sfc = StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC> {
+            sc,
+            witness: &primary_step_witness,
+            input: sfc::InputBuilder {
+                step: 0,
+                pp_digest: pp.pp_digest_coordinates(),
+                self_acc: &primary_initial_acc.U,
+                self_incoming: &pp.primary_initial_trace.u,
+                self_proof: &self_proof,
+                support_acc: &support_initial_acc.U,
+                support_incoming: support_incoming.as_slice(),
+                z_i: z_0,
+                z_0,
+                step_circuit_instances_hash_accumulator: primary_initial_sc_instances_hash_acc,
+            }
+            .build(),
+            _p: PhantomData,
+        };
+
+        let primary_initial_instances = primary_sfc.initial_instances();
+        let primary_sc_instances_hash_acc = primary_sfc.step_circuit_instances_hash_accumulator();
+
+        #[cfg(test)]
+        {
+            let _mock = info_span!("mock_debug").entered();
+            crate::halo2_proofs::dev::MockProver::run(
+                pp.primary_k_table_size,
+                &primary_sfc,
+                primary_initial_instances.clone(),
+            )
+            .unwrap()
+            .verify()
+            .unwrap();
+        }
+
+        let primary_cr = CircuitRunner::new(
+            pp.primary_k_table_size,
+            primary_sfc,
+            primary_initial_instances.clone(),
+        );
+        let primary_witness = primary_cr
+            .try_collect_witness()
+            .map_err(|err| Error::WhileCollectPrimaryWitness { step: 0, err })?;
+        pp.primary_S = primary_cr
+            .try_collect_plonk_structure()
+            .map_err(|err| Error::WhileCollectPrimaryS { err })?;
+
+        let primary_post_initial_trace = PrimaryFS::<CMain>::generate_plonk_trace(
+            &pp.primary_ck,
+            &primary_initial_instances,
+            &primary_witness,
+            &pp.primary_prover_params(),
+            &mut ro_nark(),
+        )?;
+
+        Ok(Self {
+            step: NonZeroUsize::new(1).expect("safe: 1 != 0"),
+            // Because zero step using input values for output without any folding (only formal
+            // on-circuit) - we just take initial acc-s & z_0
+            primary_z_current: z_0,
+            primary_z_0: z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances: vec![sc.instances()],
+            primary_trace: primary_post_initial_trace,
+            primary_acc: primary_initial_acc,
+            support_acc: support_initial_acc,
+            last_step: None,
+            _p: PhantomData,
+        })
+    }
+
+    /// Fold the next step, synthesized on the default [`StepCircuit::StepWitness`]
+    ///
+    /// See [`IVC::next_with_witness`] for step circuits with non-deterministic advice
+    pub fn next(
+        self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+        sc: &SC,
+    ) -> Result<Self, Error<CMain>>
+    where
+        SC::StepWitness: Default,
+    {
+        self.next_with_witness(pp, sc, &SC::StepWitness::default())
+    }
+
+    /// Fold the next step, the step circuit is synthesized on `witness`
+    pub fn next_with_witness(
+        self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+        sc: &SC,
+        witness: &SC::StepWitness,
+    ) -> Result<Self, Error<CMain>> {
+        let _span = info_span!("ivc_next", step = self.step.get()).entered();
+
+        let Self {
+            step,
+            primary_acc,
+            primary_trace,
+            primary_z_current,
+            primary_z_0,
+            primary_sc_instances_hash_acc,
+            mut primary_pub_instances,
+            support_acc,
+            last_step: _,
+            _p,
+        } = self;
+
+        let PrimaryFoldResult {
+            new_accumulator: primary_next_acc,
+            cross_term_commits: primary_proof,
+            r,
+        } = fold_primary(
+            &pp.primary_ck,
+            &pp.primary_S,
+            pp.pp_digest_coordinates(),
+            &primary_acc,
+            &primary_trace,
+        )?;
+
+        let SupportCircuitFoldResult {
+            new_accumulator: support_next_acc,
+            incoming: support_incoming,
+        } = fold_support_circuit::<CMain, CSup, SUPPORT_INSTANCES_LEN>(
+            &pp.support_ck,
+            &pp.sangria_prover_params(),
+            &support_acc,
+            support_circuit_inputs(&primary_acc.U, &primary_trace.u, &primary_proof, r, false)
+                .into_iter(),
+        )?;
+
+        let primary_sfc = StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC> {
+            sc,
+            witness,
+            input: sfc::InputBuilder {
+                step: step.get(),
+                pp_digest: pp.pp_digest_coordinates(),
+                z_i: primary_z_current,
+                z_0: primary_z_0,
+                self_acc: &primary_acc.U,
+                self_incoming: &primary_trace.u,
+                self_proof: &primary_proof,
+                support_acc: &support_acc.U,
+                support_incoming: support_incoming.as_slice(),
+                step_circuit_instances_hash_accumulator: primary_sc_instances_hash_acc,
+            }
+            .build(),
+            _p: PhantomData,
+        };
+
+        // Step circuit is synthesized only here, its output is taken from the same synthesis
+        let (primary_witness, primary_z_next) = primary_sfc
+            .collect_witness_with_output(&pp.primary_layout)
+            .map_err(|err| Error::WhileCollectPrimaryWitness {
+                step: step.get(),
+                err,
+            })?;
+
+        let primary_instances =
+            primary_sfc.instances(&primary_next_acc.U, &support_next_acc.U, &primary_z_next);
+        primary_pub_instances.push(primary_instances[1..].to_vec());
+
+        #[cfg(test)]
+        {
+            let _mock = info_span!("mock_debug").entered();
+            crate::halo2_proofs::dev::MockProver::run(
+                pp.primary_k_table_size,
+                &primary_sfc,
+                primary_instances.clone(),
+            )
+            .unwrap()
+            .verify()
+            .unwrap();
+        }
+
+        let primary_next_trace = PrimaryFS::<CMain>::generate_plonk_trace(
+            &pp.primary_ck,
+            &primary_instances,
+            &primary_witness,
+            &pp.primary_prover_params(),
+            &mut ro_nark(),
+        )?;
+
+        Ok(Self {
+            step: step.saturating_add(1),
+            primary_acc: primary_next_acc,
+            primary_trace: primary_next_trace,
+            primary_z_current: primary_z_next,
+            primary_z_0,
+            primary_sc_instances_hash_acc: primary_sfc.step_circuit_instances_hash_accumulator(),
+            primary_pub_instances,
+            support_acc: support_next_acc,
+            last_step: Some(LastStep {
+                primary_input_acc: primary_acc.U,
+                primary_incoming: primary_trace.u,
+                primary_proof,
+                support_input_acc: support_acc.U,
+                support_incoming,
+            }),
+            _p,
+        })
+    }
+
+    pub fn verify(
+        self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+    ) -> Result<Self, Error<CMain>> {
+        let _span = info_span!("ivc_verify").entered();
+        let Self {
+            step,
+            primary_acc,
+            primary_trace,
+            primary_z_current,
+            primary_z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances,
+            support_acc,
+            last_step: _,
+            _p,
+        } = &self;
+
+        let mut errors: Vec<VerifyError<CMain>> = vec![];
+
+        let (last_pub_instances, previous_pub_instances) = primary_pub_instances
+            .split_last()
+            .expect("safe: instances are collected at each step");
+
+        let expected_sc_instances_hash_acc = sc_instances_hash_acc(previous_pub_instances);
+
+        if expected_sc_instances_hash_acc != *primary_sc_instances_hash_acc {
+            errors.push(VerifyError::MismatchStepCircuitInstancesHashAcc {
+                expected: expected_sc_instances_hash_acc,
+                actual: *primary_sc_instances_hash_acc,
+            });
+        }
+
+        if primary_trace.u.instances[1..] != last_pub_instances[..] {
+            errors.push(VerifyError::MismatchStepCircuitInstances { step: step.get() });
+        }
+
+        let expected_marker = ro()
+            .absorb(
+                &sfc::InputBuilder {
+                    step: step.get(),
+                    pp_digest: pp.pp_digest_coordinates(),
+                    self_acc: &primary_acc.U,
+                    support_acc: &support_acc.U,
+                    z_i: *primary_z_current,
+                    z_0: *primary_z_0,
+                    step_circuit_instances_hash_accumulator: expected_sc_instances_hash_acc,
+
+                    // next fields not used in absorb
+                    self_incoming: &primary_trace.u,
+                    self_proof: &[],
+                    support_incoming: &[],
+                }
+                .build(),
+            )
+            .inspect(|buf| trace!("buf before marker: {buf:?}"))
+            .output(
+                NonZeroUsize::new(<CMain::ScalarExt as PrimeField>::NUM_BITS as usize).unwrap(),
+            );
+
+        if expected_marker != primary_trace.u.instances[0][0] {
+            errors.push(VerifyError::MismatchConsistencyMarker {
+                expected: expected_marker,
+                actual: primary_trace.u.instances[0][0],
+            });
+        }
+
+        if let Err(err) = PrimaryFS::<CMain>::is_sat_without_pub_instances(
+            &pp.primary_ck,
+            &pp.primary_S,
+            primary_acc,
+        ) {
+            errors.push(VerifyError::WhilePrimaryIsSat(err))
+        }
+
+        if let Err(err) = SangriaFS::<CSup, SUPPORT_INSTANCES_LEN>::is_sat(
+            &pp.support_ck,
+            &pp.support_S,
+            support_acc,
+            &[],
+        ) {
+            errors.push(VerifyError::WhileSupportIsSat(err))
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(Error::Verify(errors.into_boxed_slice()))
+        }
+    }
+
+    /// Lightweight check of the last step only
+    ///
+    /// Same as [`crate::ivc::cyclefold::IVC::check_last_step`], but the last folding of the
+    /// primary accumulator is repeated by Sangria. The hash chain of step circuit instances and
+    /// satisfiability of the accumulators are checked only by [`IVC::verify`]
+    pub fn check_last_step(
+        &self,
+        pp: &PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>,
+    ) -> Result<(), Error<CMain>> {
+        let _span = info_span!("ivc_check_last_step", step = self.step.get()).entered();
+        let Self {
+            step,
+            primary_acc,
+            primary_trace,
+            primary_z_current,
+            primary_z_0,
+            primary_sc_instances_hash_acc,
+            primary_pub_instances,
+            support_acc,
+            last_step,
+            _p,
+        } = self;
+
+        let mut errors: Vec<VerifyError<CMain>> = vec![];
+
+        if primary_pub_instances
+            .last()
+            .is_some_and(|last| primary_trace.u.instances[1..] != last[..])
+        {
+            errors.push(VerifyError::MismatchStepCircuitInstances { step: step.get() });
+        }
+
+        let expected_marker = ro()
+            .absorb(
+                &sfc::InputBuilder {
+                    step: step.get(),
+                    pp_digest: pp.pp_digest_coordinates(),
+                    self_acc: &primary_acc.U,
+                    support_acc: &support_acc.U,
+                    z_i: *primary_z_current,
+                    z_0: *primary_z_0,
+                    step_circuit_instances_hash_accumulator: *primary_sc_instances_hash_acc,
+
+                    // next fields not used in absorb
+                    self_incoming: &primary_trace.u,
+                    self_proof: &[],
+                    support_incoming: &[],
+                }
+                .build(),
+            )
+            .output(
+                NonZeroUsize::new(<CMain::ScalarExt as PrimeField>::NUM_BITS as usize).unwrap(),
+            );
+
+        if expected_marker != primary_trace.u.instances[0][0] {
+            errors.push(VerifyError::MismatchConsistencyMarker {
+                expected: expected_marker,
+                actual: primary_trace.u.instances[0][0],
+            });
+        }
+
+        if let Err(err) = primary_trace.u.sps_verify(&mut ro_nark::<CMain::Base>()) {
+            errors.push(VerifyError::WhileSpsVerify(err));
+        }
+
+        // After the zero step accumulators are the initial ones, so there is no folding to repeat
+        if let Some(LastStep {
+            primary_input_acc,
+            primary_incoming,
+            primary_proof,
+            support_input_acc,
+            support_incoming,
+        }) = last_step
+        {
+            let r = sfc::input::SelfTrace::new(primary_input_acc, primary_incoming, primary_proof)
+                .challenge(pp.pp_digest_coordinates());
+
+            if primary_input_acc.fold(primary_incoming, primary_proof, &r) != primary_acc.U {
+                errors.push(VerifyError::MismatchPrimaryFold);
+            }
+
+            match verify_support_fold(
+                pp.pp_digest_coordinates(),
+                support_input_acc,
+                support_incoming,
+            ) {
+                Ok(folded) if folded == support_acc.U => {}
+                Ok(_) => errors.push(VerifyError::MismatchSupportFold),
+                Err((index, err)) => errors.push(VerifyError::WhileSupportVerify { index, err }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Verify(errors.into_boxed_slice()))
+        }
+    }
+
+    /// Step circuit instances of each step, in the order of steps
+    ///
+    /// Their hash chain is a part of the consistency marker and is checked by
+    /// [`IVC::verify`]
+    pub fn pub_instances(&self) -> &[Vec<Vec<CMain::Scalar>>] {
+        &self.primary_pub_instances
+    }
+}
+
+struct PrimaryFoldResult<C: CurveAffine> {
+    new_accumulator: RelaxedPlonkTrace<C, MARKERS_LEN>,
+    cross_term_commits: nifs::sangria::CrossTermCommits<C>,
+    r: C::ScalarExt,
+}
+
+/// Same as [`VanillaFS::prove`], but the challenge is generated over the native field of `C`, so
+/// it can be calculated on-circuit by the step folding circuit itself, see
+/// [`sfc::input::SelfTrace::challenge`]
+fn fold_primary<C>(
+    ck: &CommitmentKey<C>,
+    S: &plonk::PlonkStructure<C::ScalarExt>,
+    pp_digest: (C::ScalarExt, C::ScalarExt),
+    accumulator: &RelaxedPlonkTrace<C, MARKERS_LEN>,
+    incoming: &FoldablePlonkTrace<C, MARKERS_LEN>,
+) -> Result<PrimaryFoldResult<C>, nifs::sangria::Error>
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+    C::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    let _primary = info_span!("primary_fold").entered();
+
+    let RelaxedPlonkTrace { U: U1, W: W1 } = accumulator;
+    let FoldablePlonkTrace { u: U2, w: W2 } = incoming;
+
+    let (cross_terms, cross_term_commits) =
+        PrimaryFS::<C>::commit_cross_terms(ck, S, U1, W1, U2, W2)?;
+
+    let r = sfc::input::SelfTrace::new(U1, U2, &cross_term_commits).challenge(pp_digest);
+    trace!("primary sangria_cha: {r:?}");
+
+    Ok(PrimaryFoldResult {
+        new_accumulator: RelaxedPlonkTrace {
+            U: U1.fold(U2, &cross_term_commits, &r),
+            W: W1.fold(W2, &cross_terms, &r),
+        },
+        cross_term_commits,
+        r,
+    })
+}
+
+/// Inputs of the support circuit, which repeat the folding of commitments done by
+/// [`RelaxedPlonkInstance::fold`]
+///
+/// First `acc_W + r * incoming_W` for each W commitment, then `E + r^k * T_k` for each cross
+/// term commitment, where `E` is the output of the previous input. At the zero step all scalars
+/// are zero, since the output accumulator is the input one
+fn support_circuit_inputs<CMain: CurveAffine>(
+    acc: &RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+    incoming: &plonk::PlonkInstance<CMain>,
+    cross_term_commits: &[CMain],
+    r: CMain::ScalarExt,
+    is_zero_step: bool,
+) -> Vec<support_circuit::InstanceInput<CMain>> {
+    let (l0, r) = if is_zero_step {
+        (CMain::ScalarExt::ZERO, CMain::ScalarExt::ZERO)
+    } else {
+        (CMain::ScalarExt::ONE, r)
+    };
+    let to_base = |v: &CMain::ScalarExt| -> CMain::Base { util::fe_to_fe(v).unwrap() };
+
+    let W_inputs = acc
+        .W_commitments
+        .iter()
+        .zip_eq(incoming.W_commitments.iter())
+        .map(|(p0, p1)| support_circuit::InstanceInput {
+            p0: *p0,
+            l0: to_base(&l0),
+            p1: *p1,
+            l1: to_base(&r),
+        });
+
+    let E_inputs = cross_term_commits
+        .iter()
+        .zip(iter::successors(Some(r), |el| Some(*el * r))) // r^1, r^2, ...
+        .scan(acc.E_commitment, |E, (T_k, power_of_r)| {
+            let input = support_circuit::InstanceInput {
+                p0: *E,
+                l0: to_base(&l0),
+                p1: *T_k,
+                l1: to_base(&power_of_r),
+            };
+            *E = best_multiexp(&[l0, power_of_r], &[*E, *T_k]).into();
+
+            Some(input)
+        });
+
+    W_inputs.chain(E_inputs).collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<CMain: CurveAffine> {
+    #[error("Error while verify: {0:?}")]
+    Verify(Box<[VerifyError<CMain>]>),
+
+    #[error("Sangria NIFS error: {0:?}")]
+    Sangria(#[from] nifs::sangria::Error),
+
+    #[error("While collecting witness on the primary circuit at step {step}: {err:?}")]
+    WhileCollectPrimaryWitness { step: usize, err: Halo2PlonkError },
+
+    #[error("While collecting plonk structure on the primary circuit: {err:?}")]
+    WhileCollectPrimaryS { err: Halo2PlonkError },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError<CMain: CurveAffine> {
+    #[error("Mismatch consistency marker: {expected:?} != {actual:?}")]
+    MismatchConsistencyMarker {
+        expected: CMain::ScalarExt,
+        actual: CMain::ScalarExt,
+    },
+
+    #[error("Mismatch step circuit instances hash accumulator: {expected:?} != {actual:?}")]
+    MismatchStepCircuitInstancesHashAcc {
+        expected: CMain::ScalarExt,
+        actual: CMain::ScalarExt,
+    },
+
+    #[error("Mismatch step circuit instances of the last step {step}")]
+    MismatchStepCircuitInstances { step: usize },
+
+    #[error("While is sat primary sangria acc: {0:?}")]
+    WhilePrimaryIsSat(Vec<nifs::sangria::VerifyError>),
+
+    #[error("While is sat support sangria acc: {0:?}")]
+    WhileSupportIsSat(Vec<nifs::sangria::VerifyError>),
+
+    #[error("While sps verify of the last incoming trace: {0:?}")]
+    WhileSpsVerify(sps::Error),
+
+    #[error("Mismatch primary sangria acc with the one folded at the last step")]
+    MismatchPrimaryFold,
+
+    #[error("While sangria verify of the last step, support trace {index}: {err:?}")]
+    WhileSupportVerify {
+        index: usize,
+        err: nifs::sangria::Error,
+    },
+
+    #[error("Mismatch support sangria acc with the one folded at the last step")]
+    MismatchSupportFold,
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize> IvcState
+    for IVC<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
+where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    fn step(&self) -> usize {
+        self.step.get()
+    }
+
+    fn accumulator_size(&self) -> usize {
+        self.primary_acc.W.size() + self.support_acc.W.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{array, path::Path};
+
+    use tracing::*;
+    use tracing_test::traced_test;
+
+    use super::{Error, VerifyError, IVC};
+    use crate::{
+        commitment::CommitmentKey,
+        halo2_proofs::arithmetic::Field,
+        ivc::{
+            step_circuit::{lookup, trivial},
+            StepCircuit,
+        },
+        sangria_prelude::bn256::{C1Affine, C1Scalar, C2Affine, C2Scalar},
+    };
+
+    /// Arity : Input/output size per fold-step for primary step-circuit
+    /// For tivial case it can be any number
+    const ARITY: usize = 5;
+
+    /// Key size for Primary Circuit
+    const PRIMARY_COMMITMENT_KEY_SIZE: usize = 23;
+    const SECONDARY_COMMITMENT_KEY_SIZE: usize = 23;
+
+    const PRIMARY_CIRCUIT_TABLE_SIZE: u32 = 20;
+
+    const FOLDER: &str = ".cache/examples";
+
+    type PublicParams<SC> = super::PublicParams<ARITY, C1Affine, C2Affine, SC>;
+
+    fn new_pp<SC: StepCircuit<ARITY, C1Scalar>>(sc: &SC) -> PublicParams<SC>
+    where
+        SC::StepWitness: Default,
+    {
+        let primary_commitment_key = unsafe {
+            CommitmentKey::<C1Affine>::load_or_setup_cache(
+                Path::new(FOLDER),
+                "bn256",
+                PRIMARY_COMMITMENT_KEY_SIZE,
+            )
+            .unwrap()
+        };
+
+        let secondary_commitment_key = unsafe {
+            CommitmentKey::<C2Affine>::load_or_setup_cache(
+                Path::new(FOLDER),
+                "grumpkin",
+                SECONDARY_COMMITMENT_KEY_SIZE,
+            )
+            .unwrap()
+        };
+
+        info!("ck generated");
+
+        let pp = super::PublicParams::new(
+            sc,
+            primary_commitment_key,
+            secondary_commitment_key,
+            PRIMARY_CIRCUIT_TABLE_SIZE,
+        )
+        .unwrap();
+        info!("pp created");
+
+        pp
+    }
+
+    fn fold<SC: StepCircuit<ARITY, C1Scalar>>(
+        sc: &SC,
+        pp: &mut PublicParams<SC>,
+        steps: usize,
+    ) -> IVC<ARITY, C1Affine, C2Affine, SC>
+    where
+        SC::StepWitness: Default,
+    {
+        let ivc = IVC::new(pp, sc, array::from_fn(|_| C1Scalar::ZERO)).expect("while step=0");
+
+        (1..=steps).fold(ivc, |ivc, step| {
+            ivc.next(pp, sc)
+                .unwrap_or_else(|err| panic!("while step={step}: {err:?}"))
+        })
+    }
+
+    fn verify_errors<SC: StepCircuit<ARITY, C1Scalar>>(
+        ivc: IVC<ARITY, C1Affine, C2Affine, SC>,
+        pp: &PublicParams<SC>,
+    ) -> Box<[VerifyError<C1Affine>]> {
+        match ivc.verify(pp) {
+            Err(Error::Verify(errors)) => errors,
+            Err(err) => panic!("unexpected error: {err:?}"),
+            Ok(_) => panic!("tampered IVC passed verify"),
+        }
+    }
+
+    fn check_last_step_errors<SC: StepCircuit<ARITY, C1Scalar>>(
+        ivc: &IVC<ARITY, C1Affine, C2Affine, SC>,
+        pp: &PublicParams<SC>,
+    ) -> Box<[VerifyError<C1Affine>]> {
+        match ivc.check_last_step(pp) {
+            Err(Error::Verify(errors)) => errors,
+            Err(err) => panic!("unexpected error: {err:?}"),
+            Ok(()) => panic!("tampered IVC passed the last step check"),
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn ivc() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+
+        let ivc = fold(&sc, &mut pp, 2);
+        ivc.check_last_step(&pp).expect("while check last step");
+        ivc.verify(&pp).expect("while verify");
+    }
+
+    /// Each part of the last step is tampered & restored in turn
+    #[traced_test]
+    #[test]
+    fn check_last_step_tampered() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+
+        let mut ivc = fold(&sc, &mut pp, 1);
+        ivc.check_last_step(&pp).expect("while check step=1");
+
+        ivc.primary_trace.u.instances[0][0] += C1Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, VerifyError::MismatchConsistencyMarker { .. })),
+            "{errors:?}"
+        );
+        ivc.primary_trace.u.instances[0][0] -= C1Scalar::ONE;
+
+        ivc.support_acc.U.u += C2Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, VerifyError::MismatchSupportFold)),
+            "{errors:?}"
+        );
+        ivc.support_acc.U.u -= C2Scalar::ONE;
+
+        ivc.primary_acc.U.u += C1Scalar::ONE;
+        let errors = check_last_step_errors(&ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, VerifyError::MismatchPrimaryFold)),
+            "{errors:?}"
+        );
+        ivc.primary_acc.U.u -= C1Scalar::ONE;
+
+        ivc.check_last_step(&pp)
+            .expect("while check restored step=1");
+    }
+
+    /// Lookup adds W-commitments to the primary trace, so the on-circuit Sangria verifier takes
+    /// more than one W support input before the cross term ones
+    #[traced_test]
+    #[test]
+    fn ivc_multiple_W_commitments() {
+        let sc = lookup::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+        assert!(pp.primary_initial_trace.u.W_commitments.len() > 1);
+
+        fold(&sc, &mut pp, 2).verify(&pp).expect("while verify");
+    }
+
+    /// `u` of the accumulator is absorbed into the consistency marker of the last step
+    #[traced_test]
+    #[test]
+    fn verify_tampered_accumulator() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+
+        let mut ivc = fold(&sc, &mut pp, 1);
+        ivc.primary_acc.U.u += C1Scalar::ONE;
+
+        let errors = verify_errors(ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, VerifyError::MismatchConsistencyMarker { .. })),
+            "{errors:?}"
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn verify_tampered_pub_instances() {
+        let sc = trivial::Circuit::<ARITY, C1Scalar>::default();
+        let mut pp = new_pp(&sc);
+
+        let mut ivc = fold(&sc, &mut pp, 1);
+        ivc.primary_pub_instances[0].push(vec![C1Scalar::ONE]);
+
+        let errors = verify_errors(ivc, &pp);
+        assert!(
+            errors
+                .iter()
+                .any(|err| matches!(err, VerifyError::MismatchStepCircuitInstancesHashAcc { .. })),
+            "{errors:?}"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::{iter, marker::PhantomData};
+
+use serde::Serialize;
+use tracing::info_span;
+
+use super::sfc::{self, StepFoldingCircuit, MARKERS_LEN};
+use crate::{
+    constants::NUM_HASH_BITS,
+    digest::{self, DigestToBits},
+    halo2_proofs::halo2curves::{
+        ff::{Field, FromUniformBytes, PrimeField, PrimeFieldBits},
+        group::prime::PrimeCurveAffine,
+        CurveAffine,
+    },
+    ivc::{
+        cyclefold::{
+            incrementally_verifiable_computation::public_params::{setup_support, Error},
+            ro_nark, support_circuit,
+        },
+        StepCircuit,
+    },
+    nifs::{
+        self,
+        sangria::{FoldablePlonkInstance, FoldablePlonkTrace, VanillaFS},
+    },
+    plonk::PlonkStructure,
+    polynomial::Expression,
+    sangria_prelude::CommitmentKey,
+    table::{CircuitLayout, CircuitRunner},
+    util,
+};
+
+pub struct PublicParams<
+    const ARITY: usize,
+    CMain,
+    CSup,
+    SC,
+    const SUPPORT_INSTANCES_LEN: usize = { support_circuit::DEFAULT_INSTANCES_LEN },
+> where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    pub primary_ck: CommitmentKey<CMain>,
+    pub primary_S: PlonkStructure<CMain::ScalarExt>,
+    pub primary_k_table_size: u32,
+    /// Layout of the step folding circuit, reused to collect its witness on each step
+    pub primary_layout: CircuitLayout<CMain::ScalarExt, sfc::Config<SC::Config>>,
+    pub primary_initial_trace: FoldablePlonkTrace<CMain, MARKERS_LEN>,
+
+    pub support_ck: CommitmentKey<CSup>,
+    pub support_S: PlonkStructure<CSup::ScalarExt>,
+    pub support_initial_trace: FoldablePlonkTrace<CSup, SUPPORT_INSTANCES_LEN>,
+
+    hash_bytes: CMain,
+
+    _p: PhantomData<SC>,
+}
+
+impl<const ARITY: usize, CMain, CSup, SC, const SUPPORT_INSTANCES_LEN: usize>
+    PublicParams<ARITY, CMain, CSup, SC, SUPPORT_INSTANCES_LEN>
+where
+    CMain: CurveAffine<Base = <CSup as PrimeCurveAffine>::Scalar>,
+    CSup: CurveAffine<Base = <CMain as PrimeCurveAffine>::Scalar>,
+    SC: StepCircuit<ARITY, CMain::Scalar>,
+    CMain::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    CSup::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// The step circuit is synthesized on the default [`StepCircuit::StepWitness`]
+    pub fn new(
+        primary_sc: &SC,
+        ck1: CommitmentKey<CMain>,
+        ck2: CommitmentKey<CSup>,
+        k_table_size: u32,
+    ) -> Result<Self, Error>
+    where
+        CMain::ScalarExt: Serialize,
+        CSup::ScalarExt: Serialize,
+        SC::StepWitness: Default,
+    {
+        let (support_S, support_initial_trace) =
+            setup_support::<CMain, CSup, SUPPORT_INSTANCES_LEN>(&ck2)?;
+
+        let _primary = info_span!("primary").entered();
+
+        let primary_step_witness = SC::StepWitness::default();
+
+        let (primary_S, primary_layout, primary_initial_trace) = {
+            let mock_S = {
+                let _s = info_span!("pre_run_mock").entered();
+
+                let mock_sfc = Self::mock_primary_sfc(
+                    primary_sc,
+                    &primary_step_witness,
+                    k_table_size,
+                    &support_S,
+                    &support_initial_trace.u,
+                );
+                let mock_instances = mock_sfc.initial_instances();
+
+                #[cfg(test)]
+                {
+                    let _mock = info_span!("mock-debug").entered();
+                    crate::halo2_proofs::dev::MockProver::run(
+                        k_table_size,
+                        &mock_sfc,
+                        mock_instances.clone(),
+                    )
+                    .unwrap()
+                    .verify()
+                    .unwrap();
+                }
+
+                CircuitRunner::new(k_table_size, mock_sfc, mock_instances)
+                    .try_collect_plonk_structure()
+                    .map_err(Error::WhileCollectS)?
+            };
+
+            let sfc = StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
+                sc: primary_sc,
+                witness: &primary_step_witness,
+                input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<
+                    CMain,
+                    CSup,
+                    SUPPORT_INSTANCES_LEN,
+                >(&mock_S, &support_S, &support_initial_trace.u),
+                _p: PhantomData,
+            };
+
+            let primary_instances = sfc.initial_instances();
+
+            #[cfg(test)]
+            {
+                let _mock = info_span!("mock-debug").entered();
+                crate::halo2_proofs::dev::MockProver::run(
+                    k_table_size,
+                    &sfc,
+                    primary_instances.clone(),
+                )
+                .unwrap()
+                .verify()
+                .unwrap();
+            }
+
+            let primary_cr = CircuitRunner::new(k_table_size, sfc, primary_instances.clone());
+            let primary_S = primary_cr
+                .try_collect_plonk_structure()
+                .map_err(Error::WhileCollectS)?;
+
+            let primary_initial_trace = VanillaFS::<CMain, MARKERS_LEN>::generate_plonk_trace(
+                &ck1,
+                &primary_instances,
+                &primary_cr
+                    .try_collect_witness()
+                    .map_err(Error::WhileCollectWitness)?,
+                &nifs::sangria::ProverParam {
+                    S: primary_S.clone(),
+                    pp_digest: (CMain::Base::ZERO, CMain::Base::ZERO),
+                },
+                &mut ro_nark(),
+            )?;
+
+            (primary_S, primary_cr.layout(), primary_initial_trace)
+        };
+
+        let hash_bytes = {
+            let _digest = info_span!("digest").entered();
+
+            #[derive(Serialize)]
+            struct Meaningful<'link, CMainScalar: PrimeField, CSupScalar: PrimeField>
+            where
+                CMainScalar: Serialize,
+                CSupScalar: Serialize,
+            {
+                primary_S: &'link PlonkStructure<CMainScalar>,
+                primary_k_table_size: &'link u32,
+                support_S: &'link PlonkStructure<CSupScalar>,
+            }
+
+            let bytes = digest::DefaultHasher::digest_to_bits(&Meaningful {
+                primary_S: &primary_S,
+                primary_k_table_size: &k_table_size,
+                support_S: &support_S,
+            })
+            .map_err(Error::Digest)?;
+
+            digest::into_curve_from_bits::<CMain>(&bytes, NUM_HASH_BITS)
+        };
+
+        Ok(Self {
+            primary_ck: ck1,
+            support_ck: ck2,
+            primary_k_table_size: k_table_size,
+            primary_layout,
+
+            primary_initial_trace,
+            support_initial_trace,
+
+            primary_S,
+            support_S,
+
+            hash_bytes,
+
+            _p: PhantomData,
+        })
+    }
+
+    /// The step folding circuit contains accumulators whose size depends on the primary plonk
+    /// structure itself, so we collect it in advance on a circuit with a minimal mock structure
+    fn mock_primary_sfc<'sc>(
+        primary_sc: &'sc SC,
+        primary_step_witness: &'sc SC::StepWitness,
+        k_table_size: u32,
+        support_S: &PlonkStructure<CMain::Base>,
+        support_initial_instance: &FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    ) -> StepFoldingCircuit<'sc, ARITY, CMain, CSup, SC> {
+        let num_io = iter::once(MARKERS_LEN)
+            .chain(primary_sc.instances().iter().map(|col| col.len()))
+            .collect::<Box<[_]>>();
+
+        StepFoldingCircuit::<ARITY, CMain, CSup, SC> {
+            sc: primary_sc,
+            witness: primary_step_witness,
+            input: sfc::Input::<ARITY, CMain::ScalarExt>::new_initial::<
+                CMain,
+                CSup,
+                SUPPORT_INSTANCES_LEN,
+            >(
+                &PlonkStructure {
+                    k: k_table_size as usize,
+                    num_io,
+                    // because with zero gates - calc count is zero - sfc panic
+                    gates: vec![Expression::Constant(CMain::ScalarExt::ZERO)],
+                    num_challenges: 3,
+                    ..Default::default()
+                },
+                support_S,
+                support_initial_instance,
+            ),
+            _p: PhantomData,
+        }
+    }
+
+    pub fn pp_digest_coordinates<F: PrimeField>(&self) -> (F, F) {
+        self.hash_bytes
+            .coordinates()
+            .map(|c| {
+                (
+                    util::fe_to_fe(c.x()).unwrap(),
+                    util::fe_to_fe(c.y()).unwrap(),
+                )
+            })
+            .unwrap()
+    }
+
+    pub fn primary_prover_params(&self) -> nifs::sangria::ProverParam<CMain> {
+        nifs::sangria::ProverParam {
+            S: self.primary_S.clone(),
+            pp_digest: self.pp_digest_coordinates(),
+        }
+    }
+
+    pub fn sangria_prover_params(&self) -> nifs::sangria::ProverParam<CSup> {
+        nifs::sangria::ProverParam {
+            S: self.support_S.clone(),
+            pp_digest: self.pp_digest_coordinates(),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::iter;
+
+use itertools::Itertools;
+use tracing::{debug, error, info_span, instrument, trace};
+
+use crate::{
+    constants::NUM_CHALLENGE_BITS,
+    halo2_proofs::{
+        halo2curves::ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
+        plonk::Error as Halo2PlonkError,
+    },
+    ivc::cyclefold::{
+        ro_acc_chip,
+        sfc::{
+            self as cyclefold_sfc,
+            input::assigned::{
+                self as cyclefold_assigned, AssignedSelfTrace, BigUintPoint, MainGateConfig,
+                NativePlonkInstance,
+            },
+        },
+    },
+    main_gate::{AdviceCyclicAssignor, AssignedValue, MainGate, RegionCtx, WrapValue},
+    poseidon::ROCircuitTrait,
+};
+
+/// Assigned version of [`super::RelaxedPlonkInstance`]
+#[derive(Clone, Debug)]
+pub struct RelaxedPlonkInstance<F: PrimeField> {
+    pub ins: NativePlonkInstance<F>,
+    pub E_commitment: BigUintPoint<AssignedValue<F>>,
+    pub u: AssignedValue<F>,
+}
+
+impl<F: PrimeField> RelaxedPlonkInstance<F> {
+    fn assign_advice_from(
+        region: &mut RegionCtx<'_, F>,
+        original: &super::RelaxedPlonkInstance<F>,
+        main_gate_config: &MainGateConfig,
+    ) -> Result<Self, Halo2PlonkError> {
+        let _s = info_span!("sangria_relaxed_plonk_instance").entered();
+        let start_offset = region.offset();
+
+        trace!("start assign at {start_offset}");
+
+        let super::RelaxedPlonkInstance {
+            ins,
+            E_commitment,
+            u,
+        } = original;
+
+        let ins = NativePlonkInstance::assign_advice_from_native(region, ins, main_gate_config)?;
+        let E_commitment = E_commitment.clone().assign(region, main_gate_config)?;
+
+        let u = main_gate_config
+            .advice_cycle_assigner()
+            .assign_next_advice(region, || "u", *u)?;
+
+        trace!("took {} rows total", region.offset() - start_offset);
+        region.next();
+
+        Ok(Self {
+            ins,
+            E_commitment,
+            u,
+        })
+    }
+
+    pub fn conditional_select<const T: usize>(
+        region: &mut RegionCtx<'_, F>,
+        mg: &MainGate<F, T>,
+        lhs: &Self,
+        rhs: &Self,
+        cond: &AssignedValue<F>,
+    ) -> Result<Self, Halo2PlonkError> {
+        let Self {
+            ins: lhs_ins,
+            E_commitment: lhs_E_commitment,
+            u: lhs_u,
+        } = lhs;
+        let Self {
+            ins: rhs_ins,
+            E_commitment: rhs_E_commitment,
+            u: rhs_u,
+        } = rhs;
+
+        Ok(Self {
+            ins: NativePlonkInstance::conditional_select(region, mg, lhs_ins, rhs_ins, cond)?,
+            E_commitment: BigUintPoint::conditional_select(
+                region,
+                mg,
+                lhs_E_commitment,
+                rhs_E_commitment,
+                cond,
+            )?,
+            u: mg.conditional_select(region, lhs_u, rhs_u, cond)?,
+        })
+    }
+
+    pub fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
+        let Self {
+            ins,
+            E_commitment,
+            u,
+        } = self;
+
+        ins.iter_wrap_values()
+            .chain(E_commitment.iter_wrap_values())
+            .chain(iter::once(WrapValue::Assigned(u.clone())))
+    }
+}
+
+/// Recursive trace of the circuit itself
+#[derive(Debug)]
+pub struct SelfTrace<F: PrimeField> {
+    pub input_accumulator: RelaxedPlonkInstance<F>,
+    pub incoming: NativePlonkInstance<F>,
+    pub proof: Box<[BigUintPoint<AssignedValue<F>>]>,
+}
+
+impl<F: PrimeField> AssignedSelfTrace<F> for SelfTrace<F> {
+    type Original = super::SelfTrace<F>;
+
+    fn assign_advice_from(
+        region: &mut RegionCtx<'_, F>,
+        original: &super::SelfTrace<F>,
+        main_gate_config: &MainGateConfig,
+    ) -> Result<Self, Halo2PlonkError> {
+        let start_offset = region.offset();
+        let _s = info_span!("self_trace").entered();
+
+        trace!("start assign at {start_offset}");
+
+        let super::SelfTrace {
+            input_accumulator,
+            incoming,
+            proof,
+        } = original;
+
+        let self_ = Self {
+            input_accumulator: RelaxedPlonkInstance::assign_advice_from(
+                region,
+                input_accumulator,
+                main_gate_config,
+            )?,
+            incoming: NativePlonkInstance::assign_advice_from_native(
+                region,
+                incoming,
+                main_gate_config,
+            )?,
+            proof: proof
+                .iter()
+                .cloned()
+                .map(|commit| commit.assign(region, main_gate_config))
+                .collect::<Result<Box<[_]>, _>>()?,
+        };
+
+        trace!("`SelfTrace` took {} rows", region.offset() - start_offset);
+
+        Ok(self_)
+    }
+
+    fn incoming(&self) -> &NativePlonkInstance<F> {
+        &self.incoming
+    }
+
+    fn iter_input_accumulator_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
+        self.input_accumulator.iter_wrap_values()
+    }
+}
+
+impl<F: PrimeField> SelfTrace<F> {
+    fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
+        let Self {
+            input_accumulator,
+            incoming,
+            proof,
+        } = self;
+
+        input_accumulator
+            .iter_wrap_values()
+            .chain(incoming.iter_wrap_values())
+            .chain(proof.iter().flat_map(|commit| commit.iter_wrap_values()))
+    }
+}
+
+/// Assigned [`super::Input`]
+pub type Input<const ARITY: usize, F> = cyclefold_assigned::Input<ARITY, F, super::SelfTrace<F>>;
+
+impl<const A: usize, F: PrimeField> Input<A, F> {
+    /// Fold `self_trace.incoming` into `self_trace.input_accumulator` by Sangria
+    ///
+    /// Consistency markers, challenges & `u` are folded natively. Commitments are non-native, so
+    /// the new ones are taken from the outputs of support circuit inputs batched in
+    /// `support_trace.incoming` after checking them: first one input per W commitment with
+    /// `acc_W + r * incoming_W`, then one input per cross term with `E + r^k * T_k`, where `E` is
+    /// the output of the previous one
+    ///
+    /// At the zero step all scalars in support circuit traces are expected to be zero
+    #[instrument(skip_all)]
+    pub fn fold_self_trace(
+        &self,
+        region: &mut RegionCtx<F>,
+        main_gate_config: &MainGateConfig,
+    ) -> Result<RelaxedPlonkInstance<F>, Halo2PlonkError>
+    where
+        F: PrimeFieldBits + FromUniformBytes<64>,
+    {
+        let SelfTrace {
+            input_accumulator: acc,
+            incoming,
+            proof,
+        } = &self.self_trace;
+
+        let W_commitments_len = acc.ins.W_commitments.len();
+        let support_inputs = self.support_trace.iter_support_inputs().collect::<Vec<_>>();
+        if support_inputs.len() < W_commitments_len + proof.len() {
+            error!(
+                "expected at least {} support circuit inputs, but got {}",
+                W_commitments_len + proof.len(),
+                support_inputs.len()
+            );
+            return Err(Halo2PlonkError::Synthesis);
+        }
+
+        let mg = MainGate::new(main_gate_config.clone());
+        let bn_chip = cyclefold_sfc::bn_chip(main_gate_config.clone());
+
+        let to_limbs = |region: &mut RegionCtx<F>, value: &AssignedValue<F>| {
+            bn_chip
+                .from_assigned_value_to_limbs(region, value)
+                .map_err(|err| {
+                    error!("bn error: {err:?}");
+                    Halo2PlonkError::Synthesis
+                })
+        };
+
+        let r_bits = ro_acc_chip(main_gate_config.clone())
+            .absorb_base(self.pp_digest.0.clone().into())
+            .absorb_base(self.pp_digest.1.clone().into())
+            .absorb_iter(self.self_trace.iter_wrap_values())
+            .inspect(|buf| trace!("buf before primary sangria_cha: {buf:?}"))
+            .squeeze_n_bits(region, NUM_CHALLENGE_BITS)?;
+        let r = mg.le_bits_to_num(region, &r_bits)?;
+
+        debug!("primary sangria_cha: {:?}", r.value());
+
+        let fold_values = |region: &mut RegionCtx<F>,
+                           acc_values: &[AssignedValue<F>],
+                           incoming_values: &[AssignedValue<F>]| {
+            acc_values
+                .iter()
+                .zip_eq(incoming_values.iter())
+                .map(|(acc_value, incoming_value)| {
+                    let r_incoming_value = mg.mul(region, &r, incoming_value)?;
+                    mg.add(region, acc_value, &r_incoming_value)
+                })
+                .collect::<Result<Vec<_>, Halo2PlonkError>>()
+        };
+
+        let consistency_markers =
+            fold_values(region, &acc.ins.instances[0], &incoming.instances[0])?;
+        let challenges = fold_values(region, &acc.ins.challenges, &incoming.challenges)?;
+        let u = mg.add(region, &acc.u, &r)?;
+
+        let zero = mg.assign_constant(region, F::ZERO)?;
+        let one = mg.assign_constant(region, F::ONE)?;
+        let is_zero_step = mg.is_zero_term(region, self.step.clone())?;
+
+        let expected_l0 = mg.conditional_select(region, &zero, &one, &is_zero_step)?;
+        let expected_l0_limbs = to_limbs(region, &expected_l0)?;
+
+        // Inputs after W & E ones are padding of the last support circuit trace
+        let (W_support_inputs, E_support_inputs) = support_inputs.split_at(W_commitments_len);
+
+        let expected_l1 = mg.conditional_select(region, &zero, &r, &is_zero_step)?;
+        let expected_l1_limbs = to_limbs(region, &expected_l1)?;
+
+        let W_commitments = acc
+            .ins
+            .W_commitments
+            .iter()
+            .zip_eq(incoming.W_commitments.iter())
+            .zip_eq(W_support_inputs.iter().cloned())
+            .enumerate()
+            .map(|(index, ((acc_W, incoming_W), support_input))| {
+                trace!("start {index} W commitment check");
+
+                let [out_x, out_y, x0, y0, l0, x1, y1, l1] = support_input;
+
+                constrain_limbs(region, &l0.1, &expected_l0_limbs)?;
+                constrain_limbs(region, &l1.1, &expected_l1_limbs)?;
+
+                BigUintPoint::constrain_equal(region, acc_W, &BigUintPoint { x: x0.1, y: y0.1 })?;
+                BigUintPoint::constrain_equal(
+                    region,
+                    incoming_W,
+                    &BigUintPoint { x: x1.1, y: y1.1 },
+                )?;
+
+                Ok(BigUintPoint {
+                    x: out_x.1,
+                    y: out_y.1,
+                })
+            })
+            .collect::<Result<Vec<_>, Halo2PlonkError>>()?;
+
+        let mut E_commitment = acc.E_commitment.clone();
+        let mut power_of_r = r.clone();
+        for (index, (cross_term_commit, support_input)) in proof
+            .iter()
+            .zip(E_support_inputs.iter().cloned())
+            .enumerate()
+        {
+            trace!("start {index} cross term commitment check");
+
+            let [out_x, out_y, x0, y0, l0, x1, y1, l1] = support_input;
+
+            let expected_l1 = mg.conditional_select(region, &zero, &power_of_r, &is_zero_step)?;
+            let expected_l1_limbs = to_limbs(region, &expected_l1)?;
+
+            constrain_limbs(region, &l0.1, &expected_l0_limbs)?;
+            constrain_limbs(region, &l1.1, &expected_l1_limbs)?;
+
+            BigUintPoint::constrain_equal(
+                region,
+                &E_commitment,
+                &BigUintPoint { x: x0.1, y: y0.1 },
+            )?;
+            BigUintPoint::constrain_equal(
+                region,
+                cross_term_commit,
+                &BigUintPoint { x: x1.1, y: y1.1 },
+            )?;
+
+            E_commitment = BigUintPoint {
+                x: out_x.1,
+                y: out_y.1,
+            };
+            power_of_r = mg.mul(region, &power_of_r, &r)?;
+        }
+
+        Ok(RelaxedPlonkInstance {
+            ins: NativePlonkInstance {
+                W_commitments,
+                instances: vec![consistency_markers],
+                challenges,
+            },
+            E_commitment,
+            u,
+        })
+    }
+}
+
+fn constrain_limbs<F: PrimeField>(
+    region: &mut RegionCtx<'_, F>,
+    lhs: &[AssignedValue<F>],
+    rhs: &[AssignedValue<F>],
+) -> Result<(), Halo2PlonkError> {
+    lhs.iter()
+        .zip_eq(rhs.iter())
+        .try_for_each(|(l, r)| region.constrain_equal(l.cell(), r.cell()))
+}
@@ -0,0 +1,263 @@
+use tracing::{instrument, trace};
+
+use super::MARKERS_LEN;
+use crate::{
+    constants::NUM_CHALLENGE_BITS,
+    halo2_proofs::halo2curves::{
+        ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
+        CurveAffine,
+    },
+    ivc::cyclefold::{
+        self,
+        sfc::input::{
+            self as cyclefold_input, BigUintPoint, NativePlonkInstance, SelfTraceInput,
+            SupportTrace,
+        },
+    },
+    nifs, plonk,
+    poseidon::{AbsorbInRO, ROTrait},
+};
+
+pub mod assigned;
+
+/// Off-circuit form of [`nifs::sangria::RelaxedPlonkInstance`] of the primary circuit
+///
+/// Only consistency markers are folded from instance columns, so `ins.instances` is the single
+/// column with them. The step circuit instances are accumulated by the hash inside the circuit
+/// itself, see [`cyclefold_input::Input::step_circuit_instances_hash_accumulator`]
+#[derive(Debug, Clone)]
+pub struct RelaxedPlonkInstance<F: PrimeField> {
+    pub(crate) ins: NativePlonkInstance<F>,
+    pub(crate) E_commitment: BigUintPoint<F>,
+    pub(crate) u: F,
+}
+
+impl<F: PrimeField> RelaxedPlonkInstance<F> {
+    pub fn new<CMain: CurveAffine<ScalarExt = F>>(
+        acc: &nifs::sangria::RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+    ) -> Self {
+        let nifs::sangria::RelaxedPlonkInstance {
+            W_commitments,
+            consistency_markers,
+            challenges,
+            E_commitment,
+            u,
+            step_circuit_instances_hash_accumulator: _,
+        } = acc;
+
+        Self {
+            ins: NativePlonkInstance {
+                W_commitments: W_commitments
+                    .iter()
+                    .map(|commitment| BigUintPoint::new(commitment).unwrap())
+                    .collect(),
+                instances: vec![consistency_markers.to_vec()],
+                challenges: challenges.clone(),
+            },
+            E_commitment: BigUintPoint::new(E_commitment).unwrap(),
+            u: *u,
+        }
+    }
+
+    fn get_without_witness(&self) -> Self {
+        Self {
+            ins: self.ins.get_without_witness(),
+            E_commitment: BigUintPoint::identity(),
+            u: F::ZERO,
+        }
+    }
+}
+
+impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for RelaxedPlonkInstance<F> {
+    fn absorb_into(&self, ro: &mut RO) {
+        let Self {
+            ins,
+            E_commitment,
+            u,
+        } = self;
+
+        ro.absorb(ins).absorb(E_commitment).absorb_field(*u);
+    }
+}
+
+/// Recursive trace of the circuit itself
+#[derive(Debug, Clone)]
+pub struct SelfTrace<F: PrimeField> {
+    pub input_accumulator: RelaxedPlonkInstance<F>,
+    pub incoming: NativePlonkInstance<F>,
+    /// Commitments to cross terms, see [`nifs::sangria::CrossTermCommits`]
+    pub proof: Box<[BigUintPoint<F>]>,
+}
+
+impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for SelfTrace<F> {
+    fn absorb_into(&self, ro: &mut RO) {
+        let Self {
+            input_accumulator,
+            incoming,
+            proof,
+        } = self;
+
+        ro.absorb(input_accumulator)
+            .absorb(incoming)
+            .absorb_iter(proof.iter());
+    }
+}
+
+impl<F: PrimeField> SelfTrace<F> {
+    pub fn new<CMain: CurveAffine<ScalarExt = F>>(
+        acc: &nifs::sangria::RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+        incoming: &plonk::PlonkInstance<CMain>,
+        cross_term_commits: &[CMain],
+    ) -> Self {
+        Self {
+            input_accumulator: RelaxedPlonkInstance::new(acc),
+            incoming: NativePlonkInstance::new(incoming),
+            proof: cross_term_commits
+                .iter()
+                .map(|commit| BigUintPoint::new(commit).unwrap())
+                .collect(),
+        }
+    }
+
+    /// Folding challenge `r`, the same as the one calculated on-circuit by
+    /// [`assigned::Input::fold_self_trace`]
+    ///
+    /// Unlike [`nifs::sangria::VanillaFS::prove`], the challenge is generated by the random oracle
+    /// over the native field of the primary circuit
+    pub fn challenge(&self, pp_digest: (F, F)) -> F
+    where
+        F: PrimeFieldBits + FromUniformBytes<64>,
+    {
+        let (pp0, pp1) = pp_digest;
+
+        cyclefold::ro_acc()
+            .absorb_field(pp0)
+            .absorb_field(pp1)
+            .absorb(self)
+            .inspect(|buf| trace!("buf before primary sangria_cha: {buf:?}"))
+            .squeeze(NUM_CHALLENGE_BITS)
+    }
+}
+
+impl<F: PrimeField> SelfTraceInput<F> for SelfTrace<F> {
+    type Accumulator = RelaxedPlonkInstance<F>;
+    type Assigned = assigned::SelfTrace<F>;
+
+    #[instrument(skip_all)]
+    fn new_initial(native_plonk_structure: &plonk::PlonkStructure<F>) -> Self {
+        let W_commitments_len = cyclefold_input::W_commitments_len(native_plonk_structure);
+        let challenges_len = native_plonk_structure.num_challenges;
+        let cross_terms_len = native_plonk_structure
+            .get_degree_for_folding()
+            .saturating_sub(1);
+
+        trace!("W_commitments_len: {W_commitments_len}, cross_terms_len: {cross_terms_len}");
+
+        SelfTrace {
+            input_accumulator: RelaxedPlonkInstance {
+                ins: NativePlonkInstance {
+                    W_commitments: vec![BigUintPoint::identity(); W_commitments_len],
+                    instances: vec![vec![F::ZERO; MARKERS_LEN]],
+                    challenges: vec![F::ZERO; challenges_len],
+                },
+                E_commitment: BigUintPoint::identity(),
+                u: F::ZERO,
+            },
+            incoming: NativePlonkInstance {
+                W_commitments: vec![BigUintPoint::identity(); W_commitments_len],
+                instances: native_plonk_structure
+                    .num_io
+                    .iter()
+                    .map(|len| vec![F::ZERO; *len])
+                    .collect(),
+                challenges: vec![F::ZERO; challenges_len],
+            },
+            proof: vec![BigUintPoint::identity(); cross_terms_len].into_boxed_slice(),
+        }
+    }
+
+    fn input_accumulator(&self) -> &Self::Accumulator {
+        &self.input_accumulator
+    }
+
+    /// Count of support circuit inputs delegated by this trace: one for each W commitment & one
+    /// for each cross term commitment
+    fn support_inputs_len(&self) -> usize {
+        self.input_accumulator.ins.W_commitments.len() + self.proof.len()
+    }
+
+    fn get_without_witness(&self) -> Self {
+        Self {
+            input_accumulator: self.input_accumulator.get_without_witness(),
+            incoming: self.incoming.get_without_witness(),
+            proof: vec![BigUintPoint::identity(); self.proof.len()].into_boxed_slice(),
+        }
+    }
+}
+
+/// Input of [`super::StepFoldingCircuit`], the same as the ProtoGalaxy one except for the self
+/// trace
+pub type Input<const ARITY: usize, F> = cyclefold_input::Input<ARITY, F, SelfTrace<F>>;
+
+pub struct InputBuilder<
+    'link,
+    CMain: CurveAffine<ScalarExt = CSup::Base>,
+    CSup: CurveAffine,
+    const ARITY: usize,
+    const SUPPORT_INSTANCES_LEN: usize,
+> {
+    pub pp_digest: (CSup::Base, CSup::Base),
+    pub step: usize,
+
+    pub self_acc: &'link nifs::sangria::RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+    pub self_incoming: &'link plonk::PlonkInstance<CMain>,
+    pub self_proof: &'link [CMain],
+
+    pub support_acc: &'link nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+    pub support_incoming: &'link [(
+        nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        nifs::sangria::CrossTermCommits<CSup>,
+    )],
+
+    pub z_0: [CMain::Scalar; ARITY],
+    pub z_i: [CMain::Scalar; ARITY],
+
+    pub step_circuit_instances_hash_accumulator: CMain::Scalar,
+}
+
+impl<
+        CMain: CurveAffine<ScalarExt = CSup::Base>,
+        CSup: CurveAffine,
+        const ARITY: usize,
+        const SUPPORT_INSTANCES_LEN: usize,
+    > InputBuilder<'_, CMain, CSup, ARITY, SUPPORT_INSTANCES_LEN>
+{
+    pub fn build(self) -> Input<ARITY, CMain::Scalar> {
+        let Self {
+            pp_digest,
+            step,
+            self_acc,
+            self_incoming,
+            self_proof,
+            support_acc,
+            support_incoming,
+            z_0,
+            z_i,
+            step_circuit_instances_hash_accumulator,
+        } = self;
+
+        let input = Input {
+            pp_digest,
+            step,
+            z_0,
+            z_i,
+            step_circuit_instances_hash_accumulator,
+            self_trace: SelfTrace::new(self_acc, self_incoming, self_proof),
+            support_trace: SupportTrace::new(support_acc, support_incoming),
+        };
+
+        trace!("builded input is: {input:?}");
+
+        input
+    }
+}
@@ -0,0 +1,488 @@
+use std::{cell::RefCell, marker::PhantomData, num::NonZeroUsize};
+
+use itertools::Itertools;
+use tracing::{error, info, info_span, instrument, trace};
+
+use crate::{
+    halo2_proofs::{
+        arithmetic::Field,
+        circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+        halo2curves::{
+            ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
+            CurveAffine,
+        },
+        plonk::{Circuit, ConstraintSystem, Error as Halo2PlonkError},
+    },
+    ivc::{
+        cyclefold::{
+            self, ro_chip,
+            sfc::{
+                input::{self as cyclefold_input, assigned::iter_consistency_marker_wrap_values},
+                sangria_adapter, Config,
+            },
+        },
+        sangria::instances_accumulator_computation,
+        StepCircuit,
+    },
+    main_gate::{MainGate, RegionCtx},
+    nifs,
+    poseidon::{ROCircuitTrait, ROTrait},
+    table::{CircuitLayout, Witness},
+};
+
+pub(super) mod input;
+pub use input::{Input, InputBuilder};
+
+/// Count of consistency markers in the instance column of [`StepFoldingCircuit`]
+///
+/// Unlike [`crate::ivc::sangria`], there is no secondary step folding circuit, so the marker of
+/// the previous step is enough
+pub const MARKERS_LEN: usize = 1;
+
+/// Step folding circuit with on-circuit Sangria verifier of the primary trace
+///
+/// Same as [`crate::ivc::cyclefold::sfc::StepFoldingCircuit`], but self trace is folded by
+/// Sangria instead of ProtoGalaxy. Commitments are still folded by the support circuit, which
+/// traces are folded by Sangria on the support curve
+#[derive(Debug)]
+pub struct StepFoldingCircuit<
+    'sc,
+    const ARITY: usize,
+    CMain: CurveAffine,
+    CSup: CurveAffine<Base = CMain::ScalarExt>,
+    SC: StepCircuit<ARITY, CMain::ScalarExt>,
+> {
+    pub sc: &'sc SC,
+    /// Non-deterministic advice of the step circuit for this step
+    pub witness: &'sc SC::StepWitness,
+    pub input: Input<ARITY, CMain::ScalarExt>,
+    pub _p: PhantomData<CSup>,
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > Clone for StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
+{
+    fn clone(&self) -> Self {
+        let Self {
+            sc,
+            witness,
+            input,
+            _p,
+        } = self;
+
+        Self {
+            sc,
+            witness,
+            input: input.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// For the initial iteration, we will give the same accumulators that we take from the input
+    ///
+    /// The step circuit instances hash accumulator is also passed through unchanged
+    pub fn initial_instances(&self) -> Vec<Vec<CMain::ScalarExt>> {
+        let _span = info_span!("consistency_marker").entered();
+
+        let mut self_ = self.input.clone();
+        assert_eq!(
+            self_.step, 0,
+            "this method can only be called for step == 0"
+        );
+
+        self_.step = 1;
+        let out_marker = cyclefold::ro().absorb(&self_).output(
+            NonZeroUsize::new(<CMain::ScalarExt as PrimeField>::NUM_BITS as usize).unwrap(),
+        );
+
+        let mut instances = self.sc.instances();
+        instances.insert(0, vec![out_marker]);
+        instances
+    }
+
+    pub fn instances<const SUPPORT_INSTANCES_LEN: usize>(
+        &self,
+        self_acc: &nifs::sangria::RelaxedPlonkInstance<CMain, MARKERS_LEN>,
+        support_acc: &nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        z_out: &[CMain::ScalarExt; ARITY],
+    ) -> Vec<Vec<CMain::ScalarExt>> {
+        let _span = info_span!("consistency_marker").entered();
+
+        let mut self_ = self.input.clone();
+
+        self_.step += 1;
+        self_.self_trace.input_accumulator = input::RelaxedPlonkInstance::new(self_acc);
+        self_.support_trace.input_accumulator =
+            cyclefold_input::SangriaAccumulatorInstance::new(support_acc);
+        self_.z_i = *z_out;
+        self_.step_circuit_instances_hash_accumulator =
+            self.step_circuit_instances_hash_accumulator();
+
+        let out_marker = cyclefold::ro()
+            .absorb(&self_)
+            .inspect(|buf| trace!("buf before sfc_out: {buf:?}"))
+            .output(
+                NonZeroUsize::new(<CMain::ScalarExt as PrimeField>::NUM_BITS as usize).unwrap(),
+            );
+
+        let mut instances = self.sc.instances();
+        instances.insert(0, vec![out_marker]);
+        instances
+    }
+
+    /// Off-circuit value of the step circuit instances hash accumulator at the output of this
+    /// circuit
+    ///
+    /// For the zero step it's equal to the input one, otherwise instances of the previous step
+    /// circuit (from `self_trace.incoming` without consistency marker) are absorbed into it
+    pub fn step_circuit_instances_hash_accumulator(&self) -> CMain::ScalarExt {
+        let Input {
+            step,
+            self_trace,
+            step_circuit_instances_hash_accumulator,
+            ..
+        } = &self.input;
+
+        if *step == 0 {
+            return *step_circuit_instances_hash_accumulator;
+        }
+
+        instances_accumulator_computation::absorb_in_native_sc_instances_accumulator(
+            step_circuit_instances_hash_accumulator,
+            &self_trace.incoming.instances[1..],
+        )
+    }
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > Circuit<CMain::ScalarExt> for StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Config = Config<SC::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            sc: self.sc,
+            witness: self.witness,
+            input: self.input.get_without_witness(),
+            _p: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<CMain::ScalarExt>) -> Self::Config {
+        let consistency_marker = meta.instance_column();
+        meta.enable_equality(consistency_marker);
+
+        Self::Config {
+            consistency_marker,
+            sc: SC::configure(meta),
+            mg: MainGate::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<CMain::ScalarExt>,
+    ) -> Result<(), Halo2PlonkError> {
+        self.synthesize_with_output(config, layouter)
+            .map(|_z_out| ())
+    }
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// Synthesize the step folding circuit once & collect its witness together with the output of
+    /// the step circuit
+    ///
+    /// See [`crate::ivc::cyclefold::sfc::StepFoldingCircuit::collect_witness_with_output`]
+    #[instrument(skip_all)]
+    pub fn collect_witness_with_output(
+        &self,
+        layout: &CircuitLayout<CMain::ScalarExt, Config<SC::Config>>,
+    ) -> Result<(Witness<CMain::ScalarExt>, [CMain::ScalarExt; ARITY]), Halo2PlonkError> {
+        let mut instances = self.sc.instances();
+        instances.insert(0, vec![CMain::ScalarExt::ZERO]);
+
+        let circuit = WithOutput {
+            sfc: self,
+            z_out: RefCell::new(None),
+        };
+
+        let witness = layout.try_collect_witness(&circuit, instances)?;
+        let z_out = circuit.z_out.take().ok_or_else(|| {
+            error!("step circuit output was not assigned");
+            Halo2PlonkError::Synthesis
+        })?;
+
+        Ok((witness, z_out))
+    }
+
+    /// Synthesize the circuit & return the output of the step circuit
+    #[instrument(name = "synthesize", skip_all)]
+    fn synthesize_with_output(
+        &self,
+        config: Config<SC::Config>,
+        mut layouter: impl Layouter<CMain::ScalarExt>,
+    ) -> Result<[AssignedCell<CMain::ScalarExt, CMain::ScalarExt>; ARITY], Halo2PlonkError> {
+        info!("start");
+
+        let input = layouter
+            .assign_region(
+                || "sfc_input",
+                |region| {
+                    let _span = info_span!("input").entered();
+
+                    let mut region = RegionCtx::new(region, 0);
+
+                    input::assigned::Input::assign_advice_from(
+                        &mut region,
+                        &self.input,
+                        &config.mg,
+                    )?
+                    .consistency_check(&mut region, &config.mg)
+                },
+            )
+            .inspect_err(|err| {
+                error!("while sfc input: {err:?}");
+            })?;
+
+        info!("sfc input done");
+
+        let z_out = {
+            let _span = info_span!("sc").entered();
+
+            self.sc
+                .synthesize_step(config.sc, &mut layouter, &input.z_i, self.witness)
+                .map_err(|err| {
+                    error!("while synthesize_step: {err:?}");
+                    Halo2PlonkError::Synthesis
+                })
+        }?;
+
+        info!("step circuit synthesize done");
+
+        let self_acc_out = layouter
+            .assign_region(
+                || "sfc primary sangria",
+                |region| {
+                    let _span = info_span!("primary_sangria").entered();
+
+                    input.fold_self_trace(&mut RegionCtx::new(region, 0), &config.mg)
+                },
+            )
+            .inspect_err(|err| {
+                error!("while sfc primary sangria: {err:?}");
+            })?;
+
+        info!("primary sangria done");
+
+        let support_circuit_acc_out = layouter
+            .assign_region(
+                || "sfc_sangria",
+                |region| {
+                    let _span = info_span!("sangria").entered();
+                    sangria_adapter::fold::<CMain, CSup>(
+                        &mut RegionCtx::new(region, 0),
+                        config.mg.clone(),
+                        &input.pp_digest,
+                        &input.support_trace,
+                    )
+                },
+            )
+            .inspect_err(|err| {
+                error!("while sfc sangria: {err:?}");
+            })?;
+
+        info!("sangria done");
+
+        let consistency_marker_output = layouter
+            .assign_region(
+                || "sfc out consistency marker",
+                |region| {
+                    let _span = info_span!("consistency_marker").entered();
+                    let mut region = RegionCtx::new(region, 0);
+
+                    let mg = MainGate::new(config.mg.clone());
+                    let is_zero_step = mg.is_zero_term(&mut region, input.step.clone())?;
+
+                    let z_out: [_; ARITY] = input
+                        .z_0
+                        .iter()
+                        .zip_eq(z_out.iter())
+                        .map(|(z_0_i, z_out_i)| {
+                            mg.conditional_select(&mut region, z_0_i, z_out_i, &is_zero_step)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                        .try_into()
+                        .unwrap();
+
+                    let self_trace_output =
+                        input::assigned::RelaxedPlonkInstance::conditional_select(
+                            &mut region,
+                            &mg,
+                            &input.self_trace.input_accumulator,
+                            &self_acc_out,
+                            &is_zero_step,
+                        )?;
+
+                    let support_trace_output =
+                        cyclefold_input::assigned::SangriaAccumulatorInstance::conditional_select(
+                            &mut region,
+                            &mg,
+                            &input.support_trace.input_accumulator,
+                            &support_circuit_acc_out,
+                            &is_zero_step,
+                        )?;
+
+                    let step_circuit_instances = input
+                        .self_trace
+                        .incoming
+                        .instances
+                        .iter()
+                        .skip(1)
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    let step_circuit_instances_hash_accumulator =
+                        instances_accumulator_computation::absorb_in_assign_sc_instances_accumulator(
+                            &mut region,
+                            config.mg.clone(),
+                            &input.step_circuit_instances_hash_accumulator,
+                            &step_circuit_instances,
+                        )?;
+
+                    let step_circuit_instances_hash_accumulator = mg.conditional_select(
+                        &mut region,
+                        &input.step_circuit_instances_hash_accumulator,
+                        &step_circuit_instances_hash_accumulator,
+                        &is_zero_step,
+                    )?;
+
+                    let next_step =
+                        mg.add_with_const(&mut region, &input.step, CMain::ScalarExt::ONE)?;
+
+                    ro_chip(config.mg.clone())
+                        .absorb_iter(iter_consistency_marker_wrap_values(
+                            (&input.pp_digest.0, &input.pp_digest.1),
+                            self_trace_output.iter_wrap_values(),
+                            &support_trace_output,
+                            &next_step,
+                            &input.z_0,
+                            &z_out,
+                            &step_circuit_instances_hash_accumulator,
+                        ))
+                        .inspect(|buf| trace!("buf before marker: {buf:?}"))
+                        .squeeze(&mut region)
+                },
+            )
+            .inspect_err(|err| {
+                error!("while sfc out consistency marker: {err:?}");
+            })?;
+
+        info!("out done");
+
+        layouter
+            .constrain_instance(
+                consistency_marker_output.cell(),
+                config.consistency_marker,
+                0,
+            )
+            .inspect_err(|err| {
+                error!("while sfc out constraint instance: {err:?}");
+            })?;
+
+        Ok(z_out)
+    }
+}
+
+/// Adapter to keep the step circuit output, while [`StepFoldingCircuit`] is synthesized by
+/// [`CircuitLayout::try_collect_witness`]
+struct WithOutput<
+    'link,
+    'sc,
+    const ARITY: usize,
+    CMain: CurveAffine,
+    CSup: CurveAffine<Base = CMain::ScalarExt>,
+    SC: StepCircuit<ARITY, CMain::ScalarExt>,
+> {
+    sfc: &'link StepFoldingCircuit<'sc, ARITY, CMain, CSup, SC>,
+    z_out: RefCell<Option<[CMain::ScalarExt; ARITY]>>,
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > Circuit<CMain::ScalarExt> for WithOutput<'_, '_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Config = Config<SC::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            sfc: self.sfc,
+            z_out: RefCell::new(None),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<CMain::ScalarExt>) -> Self::Config {
+        StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<CMain::ScalarExt>,
+    ) -> Result<(), Halo2PlonkError> {
+        let z_out = self.sfc.synthesize_with_output(config, layouter)?;
+
+        let z_out = z_out
+            .iter()
+            .map(|cell| cell.value().unwrap().copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                error!("step circuit output is unknown");
+                Halo2PlonkError::Synthesis
+            })?;
+
+        *self.z_out.borrow_mut() = Some(
+            z_out
+                .try_into()
+                .expect("the step circuit output is of `ARITY` size"),
+        );
+
+        Ok(())
+    }
+}
@@ -18,6 +18,8 @@ use crate::{
     poseidon::ROCircuitTrait,
 };
 
+use super::SelfTraceInput;
+
 pub type MainGateConfig = main_gate::MainGateConfig<{ super::super::MAIN_GATE_T }>;
 
 pub type BigUint<F> = [F; DEFAULT_LIMBS_COUNT.get()];
@@ -216,7 +218,9 @@ pub struct SelfTrace<F: PrimeField> {
     pub proof: ProtogalaxyProof<F>,
 }
 
-impl<F: PrimeField> SelfTrace<F> {
+impl<F: PrimeField> AssignedSelfTrace<F> for SelfTrace<F> {
+    type Original = super::SelfTrace<F>;
+
     fn assign_advice_from(
         region: &mut RegionCtx<'_, F>,
         original: &super::SelfTrace<F>,
@@ -252,6 +256,21 @@ impl<F: PrimeField> SelfTrace<F> {
         Ok(self_)
     }
 
+    fn incoming(&self) -> &NativePlonkInstance<F> {
+        &self.incoming
+    }
+
+    fn iter_input_accumulator_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
+        trace!(
+            "oncircuit input protogalaxy accumulator: {:?}",
+            self.input_accumulator
+        );
+
+        self.input_accumulator.iter_wrap_values()
+    }
+}
+
+impl<F: PrimeField> SelfTrace<F> {
     fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
         let Self {
             input_accumulator,
@@ -266,6 +285,23 @@ impl<F: PrimeField> SelfTrace<F> {
     }
 }
 
+/// Assigned version of [`super::SelfTraceInput`]
+pub trait AssignedSelfTrace<F: PrimeField>: Sized + fmt::Debug {
+    type Original;
+
+    fn assign_advice_from(
+        region: &mut RegionCtx<'_, F>,
+        original: &Self::Original,
+        main_gate_config: &MainGateConfig,
+    ) -> Result<Self, Halo2PlonkError>;
+
+    /// Incoming trace, with the consistency marker as the first instance
+    fn incoming(&self) -> &NativePlonkInstance<F>;
+
+    /// Values of the input accumulator, absorbed into the consistency marker
+    fn iter_input_accumulator_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>>;
+}
+
 type BigUintView<F> = (AssignedValue<F>, BigUint<AssignedValue<F>>);
 
 #[derive(Clone)]
@@ -613,7 +649,7 @@ impl<F: PrimeField> SangriaAccumulatorInstance<F> {
         })
     }
 
-    fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
+    pub fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
         let Self {
             ins,
             E_commitment,
@@ -691,13 +727,12 @@ impl<F: PrimeField> SupportIncoming<F> {
 #[derive(Debug)]
 pub struct SupportTrace<F: PrimeField> {
     pub input_accumulator: SangriaAccumulatorInstance<F>,
-    // The size from one to three
-    // Depdend on `W_commitments_len`
+    // One support circuit trace per `SupportCircuit::CAPACITY` of W-commitments
     pub incoming: Box<[SupportIncoming<F>]>,
 }
 
 impl<F: PrimeField> SupportTrace<F> {
-    fn assign_advice_from(
+    pub fn assign_advice_from(
         region: &mut RegionCtx<'_, F>,
         original: &super::SupportTrace<F>,
         main_gate_config: &MainGateConfig,
@@ -725,8 +760,19 @@ impl<F: PrimeField> SupportTrace<F> {
             .collect::<Result<Vec<_>, Halo2PlonkError>>()?
             .into_boxed_slice();
 
+        // The support circuit capacity is a compile-time parameter of the IVC, so here it's taken
+        // from the length of the support instance column
+        let capacity = original
+            .input_accumulator
+            .ins
+            .instances
+            .first()
+            .map_or(0, |instance| instance.len() / support_circuit::INPUT_LEN)
+            .max(1);
+        let incoming_max_len = W_COMMITMENTS_MAX_LEN.div_ceil(capacity);
+
         iter::repeat_with(|| &original.incoming[0])
-            .take(W_COMMITMENTS_MAX_LEN - original.incoming.len())
+            .take(incoming_max_len.saturating_sub(original.incoming.len()))
             .try_for_each(|support_plonk_instance| -> Result<(), Halo2PlonkError> {
                 SupportIncoming::assign_advice_from(
                     region,
@@ -742,6 +788,22 @@ impl<F: PrimeField> SupportTrace<F> {
         })
     }
 
+    /// Instance values of each `l0*P0 + l1*P1` computed in `incoming` traces in order, including
+    /// padding ones, see [`support_circuit::SupportCircuit::instances`]
+    pub fn iter_support_inputs(
+        &self,
+    ) -> impl '_ + Iterator<Item = [BigUintView<F>; support_circuit::INPUT_LEN]> {
+        self.incoming.iter().flat_map(|incoming| {
+            incoming
+                .instance
+                .instances
+                .first()
+                .expect("`SupportCircuit` always has instances.len() == 1 and it should always be used for sfc")
+                .chunks_exact(support_circuit::INPUT_LEN)
+                .map(|input| input.to_vec().try_into().unwrap())
+        })
+    }
+
     pub fn iter_wrap_values(&self) -> impl '_ + Iterator<Item = WrapValue<F>> {
         let Self {
             input_accumulator,
@@ -757,21 +819,23 @@ impl<F: PrimeField> SupportTrace<F> {
 }
 
 #[derive(Debug)]
-pub struct Input<const ARITY: usize, F: PrimeField> {
+pub struct Input<const ARITY: usize, F: PrimeField, ST: SelfTraceInput<F> = super::SelfTrace<F>> {
     pub pp_digest: (AssignedValue<F>, AssignedValue<F>),
 
-    pub self_trace: SelfTrace<F>,
+    pub self_trace: ST::Assigned,
     pub support_trace: SupportTrace<F>,
 
     pub step: AssignedValue<F>,
     pub z_0: [AssignedValue<F>; ARITY],
     pub z_i: [AssignedValue<F>; ARITY],
+
+    pub step_circuit_instances_hash_accumulator: AssignedValue<F>,
 }
 
-impl<const A: usize, F: PrimeField> Input<A, F> {
+impl<const A: usize, F: PrimeField, ST: SelfTraceInput<F>> Input<A, F, ST> {
     pub fn assign_advice_from(
         region: &mut RegionCtx<'_, F>,
-        original: &super::Input<A, F>,
+        original: &super::Input<A, F, ST>,
         main_gate_config: &MainGateConfig,
     ) -> Result<Self, Halo2PlonkError> {
         let _s = info_span!("input_assign").entered();
@@ -779,7 +843,7 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
         let start_offset = region.offset();
 
         let self_trace =
-            SelfTrace::assign_advice_from(region, &original.self_trace, main_gate_config)?;
+            ST::Assigned::assign_advice_from(region, &original.self_trace, main_gate_config)?;
 
         let support_trace =
             SupportTrace::assign_advice_from(region, &original.support_trace, main_gate_config)?;
@@ -799,6 +863,12 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
 
         let z_i = assigner.assign_all_advice(region, || "z_i", original.z_i.iter().cloned())?;
 
+        let step_circuit_instances_hash_accumulator = assigner.assign_next_advice(
+            region,
+            || "step_circuit_instances_hash_accumulator",
+            original.step_circuit_instances_hash_accumulator,
+        )?;
+
         region.next();
         trace!("`Input` took {} rows", region.offset() - start_offset);
 
@@ -809,6 +879,7 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
             step: step_assigned,
             z_0: z_0.try_into().unwrap(),
             z_i: z_i.try_into().unwrap(),
+            step_circuit_instances_hash_accumulator,
         })
     }
 
@@ -820,15 +891,17 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
             step,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator,
         } = self;
 
         iter_consistency_marker_wrap_values(
             (pp0, pp1),
-            &self_trace.input_accumulator,
+            self_trace.iter_input_accumulator_wrap_values(),
             &support_trace.input_accumulator,
             step,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator,
         )
     }
 
@@ -852,7 +925,7 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
 
         let provided = self
             .self_trace
-            .incoming
+            .incoming()
             .instances
             .first()
             .and_then(|instance| instance.first())
@@ -868,7 +941,9 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
 
         Ok(self)
     }
+}
 
+impl<const A: usize, F: PrimeField> Input<A, F> {
     #[instrument(skip_all)]
     pub fn support_circuit_consistency_check(
         &self,
@@ -905,22 +980,16 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
                 Halo2PlonkError::Synthesis
             })?;
 
-        for (acc_W, incoming_W, support_trace, new_acc_W, index) in itertools::multizip((
+        for (acc_W, incoming_W, support_input, new_acc_W, index) in itertools::multizip((
             self.self_trace.input_accumulator.ins.W_commitments.iter(),
             self.self_trace.incoming.W_commitments.iter(),
-            self.support_trace.incoming.iter(),
+            self.support_trace.iter_support_inputs(),
             new_acc.ins.W_commitments.iter_mut(),
             0..,
         )) {
             info!("start {index} commitment check");
 
-            let [expected_x, expected_y, x0, y0, l0, x1, y1, l1]: [_; support_circuit::INSTANCES_LEN] = support_trace.instance
-                .instances
-                .first()
-                .expect("`SupportCircuit` always has instances.len() == 1 and it should always be used for sfc")
-                .clone()
-                .try_into()
-                .unwrap();
+            let [expected_x, expected_y, x0, y0, l0, x1, y1, l1] = support_input;
 
             l0.1.iter()
                 .zip_eq(expected_l0_limbs.iter())
@@ -945,27 +1014,23 @@ impl<const A: usize, F: PrimeField> Input<A, F> {
 
 pub fn iter_consistency_marker_wrap_values<'l, const ARITY: usize, F: PrimeField>(
     pp_digest: (&'l AssignedValue<F>, &'l AssignedValue<F>),
-    self_accumulator: &'l ProtoGalaxyAccumulatorInstance<F>,
+    self_accumulator: impl 'l + Iterator<Item = WrapValue<F>>,
     paried_accumulator: &'l SangriaAccumulatorInstance<F>,
     step: &'l AssignedValue<F>,
     z_0: &'l [AssignedValue<F>; ARITY],
     z_i: &'l [AssignedValue<F>; ARITY],
+    step_circuit_instances_hash_accumulator: &'l AssignedValue<F>,
 ) -> impl 'l + Iterator<Item = WrapValue<F>> {
     let (pp0, pp1) = pp_digest;
 
-    trace!(
-        "oncircuit input protogalaxy accumulator: {:?}",
-        self_accumulator
-    );
-
     self_accumulator
-        .iter_wrap_values()
         .chain(paried_accumulator.iter_wrap_values())
         .chain(
             [pp0, pp1, step]
                 .into_iter()
                 .chain(z_0.iter())
                 .chain(z_i.iter())
+                .chain(iter::once(step_circuit_instances_hash_accumulator))
                 .map(|v| WrapValue::Assigned(v.clone())),
         )
 }
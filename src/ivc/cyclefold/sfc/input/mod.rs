@@ -1,4 +1,4 @@
-use std::{array, ops::Deref};
+use std::{array, fmt, ops::Deref};
 
 use tracing::{instrument, trace};
 
@@ -10,7 +10,7 @@ use crate::{
 };
 use crate::{
     halo2_proofs::halo2curves::{ff::PrimeField, CurveAffine},
-    ivc::cyclefold::support_circuit,
+    ivc::cyclefold::support_circuit::SupportCircuit,
     nifs::{self, sangria::accumulator::SCInstancesHashAcc},
     plonk,
     polynomial::univariate::UnivariatePoly,
@@ -44,6 +44,21 @@ impl<F: PrimeField> NativePlonkInstance<F> {
             challenges: challenges.to_vec(),
         }
     }
+
+    /// Same shape, but all commitments are identity & all values are zero
+    pub(crate) fn get_without_witness(&self) -> Self {
+        let Self {
+            W_commitments,
+            instances,
+            challenges,
+        } = self;
+
+        Self {
+            W_commitments: vec![BigUintPoint::identity(); W_commitments.len()],
+            instances: instances.iter().map(|v| vec![F::ZERO; v.len()]).collect(),
+            challenges: vec![F::ZERO; challenges.len()],
+        }
+    }
 }
 
 impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for NativePlonkInstance<F> {
@@ -69,8 +84,8 @@ pub struct SupportPlonkInstance<F: PrimeField> {
 }
 
 impl<F: PrimeField> SupportPlonkInstance<F> {
-    pub fn new<CSup: CurveAffine<Base = F>>(
-        acc: &nifs::sangria::FoldablePlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+    pub fn new<CSup: CurveAffine<Base = F>, const SUPPORT_INSTANCES_LEN: usize>(
+        acc: &nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
     ) -> Self {
         let nifs::sangria::PlonkInstance {
             W_commitments,
@@ -193,20 +208,17 @@ impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for SelfTrace<F> {
     }
 }
 
-impl<F: PrimeField> SelfTrace<F> {
-    #[instrument(skip_all)]
-    pub fn new_initial(native_plonk_structure: &plonk::PlonkStructure<F>) -> Self {
-        // SPS protocol setup
-        let W_commitments_len = match native_plonk_structure.num_challenges {
-            0 => 1,
-            1 => 1,
-            2 => 2,
-            3 => 3,
-            _ => unreachable!(">3 challenges can't be"),
-        };
+impl<F: PrimeField> SelfTraceInput<F> for SelfTrace<F> {
+    type Accumulator = ProtoGalaxyAccumulatorInstance<F>;
+    type Assigned = assigned::SelfTrace<F>;
 
+    #[instrument(skip_all)]
+    fn new_initial(native_plonk_structure: &plonk::PlonkStructure<F>) -> Self {
         let ins = NativePlonkInstance::<F> {
-            W_commitments: vec![BigUintPoint::<F>::identity(); W_commitments_len],
+            W_commitments: vec![
+                BigUintPoint::<F>::identity();
+                W_commitments_len(native_plonk_structure)
+            ],
             instances: native_plonk_structure
                 .num_io
                 .iter()
@@ -235,9 +247,64 @@ impl<F: PrimeField> SelfTrace<F> {
         }
     }
 
-    fn W_commitments_len(&self) -> usize {
+    fn input_accumulator(&self) -> &Self::Accumulator {
+        &self.input_accumulator
+    }
+
+    /// One support circuit input per W commitment, the E commitment isn't delegated
+    fn support_inputs_len(&self) -> usize {
         self.input_accumulator.ins.W_commitments.len()
     }
+
+    fn get_without_witness(&self) -> Self {
+        Self {
+            input_accumulator: ProtoGalaxyAccumulatorInstance {
+                ins: self.input_accumulator.ins.get_without_witness(),
+                betas: vec![F::ZERO; self.input_accumulator.betas.len()].into_boxed_slice(),
+                e: F::ZERO,
+            },
+            incoming: self.incoming.get_without_witness(),
+            proof: nifs::protogalaxy::Proof {
+                poly_F: UnivariatePoly::new_zeroed(self.proof.poly_F.len()),
+                poly_K: UnivariatePoly::new_zeroed(self.proof.poly_K.len()),
+            },
+        }
+    }
+}
+
+/// Recursive trace of the step folding circuit itself, folded on-circuit by the primary
+/// folding scheme
+///
+/// The rest of [`Input`] doesn't depend on the scheme: it's [`SelfTrace`] for ProtoGalaxy and
+/// [`crate::ivc::cyclefold::sangria_primary`] has the one for Sangria
+pub trait SelfTraceInput<F: PrimeField>: Clone + fmt::Debug {
+    /// Accumulator of the primary circuit, absorbed into the consistency marker
+    type Accumulator: fmt::Debug;
+    type Assigned: assigned::AssignedSelfTrace<F, Original = Self>;
+
+    /// Empty trace with the correct size of fields
+    fn new_initial(native_plonk_structure: &plonk::PlonkStructure<F>) -> Self;
+
+    fn input_accumulator(&self) -> &Self::Accumulator;
+
+    /// Count of support circuit inputs delegated by this trace
+    fn support_inputs_len(&self) -> usize;
+
+    /// Same shape, but all commitments are identity & all values are zero
+    fn get_without_witness(&self) -> Self;
+}
+
+/// Count of [`plonk::PlonkInstance::W_commitments`] produced by the SPS protocol
+pub(crate) fn W_commitments_len<F: PrimeField>(
+    plonk_structure: &plonk::PlonkStructure<F>,
+) -> usize {
+    match plonk_structure.num_challenges {
+        0 => 1,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        _ => unreachable!(">3 challenges can't be"),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -248,8 +315,8 @@ pub struct SangriaAccumulatorInstance<F: PrimeField> {
 }
 
 impl<F: PrimeField> SangriaAccumulatorInstance<F> {
-    pub fn new<CSup: CurveAffine<Base = F>>(
-        acc: &nifs::sangria::RelaxedPlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+    pub fn new<CSup: CurveAffine<Base = F>, const SUPPORT_INSTANCES_LEN: usize>(
+        acc: &nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
     ) -> Self {
         let nifs::sangria::RelaxedPlonkInstance {
             W_commitments,
@@ -315,8 +382,8 @@ pub struct SupportIncoming<F: PrimeField> {
 }
 
 impl<F: PrimeField> SupportIncoming<F> {
-    pub fn new<CSup: CurveAffine<Base = F>>(
-        instance: &nifs::sangria::FoldablePlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+    pub fn new<CSup: CurveAffine<Base = F>, const SUPPORT_INSTANCES_LEN: usize>(
+        instance: &nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
         proof: &nifs::sangria::CrossTermCommits<CSup>,
     ) -> Self {
         let proof = proof
@@ -344,8 +411,7 @@ impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for SupportIncoming<F> {
 #[derive(Debug, Clone)]
 pub struct SupportTrace<F: PrimeField> {
     pub input_accumulator: SangriaAccumulatorInstance<F>,
-    // The size from one to three
-    // Depdend on `W_commitments_len`
+    // One support circuit trace per `SupportCircuit::CAPACITY` of W-commitments
     pub incoming: Box<[SupportIncoming<F>]>,
 }
 
@@ -361,13 +427,55 @@ impl<F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for SupportTrace<F> {
 }
 
 impl<F: PrimeField> SupportTrace<F> {
-    pub fn new_initial<CSup: CurveAffine<Base = F>>(
+    pub fn new<CSup: CurveAffine<Base = F>, const SUPPORT_INSTANCES_LEN: usize>(
+        support_acc: &nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        support_incoming: &[(
+            nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+            nifs::sangria::CrossTermCommits<CSup>,
+        )],
+    ) -> Self {
+        Self {
+            input_accumulator: SangriaAccumulatorInstance::new(support_acc),
+            incoming: support_incoming
+                .iter()
+                .map(|(instance, proof)| SupportIncoming::new(instance, proof))
+                .collect(),
+        }
+    }
+
+    /// Same shape, but all values are zero
+    pub(crate) fn get_without_witness(&self) -> Self {
+        let zeroed = |instance: &SupportPlonkInstance<F>| SupportPlonkInstance {
+            W_commitments: vec![(F::ZERO, F::ZERO); instance.W_commitments.len()],
+            instances: instance
+                .instances
+                .iter()
+                .map(|v| vec![F::ZERO; v.len()])
+                .collect(),
+            challenges: vec![F::ZERO; instance.challenges.len()],
+        };
+
+        Self {
+            input_accumulator: SangriaAccumulatorInstance {
+                ins: zeroed(&self.input_accumulator.ins),
+                E_commitment: (F::ZERO, F::ZERO),
+                u: F::ZERO,
+            },
+            incoming: self
+                .incoming
+                .iter()
+                .map(|incoming| SupportIncoming {
+                    instance: zeroed(&incoming.instance),
+                    proof: vec![(F::ZERO, F::ZERO); incoming.proof.len()],
+                })
+                .collect(),
+        }
+    }
+
+    pub fn new_initial<CSup: CurveAffine<Base = F>, const SUPPORT_INSTANCES_LEN: usize>(
         support_plonk_structure: &plonk::PlonkStructure<CSup::ScalarExt>,
-        support_plonk_instance: &nifs::sangria::FoldablePlonkInstance<
-            CSup,
-            { support_circuit::INSTANCES_LEN },
-        >,
-        W_commitments_len: usize,
+        support_plonk_instance: &nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
+        incoming_len: usize,
     ) -> Self {
         let ins = SupportPlonkInstance {
             W_commitments: support_plonk_instance
@@ -409,17 +517,17 @@ impl<F: PrimeField> SupportTrace<F> {
                 E_commitment: (F::ZERO, F::ZERO),
                 u: F::ZERO,
             },
-            incoming: vec![pairing; W_commitments_len].into_boxed_slice(),
+            incoming: vec![pairing; incoming_len].into_boxed_slice(),
         }
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct Input<const ARITY: usize, F: PrimeField> {
+pub struct Input<const ARITY: usize, F: PrimeField, ST = SelfTrace<F>> {
     pub pp_digest: (F, F),
 
     /// We should check-consistency with delegated part
-    pub self_trace: SelfTrace<F>,
+    pub self_trace: ST,
 
     /// One to three traces of support_circuit from support curve
     /// We should fold challenge (r) as BigUint
@@ -432,6 +540,12 @@ pub struct Input<const ARITY: usize, F: PrimeField> {
     pub step: usize,
     pub z_0: [F; ARITY],
     pub z_i: [F; ARITY],
+
+    /// Hash chain of [`crate::ivc::StepCircuit::instances`] of all previous steps
+    ///
+    /// Calculated by
+    /// [`crate::ivc::sangria::instances_accumulator_computation::absorb_in_native_sc_instances_accumulator`]
+    pub step_circuit_instances_hash_accumulator: F,
 }
 
 #[cfg(test)]
@@ -531,11 +645,18 @@ impl<const ARITY: usize, F: PrimeField> Input<ARITY, F> {
             step,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator: gen.next().unwrap(),
         }
     }
 }
 
-impl<const ARITY: usize, F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for Input<ARITY, F> {
+impl<const ARITY: usize, F, ST, RO> AbsorbInRO<F, RO> for Input<ARITY, F, ST>
+where
+    F: PrimeField,
+    ST: SelfTraceInput<F>,
+    ST::Accumulator: AbsorbInRO<F, RO>,
+    RO: ROTrait<F>,
+{
     fn absorb_into(&self, ro: &mut RO) {
         let Self {
             pp_digest: (pp0, pp1),
@@ -544,162 +665,65 @@ impl<const ARITY: usize, F: PrimeField, RO: ROTrait<F>> AbsorbInRO<F, RO> for In
             step,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator,
         } = self;
 
         trace!(
-            "offcircuit input protogalaxy accumulator: {:?}",
-            self_trace.input_accumulator
+            "offcircuit input primary accumulator: {:?}",
+            self_trace.input_accumulator()
         );
 
-        ro.absorb(&self_trace.input_accumulator)
+        ro.absorb(self_trace.input_accumulator())
             .absorb(&support_trace.input_accumulator)
             .absorb_field(*pp0)
             .absorb_field(*pp1)
             .absorb_field(F::from(*step as u64))
             .absorb_field_iter(z_0.iter().copied())
-            .absorb_field_iter(z_i.iter().copied());
+            .absorb_field_iter(z_i.iter().copied())
+            .absorb_field(*step_circuit_instances_hash_accumulator);
     }
 }
 
-impl<const ARITY: usize, F: PrimeField> Input<ARITY, F> {
-    pub(super) fn get_without_witness(&self) -> Self {
-        // Zero out `pp_digest`.
-        let pp_digest = (F::ZERO, F::ZERO);
-
-        // Zero out `self_trace`.
-        let self_trace = SelfTrace {
-            input_accumulator: ProtoGalaxyAccumulatorInstance {
-                ins: NativePlonkInstance {
-                    W_commitments: vec![
-                        BigUintPoint::identity();
-                        self.self_trace.input_accumulator.ins.W_commitments.len()
-                    ],
-                    instances: self
-                        .self_trace
-                        .input_accumulator
-                        .ins
-                        .instances
-                        .iter()
-                        .map(|v| vec![F::ZERO; v.len()])
-                        .collect(),
-                    challenges: vec![
-                        F::ZERO;
-                        self.self_trace.input_accumulator.ins.challenges.len()
-                    ],
-                },
-                betas: vec![F::ZERO; self.self_trace.input_accumulator.betas.len()]
-                    .into_boxed_slice(),
-                e: F::ZERO,
-            },
-            incoming: NativePlonkInstance {
-                W_commitments: vec![
-                    BigUintPoint::identity();
-                    self.self_trace.incoming.W_commitments.len()
-                ],
-                instances: self
-                    .self_trace
-                    .incoming
-                    .instances
-                    .iter()
-                    .map(|v| vec![F::ZERO; v.len()])
-                    .collect(),
-                challenges: vec![F::ZERO; self.self_trace.incoming.challenges.len()],
-            },
-            proof: nifs::protogalaxy::Proof {
-                poly_F: UnivariatePoly::new_zeroed(self.self_trace.proof.poly_F.len()),
-                poly_K: UnivariatePoly::new_zeroed(self.self_trace.proof.poly_K.len()),
-            },
-        };
-
-        // Zero out `support_trace`.
-        let support_trace = SupportTrace {
-            input_accumulator: SangriaAccumulatorInstance {
-                ins: SupportPlonkInstance {
-                    W_commitments: vec![
-                        (F::ZERO, F::ZERO);
-                        self.support_trace.input_accumulator.ins.W_commitments.len()
-                    ],
-                    instances: self
-                        .support_trace
-                        .input_accumulator
-                        .ins
-                        .instances
-                        .iter()
-                        .map(|v| vec![F::ZERO; v.len()])
-                        .collect(),
-                    challenges: vec![
-                        F::ZERO;
-                        self.support_trace.input_accumulator.ins.challenges.len()
-                    ],
-                },
-                E_commitment: (F::ZERO, F::ZERO),
-                u: F::ZERO,
-            },
-            incoming: self
-                .support_trace
-                .incoming
-                .iter()
-                .map(|incoming| SupportIncoming {
-                    instance: SupportPlonkInstance {
-                        W_commitments: vec![
-                            (F::ZERO, F::ZERO);
-                            incoming.instance.W_commitments.len()
-                        ],
-                        instances: incoming
-                            .instance
-                            .instances
-                            .iter()
-                            .map(|v| vec![F::ZERO; v.len()])
-                            .collect(),
-                        challenges: vec![F::ZERO; incoming.instance.challenges.len()],
-                    },
-                    proof: vec![(F::ZERO, F::ZERO); incoming.proof.len()],
-                })
-                .collect::<Vec<_>>()
-                .into_boxed_slice(),
-        };
-
-        // Zero out `step`.
-        let step = 0;
-
-        // Zero out `z_0` and `z_i`.
-        let z_0 = array::from_fn(|_| F::ZERO);
-        let z_i = array::from_fn(|_| F::ZERO);
-
-        // Construct the new zeroed Input instance.
+impl<const ARITY: usize, F: PrimeField, ST: SelfTraceInput<F>> Input<ARITY, F, ST> {
+    pub(crate) fn get_without_witness(&self) -> Self {
         Self {
-            pp_digest,
-            self_trace,
-            support_trace,
-            step,
-            z_0,
-            z_i,
+            pp_digest: (F::ZERO, F::ZERO),
+            self_trace: self.self_trace.get_without_witness(),
+            support_trace: self.support_trace.get_without_witness(),
+            step: 0,
+            z_0: array::from_fn(|_| F::ZERO),
+            z_i: array::from_fn(|_| F::ZERO),
+            step_circuit_instances_hash_accumulator: F::ZERO,
         }
     }
 
     /// This method creates an input to initialize an empty accumulators and incoming traces of the
     /// correct size of fields
-    pub fn new_initial<CMain: CurveAffine<ScalarExt = F>, CSup: CurveAffine<Base = F>>(
+    pub fn new_initial<
+        CMain: CurveAffine<ScalarExt = F>,
+        CSup: CurveAffine<Base = F>,
+        const SUPPORT_INSTANCES_LEN: usize,
+    >(
         native_plonk_structure: &plonk::PlonkStructure<CMain::ScalarExt>,
         support_plonk_structure: &plonk::PlonkStructure<CSup::ScalarExt>,
-        support_plonk_instance: &nifs::sangria::FoldablePlonkInstance<
-            CSup,
-            { support_circuit::INSTANCES_LEN },
-        >,
+        support_plonk_instance: &nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
     ) -> Self {
-        let self_trace = SelfTrace::new_initial(native_plonk_structure);
+        let self_trace = ST::new_initial(native_plonk_structure);
 
         Self {
             pp_digest: (F::ZERO, F::ZERO),
-            support_trace: SupportTrace::new_initial::<CSup>(
+            support_trace: SupportTrace::new_initial(
                 support_plonk_structure,
                 support_plonk_instance,
-                self_trace.W_commitments_len(),
+                SupportCircuit::<CMain, SUPPORT_INSTANCES_LEN>::traces_count(
+                    self_trace.support_inputs_len(),
+                ),
             ),
             self_trace,
             step: 0,
             z_0: array::from_fn(|_| F::ZERO),
             z_i: array::from_fn(|_| F::ZERO),
+            step_circuit_instances_hash_accumulator: F::ZERO,
         }
     }
 }
@@ -709,6 +733,7 @@ pub struct InputBuilder<
     CMain: CurveAffine<ScalarExt = CSup::Base>,
     CSup: CurveAffine,
     const ARITY: usize,
+    const SUPPORT_INSTANCES_LEN: usize,
 > {
     pub pp_digest: (CSup::Base, CSup::Base),
     pub step: usize,
@@ -717,19 +742,24 @@ pub struct InputBuilder<
     pub self_incoming: &'link plonk::PlonkInstance<CMain>,
     pub self_proof: nifs::protogalaxy::Proof<CMain::Scalar>,
 
-    pub support_acc:
-        &'link nifs::sangria::RelaxedPlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+    pub support_acc: &'link nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
     pub support_incoming: &'link [(
-        nifs::sangria::FoldablePlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+        nifs::sangria::FoldablePlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
         nifs::sangria::CrossTermCommits<CSup>,
     )],
 
     pub z_0: [CMain::Scalar; ARITY],
     pub z_i: [CMain::Scalar; ARITY],
+
+    pub step_circuit_instances_hash_accumulator: CMain::Scalar,
 }
 
-impl<CMain: CurveAffine<ScalarExt = CSup::Base>, CSup: CurveAffine, const ARITY: usize>
-    InputBuilder<'_, CMain, CSup, ARITY>
+impl<
+        CMain: CurveAffine<ScalarExt = CSup::Base>,
+        CSup: CurveAffine,
+        const ARITY: usize,
+        const SUPPORT_INSTANCES_LEN: usize,
+    > InputBuilder<'_, CMain, CSup, ARITY, SUPPORT_INSTANCES_LEN>
 {
     pub fn build(self) -> Input<ARITY, CMain::Scalar> {
         let Self {
@@ -742,6 +772,7 @@ impl<CMain: CurveAffine<ScalarExt = CSup::Base>, CSup: CurveAffine, const ARITY:
             support_incoming,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator,
         } = self;
 
         let input = Input {
@@ -749,18 +780,13 @@ impl<CMain: CurveAffine<ScalarExt = CSup::Base>, CSup: CurveAffine, const ARITY:
             step,
             z_0,
             z_i,
+            step_circuit_instances_hash_accumulator,
             self_trace: SelfTrace {
                 input_accumulator: ProtoGalaxyAccumulatorInstance::new(self_acc),
                 incoming: NativePlonkInstance::new(self_incoming),
                 proof: self_proof,
             },
-            support_trace: SupportTrace {
-                input_accumulator: SangriaAccumulatorInstance::new(support_acc),
-                incoming: support_incoming
-                    .iter()
-                    .map(|(instance, proof)| SupportIncoming::new(instance, proof))
-                    .collect(),
-            },
+            support_trace: SupportTrace::new(support_acc, support_incoming),
         };
 
         trace!("builded input is: {input:?}");
@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, num::NonZeroUsize};
+use std::{cell::RefCell, marker::PhantomData, num::NonZeroUsize};
 
 use itertools::Itertools;
 use tracing::{error, info, info_span, instrument, trace};
@@ -7,32 +7,34 @@ use crate::{
     gadgets::nonnative::bn::big_uint_mul_mod_chip::BigUintMulModChip,
     halo2_proofs::{
         arithmetic::Field,
-        circuit::{Layouter, SimpleFloorPlanner},
+        circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
         halo2curves::CurveAffine,
         plonk::{Circuit, Column, ConstraintSystem, Error as Halo2PlonkError, Instance},
     },
     ivc::{
-        cyclefold::{self, ro_chip},
+        cyclefold::{self, ro_acc_chip, ro_chip},
         protogalaxy::{
             self,
             verify_chip::{self, VerifyResult},
         },
+        sangria::instances_accumulator_computation,
         StepCircuit,
     },
     main_gate::{MainGate, MainGateConfig, RegionCtx},
     nifs,
     poseidon::{ROCircuitTrait, ROTrait},
+    table::{CircuitLayout, Witness},
 };
 
-mod input;
+pub(super) mod input;
 pub use input::{Input, InputBuilder};
 
 pub mod sangria_adapter;
 
-use super::{support_circuit, DEFAULT_LIMBS_COUNT, DEFAULT_LIMB_WIDTH};
+use super::{DEFAULT_LIMBS_COUNT, DEFAULT_LIMB_WIDTH};
 use crate::halo2_proofs::halo2curves::ff::{FromUniformBytes, PrimeField, PrimeFieldBits};
 
-const MAIN_GATE_T: usize = 5;
+pub(super) const MAIN_GATE_T: usize = 5;
 
 /// 'SCC' here is 'Step Circuit Config'
 #[derive(Debug, Clone)]
@@ -51,6 +53,8 @@ pub struct StepFoldingCircuit<
     SC: StepCircuit<ARITY, CMain::ScalarExt>,
 > {
     pub sc: &'sc SC,
+    /// Non-deterministic advice of the step circuit for this step
+    pub witness: &'sc SC::StepWitness,
     pub input: Input<ARITY, CMain::ScalarExt>,
     pub _p: PhantomData<CSup>,
 }
@@ -63,10 +67,16 @@ impl<
     > Clone for StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
 {
     fn clone(&self) -> Self {
-        let Self { sc, input, _p } = self;
+        let Self {
+            sc,
+            witness,
+            input,
+            _p,
+        } = self;
 
         Self {
             sc,
+            witness,
             input: input.clone(),
             _p: PhantomData,
         }
@@ -83,6 +93,8 @@ where
     CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
 {
     /// For the initial iteration, we will give the same accumulators that we take from the input
+    ///
+    /// The step circuit instances hash accumulator is also passed through unchanged
     pub fn initial_instances(&self) -> Vec<Vec<CMain::ScalarExt>> {
         let _span = info_span!("consistency_marker").entered();
 
@@ -102,10 +114,10 @@ where
         instances
     }
 
-    pub fn instances(
+    pub fn instances<const SUPPORT_INSTANCES_LEN: usize>(
         &self,
         self_acc: &nifs::protogalaxy::AccumulatorInstance<CMain>,
-        support_acc: &nifs::sangria::RelaxedPlonkInstance<CSup, { support_circuit::INSTANCES_LEN }>,
+        support_acc: &nifs::sangria::RelaxedPlonkInstance<CSup, SUPPORT_INSTANCES_LEN>,
         z_out: &[CMain::ScalarExt; ARITY],
     ) -> Vec<Vec<CMain::ScalarExt>> {
         let _span = info_span!("consistency_marker").entered();
@@ -116,6 +128,8 @@ where
         self_.self_trace.input_accumulator = input::ProtoGalaxyAccumulatorInstance::new(self_acc);
         self_.support_trace.input_accumulator = input::SangriaAccumulatorInstance::new(support_acc);
         self_.z_i = *z_out;
+        self_.step_circuit_instances_hash_accumulator =
+            self.step_circuit_instances_hash_accumulator();
         trace!(
             "sangria support expected acc: {:?}",
             &self_.support_trace.input_accumulator
@@ -132,6 +146,29 @@ where
         instances.insert(0, vec![out_marker]);
         instances
     }
+
+    /// Off-circuit value of the step circuit instances hash accumulator at the output of this
+    /// circuit
+    ///
+    /// For the zero step it's equal to the input one, otherwise instances of the previous step
+    /// circuit (from `self_trace.incoming` without consistency marker) are absorbed into it
+    pub fn step_circuit_instances_hash_accumulator(&self) -> CMain::ScalarExt {
+        let Input {
+            step,
+            self_trace,
+            step_circuit_instances_hash_accumulator,
+            ..
+        } = &self.input;
+
+        if *step == 0 {
+            return *step_circuit_instances_hash_accumulator;
+        }
+
+        instances_accumulator_computation::absorb_in_native_sc_instances_accumulator(
+            step_circuit_instances_hash_accumulator,
+            &self_trace.incoming.instances[1..],
+        )
+    }
 }
 
 impl<
@@ -149,6 +186,7 @@ where
     fn without_witnesses(&self) -> Self {
         Self {
             sc: self.sc,
+            witness: self.witness,
             input: self.input.get_without_witness(),
             _p: PhantomData,
         }
@@ -165,12 +203,63 @@ where
         }
     }
 
-    #[instrument(skip_all)]
     fn synthesize(
         &self,
         config: Self::Config,
-        mut layouter: impl Layouter<CMain::ScalarExt>,
+        layouter: impl Layouter<CMain::ScalarExt>,
     ) -> Result<(), Halo2PlonkError> {
+        self.synthesize_with_output(config, layouter)
+            .map(|_z_out| ())
+    }
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > StepFoldingCircuit<'_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// Synthesize the step folding circuit once & collect its witness together with the output of
+    /// the step circuit
+    ///
+    /// Replaces [`StepCircuit::process_step`] followed by
+    /// [`crate::table::CircuitRunner::try_collect_witness`], which synthesize the step circuit
+    /// twice & configure the circuit on each step. The `layout` is collected once in public params,
+    /// so only the witness is synthesized here. The step folding circuit does not query its
+    /// consistency marker instance, so it's assigned only after synthesis, see
+    /// [`StepFoldingCircuit::instances`]
+    #[instrument(skip_all)]
+    pub fn collect_witness_with_output(
+        &self,
+        layout: &CircuitLayout<CMain::ScalarExt, Config<SC::Config>>,
+    ) -> Result<(Witness<CMain::ScalarExt>, [CMain::ScalarExt; ARITY]), Halo2PlonkError> {
+        let mut instances = self.sc.instances();
+        instances.insert(0, vec![CMain::ScalarExt::ZERO]);
+
+        let circuit = WithOutput {
+            sfc: self,
+            z_out: RefCell::new(None),
+        };
+
+        let witness = layout.try_collect_witness(&circuit, instances)?;
+        let z_out = circuit.z_out.take().ok_or_else(|| {
+            error!("step circuit output was not assigned");
+            Halo2PlonkError::Synthesis
+        })?;
+
+        Ok((witness, z_out))
+    }
+
+    /// Synthesize the circuit & return the output of the step circuit
+    #[instrument(name = "synthesize", skip_all)]
+    fn synthesize_with_output(
+        &self,
+        config: Config<SC::Config>,
+        mut layouter: impl Layouter<CMain::ScalarExt>,
+    ) -> Result<[AssignedCell<CMain::ScalarExt, CMain::ScalarExt>; ARITY], Halo2PlonkError> {
         info!("start");
 
         let input = layouter
@@ -199,7 +288,7 @@ where
             let _span = info_span!("sc").entered();
 
             self.sc
-                .synthesize_step(config.sc, &mut layouter, &input.z_i)
+                .synthesize_step(config.sc, &mut layouter, &input.z_i, self.witness)
                 .map_err(|err| {
                     error!("while synthesize_step: {err:?}");
                     Halo2PlonkError::Synthesis
@@ -222,7 +311,7 @@ where
                         } = protogalaxy::verify_chip::verify(
                             &mut region,
                             config.mg.clone(),
-                            ro_chip(config.mg.clone()),
+                            ro_acc_chip(config.mg.clone()),
                             verify_chip::AssignedVerifierParam {
                                 pp_digest: input.pp_digest.clone(),
                             },
@@ -316,17 +405,43 @@ where
 
                     trace!("sangria support actual acc: {:?}", &support_trace_output);
 
+                    let step_circuit_instances = input
+                        .self_trace
+                        .incoming
+                        .instances
+                        .iter()
+                        .skip(1)
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    let step_circuit_instances_hash_accumulator =
+                        instances_accumulator_computation::absorb_in_assign_sc_instances_accumulator(
+                            &mut region,
+                            config.mg.clone(),
+                            &input.step_circuit_instances_hash_accumulator,
+                            &step_circuit_instances,
+                        )?;
+
+                    let step_circuit_instances_hash_accumulator = mg.conditional_select(
+                        &mut region,
+                        &input.step_circuit_instances_hash_accumulator,
+                        &step_circuit_instances_hash_accumulator,
+                        &is_zero_step,
+                    )?;
+
                     let next_step =
                         mg.add_with_const(&mut region, &input.step, CMain::ScalarExt::ONE)?;
 
                     ro_chip(config.mg.clone())
                         .absorb_iter(input::assigned::iter_consistency_marker_wrap_values(
                             (&input.pp_digest.0, &input.pp_digest.1),
-                            &self_trace_output,
+                            self_trace_output.iter_wrap_values(),
                             &support_trace_output,
                             &next_step,
                             &input.z_0,
                             &z_out,
+                            &step_circuit_instances_hash_accumulator,
                         ))
                         .inspect(|buf| trace!("buf before marker: {buf:?}"))
                         .squeeze(&mut region)
@@ -348,6 +463,69 @@ where
                 error!("while sfc out constraint instance: {err:?}");
             })?;
 
+        Ok(z_out)
+    }
+}
+
+/// Adapter to keep the step circuit output, while [`StepFoldingCircuit`] is synthesized by
+/// [`CircuitLayout::try_collect_witness`]
+struct WithOutput<
+    'link,
+    'sc,
+    const ARITY: usize,
+    CMain: CurveAffine,
+    CSup: CurveAffine<Base = CMain::ScalarExt>,
+    SC: StepCircuit<ARITY, CMain::ScalarExt>,
+> {
+    sfc: &'link StepFoldingCircuit<'sc, ARITY, CMain, CSup, SC>,
+    z_out: RefCell<Option<[CMain::ScalarExt; ARITY]>>,
+}
+
+impl<
+        const ARITY: usize,
+        CMain: CurveAffine,
+        CSup: CurveAffine<Base = CMain::ScalarExt>,
+        SC: StepCircuit<ARITY, CMain::ScalarExt>,
+    > Circuit<CMain::ScalarExt> for WithOutput<'_, '_, ARITY, CMain, CSup, SC>
+where
+    CMain::ScalarExt: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Config = Config<SC::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            sfc: self.sfc,
+            z_out: RefCell::new(None),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<CMain::ScalarExt>) -> Self::Config {
+        StepFoldingCircuit::<'_, ARITY, CMain, CSup, SC>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<CMain::ScalarExt>,
+    ) -> Result<(), Halo2PlonkError> {
+        let z_out = self.sfc.synthesize_with_output(config, layouter)?;
+
+        let z_out = z_out
+            .iter()
+            .map(|cell| cell.value().unwrap().copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                error!("step circuit output is unknown");
+                Halo2PlonkError::Synthesis
+            })?;
+
+        *self.z_out.borrow_mut() = Some(
+            z_out
+                .try_into()
+                .expect("the step circuit output is of `ARITY` size"),
+        );
+
         Ok(())
     }
 }
@@ -17,7 +17,7 @@ use crate::{
         plonk::Error as Halo2PlonkError,
     },
     ivc::{
-        cyclefold::{ro_chip, DEFAULT_LIMBS_COUNT, DEFAULT_LIMB_WIDTH},
+        cyclefold::{ro_acc_chip, DEFAULT_LIMBS_COUNT, DEFAULT_LIMB_WIDTH},
         sangria::fold_relaxed_plonk_instance_chip::{
             self, BigUintView, FoldRelaxedPlonkInstanceChip,
         },
@@ -63,45 +63,49 @@ where
     let ecc_chip = ecc_chip::<CSup>(config.clone());
     let mg = MainGate::new(config.clone());
 
-    let sangria_cha_span = info_span!("sangria_cha").entered();
+    let m_bn = module_as_bn::<CMain::ScalarExt, CMain::Base>()
+        .inspect_err(|err| error!("Error while creating 'm_bn' in fold: {err:?}"))
+        .unwrap();
 
-    let r_bits = ro_chip(config.clone())
-        .absorb_base(pp_digest.0.clone().into())
-        .absorb_base(pp_digest.1.clone().into())
-        .absorb_iter(input.iter_wrap_values())
-        .inspect(|buf| debug!("buf before: {buf:?}"))
-        .squeeze_n_bits(region, NUM_CHALLENGE_BITS)
-        .inspect_err(|err| error!("Error while computing 'r' in fold: {err:?}"))?;
+    let mut acc = input.input_accumulator.clone();
 
-    sangria_cha_span.exit();
+    // Incoming traces are folded one by one, as [`sangria::VanillaFS::prove`] does, so each of
+    // them has its own challenge, depending on the accumulator folded so far
+    for incoming in input.incoming.iter() {
+        let sangria_cha_span = info_span!("sangria_cha").entered();
 
-    let r = mg
-        .le_bits_to_num(region, &r_bits)
-        .inspect_err(|err| error!("Error while converting 'r' to bits in fold: {err:?}"))?;
+        let r_bits = ro_acc_chip(config.clone())
+            .absorb_base(pp_digest.0.clone().into())
+            .absorb_base(pp_digest.1.clone().into())
+            .absorb_iter(acc.iter_wrap_values())
+            .absorb_iter(incoming.iter_wrap_values())
+            .inspect(|buf| debug!("buf before: {buf:?}"))
+            .squeeze_n_bits(region, NUM_CHALLENGE_BITS)
+            .inspect_err(|err| error!("Error while computing 'r' in fold: {err:?}"))?;
 
-    debug!("sangria_cha: {:?}", r.value());
+        sangria_cha_span.exit();
 
-    let r_as_bn = bn_chip
-        .from_assigned_value_to_limbs(region, &r)
-        .inspect_err(|err| error!("Error while converting 'r' to BN limbs in fold: {err:?}"))
-        .unwrap();
+        let r = mg
+            .le_bits_to_num(region, &r_bits)
+            .inspect_err(|err| error!("Error while converting 'r' to bits in fold: {err:?}"))?;
 
-    let m_bn = module_as_bn::<CMain::ScalarExt, CMain::Base>()
-        .inspect_err(|err| error!("Error while creating 'm_bn' in fold: {err:?}"))
-        .unwrap();
+        debug!("sangria_cha: {:?}", r.value());
 
-    let mut acc = input.input_accumulator.clone();
+        let r_as_bn = bn_chip
+            .from_assigned_value_to_limbs(region, &r)
+            .inspect_err(|err| error!("Error while converting 'r' to BN limbs in fold: {err:?}"))
+            .unwrap();
+
+        let input::assigned::SupportIncoming {
+            instance:
+                input::assigned::SupportPlonkInstance {
+                    W_commitments: input_W_commitments,
+                    challenges: input_challenges,
+                    instances: input_instances,
+                },
+            proof,
+        } = incoming;
 
-    for input::assigned::SupportIncoming {
-        instance:
-            input::assigned::SupportPlonkInstance {
-                W_commitments: input_W_commitments,
-                challenges: input_challenges,
-                instances: input_instances,
-            },
-        proof,
-    } in input.incoming.iter()
-    {
         let input::assigned::SangriaAccumulatorInstance {
             ins:
                 input::assigned::SupportPlonkInstance {
@@ -1,5 +1,6 @@
-use std::{marker::PhantomData, num::NonZeroUsize};
+use std::{iter, marker::PhantomData, num::NonZeroUsize};
 
+use itertools::Itertools;
 use tracing::*;
 
 use crate::{
@@ -7,7 +8,8 @@ use crate::{
     halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         halo2curves::{
-            ff::{PrimeField, PrimeFieldBits},
+            ff::{Field, PrimeField, PrimeFieldBits},
+            group::prime::PrimeCurveAffine,
             CurveAffine,
         },
         plonk::{Circuit, Column, ConstraintSystem, Error as Halo2PlonkError, Instance},
@@ -21,13 +23,36 @@ type EccChip<C> = ecc::EccChip<C, tiny_gate::Gate<<C as CurveAffine>::Base>>;
 mod tiny_gate;
 use tiny_gate::{Config as GateConfig, Gate};
 
-pub const INSTANCES_LEN: usize = 8;
+/// Number of instance values of one `l0*P0 + l1*P1` computation
+pub const INPUT_LEN: usize = 8;
+
+/// Number of [`InstanceInput`] computed in one support circuit trace by default
+///
+/// Equal to the max number of `W_commitments`, so the cyclefold IVC needs exactly one support
+/// trace per step
+pub const DEFAULT_CAPACITY: usize = 3;
+
+/// Length of the instance column of the support circuit computing `capacity` of [`InstanceInput`]
+/// in one trace
+///
+/// The cyclefold IVC takes the capacity as this length, since the folded support instances are
+/// sized by it at compile time, e.g. `IVC<.., { cyclefold::support_instances_len(1) }>`
+pub const fn instances_len(capacity: usize) -> usize {
+    INPUT_LEN * capacity
+}
+
+pub const DEFAULT_INSTANCES_LEN: usize = instances_len(DEFAULT_CAPACITY);
 
+/// Circuit computing [`SupportCircuit::CAPACITY`] of `l0*P0 + l1*P1` in one trace
+///
+/// Instance column of `INSTANCES_LEN` consists of consecutive [`InstanceInput`] slots of
+/// [`INPUT_LEN`] values, see [`SupportCircuit::instances`] & [`instances_len`]
 #[derive(Default)]
-pub struct SupportCircuit<C: CurveAffine> {
+pub struct SupportCircuit<C: CurveAffine, const INSTANCES_LEN: usize = DEFAULT_INSTANCES_LEN> {
     _p: PhantomData<C>,
 }
 
+#[derive(Debug, Clone)]
 pub struct InstanceInput<C: CurveAffine> {
     pub p0: C,
     pub l0: C::Base,
@@ -36,11 +61,23 @@ pub struct InstanceInput<C: CurveAffine> {
     pub l1: C::Base,
 }
 
+impl<C: CurveAffine> InstanceInput<C> {
+    /// Input used to fill unused slots of the support circuit: `0*O + 0*O`
+    pub fn padding() -> Self {
+        Self {
+            p0: C::identity(),
+            l0: C::Base::ZERO,
+            p1: C::identity(),
+            l1: C::Base::ZERO,
+        }
+    }
+}
+
 impl<C: CurveAffine> InstanceInput<C>
 where
     C::Base: PrimeFieldBits,
 {
-    pub fn into_instance(self) -> Vec<Vec<C::Base>> {
+    fn into_values(self) -> [C::Base; INPUT_LEN] {
         let p0 = self.p0.coordinates().unwrap();
         let p1 = self.p1.coordinates().unwrap();
 
@@ -49,7 +86,7 @@ where
             .add(&Point::from(self.p1).scalar_mul(&self.l1))
             .into_pair();
 
-        let instance: [C::Base; INSTANCES_LEN] = [
+        [
             p_out_x,
             p_out_y,
             *p0.x(),
@@ -58,14 +95,58 @@ where
             *p1.x(),
             *p1.y(),
             self.l1,
-        ];
+        ]
+    }
+}
+
+impl<C: CurveAffine, const INSTANCES_LEN: usize> SupportCircuit<C, INSTANCES_LEN> {
+    /// Number of [`InstanceInput`] computed in one trace
+    pub const CAPACITY: usize = {
+        assert!(
+            INSTANCES_LEN != 0 && INSTANCES_LEN % INPUT_LEN == 0,
+            "support circuit instances must consist of whole inputs, see `instances_len`"
+        );
+        INSTANCES_LEN / INPUT_LEN
+    };
+
+    /// Rows of one `l0*P0 + l1*P1` fit into `2^15`, so each doubling of
+    /// [`SupportCircuit::CAPACITY`] adds one to `k`
+    ///
+    /// Checked against the measured layout by `tests::min_k_table_size`
+    pub const MIN_K_TABLE_SIZE: u32 = 15 + Self::CAPACITY.next_power_of_two().ilog2();
 
-        vec![instance.to_vec()]
+    /// Number of support circuit traces required for `inputs_len` inputs
+    pub const fn traces_count(inputs_len: usize) -> usize {
+        inputs_len.div_ceil(Self::CAPACITY)
     }
 }
 
-impl<C: CurveAffine> SupportCircuit<C> {
-    pub const MIN_K_TABLE_SIZE: u32 = 15;
+impl<C: CurveAffine, const INSTANCES_LEN: usize> SupportCircuit<C, INSTANCES_LEN>
+where
+    C::Base: PrimeFieldBits,
+{
+    /// Split `inputs` into instances of [`SupportCircuit::traces_count`] traces
+    ///
+    /// The last trace is padded with [`InstanceInput::padding`]
+    pub fn instances(inputs: impl IntoIterator<Item = InstanceInput<C>>) -> Vec<Vec<Vec<C::Base>>> {
+        inputs
+            .into_iter()
+            .chunks(Self::CAPACITY)
+            .into_iter()
+            .map(|chunk| {
+                let chunk = chunk.collect::<Vec<_>>();
+                let padding_len = Self::CAPACITY - chunk.len();
+
+                let instance = chunk
+                    .into_iter()
+                    .chain(iter::repeat_with(InstanceInput::padding).take(padding_len))
+                    .flat_map(InstanceInput::into_values)
+                    .collect::<Vec<_>>();
+
+                vec![instance]
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -74,7 +155,8 @@ pub struct Config {
     instance: Column<Instance>,
 }
 
-impl<C: CurveAffine> Circuit<C::Base> for SupportCircuit<C>
+impl<C: CurveAffine, const INSTANCES_LEN: usize> Circuit<C::Base>
+    for SupportCircuit<C, INSTANCES_LEN>
 where
     C::Base: PrimeFieldBits,
 {
@@ -107,56 +189,58 @@ where
             |region| {
                 let mut ctx = RegionCtx::new(region, 0);
 
-                let [expected_x, expected_y, x0, y0, l0, x1, y1, l1] = ecc_chip
-                    .gate
-                    .assign_values_from_instance(&mut ctx, config.instance, 0)
-                    .unwrap();
+                let num_bits =
+                    NonZeroUsize::new(<C::Base as PrimeField>::NUM_BITS as usize).unwrap();
 
-                trace!("instances assigned ({})", ctx.offset());
+                for slot in 0..Self::CAPACITY {
+                    let [expected_x, expected_y, x0, y0, l0, x1, y1, l1] = ecc_chip
+                        .gate
+                        .assign_values_from_instance(&mut ctx, config.instance, slot * INPUT_LEN)
+                        .unwrap();
 
-                let [p0, p1] = [
-                    AssignedPoint::<C> { x: x0, y: y0 },
-                    AssignedPoint::<C> { x: x1, y: y1 },
-                ];
+                    trace!("slot {slot}: instances assigned ({})", ctx.offset());
 
-                let num_bits =
-                    NonZeroUsize::new(<C::Base as PrimeField>::NUM_BITS as usize).unwrap();
+                    let [p0, p1] = [
+                        AssignedPoint::<C> { x: x0, y: y0 },
+                        AssignedPoint::<C> { x: x1, y: y1 },
+                    ];
 
-                let l0_bits = ecc_chip
-                    .gate
-                    .le_num_to_bits(&mut ctx, &l0, num_bits)
-                    .unwrap();
+                    let l0_bits = ecc_chip
+                        .gate
+                        .le_num_to_bits(&mut ctx, &l0, num_bits)
+                        .unwrap();
 
-                trace!("l0 -> l0_bits, ({})", ctx.offset());
+                    trace!("slot {slot}: l0 -> l0_bits, ({})", ctx.offset());
 
-                let lhs = ecc_chip.scalar_mul(&mut ctx, &p0, &l0_bits).unwrap();
+                    let lhs = ecc_chip.scalar_mul(&mut ctx, &p0, &l0_bits).unwrap();
 
-                trace!(
-                    "p0 * l0_bits = [{:?},{:?}] ({})",
-                    lhs.x.value(),
-                    lhs.y.value(),
-                    ctx.offset()
-                );
+                    trace!(
+                        "slot {slot}: p0 * l0_bits = [{:?},{:?}] ({})",
+                        lhs.x.value(),
+                        lhs.y.value(),
+                        ctx.offset()
+                    );
 
-                let l1_bits = ecc_chip
-                    .gate
-                    .le_num_to_bits(&mut ctx, &l1, num_bits)
-                    .unwrap();
-                trace!("l1 -> l1_bits({})", ctx.offset());
+                    let l1_bits = ecc_chip
+                        .gate
+                        .le_num_to_bits(&mut ctx, &l1, num_bits)
+                        .unwrap();
+                    trace!("slot {slot}: l1 -> l1_bits({})", ctx.offset());
 
-                let rhs = ecc_chip.scalar_mul(&mut ctx, &p1, &l1_bits).unwrap();
-                trace!("p1 * l1_bits ({})", ctx.offset());
+                    let rhs = ecc_chip.scalar_mul(&mut ctx, &p1, &l1_bits).unwrap();
+                    trace!("slot {slot}: p1 * l1_bits ({})", ctx.offset());
 
-                let AssignedPoint {
-                    x: actual_x,
-                    y: actual_y,
-                } = ecc_chip.add(&mut ctx, &lhs, &rhs).unwrap();
-                trace!("add finished ({})", ctx.offset());
+                    let AssignedPoint {
+                        x: actual_x,
+                        y: actual_y,
+                    } = ecc_chip.add(&mut ctx, &lhs, &rhs).unwrap();
+                    trace!("slot {slot}: add finished ({})", ctx.offset());
 
-                ctx.constrain_equal(expected_x.cell(), actual_x.cell())
-                    .unwrap();
-                ctx.constrain_equal(expected_y.cell(), actual_y.cell())
-                    .unwrap();
+                    ctx.constrain_equal(expected_x.cell(), actual_x.cell())
+                        .unwrap();
+                    ctx.constrain_equal(expected_y.cell(), actual_y.cell())
+                        .unwrap();
+                }
 
                 Ok(())
             },
@@ -171,57 +255,108 @@ mod tests {
 
     use super::*;
     use crate::{
-        halo2_proofs::{dev::MockProver, halo2curves::group::prime::PrimeCurveAffine},
-        sangria_prelude::{bn256::C1Affine as Curve, Field},
+        halo2_proofs::dev::MockProver, sangria_prelude::bn256::C1Affine as Curve,
+        table::CircuitRunner,
     };
 
     type Base = <Curve as CurveAffine>::Base;
     type Scalar = <Curve as CurveAffine>::ScalarExt;
 
+    fn random_input(rng: &mut impl rand::RngCore) -> InstanceInput<Curve> {
+        let l0 = Scalar::random(&mut *rng);
+        let l1 = Scalar::random(&mut *rng);
+
+        InstanceInput {
+            p0: Curve::random(&mut *rng),
+            l0: Base::from_repr(l0.to_repr()).unwrap(),
+            p1: Curve::random(&mut *rng),
+            l1: Base::from_repr(l1.to_repr()).unwrap(),
+        }
+    }
+
+    fn verify<const INSTANCES_LEN: usize>(inputs: Vec<InstanceInput<Curve>>) {
+        let instances = SupportCircuit::<Curve, INSTANCES_LEN>::instances(inputs.clone());
+        assert_eq!(
+            instances.len(),
+            SupportCircuit::<Curve, INSTANCES_LEN>::traces_count(inputs.len())
+        );
+
+        for instance in instances {
+            assert_eq!(instance[0].len(), INSTANCES_LEN);
+
+            MockProver::run(
+                SupportCircuit::<Curve, INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+                &SupportCircuit::<Curve, INSTANCES_LEN>::default(),
+                instance,
+            )
+            .unwrap()
+            .verify()
+            .unwrap();
+        }
+    }
+
     #[traced_test]
     #[test]
     fn e2e() {
-        let circuit = SupportCircuit::<Curve>::default();
-
         let mut rng = rand::thread_rng();
-        let l0 = Scalar::random(&mut rng);
-        let l1 = Scalar::random(&mut rng);
-
-        let p0 = Curve::random(&mut rng);
-        let p1 = Curve::random(&mut rng);
-
-        let tmp = p0 * l0;
-        trace!("p0 * l0_bits = {:?}", tmp);
-
-        let l0 = Base::from_repr(l0.to_repr()).unwrap();
-        let l1 = Base::from_repr(l1.to_repr()).unwrap();
-
-        MockProver::run(
-            SupportCircuit::<Curve>::MIN_K_TABLE_SIZE,
-            &circuit,
-            InstanceInput { p0, l0, p1, l1 }.into_instance(),
-        )
-        .unwrap()
-        .verify()
-        .unwrap();
+        verify::<{ instances_len(1) }>(vec![random_input(&mut rng)]);
     }
 
     #[traced_test]
     #[test]
     fn e2e_zero() {
-        MockProver::run(
-            SupportCircuit::<Curve>::MIN_K_TABLE_SIZE,
-            &SupportCircuit::<Curve>::default(),
-            InstanceInput::<Curve> {
-                p0: Curve::identity(),
-                l0: Field::ZERO,
-                p1: Curve::identity(),
-                l1: Field::ZERO,
-            }
-            .into_instance(),
-        )
-        .unwrap()
-        .verify()
-        .unwrap();
+        verify::<{ instances_len(1) }>(vec![InstanceInput::padding()]);
+    }
+
+    #[traced_test]
+    #[test]
+    fn e2e_batch() {
+        let mut rng = rand::thread_rng();
+        verify::<DEFAULT_INSTANCES_LEN>(
+            iter::repeat_with(|| random_input(&mut rng))
+                .take(DEFAULT_CAPACITY + 1)
+                .collect(),
+        );
+    }
+
+    /// `MIN_K_TABLE_SIZE` is a formula, so the measured layout of each capacity should fit into it
+    #[traced_test]
+    #[test]
+    fn min_k_table_size() {
+        fn check<const INSTANCES_LEN: usize>() {
+            let capacity = SupportCircuit::<Curve, INSTANCES_LEN>::CAPACITY;
+
+            let stats = CircuitRunner::<Base, _>::new(
+                SupportCircuit::<Curve, INSTANCES_LEN>::MIN_K_TABLE_SIZE,
+                SupportCircuit::<Curve, INSTANCES_LEN>::default(),
+                SupportCircuit::<Curve, INSTANCES_LEN>::instances([InstanceInput::padding()])
+                    .pop()
+                    .expect("one input always gives one trace"),
+            )
+            .try_collect_stats()
+            .unwrap();
+
+            info!("capacity {capacity}: {stats}");
+            assert!(stats.is_fit(), "capacity {capacity}: {stats}");
+        }
+
+        check::<{ instances_len(1) }>();
+        check::<{ instances_len(2) }>();
+        check::<DEFAULT_INSTANCES_LEN>();
+        check::<{ instances_len(4) }>();
+    }
+
+    #[test]
+    fn traces_count() {
+        type Capacity1 = SupportCircuit<Curve, { instances_len(1) }>;
+        type Capacity3 = SupportCircuit<Curve, { instances_len(3) }>;
+
+        assert_eq!(Capacity3::CAPACITY, 3);
+        assert_eq!(Capacity3::traces_count(1), 1);
+        assert_eq!(Capacity3::traces_count(3), 1);
+        assert_eq!(Capacity3::traces_count(4), 2);
+        assert_eq!(Capacity1::traces_count(3), 3);
+        assert_eq!(Capacity1::MIN_K_TABLE_SIZE, 15);
+        assert_eq!(Capacity3::MIN_K_TABLE_SIZE, 17);
     }
 }
@@ -7,9 +7,13 @@ pub mod protogalaxy;
 
 pub mod cyclefold;
 
+pub mod runner;
+pub use runner::{IvcRunner, IvcState};
+
 pub use cyclefold::incrementally_verifiable_computation::IVC as CyclefoldIVC;
+pub use cyclefold::sangria_primary::IVC as CyclefoldSangriaIVC;
 pub use halo2_proofs::circuit::SimpleFloorPlanner;
 
 pub use sangria::incrementally_verifiable_computation::{
-    Instances, StepCircuit, SynthesisError, IVC as SangriaIVC,
+    Instances, PubInstances, StepCircuit, SynthesisError, IVC as SangriaIVC,
 };
@@ -104,7 +104,7 @@ pub mod verify_chip {
             Ok(())
         }
 
-        fn conditional_select<const T: usize>(
+        pub fn conditional_select<const T: usize>(
             region: &mut RegionCtx<'_, F>,
             mg: &MainGate<F, T>,
             lhs: &Self,
@@ -0,0 +1,416 @@
+//! Driver of IVC folding
+//!
+//! [`IvcRunner`] folds one step for each item of the input iterator. The step itself is a closure,
+//! so any IVC implementing [`IvcState`] can be driven, e.g. for [`crate::ivc::CyclefoldIVC`]:
+//!
+//! ```ignore
+//! let output = IvcRunner::new()
+//!     .with_checkpoint_interval(NonZeroUsize::new(10).unwrap())
+//!     .run(ivc, witnesses, |ivc, witness| ivc.next_with_witness(&pp, &sc, &witness))?;
+//!
+//! output.state.verify(&pp)?;
+//! ```
+//!
+//! [`Hooks`] are called before & after each step, the latter with [`StepStats`] of the step, and
+//! every `checkpoint_interval` steps. The run stops early, if a hook returns
+//! [`ControlFlow::Break`] or [`CancellationToken::cancel`] is called from any other place.
+
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use tracing::*;
+
+/// IVC, which can be driven by [`IvcRunner`]
+pub trait IvcState {
+    /// Number of steps folded so far, including the zero one
+    fn step(&self) -> usize;
+
+    /// Number of field elements in the witnesses of all accumulators
+    fn accumulator_size(&self) -> usize;
+}
+
+/// Statistics of one step, collected by [`IvcRunner`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepStats {
+    /// [`IvcState::step`] before this step
+    pub step: usize,
+    /// Time of the step closure call
+    pub elapsed: Duration,
+    /// [`IvcState::accumulator_size`] after this step
+    pub accumulator_size: usize,
+    /// Memory after this step, if [`IvcRunner::with_memory_probe`] is set
+    pub memory: Option<usize>,
+}
+
+/// Callbacks of [`IvcRunner`], all of them do nothing by default
+///
+/// Returning [`ControlFlow::Break`] stops the run, see [`StopReason::Hook`]
+pub trait Hooks<S> {
+    fn before_step(&mut self, _step: usize, _state: &S) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn after_step(&mut self, _stats: &StepStats, _state: &S) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after [`Hooks::after_step`] every [`IvcRunner::with_checkpoint_interval`] steps
+    fn checkpoint(&mut self, _stats: &StepStats, _state: &S) {}
+}
+
+/// [`Hooks`] doing nothing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoHooks;
+
+impl<S> Hooks<S> for NoHooks {}
+
+impl<S, H: Hooks<S>> Hooks<S> for &mut H {
+    fn before_step(&mut self, step: usize, state: &S) -> ControlFlow<()> {
+        (**self).before_step(step, state)
+    }
+
+    fn after_step(&mut self, stats: &StepStats, state: &S) -> ControlFlow<()> {
+        (**self).after_step(stats, state)
+    }
+
+    fn checkpoint(&mut self, stats: &StepStats, state: &S) {
+        (**self).checkpoint(stats, state)
+    }
+}
+
+/// Shared flag to stop [`IvcRunner::run`] from another thread, checked before each step
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Why [`IvcRunner::run`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// All inputs are folded
+    InputsExhausted,
+    /// One of [`Hooks`] returned [`ControlFlow::Break`]
+    Hook,
+    /// [`CancellationToken::cancel`] was called
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct RunOutput<S> {
+    /// IVC after the last folded step
+    pub state: S,
+    /// Statistics of each folded step
+    pub stats: Vec<StepStats>,
+    pub stop_reason: StopReason,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("while fold step {step}: {err:?}")]
+pub struct RunError<E> {
+    /// [`IvcState::step`] of the failed step
+    pub step: usize,
+    /// Statistics of steps folded before the failed one
+    pub stats: Vec<StepStats>,
+    pub err: E,
+}
+
+/// Driver of IVC, see [module-level](self) docs
+pub struct IvcRunner<H = NoHooks> {
+    hooks: H,
+    checkpoint_interval: Option<NonZeroUsize>,
+    cancellation: Option<CancellationToken>,
+    memory_probe: Option<Box<dyn Fn() -> usize>>,
+}
+
+impl IvcRunner {
+    pub fn new() -> Self {
+        Self {
+            hooks: NoHooks,
+            checkpoint_interval: None,
+            cancellation: None,
+            memory_probe: None,
+        }
+    }
+}
+
+impl Default for IvcRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> fmt::Debug for IvcRunner<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IvcRunner")
+            .field("checkpoint_interval", &self.checkpoint_interval)
+            .field("cancellation", &self.cancellation)
+            .field("memory_probe", &self.memory_probe.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<H> IvcRunner<H> {
+    pub fn with_hooks<NH>(self, hooks: NH) -> IvcRunner<NH> {
+        let Self {
+            hooks: _,
+            checkpoint_interval,
+            cancellation,
+            memory_probe,
+        } = self;
+
+        IvcRunner {
+            hooks,
+            checkpoint_interval,
+            cancellation,
+            memory_probe,
+        }
+    }
+
+    /// Call [`Hooks::checkpoint`] after every `interval` folded steps
+    pub fn with_checkpoint_interval(mut self, interval: NonZeroUsize) -> Self {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Source of [`StepStats::memory`], e.g. the current heap size from the allocator
+    pub fn with_memory_probe(mut self, probe: impl Fn() -> usize + 'static) -> Self {
+        self.memory_probe = Some(Box::new(probe));
+        self
+    }
+
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    pub fn into_hooks(self) -> H {
+        self.hooks
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Fold `fold_step(state, input)` for each of `inputs`, until they are exhausted or the run
+    /// is stopped
+    ///
+    /// The state is consumed by `fold_step`, so in case of its error only [`RunError`] is left
+    pub fn run<S, I, E>(
+        &mut self,
+        mut state: S,
+        inputs: impl IntoIterator<Item = I>,
+        mut fold_step: impl FnMut(S, I) -> Result<S, E>,
+    ) -> Result<RunOutput<S>, RunError<E>>
+    where
+        S: IvcState,
+        H: Hooks<S>,
+    {
+        let mut stats = Vec::new();
+
+        let stop_reason = 'run: {
+            for input in inputs {
+                if self.is_cancelled() {
+                    break 'run StopReason::Cancelled;
+                }
+
+                let step = state.step();
+                if self.hooks.before_step(step, &state).is_break() {
+                    break 'run StopReason::Hook;
+                }
+
+                let _span = info_span!("ivc_runner_step", step).entered();
+
+                let started = Instant::now();
+                state = match fold_step(state, input) {
+                    Ok(state) => state,
+                    Err(err) => return Err(RunError { step, stats, err }),
+                };
+
+                let step_stats = StepStats {
+                    step,
+                    elapsed: started.elapsed(),
+                    accumulator_size: state.accumulator_size(),
+                    memory: self.memory_probe.as_ref().map(|probe| probe()),
+                };
+                debug!("step stats: {step_stats:?}");
+
+                let flow = self.hooks.after_step(&step_stats, &state);
+
+                if self
+                    .checkpoint_interval
+                    .is_some_and(|interval| (stats.len() + 1) % interval.get() == 0)
+                {
+                    self.hooks.checkpoint(&step_stats, &state);
+                }
+
+                stats.push(step_stats);
+
+                if flow.is_break() {
+                    break 'run StopReason::Hook;
+                }
+            }
+
+            StopReason::InputsExhausted
+        };
+
+        Ok(RunOutput {
+            state,
+            stats,
+            stop_reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts steps & fails on the input `FAIL_ON`
+    #[derive(Debug, PartialEq, Eq)]
+    struct Counter(usize);
+
+    const FAIL_ON: u32 = 100;
+
+    impl IvcState for Counter {
+        fn step(&self) -> usize {
+            self.0
+        }
+
+        fn accumulator_size(&self) -> usize {
+            self.0 * 2
+        }
+    }
+
+    fn fold(state: Counter, input: u32) -> Result<Counter, u32> {
+        if input == FAIL_ON {
+            Err(input)
+        } else {
+            Ok(Counter(state.0 + 1))
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        before: Vec<usize>,
+        after: Vec<usize>,
+        checkpoints: Vec<usize>,
+        stop_after: Option<usize>,
+    }
+
+    impl Hooks<Counter> for Recorder {
+        fn before_step(&mut self, step: usize, _state: &Counter) -> ControlFlow<()> {
+            self.before.push(step);
+            ControlFlow::Continue(())
+        }
+
+        fn after_step(&mut self, stats: &StepStats, state: &Counter) -> ControlFlow<()> {
+            assert_eq!(stats.accumulator_size, state.accumulator_size());
+            self.after.push(stats.step);
+
+            match self.stop_after {
+                Some(step) if step == stats.step => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+
+        fn checkpoint(&mut self, stats: &StepStats, _state: &Counter) {
+            self.checkpoints.push(stats.step);
+        }
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut recorder = Recorder::default();
+
+        let output = IvcRunner::new()
+            .with_hooks(&mut recorder)
+            .with_checkpoint_interval(NonZeroUsize::new(2).unwrap())
+            .with_memory_probe(|| 42)
+            .run(Counter(1), 0..5, fold)
+            .unwrap();
+
+        assert_eq!(output.state, Counter(6));
+        assert_eq!(output.stop_reason, StopReason::InputsExhausted);
+        assert_eq!(
+            output.stats.iter().map(|s| s.step).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+        assert!(output.stats.iter().all(|s| s.memory == Some(42)));
+
+        assert_eq!(recorder.before, [1, 2, 3, 4, 5]);
+        assert_eq!(recorder.after, [1, 2, 3, 4, 5]);
+        assert_eq!(recorder.checkpoints, [2, 4]);
+    }
+
+    #[test]
+    fn stopped_by_hook() {
+        let mut recorder = Recorder {
+            stop_after: Some(2),
+            ..Default::default()
+        };
+
+        let output = IvcRunner::new()
+            .with_hooks(&mut recorder)
+            .run(Counter(1), 0..5, fold)
+            .unwrap();
+
+        assert_eq!(output.state, Counter(3));
+        assert_eq!(output.stop_reason, StopReason::Hook);
+        assert_eq!(output.stats.len(), 2);
+    }
+
+    #[test]
+    fn cancelled() {
+        let token = CancellationToken::new();
+
+        let mut runner = IvcRunner::new().with_cancellation(token.clone());
+        let output = runner
+            .run(Counter(1), 0..5, |state, input| {
+                if state.0 == 3 {
+                    token.cancel();
+                }
+                fold(state, input)
+            })
+            .unwrap();
+
+        assert_eq!(output.state, Counter(4));
+        assert_eq!(output.stop_reason, StopReason::Cancelled);
+    }
+
+    #[test]
+    fn failed() {
+        let err = IvcRunner::new()
+            .run(Counter(1), [0, 1, FAIL_ON, 2], fold)
+            .unwrap_err();
+
+        assert_eq!(err.step, 3);
+        assert_eq!(err.stats.len(), 2);
+        assert_eq!(err.err, FAIL_ON);
+    }
+}
@@ -16,12 +16,13 @@ use crate::{
             public_params::PublicParams,
         },
         step_folding_circuit::{StepFoldingCircuit, StepInputs},
+        IvcState,
     },
     main_gate::MainGateConfig,
     nifs::{
         self,
         sangria::{
-            accumulator::{FoldablePlonkTrace, RelaxedPlonkTrace},
+            accumulator::{FoldablePlonkTrace, RelaxedPlonkTrace, SCInstancesHashAcc},
             FoldablePlonkInstance, GetConsistencyMarkers, VanillaFS, VerifyError,
         },
     },
@@ -33,6 +34,37 @@ use crate::{
 
 pub type Instances<F> = Vec<Vec<F>>;
 
+/// Public input (instances) of each step, which [`IVC`] keeps for [`IVC::verify`]
+///
+/// By default all of them are kept, see [`IVC::with_constant_size_pub_instances`] to keep only the
+/// last ones
+#[derive(Debug, Clone)]
+pub enum PubInstances<F> {
+    /// Instances of each folded step, so verifier memory is linear in the number of steps
+    All(Vec<Instances<F>>),
+    /// Constant-size commitment to the instances of all folded steps
+    Last {
+        /// Hash accumulator of step circuit instances before the last folded step
+        hash_accumulator: SCInstancesHashAcc<F>,
+        /// Instances of the last folded step, `None` until the first step is folded
+        instances: Option<Instances<F>>,
+    },
+}
+
+impl<F: Clone> PubInstances<F> {
+    fn push(&mut self, hash_accumulator: &SCInstancesHashAcc<F>, instances: Instances<F>) {
+        match self {
+            Self::All(all) => all.push(instances),
+            Self::Last { .. } => {
+                *self = Self::Last {
+                    hash_accumulator: hash_accumulator.clone(),
+                    instances: Some(instances),
+                }
+            }
+        }
+    }
+}
+
 // TODO #31 docs
 struct StepCircuitContext<const ARITY: usize, C, SC>
 where
@@ -44,12 +76,51 @@ where
 
     /// Public input (instance) from each step
     ///
-    /// For further checking of hash-accumulator correctness, we save each instance
-    pub_instances: Vec<Instances<C::Scalar>>,
+    /// For further checking of hash-accumulator correctness, we save each instance or only the
+    /// last one, see [`PubInstances`]
+    pub_instances: PubInstances<C::Scalar>,
 
     _p: PhantomData<SC>,
 }
 
+impl<const ARITY: usize, C, SC> StepCircuitContext<ARITY, C, SC>
+where
+    C: CurveAffine,
+    C::Base: PrimeFieldBits + FromUniformBytes<64>,
+{
+    fn is_sat_pub_instances(&self) -> Result<(), VerifyError> {
+        match &self.pub_instances {
+            PubInstances::All(all) => {
+                VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_pub_instances(
+                    &self.relaxed_trace,
+                    all,
+                )
+            }
+            PubInstances::Last {
+                hash_accumulator,
+                instances,
+            } => VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_last_pub_instances(
+                &self.relaxed_trace,
+                hash_accumulator,
+                instances.as_deref(),
+            ),
+        }
+    }
+
+    /// Keep only the last instances from now on
+    fn with_constant_size_pub_instances(mut self) -> Self {
+        self.pub_instances = PubInstances::Last {
+            hash_accumulator: self
+                .relaxed_trace
+                .U
+                .step_circuit_instances_hash_accumulator
+                .clone(),
+            instances: None,
+        };
+        self
+    }
+}
+
 // TODO #31 docs
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -141,6 +212,8 @@ where
     C2::ScalarExt: Serialize,
     SC1: StepCircuit<A1, C1::Scalar>,
     SC2: StepCircuit<A2, C2::Scalar>,
+    SC1::StepWitness: Default,
+    SC2::StepWitness: Default,
     C1::Base: PrimeFieldBits + FromUniformBytes<64>,
     C2::Base: PrimeFieldBits + FromUniformBytes<64>,
 {
@@ -214,7 +287,13 @@ where
         // For use as first version of `U` in primary circuit synthesize
         let secondary_pre_round_plonk_trace = pp.secondary_initial_plonk_trace();
 
-        let primary_z_output = primary.process_step(&primary_z_0, pp.primary.k_table_size())?;
+        // The zero step is formal, so it's synthesized on the default witness
+        let primary_step_witness = SC1::StepWitness::default();
+        let primary_z_output = primary.process_step(
+            &primary_z_0,
+            &primary_step_witness,
+            pp.primary.k_table_size(),
+        )?;
         debug!("primary z output calculated off-circuit");
 
         // Will be used as input & output `U` of zero-step of IVC
@@ -250,6 +329,7 @@ where
 
         let primary_sfc = StepFoldingCircuit::<'_, A1, C2, SC1, RP1::OnCircuit, T> {
             step_circuit: primary,
+            witness: &primary_step_witness,
             input: StepInputs::<'_, A1, C2, RP1::OnCircuit> {
                 step: C2::Base::ZERO,
                 step_pp: pp.primary.params(),
@@ -314,8 +394,12 @@ where
         primary_span.exit();
         let _secondary_span = info_span!("secondary").entered();
 
-        let secondary_z_output =
-            secondary.process_step(&secondary_z_0, pp.secondary.k_table_size())?;
+        let secondary_step_witness = SC2::StepWitness::default();
+        let secondary_z_output = secondary.process_step(
+            &secondary_z_0,
+            &secondary_step_witness,
+            pp.secondary.k_table_size(),
+        )?;
 
         // Will be used as input & output `U` of zero-step of IVC
         let secondary_consistency_marker = {
@@ -344,6 +428,7 @@ where
 
         let secondary_sfc = StepFoldingCircuit::<'_, A2, C1, SC2, RP2::OnCircuit, T> {
             step_circuit: secondary,
+            witness: &secondary_step_witness,
             input: StepInputs::<'_, A2, C1, RP2::OnCircuit> {
                 step: C1::Base::ZERO,
                 step_pp: pp.secondary.params(),
@@ -412,26 +497,55 @@ where
                 z_0: primary_z_0,
                 z_i: primary_z_output,
                 relaxed_trace: primary_relaxed_trace,
-                pub_instances: vec![],
+                pub_instances: PubInstances::All(vec![]),
                 _p: PhantomData,
             },
             secondary: StepCircuitContext {
                 z_0: secondary_z_0,
                 z_i: secondary_z_output,
                 relaxed_trace: secondary_relaxed_trace,
-                pub_instances: vec![],
+                pub_instances: PubInstances::All(vec![]),
                 _p: PhantomData,
             },
         })
     }
 
-    #[instrument(name = "ivc_fold_step", skip_all, fields(step = self.step))]
+    /// Both step circuits are synthesized on the default [`StepCircuit::StepWitness`]
+    ///
+    /// See [`IVC::fold_step_with_witness`] for step circuits with non-deterministic advice
     pub fn fold_step<const T: usize, RP1, RP2>(
         &mut self,
         pp: &PublicParams<'_, A1, A2, T, C1, C2, SC1, SC2, RP1, RP2>,
         primary: &SC1,
         secondary: &SC2,
     ) -> Result<(), Error>
+    where
+        RP1: ROPair<C1::Scalar, Config = MainGateConfig<T>>,
+        RP2: ROPair<C2::Scalar, Config = MainGateConfig<T>>,
+    {
+        self.fold_step_with_witness(
+            pp,
+            primary,
+            &SC1::StepWitness::default(),
+            secondary,
+            &SC2::StepWitness::default(),
+        )
+    }
+
+    /// Same as [`IVC::fold_step`], but step circuits are synthesized on `primary_witness` &
+    /// `secondary_witness`
+    ///
+    /// So step circuits themselves stay the same for all steps & the witness of each step can be
+    /// taken from any external source
+    #[instrument(name = "ivc_fold_step", skip_all, fields(step = self.step))]
+    pub fn fold_step_with_witness<const T: usize, RP1, RP2>(
+        &mut self,
+        pp: &PublicParams<'_, A1, A2, T, C1, C2, SC1, SC2, RP1, RP2>,
+        primary: &SC1,
+        primary_witness: &SC1::StepWitness,
+        secondary: &SC2,
+        secondary_witness: &SC2::StepWitness,
+    ) -> Result<(), Error>
     where
         RP1: ROPair<C1::Scalar, Config = MainGateConfig<T>>,
         RP2: ROPair<C2::Scalar, Config = MainGateConfig<T>>,
@@ -448,14 +562,23 @@ where
                 &self.secondary_trace,
             )?;
 
-        self.secondary
-            .pub_instances
-            .push(self.secondary_trace[0].u.instances.clone());
+        self.secondary.pub_instances.push(
+            &self
+                .secondary
+                .relaxed_trace
+                .U
+                .step_circuit_instances_hash_accumulator,
+            self.secondary_trace[0].u.instances.clone(),
+        );
 
         debug!("prepare primary td");
 
         // Prepare primary constraint system for folding
-        let primary_z_next = primary.process_step(&self.primary.z_i, pp.primary.k_table_size())?;
+        let primary_z_next = primary.process_step(
+            &self.primary.z_i,
+            primary_witness,
+            pp.primary.k_table_size(),
+        )?;
 
         let primary_consistency_marker = {
             let _s = info_span!("generate_instance").entered();
@@ -483,6 +606,7 @@ where
 
         let primary_sfc = StepFoldingCircuit::<'_, A1, C2, SC1, RP1::OnCircuit, T> {
             step_circuit: primary,
+            witness: primary_witness,
             input: StepInputs::<'_, A1, C2, RP1::OnCircuit> {
                 step: C2::Base::from_u128(self.step as u128),
                 step_pp: pp.primary.params(),
@@ -541,17 +665,25 @@ where
                 self.primary.relaxed_trace.clone(),
                 &primary_plonk_trace,
             )?;
-        self.primary
-            .pub_instances
-            .push(primary_plonk_trace[0].u.instances.clone());
+        self.primary.pub_instances.push(
+            &self
+                .primary
+                .relaxed_trace
+                .U
+                .step_circuit_instances_hash_accumulator,
+            primary_plonk_trace[0].u.instances.clone(),
+        );
 
         primary_span.exit();
         let _secondary_span = info_span!("secondary").entered();
 
         debug!("start fold step with folding 'primary' by 'secondary'");
 
-        let next_secondary_z_i =
-            secondary.process_step(&self.secondary.z_i, pp.secondary.k_table_size())?;
+        let next_secondary_z_i = secondary.process_step(
+            &self.secondary.z_i,
+            secondary_witness,
+            pp.secondary.k_table_size(),
+        )?;
 
         let secondary_consistency_marker = {
             let _s = info_span!("generate_instance");
@@ -579,6 +711,7 @@ where
 
         let secondary_sfc = StepFoldingCircuit::<'_, A2, C1, SC2, RP2::OnCircuit, T> {
             step_circuit: secondary,
+            witness: secondary_witness,
             input: StepInputs::<'_, A2, C1, RP2::OnCircuit> {
                 step: C1::Base::from_u128(self.step as u128),
                 step_pp: pp.secondary.params(),
@@ -634,11 +767,77 @@ where
         Ok(())
     }
 
+    /// Keep only the hash accumulator & instances of the last folded step, instead of instances
+    /// of each step, so verifier memory does not depend on the number of steps
+    ///
+    /// Instances folded before this call are no longer checked by [`IVC::verify`], use
+    /// [`IVC::verify_with_pub_instances`] to check the whole history
+    pub fn with_constant_size_pub_instances(self) -> Self {
+        Self {
+            primary: self.primary.with_constant_size_pub_instances(),
+            secondary: self.secondary.with_constant_size_pub_instances(),
+            ..self
+        }
+    }
+
+    pub fn primary_pub_instances(&self) -> &PubInstances<C1::Scalar> {
+        &self.primary.pub_instances
+    }
+
+    pub fn secondary_pub_instances(&self) -> &PubInstances<C2::Scalar> {
+        &self.secondary.pub_instances
+    }
+
     #[instrument(name = "ivc_verify", skip_all)]
     pub fn verify<const T: usize, RP1, RP2>(
         &mut self,
         pp: &PublicParams<'_, A1, A2, T, C1, C2, SC1, SC2, RP1, RP2>,
     ) -> Result<(), Error>
+    where
+        RP1: ROPair<C1::Scalar, Config = MainGateConfig<T>>,
+        RP2: ROPair<C2::Scalar, Config = MainGateConfig<T>>,
+    {
+        let primary_pub_instances = self.primary.is_sat_pub_instances();
+        let secondary_pub_instances = self.secondary.is_sat_pub_instances();
+
+        self.verify_with(pp, primary_pub_instances, secondary_pub_instances)
+    }
+
+    /// Same as [`IVC::verify`], but the hash accumulators of step circuit instances are checked
+    /// against instances of each folded step, streamed by the caller
+    ///
+    /// Instances kept by [`IVC`] are not used, so this works in any [`PubInstances`] mode
+    #[instrument(name = "ivc_verify_with_pub_instances", skip_all)]
+    pub fn verify_with_pub_instances<const T: usize, RP1, RP2>(
+        &mut self,
+        pp: &PublicParams<'_, A1, A2, T, C1, C2, SC1, SC2, RP1, RP2>,
+        primary_pub_instances: impl IntoIterator<Item = impl AsRef<[Vec<C1::Scalar>]>>,
+        secondary_pub_instances: impl IntoIterator<Item = impl AsRef<[Vec<C2::Scalar>]>>,
+    ) -> Result<(), Error>
+    where
+        RP1: ROPair<C1::Scalar, Config = MainGateConfig<T>>,
+        RP2: ROPair<C2::Scalar, Config = MainGateConfig<T>>,
+    {
+        let primary_pub_instances =
+            VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_pub_instances(
+                &self.primary.relaxed_trace,
+                primary_pub_instances,
+            );
+        let secondary_pub_instances =
+            VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_pub_instances(
+                &self.secondary.relaxed_trace,
+                secondary_pub_instances,
+            );
+
+        self.verify_with(pp, primary_pub_instances, secondary_pub_instances)
+    }
+
+    fn verify_with<const T: usize, RP1, RP2>(
+        &self,
+        pp: &PublicParams<'_, A1, A2, T, C1, C2, SC1, SC2, RP1, RP2>,
+        primary_pub_instances: Result<(), VerifyError>,
+        secondary_pub_instances: Result<(), VerifyError>,
+    ) -> Result<(), Error>
     where
         RP1: ROPair<C1::Scalar, Config = MainGateConfig<T>>,
         RP2: ROPair<C2::Scalar, Config = MainGateConfig<T>>,
@@ -699,12 +898,13 @@ where
             });
         });
 
-        if let Err(err) = VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat(
-            pp.primary.ck(),
-            pp.primary.S(),
-            &self.primary.relaxed_trace,
-            &self.primary.pub_instances,
-        ) {
+        if let Err(err) =
+            VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_without_pub_instances(
+                pp.primary.ck(),
+                pp.primary.S(),
+                &self.primary.relaxed_trace,
+            )
+        {
             errors.extend(err.into_iter().map(|err| VerificationError::NotSat {
                 err,
                 is_primary: true,
@@ -712,12 +912,21 @@ where
             }));
         }
 
-        if let Err(err) = VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat(
-            pp.secondary.ck(),
-            pp.secondary.S(),
-            &self.secondary.relaxed_trace,
-            &self.secondary.pub_instances,
-        ) {
+        if let Err(err) = primary_pub_instances {
+            errors.push(VerificationError::NotSat {
+                err,
+                is_primary: true,
+                is_relaxed: true,
+            });
+        }
+
+        if let Err(err) =
+            VanillaFS::<_, { CONSISTENCY_MARKERS_COUNT }>::is_sat_without_pub_instances(
+                pp.secondary.ck(),
+                pp.secondary.S(),
+                &self.secondary.relaxed_trace,
+            )
+        {
             errors.extend(err.into_iter().map(|err| VerificationError::NotSat {
                 err,
                 is_primary: false,
@@ -725,6 +934,14 @@ where
             }));
         }
 
+        if let Err(err) = secondary_pub_instances {
+            errors.push(VerificationError::NotSat {
+                err,
+                is_primary: false,
+                is_relaxed: true,
+            });
+        }
+
         if let Err(err) = pp.secondary.S().is_sat(
             pp.secondary.ck(),
             &mut RP1::OffCircuit::new(pp.primary.params().ro_constant().clone()),
@@ -746,6 +963,24 @@ where
     }
 }
 
+impl<const A1: usize, const A2: usize, C1, C2, SC1, SC2> IvcState for IVC<A1, A2, C1, C2, SC1, SC2>
+where
+    C1: CurveAffine<Base = <C2 as PrimeCurveAffine>::Scalar>,
+    C2: CurveAffine<Base = <C1 as PrimeCurveAffine>::Scalar>,
+    SC1: StepCircuit<A1, C1::Scalar>,
+    SC2: StepCircuit<A2, C2::Scalar>,
+    C1::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+    C2::Scalar: PrimeFieldBits + FromUniformBytes<64>,
+{
+    fn step(&self) -> usize {
+        self.step
+    }
+
+    fn accumulator_size(&self) -> usize {
+        self.primary.relaxed_trace.W.size() + self.secondary.relaxed_trace.W.size()
+    }
+}
+
 fn get_consistency_marker_input<C: CurveAffine>(ins: &FoldablePlonkInstance<C>) -> C::ScalarExt {
     GetConsistencyMarkers::<CONSISTENCY_MARKERS_COUNT, _>::get_consistency_markers(ins)[0]
 }
@@ -756,3 +991,186 @@ fn get_consistency_marker_output<C: CurveAffine>(ins: &FoldablePlonkInstance<C>)
     )
     .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{array, path::Path};
+
+    use halo2_proofs::plonk::{Column, Instance};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        commitment::CommitmentKey,
+        ivc::step_circuit::{trivial, AssignedCell, ConstraintSystem, Layouter},
+        sangria_prelude::bn256::{
+            new_default_pp, C1Affine, C1Scalar, C2Affine, C2Scalar, PublicParams,
+        },
+    };
+
+    const A1: usize = 2;
+    const A2: usize = 1;
+
+    const FOLD_STEP_COUNT: usize = 2;
+
+    const CIRCUIT_TABLE_SIZE: u32 = 17;
+    const COMMITMENT_KEY_SIZE: usize = 21;
+
+    const FOLDER: &str = ".cache/examples";
+
+    /// Exposes `z_i` as public input, so each step has the same non-empty instances
+    struct InstancesCircuit;
+
+    impl<const A: usize, F: PrimeField> StepCircuit<A, F> for InstancesCircuit {
+        type Config = [Column<Instance>; A];
+        type StepWitness = ();
+
+        fn instances(&self) -> Vec<Vec<F>> {
+            (0..A).map(|i| vec![F::from(i as u64)]).collect()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+            array::from_fn(|_| {
+                let column = cs.instance_column();
+                cs.enable_equality(column);
+                column
+            })
+        }
+
+        fn synthesize_step(
+            &self,
+            config: Self::Config,
+            layouter: &mut impl Layouter<F>,
+            z_i: &[AssignedCell<F, F>; A],
+            _witness: &Self::StepWitness,
+        ) -> Result<[AssignedCell<F, F>; A], SynthesisError> {
+            for (input, instance) in z_i.iter().zip(config) {
+                layouter.constrain_instance(input.cell(), instance, 0)?;
+            }
+
+            Ok(z_i.clone())
+        }
+    }
+
+    type TestIVC =
+        IVC<A1, A2, C1Affine, C2Affine, InstancesCircuit, trivial::Circuit<A2, C2Scalar>>;
+
+    fn commitment_keys() -> (CommitmentKey<C1Affine>, CommitmentKey<C2Affine>) {
+        unsafe {
+            (
+                CommitmentKey::load_or_setup_cache(Path::new(FOLDER), "bn256", COMMITMENT_KEY_SIZE)
+                    .unwrap(),
+                CommitmentKey::load_or_setup_cache(
+                    Path::new(FOLDER),
+                    "grumpkin",
+                    COMMITMENT_KEY_SIZE,
+                )
+                .unwrap(),
+            )
+        }
+    }
+
+    fn fold(
+        pp: &PublicParams<'_, A1, InstancesCircuit, A2, trivial::Circuit<A2, C2Scalar>>,
+        constant_size: bool,
+    ) -> TestIVC {
+        let sc2 = trivial::Circuit::<A2, C2Scalar>::default();
+
+        let mut ivc = IVC::new(
+            pp,
+            &InstancesCircuit,
+            array::from_fn(|i| C1Scalar::from(i as u64)),
+            &sc2,
+            array::from_fn(|i| C2Scalar::from(i as u64)),
+            false,
+        )
+        .unwrap();
+
+        if constant_size {
+            ivc = ivc.with_constant_size_pub_instances();
+        }
+
+        for _ in 0..FOLD_STEP_COUNT {
+            ivc.fold_step(pp, &InstancesCircuit, &sc2).unwrap();
+        }
+
+        ivc
+    }
+
+    #[traced_test]
+    #[test]
+    fn all_pub_instances() {
+        let (ck1, ck2) = commitment_keys();
+        let sc2 = trivial::Circuit::<A2, C2Scalar>::default();
+        let pp = new_default_pp::<A1, _, A2, _>(
+            CIRCUIT_TABLE_SIZE,
+            &ck1,
+            &InstancesCircuit,
+            CIRCUIT_TABLE_SIZE,
+            &ck2,
+            &sc2,
+        );
+
+        let mut ivc = fold(&pp, false);
+
+        let PubInstances::All(primary) = ivc.primary_pub_instances().clone() else {
+            panic!("all instances expected by default");
+        };
+        let PubInstances::All(secondary) = ivc.secondary_pub_instances().clone() else {
+            panic!("all instances expected by default");
+        };
+        assert_eq!(primary.len(), FOLD_STEP_COUNT);
+
+        ivc.verify(&pp).unwrap();
+        ivc.verify_with_pub_instances(&pp, &primary, &secondary)
+            .unwrap();
+    }
+
+    #[traced_test]
+    #[test]
+    fn last_pub_instances() {
+        let (ck1, ck2) = commitment_keys();
+        let sc2 = trivial::Circuit::<A2, C2Scalar>::default();
+        let pp = new_default_pp::<A1, _, A2, _>(
+            CIRCUIT_TABLE_SIZE,
+            &ck1,
+            &InstancesCircuit,
+            CIRCUIT_TABLE_SIZE,
+            &ck2,
+            &sc2,
+        );
+
+        let mut ivc = fold(&pp, true);
+
+        assert!(matches!(
+            ivc.primary_pub_instances(),
+            PubInstances::Last {
+                instances: Some(_),
+                ..
+            }
+        ));
+        ivc.verify(&pp).unwrap();
+
+        // Tamper with the step circuit part of the last instances, the first column is
+        // consistency markers and is not absorbed into the hash accumulator
+        match &mut ivc.primary.pub_instances {
+            PubInstances::Last {
+                instances: Some(instances),
+                ..
+            } => instances[1][0] += C1Scalar::ONE,
+            _ => unreachable!(),
+        }
+
+        match ivc.verify(&pp) {
+            Err(Error::VerifyFailed(errors)) => assert!(errors.iter().any(|err| matches!(
+                err,
+                VerificationError::NotSat {
+                    err: VerifyError::InstanceMismatch,
+                    is_primary: true,
+                    is_relaxed: true,
+                }
+            ))),
+            other => panic!("instance mismatch expected, got {other:?}"),
+        }
+    }
+}
@@ -51,19 +51,54 @@ pub fn absorb_in_sc_instances_accumulator<C: CurveAffine>(
 where
     C::Base: PrimeFieldBits + FromUniformBytes<64>,
 {
-    let num_bits = NonZeroUsize::new(<C::Base as PrimeField>::NUM_BITS as usize)
+    let instances = instances
+        .iter()
+        .map(|instance| {
+            instance
+                .iter()
+                .map(|i| C::scalar_to_base(i).unwrap())
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    let hash_in_base = absorb_in_native_sc_instances_accumulator::<C::Base>(
+        &C::scalar_to_base(instances_hash_accumulator).unwrap(),
+        &instances,
+    );
+
+    C::base_to_scalar(&hash_in_base).unwrap()
+}
+
+/// Initial value of [`absorb_in_native_sc_instances_accumulator`] hash chain
+#[instrument(skip_all)]
+pub fn get_initial_native_sc_instances_accumulator<F>() -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    absorb_in_native_sc_instances_accumulator(&F::ZERO, &[])
+}
+
+/// Off-circuit version of [`absorb_in_assign_sc_instances_accumulator`] for the case, when
+/// instances are in the field of the circuit, that accumulates them
+#[instrument(skip_all)]
+pub fn absorb_in_native_sc_instances_accumulator<F>(
+    instances_hash_accumulator: &F,
+    instances: &[Vec<F>],
+) -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    let num_bits = NonZeroUsize::new(<F as PrimeField>::NUM_BITS as usize)
         .expect("unattainably: num_bits can't be zero");
 
-    let hash_in_base: C::Base = PoseidonHash::<C::Base, T, RATE>::new(default_spec())
+    PoseidonHash::<F, T, RATE>::new(default_spec())
         .absorb_field_iter(
             iter::once(instances_hash_accumulator)
                 .chain(instances.iter().flat_map(|instance| instance.iter()))
-                .map(|i| C::scalar_to_base(i).unwrap()),
+                .copied(),
         )
         .inspect(|buf| debug!("off-circuit buf of instances: {buf:?}"))
-        .output::<C::Base>(num_bits);
-
-    C::base_to_scalar(&hash_in_base).unwrap()
+        .output::<F>(num_bits)
 }
 
 #[instrument(skip_all)]
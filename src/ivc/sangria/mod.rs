@@ -6,4 +6,6 @@ pub mod public_params;
 pub mod step_folding_circuit;
 
 pub use incrementally_verifiable_computation::IVC;
-pub use public_params::{CircuitPublicParamsInput, PublicParams};
+pub use public_params::{
+    CircuitPublicParamsInput, CircuitStatsInput, PublicParams, PublicParamsStats,
+};
@@ -29,7 +29,7 @@ use crate::{
     },
     plonk::PlonkStructure,
     poseidon::{random_oracle::ROTrait, ROPair},
-    table::CircuitRunner,
+    table::{CircuitRunner, CircuitStats},
     util::ScalarToBase,
 };
 
@@ -216,6 +216,31 @@ impl<'key, 'circuit, const A: usize, C: CurveAffine, RPArgs, SC: StepCircuit<A,
     }
 }
 
+/// Step circuit with params required to analyze its step folding circuit before any commitment
+/// key is created, see [`PublicParams::collect_stats`]
+pub struct CircuitStatsInput<'circuit, RPArgs, SC> {
+    step_circuit: &'circuit SC,
+    k_table_size: u32,
+    ro_constant: RPArgs,
+}
+
+impl<'circuit, RPArgs, SC> CircuitStatsInput<'circuit, RPArgs, SC> {
+    pub fn new(k_table_size: u32, ro_constant: RPArgs, step_circuit: &'circuit SC) -> Self {
+        Self {
+            step_circuit,
+            k_table_size,
+            ro_constant,
+        }
+    }
+}
+
+/// Size & folding cost of both step folding circuits, see [`PublicParams::collect_stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicParamsStats {
+    pub primary: CircuitStats,
+    pub secondary: CircuitStats,
+}
+
 impl<
         'key,
         const A1: usize,
@@ -237,6 +262,8 @@ where
 
     SC1: StepCircuit<A1, C1::Scalar>,
     SC2: StepCircuit<A2, C2::Scalar>,
+    SC1::StepWitness: Default,
+    SC2::StepWitness: Default,
 
     RP1: ROPair<C1::Scalar, Config = MainGateConfig<MAIN_GATE_T>>,
     RP2: ROPair<C2::Scalar, Config = MainGateConfig<MAIN_GATE_T>>,
@@ -262,8 +289,10 @@ where
             let primary_step_params =
                 StepParams::new(limb_width, limbs_count, primary.ro_constant.clone());
 
+            let primary_step_witness = SC1::StepWitness::default();
             let primary_sfc = StepFoldingCircuit::<'_, A1, C2, SC1, RP1::OnCircuit, MAIN_GATE_T> {
                 step_circuit: primary.step_circuit,
+                witness: &primary_step_witness,
                 input: StepInputs::without_witness::<
                     StepFoldingCircuit<'_, A2, C1, SC2, RP2::OnCircuit, MAIN_GATE_T>,
                 >(
@@ -295,6 +324,7 @@ where
                 &secondary_initial_step_params,
             );
 
+            let secondary_step_witness = SC2::StepWitness::default();
             let secondary_consistenty_markers: [C2::Scalar; 2] = [
                 C1::scalar_to_base(
                     &GetConsistencyMarkers::<CONSISTENCY_MARKERS_COUNT, _>::get_consistency_markers(
@@ -313,9 +343,11 @@ where
                     public_params_hash: &secondary_initial_step_input.public_params_hash,
                     step: 1,
                     z_0: &secondary_initial_step_input.z_0,
-                    z_i: &secondary
-                        .step_circuit
-                        .process_step(&secondary_initial_step_input.z_0, secondary.k_table_size)?,
+                    z_i: &secondary.step_circuit.process_step(
+                        &secondary_initial_step_input.z_0,
+                        &secondary_step_witness,
+                        secondary.k_table_size,
+                    )?,
                     relaxed: &secondary_initial_step_input.U.clone(),
                     limb_width,
                     limbs_count,
@@ -325,6 +357,7 @@ where
 
             let secondary_sfc = StepFoldingCircuit::<'_, A2, C1, SC2, RP2::OnCircuit, MAIN_GATE_T> {
                 step_circuit: secondary.step_circuit,
+                witness: &secondary_step_witness,
                 input: secondary_initial_step_input,
             };
 
@@ -386,6 +419,228 @@ where
         Ok(self_)
     }
 
+    /// Synthesize both step folding circuits without witness and report their size & folding
+    /// cost, see [`CircuitStats`]
+    ///
+    /// Does not need commitment keys, so it can be used to choose `k` and key sizes before
+    /// calling [`PublicParams::new`]
+    #[instrument(name = "pp_stats", skip_all)]
+    pub fn collect_stats(
+        primary: CircuitStatsInput<'_, RP1::Args, SC1>,
+        secondary: CircuitStatsInput<'_, RP2::Args, SC2>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<PublicParamsStats, Error> {
+        let primary_num_io = iter::once(CONSISTENCY_MARKERS_COUNT)
+            .chain(primary.step_circuit.instances().iter().map(Vec::len))
+            .collect::<Box<[_]>>();
+
+        let secondary_num_io = iter::once(CONSISTENCY_MARKERS_COUNT)
+            .chain(secondary.step_circuit.instances().iter().map(Vec::len))
+            .collect::<Box<[_]>>();
+
+        let primary_stats = {
+            let _primary_span = info_span!("primary").entered();
+            Self::primary_stats(
+                &primary,
+                primary.k_table_size,
+                &primary_num_io,
+                &secondary_num_io,
+                limb_width,
+                limbs_count,
+            )
+        }?;
+
+        let secondary_stats = {
+            let _secondary_span = info_span!("secondary").entered();
+            Self::secondary_stats(
+                &secondary,
+                secondary.k_table_size,
+                &secondary_num_io,
+                &primary_num_io,
+                limb_width,
+                limbs_count,
+            )
+        }?;
+
+        Ok(PublicParamsStats {
+            primary: primary_stats,
+            secondary: secondary_stats,
+        })
+    }
+
+    /// Find the minimal `k` for both step folding circuits and the matching commitment key sizes
+    ///
+    /// `k_table_size` of inputs is used as the lower bound of search, so passing `1` gives the
+    /// minimal possible `k`. The result is reported as [`PublicParamsStats`]: use
+    /// [`CircuitStats::k_table_size`] & [`CircuitStats::commitment_key_size`] of each circuit
+    /// to create commitment keys and [`CircuitPublicParamsInput`] for [`PublicParams::new`]
+    #[instrument(name = "pp_min_k", skip_all)]
+    pub fn find_min_k_table_sizes(
+        primary: CircuitStatsInput<'_, RP1::Args, SC1>,
+        secondary: CircuitStatsInput<'_, RP2::Args, SC2>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<PublicParamsStats, Error> {
+        let primary_num_io = iter::once(CONSISTENCY_MARKERS_COUNT)
+            .chain(primary.step_circuit.instances().iter().map(Vec::len))
+            .collect::<Box<[_]>>();
+
+        let secondary_num_io = iter::once(CONSISTENCY_MARKERS_COUNT)
+            .chain(secondary.step_circuit.instances().iter().map(Vec::len))
+            .collect::<Box<[_]>>();
+
+        let primary_stats = CircuitStats::find_min_k(primary.k_table_size, |k_table_size| {
+            Self::primary_stats(
+                &primary,
+                k_table_size,
+                &primary_num_io,
+                &secondary_num_io,
+                limb_width,
+                limbs_count,
+            )
+        })?;
+        info!("primary k table size is {}", primary_stats.k_table_size);
+
+        let secondary_stats = CircuitStats::find_min_k(secondary.k_table_size, |k_table_size| {
+            Self::secondary_stats(
+                &secondary,
+                k_table_size,
+                &secondary_num_io,
+                &primary_num_io,
+                limb_width,
+                limbs_count,
+            )
+        })?;
+        info!("secondary k table size is {}", secondary_stats.k_table_size);
+
+        Ok(PublicParamsStats {
+            primary: primary_stats,
+            secondary: secondary_stats,
+        })
+    }
+
+    /// Find the minimal `k` for both step folding circuits, create commitment keys of matching
+    /// sizes and build public params with them
+    ///
+    /// Same as [`crate::ivc::cyclefold::PublicParams::new_with_min_k`], but public params only
+    /// borrow commitment keys, so `setup_primary_ck` & `setup_secondary_ck` return references to
+    /// keys owned by the caller, e.g. in a [`std::cell::OnceCell`]. They receive the required
+    /// commitment key size, see [`CircuitStats::commitment_key_size`]
+    pub fn new_with_min_k(
+        primary: CircuitStatsInput<'_, RP1::Args, SC1>,
+        secondary: CircuitStatsInput<'_, RP2::Args, SC2>,
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+        setup_primary_ck: impl FnOnce(usize) -> &'key CommitmentKey<C1>,
+        setup_secondary_ck: impl FnOnce(usize) -> &'key CommitmentKey<C2>,
+    ) -> Result<Self, Error> {
+        let CircuitStatsInput {
+            step_circuit: primary_sc,
+            k_table_size: primary_lower_bound,
+            ro_constant: primary_ro_constant,
+        } = primary;
+        let CircuitStatsInput {
+            step_circuit: secondary_sc,
+            k_table_size: secondary_lower_bound,
+            ro_constant: secondary_ro_constant,
+        } = secondary;
+
+        let PublicParamsStats {
+            primary: primary_stats,
+            secondary: secondary_stats,
+        } = Self::find_min_k_table_sizes(
+            CircuitStatsInput::new(primary_lower_bound, primary_ro_constant.clone(), primary_sc),
+            CircuitStatsInput::new(
+                secondary_lower_bound,
+                secondary_ro_constant.clone(),
+                secondary_sc,
+            ),
+            limb_width,
+            limbs_count,
+        )?;
+
+        Self::new(
+            CircuitPublicParamsInput::new(
+                primary_stats.k_table_size,
+                setup_primary_ck(primary_stats.commitment_key_size),
+                primary_ro_constant,
+                primary_sc,
+            ),
+            CircuitPublicParamsInput::new(
+                secondary_stats.k_table_size,
+                setup_secondary_ck(secondary_stats.commitment_key_size),
+                secondary_ro_constant,
+                secondary_sc,
+            ),
+            limb_width,
+            limbs_count,
+        )
+    }
+
+    fn primary_stats(
+        primary: &CircuitStatsInput<'_, RP1::Args, SC1>,
+        k_table_size: u32,
+        primary_num_io: &[usize],
+        secondary_num_io: &[usize],
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<CircuitStats, Error> {
+        let primary_step_params =
+            StepParams::new(limb_width, limbs_count, primary.ro_constant.clone());
+
+        let primary_step_witness = SC1::StepWitness::default();
+        let primary_sfc = StepFoldingCircuit::<'_, A1, C2, SC1, RP1::OnCircuit, MAIN_GATE_T> {
+            step_circuit: primary.step_circuit,
+            witness: &primary_step_witness,
+            input: StepInputs::without_witness::<
+                StepFoldingCircuit<'_, A2, C1, SC2, RP2::OnCircuit, MAIN_GATE_T>,
+            >(
+                k_table_size,
+                primary_num_io,
+                secondary_num_io,
+                &primary_step_params,
+            ),
+        };
+        let primary_instances =
+            primary_sfc.instances([C1::Scalar::ZERO; CONSISTENCY_MARKERS_COUNT]);
+
+        Ok(CircuitRunner::new(k_table_size, primary_sfc, primary_instances).try_collect_stats()?)
+    }
+
+    fn secondary_stats(
+        secondary: &CircuitStatsInput<'_, RP2::Args, SC2>,
+        k_table_size: u32,
+        secondary_num_io: &[usize],
+        primary_num_io: &[usize],
+        limb_width: NonZeroUsize,
+        limbs_count: NonZeroUsize,
+    ) -> Result<CircuitStats, Error> {
+        let secondary_step_params =
+            StepParams::new(limb_width, limbs_count, secondary.ro_constant.clone());
+
+        let secondary_step_witness = SC2::StepWitness::default();
+        let secondary_sfc = StepFoldingCircuit::<'_, A2, C1, SC2, RP2::OnCircuit, MAIN_GATE_T> {
+            step_circuit: secondary.step_circuit,
+            witness: &secondary_step_witness,
+            input: StepInputs::without_witness::<
+                StepFoldingCircuit<'_, A1, C2, SC1, RP1::OnCircuit, MAIN_GATE_T>,
+            >(
+                k_table_size,
+                secondary_num_io,
+                primary_num_io,
+                &secondary_step_params,
+            ),
+        };
+        let secondary_instances =
+            secondary_sfc.instances([C2::Scalar::ZERO; CONSISTENCY_MARKERS_COUNT]);
+
+        Ok(
+            CircuitRunner::new(k_table_size, secondary_sfc, secondary_instances)
+                .try_collect_stats()?,
+        )
+    }
+
     pub fn secondary_initial_plonk_trace(
         &self,
     ) -> &FoldablePlonkTrace<C2, { CONSISTENCY_MARKERS_COUNT }> {
@@ -444,7 +699,7 @@ where
 
 #[cfg(test)]
 mod pp_test {
-    use std::{fs, path::Path};
+    use std::{cell::OnceCell, fs, path::Path};
 
     use bn256::G1 as C1;
     use grumpkin::G1 as C2;
@@ -532,4 +787,58 @@ mod pp_test {
         .digest::<C1Affine>()
         .unwrap();
     }
+
+    #[traced_test]
+    #[test]
+    fn new_with_min_k() {
+        type Scalar1 = <C1 as Group>::Scalar;
+        type Scalar2 = <C2 as Group>::Scalar;
+
+        type PP<'key> = PublicParams<
+            'key,
+            1,
+            1,
+            5,
+            C1Affine,
+            C2Affine,
+            step_circuit::trivial::Circuit<1, Scalar1>,
+            step_circuit::trivial::Circuit<1, Scalar2>,
+            RandomOracle<5, 4>,
+            RandomOracle<5, 4>,
+        >;
+
+        let spec1 = RandomOracleConstant::<5, 4, Scalar1>::new(10, 10);
+        let spec2 = RandomOracleConstant::<5, 4, Scalar2>::new(10, 10);
+
+        let sc1 = trivial::Circuit::default();
+        let sc2 = trivial::Circuit::default();
+
+        let stats = PP::find_min_k_table_sizes(
+            CircuitStatsInput::new(1, spec1.clone(), &sc1),
+            CircuitStatsInput::new(1, spec2.clone(), &sc2),
+            LIMB_WIDTH,
+            LIMBS_COUNT_LIMIT,
+        )
+        .unwrap();
+
+        let primary_ck = OnceCell::new();
+        let secondary_ck = OnceCell::new();
+
+        let pp = PP::new_with_min_k(
+            CircuitStatsInput::new(1, spec1, &sc1),
+            CircuitStatsInput::new(1, spec2, &sc2),
+            LIMB_WIDTH,
+            LIMBS_COUNT_LIMIT,
+            |k| primary_ck.get_or_init(|| get_or_create_commitment_key(k, "bn256").unwrap()),
+            |k| secondary_ck.get_or_init(|| get_or_create_commitment_key(k, "grumpkin").unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(pp.primary.k_table_size(), stats.primary.k_table_size);
+        assert_eq!(pp.secondary.k_table_size(), stats.secondary.k_table_size);
+        assert_eq!(
+            primary_ck.get().unwrap().len(),
+            1 << stats.primary.commitment_key_size
+        );
+    }
 }
@@ -26,7 +26,7 @@ use crate::{
         self,
         accumulator::{FoldablePlonkInstance, RelaxedPlonkInstance},
     },
-    plonk::PlonkInstance,
+    plonk::{permutation, PlonkInstance},
     poseidon::ROCircuitTrait,
     table::ConstraintSystemMetainfo,
 };
@@ -154,9 +154,10 @@ where
         let mut cs = ConstraintSystem::<C::Scalar>::default();
 
         PairedCircuit::configure(&mut cs);
+        let permutation = permutation::Arguments::configure_by_default(&mut cs);
 
         let constraint_system_metainfo =
-            ConstraintSystemMetainfo::build(k_table_size as usize, &cs);
+            ConstraintSystemMetainfo::build(k_table_size as usize, &cs, permutation.as_ref());
 
         let Some((consistency_markers, step_circuit_instances)) = native_num_io.split_first()
         else {
@@ -266,6 +267,8 @@ where
     RO: ROCircuitTrait<C::Base>,
 {
     pub step_circuit: &'link SC,
+    /// Non-deterministic advice of [`StepFoldingCircuit::step_circuit`] for this step
+    pub witness: &'link SC::StepWitness,
     pub input: StepInputs<'link, ARITY, C, RO>,
 }
 
@@ -309,6 +312,7 @@ where
 
         Self {
             step_circuit: self.step_circuit,
+            witness: self.witness,
             input: StepInputs {
                 step: C::Base::ZERO,
                 step_pp: self.input.step_pp,
@@ -636,7 +640,12 @@ where
 
         let z_output = self
             .step_circuit
-            .synthesize_step(config.step_config, &mut layouter, &assigned_input)
+            .synthesize_step(
+                config.step_config,
+                &mut layouter,
+                &assigned_input,
+                self.witness,
+            )
             .map_err(|err| {
                 error!("while synthesize_step: {err:?}");
                 Halo2PlonkError::Synthesis
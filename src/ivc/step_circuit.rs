@@ -1,5 +1,11 @@
 use tracing::*;
 
+pub mod combinators;
+pub use combinators::{Chain, Parallel, Repeat, RepeatWitness};
+
+pub mod committed;
+pub use committed::Committed;
+
 use super::sangria::fold_relaxed_plonk_instance_chip;
 pub use crate::halo2_proofs::{
     circuit::{AssignedCell, Layouter},
@@ -40,6 +46,11 @@ pub enum SynthesisError {
 /// methods are expected to take as input a vector of size equal to
 /// arity and output a vector of size equal to arity.
 ///
+/// # Step witness
+/// Non-deterministic input of a step is passed explicitly as [`StepCircuit::StepWitness`], so
+/// the step circuit itself can stay immutable between steps & the witness of each step can be
+/// taken from an external data source. Circuits without such input use `()`.
+///
 /// # References
 /// - For a detailed understanding of IVC and the context in which a trait
 ///   `StepCircuit` might be used, refer to the 'Section 5' of
@@ -55,6 +66,13 @@ pub trait StepCircuit<const ARITY: usize, F: PrimeField> {
     /// TODO improve
     type Config: Clone;
 
+    /// Non-deterministic advice of one step, passed to [`StepCircuit::synthesize_step`] &
+    /// [`StepCircuit::process_step`]
+    ///
+    /// IVC setup & the formal zero step synthesize the circuit on `StepWitness::default()`, so
+    /// it must give the same circuit shape as the witnesses of real steps
+    type StepWitness;
+
     /// Returns a vector of public input instances for each step.
     ///
     /// This method allows for the specification of public input instances at each step
@@ -76,6 +94,16 @@ pub trait StepCircuit<const ARITY: usize, F: PrimeField> {
     /// This setup is crucial for the functioning of the IVC-based system.
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config;
 
+    /// Native implementation of this step circuit, if any
+    ///
+    /// By default there is none. Step circuits implementing [`NativeStep`] can return `Some(self)`
+    /// here, then the default [`StepCircuit::process_step`] calls [`NativeStep::native_step`]
+    /// instead of synthesis. Consistency of both can be checked with
+    /// [`crate::util::mock_prover::check_native_step`]
+    fn native(&self) -> Option<&dyn NativeStep<ARITY, F, Self::StepWitness>> {
+        None
+    }
+
     /// Sythesize the circuit for a computation step and return variable
     /// that corresponds to the output of the step z_{i+1}
     /// this method will be called when we synthesize the IVC_Circuit
@@ -86,6 +114,7 @@ pub trait StepCircuit<const ARITY: usize, F: PrimeField> {
         config: Self::Config,
         layouter: &mut impl Layouter<F>,
         z_i: &[AssignedCell<F, F>; ARITY],
+        witness: &Self::StepWitness,
     ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError>;
 
     /// Off-circuit version of [`StepCircuit::synthesize_step`]
@@ -97,23 +126,29 @@ pub trait StepCircuit<const ARITY: usize, F: PrimeField> {
     /// The default implementation includes calling step synthesis on `TableData` where table size is
     /// equal to that specified in the IVC fold call. However, if these calculations are long and resource
     /// intensive, it is possible to implement this logic off-circuit "honestly" with regular code, which may
-    /// be more lightweight, but will require consistency testing.
+    /// be more lightweight, but will require consistency testing. See [`StepCircuit::native`] for
+    /// this case.
     #[instrument(skip_all)]
     fn process_step(
         &self,
         z_i: &[F; ARITY],
+        witness: &Self::StepWitness,
         k_table_size: u32,
     ) -> Result<[F; ARITY], SynthesisError> {
+        if let Some(native) = self.native() {
+            return Ok(native.native_step(z_i, witness));
+        }
+
         let mut cs = ConstraintSystem::default();
         let col = cs.advice_column();
         let config = Self::configure(&mut cs);
 
-        let mut witness = WitnessCollector {
+        let mut collector = WitnessCollector {
             instances: vec![vec![F::ZERO, F::ZERO]],
             advice: vec![vec![F::ZERO.into(); 1 << k_table_size as usize]; cs.num_advice_columns()],
         };
         let mut layouter =
-            SingleChipLayouter::<'_, F, _>::new(&mut witness, vec![]).map_err(|err| {
+            SingleChipLayouter::<'_, F, _>::new(&mut collector, vec![]).map_err(|err| {
                 error!("while creation of layouter in `process_step`: {err:?}");
                 SynthesisError::Halo2(err)
             })?;
@@ -141,11 +176,25 @@ pub trait StepCircuit<const ARITY: usize, F: PrimeField> {
                 SynthesisError::Halo2(err)
             })?;
 
-        self.synthesize_step(config, &mut layouter, &assigned_z_i.try_into().unwrap())
-            .map(|z_out| z_out.map(|cell| cell.value().unwrap().copied().unwrap()))
+        self.synthesize_step(
+            config,
+            &mut layouter,
+            &assigned_z_i.try_into().unwrap(),
+            witness,
+        )
+        .map(|z_out| z_out.map(|cell| cell.value().unwrap().copied().unwrap()))
     }
 }
 
+/// Pure off-circuit version of [`StepCircuit::synthesize_step`]
+///
+/// Must calculate the same `z_out` as the synthesized circuit, see [`StepCircuit::native`]
+///
+/// `StepWitness` is [`StepCircuit::StepWitness`] of the same circuit
+pub trait NativeStep<const ARITY: usize, F: PrimeField, StepWitness> {
+    fn native_step(&self, z_i: &[F; ARITY], witness: &StepWitness) -> [F; ARITY];
+}
+
 pub mod trivial {
     use std::marker::PhantomData;
 
@@ -154,7 +203,7 @@ pub mod trivial {
         plonk::ConstraintSystem,
     };
 
-    use super::{StepCircuit, SynthesisError};
+    use super::{NativeStep, StepCircuit, SynthesisError};
     use crate::ff::PrimeField;
 
     /// A trivial step circuit that simply returns the input
@@ -171,6 +220,7 @@ pub mod trivial {
         ///
         /// TODO improve
         type Config = ();
+        type StepWitness = ();
 
         /// Configure the step circuit. This method initializes necessary
         /// fixed columns and advice columns, but does not create any instance
@@ -179,6 +229,10 @@ pub mod trivial {
         /// This setup is crucial for the functioning of the IVC-based system.
         fn configure(_cs: &mut ConstraintSystem<F>) -> Self::Config {}
 
+        fn native(&self) -> Option<&dyn NativeStep<ARITY, F, ()>> {
+            Some(self)
+        }
+
         /// Sythesize the circuit for a computation step and return variable
         /// that corresponds to the output of the step z_{i+1}
         /// this method will be called when we synthesize the IVC_Circuit
@@ -189,18 +243,26 @@ pub mod trivial {
             _config: Self::Config,
             _layouter: &mut impl Layouter<F>,
             z_i: &[AssignedCell<F, F>; ARITY],
+            _witness: &Self::StepWitness,
         ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
             Ok(z_i.clone())
         }
     }
 
+    impl<const ARITY: usize, F: PrimeField> NativeStep<ARITY, F, ()> for Circuit<ARITY, F> {
+        fn native_step(&self, z_i: &[F; ARITY], _witness: &()) -> [F; ARITY] {
+            *z_i
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use std::array;
 
         use halo2_proofs::halo2curves::pasta::Fq;
+        use rand_core::OsRng;
 
-        use crate::util::mock_prover::MockProver;
+        use crate::util::mock_prover::{check_native_step, MockProver};
 
         #[test]
         fn simple() {
@@ -210,5 +272,212 @@ pub mod trivial {
                 .verify(z_in)
                 .unwrap();
         }
+
+        #[test]
+        fn native() {
+            check_native_step(
+                10,
+                &super::Circuit::<10, Fq>::default(),
+                &(),
+                vec![],
+                4,
+                OsRng,
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Step circuit with a lookup, so its trace has more than one W-commitment
+#[cfg(test)]
+pub(crate) mod lookup {
+    use std::marker::PhantomData;
+
+    use halo2_proofs::{
+        circuit::{AssignedCell, Layouter},
+        plonk::ConstraintSystem,
+    };
+
+    use super::{NativeStep, StepCircuit, SynthesisError};
+    use crate::{
+        ff::PrimeFieldBits,
+        gadgets::range::{RangeChip, RangeConfig},
+        main_gate::{MainGate, RegionCtx},
+    };
+
+    const T: usize = 2;
+    const LIMB_BITS: usize = 8;
+    const RANGE_BITS: usize = 16;
+
+    /// `z_out[0] = z_i[0] + 1`, where `z_i[0] < 2^16` is checked by lookups, the rest of `z_i`
+    /// is returned as is
+    #[derive(Clone, Debug, Default)]
+    pub struct Circuit<const ARITY: usize, F: PrimeFieldBits> {
+        _p: PhantomData<F>,
+    }
+
+    impl<const ARITY: usize, F: PrimeFieldBits> StepCircuit<ARITY, F> for Circuit<ARITY, F> {
+        type Config = RangeConfig<T>;
+        type StepWitness = ();
+
+        fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+            let main_gate = MainGate::<F, T>::configure(cs);
+            RangeChip::configure(cs, main_gate, LIMB_BITS)
+        }
+
+        fn native(&self) -> Option<&dyn NativeStep<ARITY, F, ()>> {
+            Some(self)
+        }
+
+        fn synthesize_step(
+            &self,
+            config: Self::Config,
+            layouter: &mut impl Layouter<F>,
+            z_i: &[AssignedCell<F, F>; ARITY],
+            _witness: &Self::StepWitness,
+        ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
+            let chip = RangeChip::<F, T>::new(config);
+            chip.load(layouter)?;
+
+            let z_out_0 = layouter.assign_region(
+                || "range checked increment",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    chip.range_check(ctx, &z_i[0], RANGE_BITS)?;
+                    chip.main_gate().add_with_const(ctx, &z_i[0], F::ONE)
+                },
+            )?;
+
+            let mut z_out = z_i.clone();
+            z_out[0] = z_out_0;
+            Ok(z_out)
+        }
+    }
+
+    impl<const ARITY: usize, F: PrimeFieldBits> NativeStep<ARITY, F, ()> for Circuit<ARITY, F> {
+        fn native_step(&self, z_i: &[F; ARITY], _witness: &()) -> [F; ARITY] {
+            let mut z_out = *z_i;
+            z_out[0] += F::ONE;
+            z_out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, halo2curves::bn256::Fr};
+    use rand_core::OsRng;
+
+    use super::*;
+    use crate::{
+        ff::Field,
+        main_gate::{MainGate, MainGateConfig},
+        util::mock_prover::{check_native_step, MockProver, NativeStepCheckFailure},
+    };
+
+    const K: u32 = 10;
+
+    /// `z_out = z_i + w`, where `w` is the witness of the step
+    struct AddWitness;
+
+    impl StepCircuit<1, Fr> for AddWitness {
+        type Config = MainGateConfig<5>;
+        type StepWitness = Fr;
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MainGate::configure(cs)
+        }
+
+        fn synthesize_step(
+            &self,
+            config: Self::Config,
+            layouter: &mut impl Layouter<Fr>,
+            z_i: &[AssignedCell<Fr, Fr>; 1],
+            witness: &Self::StepWitness,
+        ) -> Result<[AssignedCell<Fr, Fr>; 1], SynthesisError> {
+            let main_gate = MainGate::new(config);
+
+            let z_out = layouter.assign_region(
+                || "add_witness",
+                |region| {
+                    let mut ctx = RegionCtx::new(region, 0);
+                    let witness = main_gate.assign_value(&mut ctx, Value::known(*witness))?;
+                    main_gate.add(&mut ctx, &z_i[0], &witness)
+                },
+            )?;
+
+            Ok([z_out])
+        }
+    }
+
+    impl NativeStep<1, Fr, Fr> for AddWitness {
+        fn native_step(&self, z_i: &[Fr; 1], witness: &Fr) -> [Fr; 1] {
+            [z_i[0] + witness]
+        }
+    }
+
+    /// Same circuit as [`AddWitness`], but its native step forgets about the witness
+    struct IgnoreWitness;
+
+    impl StepCircuit<1, Fr> for IgnoreWitness {
+        type Config = MainGateConfig<5>;
+        type StepWitness = Fr;
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            AddWitness::configure(cs)
+        }
+
+        fn synthesize_step(
+            &self,
+            config: Self::Config,
+            layouter: &mut impl Layouter<Fr>,
+            z_i: &[AssignedCell<Fr, Fr>; 1],
+            witness: &Self::StepWitness,
+        ) -> Result<[AssignedCell<Fr, Fr>; 1], SynthesisError> {
+            AddWitness.synthesize_step(config, layouter, z_i, witness)
+        }
+    }
+
+    impl NativeStep<1, Fr, Fr> for IgnoreWitness {
+        fn native_step(&self, z_i: &[Fr; 1], _witness: &Fr) -> [Fr; 1] {
+            *z_i
+        }
+    }
+
+    #[test]
+    fn native_step_witness() {
+        for witness in [Fr::ZERO, Fr::from(100)] {
+            check_native_step(K, &AddWitness, &witness, vec![], 2, OsRng).unwrap();
+        }
+
+        // Without witness both match
+        check_native_step(K, &IgnoreWitness, &Fr::ZERO, vec![], 2, OsRng).unwrap();
+
+        assert!(matches!(
+            check_native_step(K, &IgnoreWitness, &Fr::ONE, vec![], 2, OsRng),
+            Err(NativeStepCheckFailure::Verify { .. })
+        ));
+    }
+
+    #[test]
+    fn step_witness() {
+        let z_in = [Fr::from(7)];
+
+        for witness in [Fr::ZERO, Fr::ONE, Fr::from(100)] {
+            let z_out = AddWitness.process_step(&z_in, &witness, K).unwrap();
+            assert_eq!(z_out, [z_in[0] + witness]);
+
+            MockProver::run_with_witness(K, &AddWitness, &witness, vec![], z_in)
+                .unwrap()
+                .verify(z_out)
+                .unwrap();
+        }
+
+        assert!(
+            MockProver::run_with_witness(K, &AddWitness, &Fr::ONE, vec![], z_in)
+                .unwrap()
+                .verify(z_in)
+                .is_err()
+        );
     }
 }
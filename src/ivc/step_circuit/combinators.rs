@@ -0,0 +1,349 @@
+//! Combinators for composing step circuits without boilerplate
+//!
+//! - [`Repeat`] applies the same step circuit `N` times: `z_out = F(F(...F(z_in)))`
+//! - [`Chain`] feeds the output of the first step circuit into the second one
+//! - [`Parallel`] runs two step circuits side by side on the halves of `z_i`, its arity is the
+//!   sum of their arities
+//!
+//! [`StepCircuit::process_step`] of combinators is implemented through the same method of inner
+//! circuits, so off-circuit implementations of them are reused.
+//!
+//! Instance columns of inner circuits are created in the order of [`StepCircuit::configure`]
+//! calls, so [`StepCircuit::instances`] concatenates instances of the first & the second circuit.
+//!
+//! [`StepCircuit::StepWitness`] of combinators consists of witnesses of inner circuits: one per
+//! application for [`Repeat`] & a pair for [`Chain`] & [`Parallel`].
+
+use std::array;
+
+use tracing::*;
+
+use super::{AssignedCell, ConstraintSystem, Layouter, StepCircuit, SynthesisError};
+use crate::ff::PrimeField;
+
+/// Applies `SC` `N` times, the config is shared between all applications
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Repeat<SC, const N: usize> {
+    inner: SC,
+}
+
+impl<SC, const N: usize> Repeat<SC, N> {
+    pub fn new(inner: SC) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &SC {
+        &self.inner
+    }
+}
+
+impl<const ARITY: usize, F, SC, const N: usize> StepCircuit<ARITY, F> for Repeat<SC, N>
+where
+    F: PrimeField,
+    SC: StepCircuit<ARITY, F>,
+{
+    type Config = SC::Config;
+    type StepWitness = RepeatWitness<SC::StepWitness, N>;
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.inner.instances()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        SC::configure(cs)
+    }
+
+    fn synthesize_step(
+        &self,
+        config: Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; ARITY],
+        witness: &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
+        witness
+            .0
+            .iter()
+            .enumerate()
+            .try_fold(z_i.clone(), |z, (step, witness)| {
+                self.inner
+                    .synthesize_step(config.clone(), layouter, &z, witness)
+                    .inspect_err(|err| error!("while repeat {step}: {err:?}"))
+            })
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; ARITY],
+        witness: &Self::StepWitness,
+        k_table_size: u32,
+    ) -> Result<[F; ARITY], SynthesisError> {
+        witness.0.iter().try_fold(*z_i, |z, witness| {
+            self.inner.process_step(&z, witness, k_table_size)
+        })
+    }
+}
+
+/// [`StepCircuit::StepWitness`] of [`Repeat`], witness of `SC` for each of `N` applications
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepeatWitness<W, const N: usize>(pub [W; N]);
+
+impl<W: Default, const N: usize> Default for RepeatWitness<W, N> {
+    fn default() -> Self {
+        Self(array::from_fn(|_| W::default()))
+    }
+}
+
+/// Applies `SC1` & then `SC2` to its output
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain<SC1, SC2> {
+    first: SC1,
+    second: SC2,
+}
+
+impl<SC1, SC2> Chain<SC1, SC2> {
+    pub fn new(first: SC1, second: SC2) -> Self {
+        Self { first, second }
+    }
+
+    pub fn first(&self) -> &SC1 {
+        &self.first
+    }
+
+    pub fn second(&self) -> &SC2 {
+        &self.second
+    }
+}
+
+impl<const ARITY: usize, F, SC1, SC2> StepCircuit<ARITY, F> for Chain<SC1, SC2>
+where
+    F: PrimeField,
+    SC1: StepCircuit<ARITY, F>,
+    SC2: StepCircuit<ARITY, F>,
+{
+    type Config = (SC1::Config, SC2::Config);
+    type StepWitness = (SC1::StepWitness, SC2::StepWitness);
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.first
+            .instances()
+            .into_iter()
+            .chain(self.second.instances())
+            .collect()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        (SC1::configure(cs), SC2::configure(cs))
+    }
+
+    fn synthesize_step(
+        &self,
+        (first_config, second_config): Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; ARITY],
+        (first_witness, second_witness): &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
+        let z_mid = self
+            .first
+            .synthesize_step(first_config, layouter, z_i, first_witness)?;
+        self.second
+            .synthesize_step(second_config, layouter, &z_mid, second_witness)
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; ARITY],
+        (first_witness, second_witness): &Self::StepWitness,
+        k_table_size: u32,
+    ) -> Result<[F; ARITY], SynthesisError> {
+        let z_mid = self.first.process_step(z_i, first_witness, k_table_size)?;
+        self.second
+            .process_step(&z_mid, second_witness, k_table_size)
+    }
+}
+
+/// Applies `SC1` to the first `A1` elements of `z_i` & `SC2` to the last `A2` ones
+///
+/// Implements [`StepCircuit`] for `ARITY = A1 + A2` only, other arities panic at
+/// [`StepCircuit::configure`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parallel<SC1, SC2, const A1: usize, const A2: usize> {
+    first: SC1,
+    second: SC2,
+}
+
+impl<SC1, SC2, const A1: usize, const A2: usize> Parallel<SC1, SC2, A1, A2> {
+    pub fn new(first: SC1, second: SC2) -> Self {
+        Self { first, second }
+    }
+
+    pub fn first(&self) -> &SC1 {
+        &self.first
+    }
+
+    pub fn second(&self) -> &SC2 {
+        &self.second
+    }
+
+    /// Splits `z` into parts of `A1` & `A2` elements
+    fn split<T: Clone, const ARITY: usize>(z: &[T; ARITY]) -> ([T; A1], [T; A2]) {
+        assert_eq!(ARITY, A1 + A2, "arity of `Parallel` must be `A1 + A2`");
+        (
+            array::from_fn(|i| z[i].clone()),
+            array::from_fn(|i| z[A1 + i].clone()),
+        )
+    }
+
+    fn join<T: Clone, const ARITY: usize>(first: [T; A1], second: [T; A2]) -> [T; ARITY] {
+        let joined = first.into_iter().chain(second).collect::<Vec<_>>();
+        array::from_fn(|i| joined[i].clone())
+    }
+}
+
+impl<const ARITY: usize, F, SC1, SC2, const A1: usize, const A2: usize> StepCircuit<ARITY, F>
+    for Parallel<SC1, SC2, A1, A2>
+where
+    F: PrimeField,
+    SC1: StepCircuit<A1, F>,
+    SC2: StepCircuit<A2, F>,
+{
+    type Config = (SC1::Config, SC2::Config);
+    type StepWitness = (SC1::StepWitness, SC2::StepWitness);
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.first
+            .instances()
+            .into_iter()
+            .chain(self.second.instances())
+            .collect()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        assert_eq!(ARITY, A1 + A2, "arity of `Parallel` must be `A1 + A2`");
+        (SC1::configure(cs), SC2::configure(cs))
+    }
+
+    fn synthesize_step(
+        &self,
+        (first_config, second_config): Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; ARITY],
+        (first_witness, second_witness): &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; ARITY], SynthesisError> {
+        let (first_z_i, second_z_i) = Self::split(z_i);
+
+        Ok(Self::join(
+            self.first
+                .synthesize_step(first_config, layouter, &first_z_i, first_witness)?,
+            self.second
+                .synthesize_step(second_config, layouter, &second_z_i, second_witness)?,
+        ))
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; ARITY],
+        (first_witness, second_witness): &Self::StepWitness,
+        k_table_size: u32,
+    ) -> Result<[F; ARITY], SynthesisError> {
+        let (first_z_i, second_z_i) = Self::split(z_i);
+
+        Ok(Self::join(
+            self.first
+                .process_step(&first_z_i, first_witness, k_table_size)?,
+            self.second
+                .process_step(&second_z_i, second_witness, k_table_size)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::{
+        gadgets::poseidon_step_circuit::TestPoseidonCircuit, ivc::step_circuit::trivial,
+        util::mock_prover::MockProver,
+    };
+
+    const K: u32 = 12;
+
+    /// Off-circuit output of combinator is built from inner circuits, so the match of on-circuit
+    /// output checks the composition in `synthesize_step`
+    fn check<const ARITY: usize, SC>(step_circuit: &SC, z_in: [Fr; ARITY])
+    where
+        SC: StepCircuit<ARITY, Fr>,
+        SC::StepWitness: Default,
+    {
+        let expected = step_circuit
+            .process_step(&z_in, &Default::default(), K)
+            .unwrap();
+
+        MockProver::run(K, step_circuit, vec![], z_in)
+            .unwrap()
+            .verify(expected)
+            .unwrap();
+    }
+
+    fn hash(z: Fr) -> Fr {
+        TestPoseidonCircuit::<Fr>::default()
+            .process_step(&[z], &(), K)
+            .unwrap()[0]
+    }
+
+    #[test]
+    fn repeat() {
+        let circuit = Repeat::<_, 3>::new(TestPoseidonCircuit::<Fr>::default());
+
+        let z_in = [Fr::from(7)];
+        assert_eq!(
+            circuit.process_step(&z_in, &Default::default(), K).unwrap(),
+            [hash(hash(hash(z_in[0])))]
+        );
+
+        check(&circuit, z_in);
+    }
+
+    #[test]
+    fn chain() {
+        let circuit = Chain::new(
+            TestPoseidonCircuit::<Fr>::default(),
+            Repeat::<_, 2>::new(TestPoseidonCircuit::<Fr>::default()),
+        );
+
+        let z_in = [Fr::from(7)];
+        assert_eq!(
+            circuit.process_step(&z_in, &Default::default(), K).unwrap(),
+            [hash(hash(hash(z_in[0])))]
+        );
+
+        check(&circuit, z_in);
+    }
+
+    #[test]
+    fn parallel() {
+        let circuit = Parallel::<_, _, 2, 1>::new(
+            trivial::Circuit::<2, Fr>::default(),
+            TestPoseidonCircuit::<Fr>::default(),
+        );
+
+        let z_in = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        assert_eq!(
+            circuit.process_step(&z_in, &Default::default(), K).unwrap(),
+            [z_in[0], z_in[1], hash(z_in[2])]
+        );
+
+        check(&circuit, z_in);
+    }
+
+    #[test]
+    #[should_panic]
+    fn parallel_wrong_arity() {
+        let circuit = Parallel::<_, _, 2, 1>::new(
+            trivial::Circuit::<2, Fr>::default(),
+            TestPoseidonCircuit::<Fr>::default(),
+        );
+
+        let _ = StepCircuit::<4, Fr>::process_step(&circuit, &[Fr::from(1); 4], &((), ()), K);
+    }
+}
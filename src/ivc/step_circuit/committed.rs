@@ -0,0 +1,375 @@
+//! Committed-state mode for step circuits with a large state
+//!
+//! [`Committed`] wraps any [`StepCircuit<STATE_LEN, F>`] into [`StepCircuit<1, F>`]: instead of
+//! the whole state, only its Poseidon commitment ([`commit`]) flows between folding steps as
+//! `z_i`, so the consistency marker of IVC absorbs one element per step regardless of the state
+//! size.
+//!
+//! At each step the opening (the state itself) is assigned as a private witness, its commitment
+//! is constrained to be equal to `z_i[0]`, the wrapped circuit is synthesized on it & the
+//! commitment to its output becomes `z_out[0]`.
+//!
+//! Openings are kept by [`Committed`] itself: the initial one is passed to [`Committed::new`] &
+//! each output of the wrapped circuit is remembered, so the IVC only passes commitments around.
+//! Besides the initial one, only the last [`OPENINGS_LIMIT`] openings are kept, so memory does
+//! not grow with the number of folded steps.
+//! Use [`Committed::z_0`] as the initial input of IVC & [`Committed::opening`] to get the state
+//! behind the output of IVC.
+
+use std::{cell::RefCell, collections::VecDeque};
+
+use tracing::*;
+
+use super::{AssignedCell, ConstraintSystem, Layouter, StepCircuit, SynthesisError};
+use crate::{
+    ff::{FromUniformBytes, PrimeFieldBits},
+    halo2_proofs::plonk::Error as Halo2PlonkError,
+    main_gate::{AdviceCyclicAssignor, MainGate, MainGateConfig, RegionCtx, WrapValue},
+    poseidon::{
+        sponge::{self, IOPattern},
+        PoseidonSponge, PoseidonSpongeChip, Spec,
+    },
+    sangria_prelude::{
+        DEFAULT_RANDOM_ORACLE_RATE as RATE, DEFAULT_RANDOM_ORACLE_SIZE as T,
+        POSEIDON_DEFAULT_R_F as R_F, POSEIDON_DEFAULT_R_P as R_P,
+    },
+};
+
+/// How many of the last remembered openings [`Committed`] keeps besides the initial one
+///
+/// A folding step needs the opening of its input (the output of the previous step) & of its own
+/// output, the rest is a margin for repeated synthesis of the same step
+pub const OPENINGS_LIMIT: usize = 4;
+
+/// Domain separator of the state commitment, see [`IOPattern::tag`]
+const DOMAIN: &[u8] = b"sirius/committed-state";
+
+fn default_spec<F: FromUniformBytes<64>>() -> Spec<F, T, RATE> {
+    Spec::new(R_F, R_P)
+}
+
+/// Off-circuit commitment to the state
+pub fn commit<F, const STATE_LEN: usize>(state: &[F; STATE_LEN]) -> F
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    let mut sponge =
+        PoseidonSponge::<F, T, RATE>::new(default_spec(), &IOPattern::digest(STATE_LEN), DOMAIN);
+
+    let commitment = sponge
+        .absorb(state)
+        .and_then(|sponge| sponge.squeeze(1))
+        .expect("unattainably: calls follow the io pattern")[0];
+
+    sponge
+        .finish()
+        .expect("unattainably: calls follow the io pattern");
+
+    commitment
+}
+
+/// 'SCC' here is 'Step Circuit Config'
+#[derive(Debug, Clone)]
+pub struct Config<SCC> {
+    pub sc: SCC,
+    pub mg: MainGateConfig<T>,
+}
+
+/// Wraps `SC` into the step circuit, whose `z_i` is a commitment to the state of `SC`, see
+/// [module-level](self) docs
+pub struct Committed<const STATE_LEN: usize, F, SC>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    inner: SC,
+    z_0: F,
+    initial_state: [F; STATE_LEN],
+    /// Last [`OPENINGS_LIMIT`] remembered openings with their commitments, oldest first
+    openings: RefCell<VecDeque<(F, [F; STATE_LEN])>>,
+}
+
+impl<const STATE_LEN: usize, F, SC> Committed<STATE_LEN, F, SC>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    pub fn new(inner: SC, initial_state: [F; STATE_LEN]) -> Self {
+        Self {
+            inner,
+            z_0: commit(&initial_state),
+            initial_state,
+            openings: RefCell::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &SC {
+        &self.inner
+    }
+
+    /// Initial input of IVC: the commitment to the initial state
+    pub fn z_0(&self) -> [F; 1] {
+        [self.z_0]
+    }
+
+    /// State behind the commitment, if it is the initial one or one of the last
+    /// [`OPENINGS_LIMIT`] calculated by this circuit
+    pub fn opening(&self, commitment: &F) -> Option<[F; STATE_LEN]> {
+        if commitment == &self.z_0 {
+            return Some(self.initial_state);
+        }
+
+        self.openings
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|(known, state)| (known == commitment).then_some(*state))
+    }
+
+    fn remember(&self, state: [F; STATE_LEN]) -> F {
+        let commitment = commit(&state);
+
+        let mut openings = self.openings.borrow_mut();
+        openings.retain(|(known, _)| known != &commitment);
+        if openings.len() == OPENINGS_LIMIT {
+            openings.pop_front();
+        }
+        openings.push_back((commitment, state));
+
+        commitment
+    }
+
+    /// Hash the assigned state with copy-constraints on its cells
+    fn assign_commitment(
+        ctx: &mut RegionCtx<'_, F>,
+        config: &MainGateConfig<T>,
+        state: &[AssignedCell<F, F>; STATE_LEN],
+    ) -> Result<AssignedCell<F, F>, SynthesisError> {
+        let pattern = IOPattern::digest(STATE_LEN);
+        let mut sponge = PoseidonSpongeChip::<F, T, RATE>::new(
+            ctx,
+            config.clone(),
+            default_spec(),
+            &pattern,
+            DOMAIN,
+        )
+        .map_err(Self::sponge_err)?;
+
+        let state = state
+            .iter()
+            .map(|cell| WrapValue::Assigned(cell.clone()))
+            .collect::<Vec<_>>();
+
+        let commitment = sponge
+            .absorb(ctx, &state)
+            .and_then(|sponge| sponge.squeeze(ctx, 1))
+            .map_err(Self::sponge_err)?
+            .remove(0);
+
+        sponge.finish().map_err(Self::sponge_err)?;
+
+        Ok(commitment)
+    }
+
+    fn sponge_err(err: sponge::Error) -> SynthesisError {
+        match err {
+            sponge::Error::Halo2(err) => SynthesisError::Halo2(err),
+            err => {
+                error!("while state commitment: {err:?}");
+                SynthesisError::Halo2(Halo2PlonkError::Synthesis)
+            }
+        }
+    }
+}
+
+impl<const STATE_LEN: usize, F, SC> StepCircuit<1, F> for Committed<STATE_LEN, F, SC>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+    SC: StepCircuit<STATE_LEN, F>,
+{
+    type Config = Config<SC::Config>;
+    type StepWitness = SC::StepWitness;
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.inner.instances()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        Config {
+            sc: SC::configure(cs),
+            mg: MainGate::configure(cs),
+        }
+    }
+
+    fn synthesize_step(
+        &self,
+        config: Self::Config,
+        layouter: &mut impl Layouter<F>,
+        z_i: &[AssignedCell<F, F>; 1],
+        witness: &Self::StepWitness,
+    ) -> Result<[AssignedCell<F, F>; 1], SynthesisError> {
+        let Config { sc, mg } = config;
+
+        // Without the opening (e.g. while collecting the plonk structure on dummy input) any
+        // state fits, the commitment check will not be satisfied anyway
+        let state = z_i[0]
+            .value()
+            .unwrap()
+            .and_then(|commitment| self.opening(commitment))
+            .unwrap_or_else(|| {
+                debug!("no opening for the input commitment, zero state is used");
+                [F::ZERO; STATE_LEN]
+            });
+
+        let state = layouter.assign_region(
+            || "committed_state_opening",
+            |region| {
+                let mut ctx = RegionCtx::new(region, 0);
+
+                let state: [_; STATE_LEN] = mg
+                    .advice_cycle_assigner()
+                    .assign_all_advice(&mut ctx, || "state", state.iter().copied())?
+                    .try_into()
+                    .unwrap();
+                ctx.next();
+
+                let commitment = Self::assign_commitment(&mut ctx, &mg, &state).map_err(|err| {
+                    error!("while input state commitment: {err:?}");
+                    Halo2PlonkError::Synthesis
+                })?;
+                ctx.constrain_equal(commitment.cell(), z_i[0].cell())?;
+
+                Ok(state)
+            },
+        )?;
+
+        let state_out = self.inner.synthesize_step(sc, layouter, &state, witness)?;
+
+        if let Some(state_out) = state_out
+            .iter()
+            .map(|cell| cell.value().unwrap().copied())
+            .collect::<Option<Vec<_>>>()
+        {
+            self.remember(state_out.try_into().unwrap());
+        }
+
+        let z_out = layouter.assign_region(
+            || "committed_state_output",
+            |region| {
+                Self::assign_commitment(&mut RegionCtx::new(region, 0), &mg, &state_out).map_err(
+                    |err| {
+                        error!("while output state commitment: {err:?}");
+                        Halo2PlonkError::Synthesis
+                    },
+                )
+            },
+        )?;
+
+        Ok([z_out])
+    }
+
+    fn process_step(
+        &self,
+        z_i: &[F; 1],
+        witness: &Self::StepWitness,
+        k_table_size: u32,
+    ) -> Result<[F; 1], SynthesisError> {
+        let state = self.opening(&z_i[0]).ok_or_else(|| {
+            error!("no opening for the input commitment {:?}", z_i[0]);
+            SynthesisError::Halo2(Halo2PlonkError::Synthesis)
+        })?;
+
+        let state_out = self.inner.process_step(&state, witness, k_table_size)?;
+
+        Ok([self.remember(state_out)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        ff::Field,
+        gadgets::poseidon_step_circuit::TestPoseidonCircuit,
+        ivc::step_circuit::{trivial, Parallel},
+        util::mock_prover::MockProver,
+    };
+
+    const K: u32 = 13;
+
+    type Inner = Parallel<trivial::Circuit<2, Fr>, TestPoseidonCircuit<Fr>, 2, 1>;
+
+    fn circuit() -> Committed<3, Fr, Inner> {
+        Committed::new(
+            Parallel::new(trivial::Circuit::default(), TestPoseidonCircuit::default()),
+            [Fr::from(1), Fr::from(2), Fr::from(3)],
+        )
+    }
+
+    #[traced_test]
+    #[test]
+    fn matches_off_circuit() {
+        let circuit = circuit();
+
+        let z_0 = circuit.z_0();
+        let state_0 = circuit.opening(&z_0[0]).unwrap();
+        assert_eq!(z_0, [commit(&state_0)]);
+
+        let z_1 = circuit.process_step(&z_0, &((), ()), K).unwrap();
+        let state_1 = circuit.opening(&z_1[0]).unwrap();
+        assert_eq!(
+            state_1,
+            circuit
+                .inner()
+                .process_step(&state_0, &((), ()), K)
+                .unwrap()
+        );
+
+        MockProver::run(K, &circuit, vec![], z_0)
+            .unwrap()
+            .verify(z_1)
+            .unwrap();
+
+        let z_2 = circuit.process_step(&z_1, &((), ()), K).unwrap();
+
+        MockProver::run(K, &circuit, vec![], z_1)
+            .unwrap()
+            .verify(z_2)
+            .unwrap();
+    }
+
+    #[traced_test]
+    #[test]
+    fn openings_bounded() {
+        let circuit = circuit();
+
+        let mut z = vec![circuit.z_0()];
+        for _ in 0..=OPENINGS_LIMIT {
+            let z_next = circuit
+                .process_step(z.last().unwrap(), &((), ()), K)
+                .unwrap();
+            z.push(z_next);
+        }
+
+        assert_eq!(circuit.openings.borrow().len(), OPENINGS_LIMIT);
+        assert!(circuit.opening(&z[0][0]).is_some());
+        assert!(circuit.opening(&z[1][0]).is_none());
+        assert!(z[z.len() - OPENINGS_LIMIT..]
+            .iter()
+            .all(|z_i| circuit.opening(&z_i[0]).is_some()));
+    }
+
+    #[traced_test]
+    #[test]
+    fn unknown_commitment() {
+        let circuit = circuit();
+        let z_in = [circuit.z_0()[0] + Fr::ONE];
+
+        assert!(circuit.process_step(&z_in, &((), ()), K).is_err());
+        assert!(MockProver::run(K, &circuit, vec![], z_in)
+            .unwrap()
+            .verify(z_in)
+            .is_err());
+    }
+}
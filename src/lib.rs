@@ -5,6 +5,7 @@
 
 pub mod commitment;
 pub mod constants;
+pub mod curve_cycle;
 pub mod digest;
 pub mod fft;
 pub mod gadgets;
@@ -43,20 +44,75 @@ pub mod cyclefold_prelude {
 
         pub type C1Scalar = <G1 as Group>::Scalar;
         pub type C2Scalar = <G2 as Group>::Scalar;
+
+        pub type Cycle = crate::curve_cycle::Bn256Grumpkin;
     }
 
-    pub use crate::ivc::cyclefold::{PublicParams, IVC};
+    use serde::Serialize;
+
+    pub use crate::{
+        commitment::CommitmentKey,
+        curve_cycle::{Bn256Grumpkin, CurveCycle, PallasVesta, Reversed, Secp256k1Secq256k1},
+        ivc::{
+            cyclefold::{PublicParams, PublicParamsError, IVC},
+            StepCircuit,
+        },
+    };
+    use crate::{
+        ff::{FromUniformBytes, PrimeFieldBits},
+        group::prime::PrimeCurveAffine,
+    };
+
+    /// [`PublicParams`] over the curves of `Cycle`
+    pub type CyclePublicParams<const ARITY: usize, Cycle, SC> =
+        PublicParams<ARITY, <Cycle as CurveCycle>::C1, <Cycle as CurveCycle>::C2, SC>;
+
+    /// [`IVC`] over the curves of `Cycle`
+    pub type CycleIVC<const ARITY: usize, Cycle, SC> =
+        IVC<ARITY, <Cycle as CurveCycle>::C1, <Cycle as CurveCycle>::C2, SC>;
+
+    /// This function creates public parameters for IVC over the curves of `Cycle`
+    ///
+    /// Same as [`PublicParams::new`], but curves are taken from `Cycle`. Limbs & random oracle
+    /// of cyclefold are fixed, see [`crate::ivc::cyclefold::DEFAULT_LIMB_WIDTH`]
+    pub fn new_default_pp<Cycle, const ARITY: usize, SC>(
+        sc: &SC,
+        primary_commitment_key: CommitmentKey<Cycle::C1>,
+        secondary_commitment_key: CommitmentKey<Cycle::C2>,
+        primary_k_table_size: u32,
+    ) -> Result<CyclePublicParams<ARITY, Cycle, SC>, PublicParamsError>
+    where
+        Cycle: CurveCycle,
+        <Cycle::C1 as PrimeCurveAffine>::Scalar: PrimeFieldBits + FromUniformBytes<64> + Serialize,
+        <Cycle::C2 as PrimeCurveAffine>::Scalar: PrimeFieldBits + FromUniformBytes<64> + Serialize,
+        SC: StepCircuit<ARITY, <Cycle::C1 as PrimeCurveAffine>::Scalar>,
+        SC::StepWitness: Default,
+    {
+        CyclePublicParams::<ARITY, Cycle, SC>::new(
+            sc,
+            primary_commitment_key,
+            secondary_commitment_key,
+            primary_k_table_size,
+        )
+    }
 }
 
 pub mod sangria_prelude {
     use std::num::NonZeroUsize;
 
-    use crate::ff::{FromUniformBytes, PrimeFieldBits};
+    use serde::Serialize;
+
     pub use crate::{
         commitment::CommitmentKey,
+        curve_cycle::{Bn256Grumpkin, CurveCycle, PallasVesta, Reversed, Secp256k1Secq256k1},
         ff::{Field, PrimeField},
         ivc::{SangriaIVC, StepCircuit},
     };
+    use crate::{
+        ff::{FromUniformBytes, PrimeFieldBits},
+        group::prime::PrimeCurveAffine,
+        halo2curves::CurveAffine,
+    };
 
     /// Within the IVC framework, on-circuit & off-circuit random oracle will be used
     ///
@@ -76,12 +132,16 @@ pub mod sangria_prelude {
     /// The IVC uses big uint math on-circuit and this parameter allows you to configure
     /// the limb width of limb
     ///
+    /// Only for Sangria IVC, cyclefold has own [`crate::ivc::cyclefold::DEFAULT_LIMB_WIDTH`]
+    ///
     /// Safety: because 32 != 0
     pub const DEFAULT_LIMB_WIDTH: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(32) };
 
     /// The IVC uses big uint math on-circuit and this parameter allows you to configure
     /// the maximum number of limbs allowed
     ///
+    /// Only for Sangria IVC, cyclefold has own [`crate::ivc::cyclefold::DEFAULT_LIMBS_COUNT`]
+    ///
     /// Safety: because 10 != 0
     pub const DEFAULT_LIMBS_COUNT_LIMIT: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(10) };
 
@@ -89,19 +149,76 @@ pub mod sangria_prelude {
     pub const DEFAULT_RANDOM_ORACLE_SIZE: usize = 5;
     pub const DEFAULT_RANDOM_ORACLE_RATE: usize = DEFAULT_RANDOM_ORACLE_SIZE - 1;
 
+    /// Number of complete rounds of poseidon
+    pub const POSEIDON_DEFAULT_R_F: usize = 10;
+
+    /// Number of partial rounds of poseidon
+    pub const POSEIDON_DEFAULT_R_P: usize = 10;
+
     /// Create constants for random oracle, with R_F & R_P as defaults
     pub fn default_random_oracle_constant<F>(
     ) -> RandomOracleConstant<F, DEFAULT_STEP_FOLDING_CIRCUIT_SIZE, DEFAULT_RANDOM_ORACLE_RATE>
     where
         F: serde::Serialize + FromUniformBytes<64> + PrimeFieldBits,
     {
-        /// Number of complete rounds
-        const POSEIDON_DEFUALT_R_F: usize = 10;
+        RandomOracleConstant::new(POSEIDON_DEFAULT_R_F, POSEIDON_DEFAULT_R_P)
+    }
 
-        /// Number of partial rounds
-        const POSEIDON_DEFAULT_R_P: usize = 10;
+    /// [`crate::ivc::sangria::PublicParams`] over the curves of `Cycle` with default random
+    /// oracles
+    pub type PublicParams<'l, Cycle, const A1: usize, SC1, const A2: usize, SC2> =
+        crate::ivc::sangria::PublicParams<
+            'l,
+            A1,
+            A2,
+            DEFAULT_STEP_FOLDING_CIRCUIT_SIZE,
+            <Cycle as CurveCycle>::C1,
+            <Cycle as CurveCycle>::C2,
+            SC1,
+            SC2,
+            RandomOracle<DEFAULT_RANDOM_ORACLE_SIZE, DEFAULT_RANDOM_ORACLE_RATE>,
+            RandomOracle<DEFAULT_RANDOM_ORACLE_SIZE, DEFAULT_RANDOM_ORACLE_RATE>,
+        >;
 
-        RandomOracleConstant::new(POSEIDON_DEFUALT_R_F, POSEIDON_DEFAULT_R_P)
+    /// This function creates public parameters for IVC over the curves of `Cycle`
+    ///
+    /// All values except the input are taken from `Cycle`
+    pub fn new_default_pp<'k, Cycle, const A1: usize, SC1, const A2: usize, SC2>(
+        primary_k_table_size: u32,
+        primary_commitment_key: &'k CommitmentKey<Cycle::C1>,
+        sc1: &SC1,
+        secondary_k_table_size: u32,
+        secondary_commitment_key: &'k CommitmentKey<Cycle::C2>,
+        sc2: &SC2,
+    ) -> PublicParams<'k, Cycle, A1, SC1, A2, SC2>
+    where
+        Cycle: CurveCycle,
+        Cycle::C1: Serialize,
+        Cycle::C2: Serialize,
+        <Cycle::C1 as CurveAffine>::Base: PrimeFieldBits + FromUniformBytes<64> + Serialize,
+        <Cycle::C2 as CurveAffine>::Base: PrimeFieldBits + FromUniformBytes<64> + Serialize,
+        SC1: StepCircuit<A1, <Cycle::C1 as PrimeCurveAffine>::Scalar>,
+        SC2: StepCircuit<A2, <Cycle::C2 as PrimeCurveAffine>::Scalar>,
+        SC1::StepWitness: Default,
+        SC2::StepWitness: Default,
+    {
+        PublicParams::<'k, Cycle, A1, SC1, A2, SC2>::new(
+            crate::ivc::sangria::CircuitPublicParamsInput::new(
+                primary_k_table_size,
+                primary_commitment_key,
+                Cycle::poseidon_spec(),
+                sc1,
+            ),
+            crate::ivc::sangria::CircuitPublicParamsInput::new(
+                secondary_k_table_size,
+                secondary_commitment_key,
+                Cycle::poseidon_spec(),
+                sc2,
+            ),
+            Cycle::SANGRIA_LIMB_WIDTH,
+            Cycle::SANGRIA_LIMBS_COUNT,
+        )
+        .unwrap()
     }
 
     /// All imports and alias related to what will use bn256 & grumpkin as the first and second
@@ -126,25 +243,10 @@ pub mod sangria_prelude {
         pub type C1Scalar = <G1 as Group>::Scalar;
         pub type C2Scalar = <G2 as Group>::Scalar;
 
+        pub type Cycle = crate::curve_cycle::Bn256Grumpkin;
+
         pub type PublicParams<'l, const A1: usize, C1, const A2: usize, C2> =
-            crate::ivc::sangria::PublicParams<
-                'l,
-                A1,
-                A2,
-                { super::DEFAULT_STEP_FOLDING_CIRCUIT_SIZE },
-                C1Affine,
-                C2Affine,
-                C1,
-                C2,
-                super::RandomOracle<
-                    { super::DEFAULT_RANDOM_ORACLE_SIZE },
-                    { super::DEFAULT_RANDOM_ORACLE_RATE },
-                >,
-                super::RandomOracle<
-                    { super::DEFAULT_RANDOM_ORACLE_SIZE },
-                    { super::DEFAULT_RANDOM_ORACLE_RATE },
-                >,
-            >;
+            super::PublicParams<'l, Cycle, A1, C1, A2, C2>;
 
         /// This function creates public parameters for IVC
         ///
@@ -160,24 +262,17 @@ pub mod sangria_prelude {
         where
             C1: StepCircuit<A1, C1Scalar>,
             C2: StepCircuit<A2, C2Scalar>,
+            C1::StepWitness: Default,
+            C2::StepWitness: Default,
         {
-            PublicParams::new(
-                crate::ivc::sangria::CircuitPublicParamsInput::new(
-                    primary_k_table_size,
-                    primary_commitment_key,
-                    super::default_random_oracle_constant(),
-                    sc1,
-                ),
-                crate::ivc::sangria::CircuitPublicParamsInput::new(
-                    secondary_k_table_size,
-                    secondary_commitment_key,
-                    super::default_random_oracle_constant(),
-                    sc2,
-                ),
-                super::DEFAULT_LIMB_WIDTH,
-                super::DEFAULT_LIMBS_COUNT_LIMIT,
+            super::new_default_pp::<Cycle, A1, C1, A2, C2>(
+                primary_k_table_size,
+                primary_commitment_key,
+                sc1,
+                secondary_k_table_size,
+                secondary_commitment_key,
+                sc2,
             )
-            .unwrap()
         }
     }
 
@@ -203,25 +298,10 @@ pub mod sangria_prelude {
         pub type C1Scalar = <G1 as Group>::Scalar;
         pub type C2Scalar = <G2 as Group>::Scalar;
 
+        pub type Cycle = crate::curve_cycle::Reversed<crate::curve_cycle::Bn256Grumpkin>;
+
         pub type PublicParams<'l, const A1: usize, C1, const A2: usize, C2> =
-            crate::ivc::sangria::PublicParams<
-                'l,
-                A1,
-                A2,
-                { super::DEFAULT_STEP_FOLDING_CIRCUIT_SIZE },
-                C1Affine,
-                C2Affine,
-                C1,
-                C2,
-                super::RandomOracle<
-                    { super::DEFAULT_RANDOM_ORACLE_SIZE },
-                    { super::DEFAULT_RANDOM_ORACLE_RATE },
-                >,
-                super::RandomOracle<
-                    { super::DEFAULT_RANDOM_ORACLE_SIZE },
-                    { super::DEFAULT_RANDOM_ORACLE_RATE },
-                >,
-            >;
+            super::PublicParams<'l, Cycle, A1, C1, A2, C2>;
 
         /// This function creates public parameters for IVC
         ///
@@ -237,24 +317,17 @@ pub mod sangria_prelude {
         where
             C1: StepCircuit<A1, C1Scalar>,
             C2: StepCircuit<A2, C2Scalar>,
+            C1::StepWitness: Default,
+            C2::StepWitness: Default,
         {
-            PublicParams::new(
-                crate::ivc::sangria::CircuitPublicParamsInput::new(
-                    primary_k_table_size,
-                    primary_commitment_key,
-                    super::default_random_oracle_constant(),
-                    sc1,
-                ),
-                crate::ivc::sangria::CircuitPublicParamsInput::new(
-                    secondary_k_table_size,
-                    secondary_commitment_key,
-                    super::default_random_oracle_constant(),
-                    sc2,
-                ),
-                super::DEFAULT_LIMB_WIDTH,
-                super::DEFAULT_LIMBS_COUNT_LIMIT,
+            super::new_default_pp::<Cycle, A1, C1, A2, C2>(
+                primary_k_table_size,
+                primary_commitment_key,
+                sc1,
+                secondary_k_table_size,
+                secondary_commitment_key,
+                sc2,
             )
-            .unwrap()
         }
     }
 }
@@ -813,7 +813,8 @@ impl<F: PrimeFieldBits, const T: usize> MainGate<F, T> {
         input: AssignedValue<F>,
         bit_len: NonZeroUsize,
     ) -> Result<Vec<AssignedValue<F>>, Error> {
-        // TODO: ensure a is less than F.size() - 1
+        // TODO: ensure a is less than F.size() - 1, see
+        // `gadgets::range::RangeChip::to_canonical_bits` for the checked version
 
         let mut bits: Vec<bool> = input
             .value()
@@ -885,6 +886,7 @@ mod tests {
                 num_selectors: cs.num_selectors,
                 num_challenges: cs.num_challenges(),
                 num_lookups: 0,
+                num_permutation_columns: 0,
             },
         )
     }
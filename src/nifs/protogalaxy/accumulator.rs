@@ -55,6 +55,15 @@ impl<C: CurveAffine> Accumulator<C> {
     pub fn W_commitment_len(&self) -> usize {
         self.trace.u.W_commitments.len()
     }
+
+    /// Instance part of the accumulator, without cloning the witness
+    pub fn instance(&self) -> AccumulatorInstance<C> {
+        AccumulatorInstance {
+            ins: self.trace.u.clone(),
+            betas: self.betas.clone(),
+            e: self.e,
+        }
+    }
 }
 
 /// Represents an accumulator for folding multiple instances into a single instance,
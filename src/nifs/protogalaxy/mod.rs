@@ -1,7 +1,7 @@
 use std::{iter, marker::PhantomData};
 
 use itertools::Itertools;
-use tracing::{debug, instrument, trace, warn};
+use tracing::{debug, instrument, trace};
 
 use crate::{
     commitment::CommitmentKey,
@@ -11,7 +11,7 @@ use crate::{
     ivc::protogalaxy::verify_chip::BigUintPoint,
     nifs::protogalaxy::poly::{get_count_of_valuation_with_padding, PolyContext},
     plonk::{self, eval, PlonkInstance, PlonkStructure, PlonkTrace, PlonkWitness},
-    polynomial::{lagrange, sparse, univariate::UnivariatePoly},
+    polynomial::{lagrange, univariate::UnivariatePoly},
     poseidon::{AbsorbInRO, ROTrait},
     sps::{self, SpecialSoundnessVerifier},
     util,
@@ -560,7 +560,7 @@ pub enum VerifyError<F: PrimeField> {
     #[error("Expected `e` {expected_e:?}, but evaluated is {evaluated_e:?}")]
     MismatchE { expected_e: F, evaluated_e: F },
     #[error("Permutation check failed")]
-    PermCheckFailed { mismatch_count: usize },
+    PermCheckFailed,
     #[error("Commitment of")]
     WitnessCommitmentMismatch(Box<[usize]>),
     #[error("While calculate E: {0:?}")]
@@ -663,28 +663,14 @@ impl<C: CurveAffine, const L: usize> ProtoGalaxy<C, L> {
     ) -> Result<(), VerifyError<C::ScalarExt>> {
         let PlonkTrace { u, w } = &acc.trace;
 
-        let Z = u
-            .instances
-            .iter()
-            .flat_map(|inst| inst.iter())
-            .chain(w.W[0].iter().take((1 << S.k) * S.num_advice_columns))
-            .copied()
-            .collect::<Vec<_>>();
-
-        let mismatch_count = sparse::matrix_multiply(&S.permutation_matrix(), &Z)
-            .into_iter()
-            .zip_eq(Z)
-            .enumerate()
-            .filter_map(|(row, (y, z))| C::ScalarExt::ZERO.ne(&(y - z)).then_some(row))
-            .inspect(|row| {
-                warn!("permutation mismatch at {row}");
-            })
-            .count();
-
-        if mismatch_count == 0 {
+        if S.is_sat_permutation(
+            &S.permutation_data,
+            |column, row| u.instances[column][row],
+            &w.W,
+        ) {
             Ok(())
         } else {
-            Err(VerifyError::PermCheckFailed { mismatch_count })
+            Err(VerifyError::PermCheckFailed)
         }
     }
 
@@ -90,7 +90,12 @@ impl<C: Circuit<Scalar>> Mock<C> {
         });
         info!("circuit runners ready");
 
-        let ck = commitment::setup_smallest_key(k_table_size, &circuits_runners[0].cs, b"");
+        let ck = commitment::setup_smallest_key(
+            k_table_size,
+            &circuits_runners[0].cs,
+            circuits_runners[0].permutation.as_ref(),
+            b"",
+        );
         let S = circuits_runners[0]
             .try_collect_plonk_structure()
             .expect("failed to collect plonk structure");
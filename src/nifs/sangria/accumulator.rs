@@ -502,4 +502,9 @@ impl<F: PrimeField> RelaxedPlonkWitness<F> {
             E: vec![F::ZERO; 1 << k_table_size].into_boxed_slice(),
         }
     }
+
+    /// Number of field elements in `W` & `E`
+    pub fn size(&self) -> usize {
+        self.inner.size() + self.E.len()
+    }
 }
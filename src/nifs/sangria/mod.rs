@@ -1,4 +1,4 @@
-use std::{iter, marker::PhantomData, num::NonZeroUsize};
+use std::{marker::PhantomData, num::NonZeroUsize};
 
 use count_to_non_zero::CountToNonZeroExt;
 use itertools::Itertools;
@@ -20,17 +20,14 @@ use crate::{
         halo2curves::ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
         plonk::Error as Halo2Error,
     },
-    ivc::{sangria::instances_accumulator_computation, Instances},
+    ivc::sangria::instances_accumulator_computation,
     nifs::sangria::accumulator::RelaxedPlonkWitness,
     plonk::{
         self,
         eval::{Error as EvalError, GetDataForEval, PlonkEvalDomain},
         PlonkStructure, PlonkWitness,
     },
-    polynomial::{
-        graph_evaluator::GraphEvaluator,
-        sparse::{self, SparseMatrix},
-    },
+    polynomial::graph_evaluator::GraphEvaluator,
     poseidon::ROTrait,
     sps::{Error as SpsError, SpecialSoundnessVerifier},
 };
@@ -110,6 +107,7 @@ where
         let data = PlonkEvalDomain {
             num_advice: S.num_advice_columns,
             num_lookup: S.num_lookups(),
+            num_permutation: S.num_permutation_columns(),
             challenges: &concat_vec!(
                 &U1.challenges,
                 &[U1.u],
@@ -321,8 +319,8 @@ pub enum VerifyError {
     InstancesHashMismatch,
     #[error("(Relaxed) plonk relation not satisfied: commitment of E")]
     ECommitmentMismatch,
-    #[error("Permutation check fail: mismatch_count {mismatch_count}")]
-    PermCheckFail { mismatch_count: usize },
+    #[error("Permutation check fail: log-derivative sums mismatch")]
+    PermCheckFail,
     #[error("Instance mismatch")]
     InstanceMismatch,
 }
@@ -341,6 +339,7 @@ where
         let data = PlonkEvalDomain {
             num_advice: S.num_advice_columns,
             num_lookup: S.num_lookups(),
+            num_permutation: S.num_permutation_columns(),
             challenges: &concat_vec!(&U.challenges, &[U.u]),
             selectors: &S.selectors,
             fixed: &S.fixed_columns,
@@ -386,69 +385,33 @@ where
         S: &PlonkStructure<C::ScalarExt>,
         acc: &RelaxedPlonkTrace<C, MARKERS_LEN>,
     ) -> Result<(), VerifyError> {
-        /// Under this collapsing scheme, `instance` columns other than consistency markers are not
-        /// foldeded, but accumulated using hash. Therefore, they need to be cut out for
-        /// `is_sat_permutation`.
-        ///
-        /// To account for these permutations in the `Relaxed` version, we add them to StepFoldingCircuit
-        /// as a copy constraint with private input (witness)
-        fn permutation_data_without_step_circuit_instances<F: PrimeField>(
-            S: &PlonkStructure<F>,
-        ) -> SparseMatrix<F> {
-            S.permutation_data
-                .clone()
-                .rm_copy_constraints(1..S.num_io.len())
-                .matrix(S.k, &S.num_io, S.num_advice_columns)
-        }
-
-        /// While checking permutations, we need to line up all instance columns one after the other, but
-        /// since we cut out all instance columns except the null column (consistency_marker) for the
-        /// `Relaxed*` version we need to augment them based on [`PlonkStructure::num_io`].
-        fn iter_flat_instances_with_padding<'b, C: CurveAffine, const MARKERS_LEN: usize>(
-            U: &'b RelaxedPlonkInstance<C, MARKERS_LEN>,
-            S: &'b PlonkStructure<C::ScalarExt>,
-        ) -> impl 'b + Iterator<Item = C::ScalarExt> {
-            U.consistency_markers.iter().copied().chain(
-                S.num_io
-                    .iter()
-                    .skip(1)
-                    // Use 0xfffffff only for easy debug
-                    .flat_map(|len| iter::repeat(C::ScalarExt::from_u128(0xfffffff)).take(*len)),
-            )
-        }
-
         let RelaxedPlonkTrace { U, W } = acc;
 
-        let Z = iter_flat_instances_with_padding(U, S)
-            .chain(
-                W.W[0]
-                    .iter()
-                    .take((1 << S.k) * S.num_advice_columns)
-                    .copied(),
-            )
-            .collect::<Vec<_>>();
-
-        let mismatch_count =
-            sparse::matrix_multiply(&permutation_data_without_step_circuit_instances(S), &Z)
-                .into_iter()
-                .zip_eq(Z)
-                .enumerate()
-                .filter(|(row, (y, z))| {
-                    let diff = *y - *z;
-
-                    if diff.is_zero().into() {
-                        false
-                    } else {
-                        warn!("permutation mismatch at {row} with: {y:?} - {z:?} = {diff:?}");
-                        true
-                    }
-                })
-                .count();
-
-        if mismatch_count == 0 {
+        // Under this collapsing scheme, `instance` columns other than consistency markers are not
+        // foldeded, but accumulated using hash. Therefore, their copy constraints are cut out for
+        // `is_sat_permutation`.
+        //
+        // To account for these permutations in the `Relaxed` version, we add them to
+        // StepFoldingCircuit as a copy constraint with private input (witness)
+        let permutation_data = S
+            .permutation_data
+            .clone()
+            .rm_copy_constraints(1..S.num_io.len());
+
+        let is_sat = S.is_sat_permutation(
+            &permutation_data,
+            |column, row| match column {
+                0 => U.consistency_markers[row],
+                // Cut out columns are not constrained, any value fits; 0xfffffff only for easy debug
+                _ => C::ScalarExt::from_u128(0xfffffff),
+            },
+            &W.W,
+        );
+
+        if is_sat {
             Ok(())
         } else {
-            Err(VerifyError::PermCheckFail { mismatch_count })
+            Err(VerifyError::PermCheckFail)
         }
     }
 
@@ -473,24 +436,28 @@ where
         Ok(())
     }
 
+    /// Recalculate the hash accumulator of step circuit instances from the instances of each
+    /// folded step & compare it with the accumulated one
+    ///
+    /// `pub_instances` can be any iterator, so the whole history is not required to be in memory
     pub fn is_sat_pub_instances(
         acc: &RelaxedPlonkTrace<C, MARKERS_LEN>,
-        pub_instances: &[Vec<Vec<<C as CurveAffine>::ScalarExt>>],
+        pub_instances: impl IntoIterator<Item = impl AsRef<[Vec<<C as CurveAffine>::ScalarExt>]>>,
     ) -> Result<(), VerifyError> {
         match acc.U.step_circuit_instances_hash_accumulator {
             accumulator::SCInstancesHashAcc::None => {
-                assert!(pub_instances.iter().all(|instances| instances.get_step_circuit_instances().is_empty()));
+                assert!(pub_instances.into_iter().all(|instances| instances.as_ref().get_step_circuit_instances().is_empty()));
                 Ok(())
             }
             accumulator::SCInstancesHashAcc::Hash(step_circuit_instances_hash_accumulator) => {
                 pub_instances
-                    .iter()
+                    .into_iter()
                     .fold(
                         instances_accumulator_computation::get_initial_sc_instances_accumulator::<C>(),
                         |acc, instances| {
                             instances_accumulator_computation::absorb_in_sc_instances_accumulator::<C>(
                                 &acc,
-                                instances.get_step_circuit_instances(),
+                                instances.as_ref().get_step_circuit_instances(),
                             )
                         },
                     )
@@ -501,6 +468,32 @@ where
         }
     }
 
+    /// Constant-size alternative of [`VanillaFS::is_sat_pub_instances`]
+    ///
+    /// Only the hash accumulator before the last folded step & instances of this step are
+    /// required. If no step was folded (`last_instances` is `None`), the hash accumulator should
+    /// remain unchanged
+    pub fn is_sat_last_pub_instances(
+        acc: &RelaxedPlonkTrace<C, MARKERS_LEN>,
+        prev_hash_accumulator: &accumulator::SCInstancesHashAcc<C::ScalarExt>,
+        last_instances: Option<&[Vec<C::ScalarExt>]>,
+    ) -> Result<(), VerifyError> {
+        let expected = match last_instances {
+            Some(instances) => prev_hash_accumulator.as_ref().map(|prev| {
+                instances_accumulator_computation::absorb_in_sc_instances_accumulator::<C>(
+                    prev,
+                    instances.get_step_circuit_instances(),
+                )
+            }),
+            None => prev_hash_accumulator.clone(),
+        };
+
+        expected
+            .ne(&acc.U.step_circuit_instances_hash_accumulator)
+            .then_some(VerifyError::InstanceMismatch)
+            .err_or(())
+    }
+
     /// Comprehensive satisfaction check for an accumulator.
     ///
     /// This method runs multiple checks ([`IsSatAccumulation::is_sat_accumulation`],
@@ -511,6 +504,28 @@ where
         S: &PlonkStructure<C::ScalarExt>,
         acc: &RelaxedPlonkTrace<C, MARKERS_LEN>,
         pub_instances: &[Vec<Vec<C::ScalarExt>>],
+    ) -> Result<(), Vec<VerifyError>> {
+        let mut errors = Self::is_sat_without_pub_instances(ck, S, acc)
+            .err()
+            .unwrap_or_default();
+
+        if let Err(err) = Self::is_sat_pub_instances(acc, pub_instances) {
+            errors.push(err);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Same as [`VanillaFS::is_sat`], but without [`VanillaFS::is_sat_pub_instances`], for the
+    /// case when step circuit instances are checked separately
+    pub fn is_sat_without_pub_instances(
+        ck: &CommitmentKey<C>,
+        S: &PlonkStructure<C::ScalarExt>,
+        acc: &RelaxedPlonkTrace<C, MARKERS_LEN>,
     ) -> Result<(), Vec<VerifyError>> {
         let mut errors = vec![];
 
@@ -526,10 +541,6 @@ where
             errors.push(err);
         }
 
-        if let Err(err) = Self::is_sat_pub_instances(acc, pub_instances) {
-            errors.push(err);
-        }
-
         if errors.is_empty() {
             Ok(())
         } else {
@@ -583,7 +594,7 @@ impl<C: CurveAffine> GetStepCircuitInstances<C::ScalarExt> for PlonkInstance<C>
     }
 }
 
-impl<F: PrimeField> GetStepCircuitInstances<F> for Instances<F> {
+impl<F: PrimeField> GetStepCircuitInstances<F> for [Vec<F>] {
     fn get_step_circuit_instances(&self) -> &[Vec<F>] {
         &self[1..]
     }
@@ -82,7 +82,7 @@ where
     const R_P: usize = 3;
 
     let td1 = CircuitRunner::new(K, circuit1, public_inputs1.clone());
-    let ck = commitment::setup_smallest_key(K, &td1.cs, b"prepare_trace");
+    let ck = commitment::setup_smallest_key(K, &td1.cs, td1.permutation.as_ref(), b"prepare_trace");
 
     let S = td1.try_collect_plonk_structure()?;
     let W1 = td1.try_collect_witness()?;
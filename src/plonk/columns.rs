@@ -0,0 +1,478 @@
+//! Compact storage for the preprocessed (non-folded) columns of [`super::PlonkStructure`]
+//!
+//! Selectors and fixed columns have `2^k` rows, but in practice most of them are almost empty:
+//! a selector is usually enabled in a small number of regions, and a fixed column is filled with
+//! constants in a few places only. Storing them densely makes the [`super::PlonkStructure`]
+//! (and therefore every public params) huge for large `k`.
+//!
+//! This module provides:
+//! - [`SelectorColumn`] & [`Selectors`] - bit-packed selectors
+//! - [`FixedColumn`] & [`FixedColumns`] - fixed columns with run-length encoding when it pays off
+//! - [`ColumnsView`] - unified read access for dense & compressed representations, used by
+//!   [`super::eval::GetDataForEval`]
+//!
+//! Both containers serialize exactly as their dense versions (`Vec<Vec<bool>>` & `Vec<Vec<F>>`),
+//! so digests of public params do not depend on the chosen representation.
+
+use std::iter;
+
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+
+use crate::ff::PrimeField;
+
+/// Read-only access to a set of columns of equal length
+pub trait ColumnsView<T: Copy> {
+    fn columns_count(&self) -> usize;
+
+    /// Rows count of the first column, `None` if there are no columns
+    fn rows_count(&self) -> Option<usize>;
+
+    /// Value of cell, `None` if `column` or `row` out of boundary
+    fn get_cell(&self, column: usize, row: usize) -> Option<T>;
+}
+
+impl<T: Copy, V: AsRef<[T]>> ColumnsView<T> for [V] {
+    fn columns_count(&self) -> usize {
+        self.len()
+    }
+
+    fn rows_count(&self) -> Option<usize> {
+        self.first().map(|column| column.as_ref().len())
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> Option<T> {
+        self.get(column)?.as_ref().get(row).copied()
+    }
+}
+
+impl<T: Copy, V: AsRef<[T]>> ColumnsView<T> for Vec<V> {
+    fn columns_count(&self) -> usize {
+        self.as_slice().columns_count()
+    }
+
+    fn rows_count(&self) -> Option<usize> {
+        self.as_slice().rows_count()
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> Option<T> {
+        self.as_slice().get_cell(column, row)
+    }
+}
+
+impl<T: Copy, CV: ColumnsView<T> + ?Sized> ColumnsView<T> for &CV {
+    fn columns_count(&self) -> usize {
+        (**self).columns_count()
+    }
+
+    fn rows_count(&self) -> Option<usize> {
+        (**self).rows_count()
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> Option<T> {
+        (**self).get_cell(column, row)
+    }
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Bit-packed selector column
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectorColumn {
+    len: usize,
+    words: Box<[u64]>,
+}
+
+impl SelectorColumn {
+    /// Create column with all rows disabled
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            words: vec![0; len.div_ceil(WORD_BITS)].into_boxed_slice(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, row: usize) -> Option<bool> {
+        (row < self.len).then(|| (self.words[row / WORD_BITS] >> (row % WORD_BITS)) & 1 == 1)
+    }
+
+    /// Enable selector on `row`, `None` if `row` out of boundary
+    pub fn enable(&mut self, row: usize) -> Option<()> {
+        if row >= self.len {
+            return None;
+        }
+        self.words[row / WORD_BITS] |= 1 << (row % WORD_BITS);
+        Some(())
+    }
+
+    pub fn iter(&self) -> impl '_ + Iterator<Item = bool> {
+        (0..self.len).map(|row| (self.words[row / WORD_BITS] >> (row % WORD_BITS)) & 1 == 1)
+    }
+
+    /// Count of rows where selector is enabled
+    pub fn count_enabled(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+impl From<&[bool]> for SelectorColumn {
+    fn from(value: &[bool]) -> Self {
+        let mut column = Self::new(value.len());
+        value
+            .iter()
+            .enumerate()
+            .filter(|(_, is_enabled)| **is_enabled)
+            .for_each(|(row, _)| {
+                column.enable(row);
+            });
+        column
+    }
+}
+
+impl Serialize for SelectorColumn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Set of bit-packed selector columns
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selectors(Vec<SelectorColumn>);
+
+impl Selectors {
+    /// Create `count` selector columns of `len` rows, all disabled
+    pub fn new(count: usize, len: usize) -> Self {
+        Self(vec![SelectorColumn::new(len); count])
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SelectorColumn> {
+        self.0.iter()
+    }
+
+    pub fn get_mut(&mut self, column: usize) -> Option<&mut SelectorColumn> {
+        self.0.get_mut(column)
+    }
+
+    /// Approximate heap size of this representation in bytes
+    pub fn heap_size(&self) -> usize {
+        self.0
+            .iter()
+            .map(|column| column.words.len() * std::mem::size_of::<u64>())
+            .sum()
+    }
+}
+
+impl From<Vec<Vec<bool>>> for Selectors {
+    fn from(value: Vec<Vec<bool>>) -> Self {
+        Self(
+            value
+                .iter()
+                .map(|column| SelectorColumn::from(column.as_slice()))
+                .collect(),
+        )
+    }
+}
+
+impl ColumnsView<bool> for Selectors {
+    fn columns_count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn rows_count(&self) -> Option<usize> {
+        self.0.first().map(SelectorColumn::len)
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> Option<bool> {
+        self.0.get(column)?.get(row)
+    }
+}
+
+impl Serialize for Selectors {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for column in self.0.iter() {
+            seq.serialize_element(column)?;
+        }
+        seq.end()
+    }
+}
+
+/// Fixed column, stored either densely or as a sequence of runs of equal values
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FixedColumn<F: PrimeField> {
+    Dense(Box<[F]>),
+    /// Each `(start, value)` covers rows from `start` up to the `start` of the next run
+    ///
+    /// The first run always starts at zero row
+    RunLength {
+        len: usize,
+        runs: Box<[(usize, F)]>,
+    },
+}
+
+impl<F: PrimeField> FixedColumn<F> {
+    /// Store column densely
+    pub fn dense(values: Vec<F>) -> Self {
+        Self::Dense(values.into_boxed_slice())
+    }
+
+    /// Store column with run-length encoding
+    pub fn run_length(values: &[F]) -> Self {
+        let mut runs: Vec<(usize, F)> = vec![];
+        for (row, value) in values.iter().enumerate() {
+            if !runs.last().is_some_and(|(_, last)| last.eq(value)) {
+                runs.push((row, *value));
+            }
+        }
+
+        Self::RunLength {
+            len: values.len(),
+            runs: runs.into_boxed_slice(),
+        }
+    }
+
+    /// Choose the smallest representation for this column
+    pub fn compress(values: Vec<F>) -> Self {
+        let runs_count = 1 + values
+            .windows(2)
+            .filter(|pair| pair[0].ne(&pair[1]))
+            .count();
+
+        // Each run stores value & row index
+        let run_size = std::mem::size_of::<(usize, F)>();
+        if runs_count * run_size < values.len() * std::mem::size_of::<F>() {
+            Self::run_length(&values)
+        } else {
+            Self::dense(values)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Dense(values) => values.len(),
+            Self::RunLength { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, row: usize) -> Option<F> {
+        match self {
+            Self::Dense(values) => values.get(row).copied(),
+            Self::RunLength { len, runs } => {
+                if row >= *len {
+                    return None;
+                }
+                let run_index = runs.partition_point(|(start, _)| *start <= row);
+                Some(runs[run_index - 1].1)
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl '_ + Iterator<Item = F> {
+        (0..self.len()).map(|row| self.get(row).expect("row in boundary"))
+    }
+
+    /// Approximate heap size of this representation in bytes
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Self::Dense(values) => std::mem::size_of_val(values.as_ref()),
+            Self::RunLength { runs, .. } => std::mem::size_of_val(runs.as_ref()),
+        }
+    }
+}
+
+impl<F: PrimeField> Serialize for FixedColumn<F>
+where
+    F: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Set of fixed columns, each of them compressed independently
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedColumns<F: PrimeField>(Vec<FixedColumn<F>>);
+
+impl<F: PrimeField> Default for FixedColumns<F> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<F: PrimeField> FixedColumns<F> {
+    /// Store all columns densely
+    pub fn dense(columns: Vec<Vec<F>>) -> Self {
+        Self(columns.into_iter().map(FixedColumn::dense).collect())
+    }
+
+    /// Choose the smallest representation for each column
+    pub fn compress(columns: Vec<Vec<F>>) -> Self {
+        Self(columns.into_iter().map(FixedColumn::compress).collect())
+    }
+
+    /// `count` columns of `len` zeros, used when only the shape of the table matters
+    pub fn zeroed(count: usize, len: usize) -> Self {
+        Self(
+            iter::repeat_with(|| FixedColumn::RunLength {
+                len,
+                runs: Box::new([(0, F::ZERO)]),
+            })
+            .take(count)
+            .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FixedColumn<F>> {
+        self.0.iter()
+    }
+
+    /// Approximate heap size of this representation in bytes
+    pub fn heap_size(&self) -> usize {
+        self.0.iter().map(FixedColumn::heap_size).sum()
+    }
+}
+
+impl<F: PrimeField> ColumnsView<F> for FixedColumns<F> {
+    fn columns_count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn rows_count(&self) -> Option<usize> {
+        self.0.first().map(FixedColumn::len)
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> Option<F> {
+        self.0.get(column)?.get(row)
+    }
+}
+
+impl<F: PrimeField> Serialize for FixedColumns<F>
+where
+    F: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for column in self.0.iter() {
+            seq.serialize_element(column)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        digest::{DefaultHasher, DigestToBits},
+        ff::Field,
+        halo2curves::bn256::Fr,
+    };
+
+    fn fixed_sample() -> Vec<Vec<Fr>> {
+        let mut sparse = vec![Fr::ZERO; 1 << 10];
+        sparse[3] = Fr::from(3);
+        sparse[100..200].fill(Fr::ONE);
+
+        let dense = (0..1 << 10).map(|i| Fr::from(i as u64)).collect();
+
+        vec![sparse, dense]
+    }
+
+    fn selectors_sample() -> Vec<Vec<bool>> {
+        let mut selector = vec![false; 1 << 10];
+        selector[0] = true;
+        selector[63] = true;
+        selector[64] = true;
+        selector[500..600].fill(true);
+
+        vec![selector, vec![false; 1 << 10]]
+    }
+
+    #[test]
+    fn selectors_roundtrip() {
+        let dense = selectors_sample();
+        let packed = Selectors::from(dense.clone());
+
+        assert_eq!(packed.columns_count(), dense.columns_count());
+        assert_eq!(packed.rows_count(), dense.rows_count());
+        for (column, values) in dense.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                assert_eq!(packed.get_cell(column, row), Some(*value));
+            }
+        }
+        assert_eq!(packed.get_cell(0, 1 << 10), None);
+        assert_eq!(packed.get_cell(2, 0), None);
+        assert_eq!(packed.iter().next().unwrap().count_enabled(), 103);
+    }
+
+    #[test]
+    fn fixed_roundtrip() {
+        let dense = fixed_sample();
+        let compressed = FixedColumns::compress(dense.clone());
+
+        assert!(matches!(compressed.0[0], FixedColumn::RunLength { .. }));
+        assert!(matches!(compressed.0[1], FixedColumn::Dense(_)));
+
+        for (column, values) in dense.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                assert_eq!(compressed.get_cell(column, row), Some(*value));
+            }
+        }
+        assert_eq!(compressed.get_cell(0, 1 << 10), None);
+    }
+
+    #[test]
+    fn same_digest() {
+        let fixed = fixed_sample();
+        let selectors = selectors_sample();
+
+        let expected = DefaultHasher::digest_to_bits(&(&selectors, &fixed)).unwrap();
+
+        assert_eq!(
+            DefaultHasher::digest_to_bits(&(
+                Selectors::from(selectors.clone()),
+                FixedColumns::compress(fixed.clone())
+            ))
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            DefaultHasher::digest_to_bits(&(
+                Selectors::from(selectors),
+                FixedColumns::dense(fixed)
+            ))
+            .unwrap(),
+            expected
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::columns::{ColumnsView, FixedColumns, Selectors};
 use crate::{ff::PrimeField, polynomial::ColumnIndex};
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq, Clone)]
@@ -28,17 +29,20 @@ pub enum Error {
 ///
 /// Implementors of this trait are responsible for providing access to various
 /// kinds of data: challenges, selectors, fixed columns, etc.
+///
+/// Selectors & fixed columns are accessed through [`ColumnsView`], so both dense (`Vec<Vec<_>>`)
+/// and compressed ([`Selectors`], [`FixedColumns`]) representations can be used
 pub trait GetDataForEval<F: PrimeField> {
     fn get_challenges(&self) -> &impl AsRef<[F]>;
-    fn get_selectors(&self) -> &impl AsRef<[Vec<bool>]>;
-    fn get_fixed(&self) -> &impl AsRef<[Vec<F>]>;
+    fn get_selectors(&self) -> &impl ColumnsView<bool>;
+    fn get_fixed(&self) -> &impl ColumnsView<F>;
 
     fn num_lookup(&self) -> usize;
     fn num_selectors(&self) -> usize {
-        self.get_selectors().as_ref().len()
+        self.get_selectors().columns_count()
     }
     fn num_fixed(&self) -> usize {
-        self.get_fixed().as_ref().len()
+        self.get_fixed().columns_count()
     }
 
     fn eval_advice_var(&self, row_index: usize, colulm_index: usize) -> Result<F, Error>;
@@ -46,26 +50,28 @@ pub trait GetDataForEval<F: PrimeField> {
     /// Total row size of the evaluation domain
     fn row_size(&self) -> usize {
         self.get_fixed()
-            .as_ref()
-            .first()
-            .map(Vec::len)
-            .or_else(|| self.get_selectors().as_ref().first().map(Vec::len))
+            .rows_count()
+            .or_else(|| self.get_selectors().rows_count())
             .expect("Fixed & Selectors can't be empty in one time")
     }
 
     /// evaluate a single column variable on specific row
     fn eval_column_var(&self, row: usize, index: usize) -> Result<F, Error> {
-        let selectors = self.get_selectors().as_ref();
-        let fixed = self.get_fixed().as_ref();
-
-        selectors
-            .get(index)
-            .map(|selector| if selector[row] { F::ONE } else { F::ZERO })
-            .or_else(|| fixed.get(index - selectors.len()).map(|fixed| fixed[row]))
-            .map_or_else(
-                || self.eval_advice_var(row, index - selectors.len() - fixed.len()),
-                Ok,
-            )
+        let num_selectors = self.num_selectors();
+        let num_fixed = self.num_fixed();
+
+        if index < num_selectors {
+            self.get_selectors()
+                .get_cell(index, row)
+                .map(|is_enabled| if is_enabled { F::ONE } else { F::ZERO })
+                .ok_or(Error::RowIndexOutOfBoundary { row_index: row })
+        } else if index < num_selectors + num_fixed {
+            self.get_fixed()
+                .get_cell(index - num_selectors, row)
+                .ok_or(Error::RowIndexOutOfBoundary { row_index: row })
+        } else {
+            self.eval_advice_var(row, index - num_selectors - num_fixed)
+        }
     }
 
     fn eval_challenge(&self, index: usize) -> Result<F, Error> {
@@ -84,8 +90,8 @@ pub trait GetDataForEval<F: PrimeField> {
 pub struct LookupEvalDomain<'a, F: PrimeField> {
     pub(crate) num_lookup: usize,
     pub(crate) challenges: Vec<F>,
-    pub(crate) selectors: &'a Vec<Vec<bool>>,
-    pub(crate) fixed: &'a Vec<Vec<F>>,
+    pub(crate) selectors: &'a Selectors,
+    pub(crate) fixed: &'a FixedColumns<F>,
     pub(crate) advice: &'a [Vec<F>],
 }
 
@@ -93,10 +99,13 @@ pub struct LookupEvalDomain<'a, F: PrimeField> {
 pub struct PlonkEvalDomain<'a, F: PrimeField> {
     pub(crate) num_advice: usize,
     pub(crate) num_lookup: usize,
+    /// Number of advice columns in [`super::permutation::Arguments`], each of them adds helpers
+    /// `(h, g)` to the last round
+    pub(crate) num_permutation: usize,
     // concatenation of challenges from two RelaxedPlonkInstance
     pub(crate) challenges: &'a [F],
-    pub(crate) selectors: &'a [Vec<bool>],
-    pub(crate) fixed: &'a [Vec<F>],
+    pub(crate) selectors: &'a Selectors,
+    pub(crate) fixed: &'a FixedColumns<F>,
     // [`RelaxedPlonkWitness::W`] for first instance
     pub(crate) W1s: &'a [Vec<F>],
     // [`RelaxedPlonkWitness::W`] for second instance
@@ -112,11 +121,11 @@ impl<F: PrimeField> GetDataForEval<F> for LookupEvalDomain<'_, F> {
         &self.challenges
     }
 
-    fn get_selectors(&self) -> &impl AsRef<[Vec<bool>]> {
+    fn get_selectors(&self) -> &impl ColumnsView<bool> {
         self.selectors
     }
 
-    fn get_fixed(&self) -> &impl AsRef<[Vec<F>]> {
+    fn get_fixed(&self) -> &impl ColumnsView<F> {
         self.fixed
     }
 
@@ -142,12 +151,12 @@ impl<F: PrimeField> GetDataForEval<F> for PlonkEvalDomain<'_, F> {
         &self.challenges
     }
 
-    fn get_selectors(&self) -> &impl AsRef<[Vec<bool>]> {
-        &self.selectors
+    fn get_selectors(&self) -> &impl ColumnsView<bool> {
+        self.selectors
     }
 
-    fn get_fixed(&self) -> &impl AsRef<[Vec<F>]> {
-        &self.fixed
+    fn get_fixed(&self) -> &impl ColumnsView<F> {
+        self.fixed
     }
 
     fn eval_advice_var(&self, row: usize, index: usize) -> Result<F, Error> {
@@ -155,7 +164,7 @@ impl<F: PrimeField> GetDataForEval<F> for PlonkEvalDomain<'_, F> {
         let num_advice = self.num_advice;
         let num_lookup = self.num_lookup();
         // maximum index for one instance
-        let max_width = num_advice + num_lookup * 5;
+        let max_width = num_advice + num_lookup * 5 + self.num_permutation * 2;
         let (is_first_instance, index) = if index < max_width {
             (true, index)
         } else {
@@ -172,6 +181,20 @@ impl<F: PrimeField> GetDataForEval<F> for PlonkEvalDomain<'_, F> {
                 return Ok((0, index));
             }
 
+            if index >= num_advice + num_lookup * 5 {
+                // helpers of permutation argument follow lookup helpers in the last round
+                let permutation_index = index - num_advice - num_lookup * 5;
+                return match num_witness {
+                    3 => Ok((2, num_lookup * 2 + permutation_index)),
+                    num_witness => Err(Error::InvalidWitnessIndex {
+                        num_witness,
+                        num_advice,
+                        num_lookup,
+                        index,
+                    }),
+                };
+            }
+
             let lookup_index = (index - num_advice) / 5;
             let lookup_sub_index = (index - num_advice) % 5;
             let (is_first_round, lookup_sub_index) = if lookup_sub_index < 3 {
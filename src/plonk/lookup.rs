@@ -130,10 +130,17 @@ impl<F: PrimeField> Arguments<F> {
     }
 
     /// TODO #159
-    pub fn to_expressions(&self, cs: &ConstraintSystem<F>) -> impl Iterator<Item = Expression<F>> {
+    ///
+    /// - `challenge_index`: index of challenge used in log-derivative relations, see
+    ///   [`Arguments::log_derivative_lhs_and_rhs`]
+    pub fn to_expressions(
+        &self,
+        cs: &ConstraintSystem<F>,
+        challenge_index: usize,
+    ) -> impl Iterator<Item = Expression<F>> {
         self.vanishing_lookup_polys(cs)
             .into_iter()
-            .chain(self.log_derivative_lhs_and_rhs(cs))
+            .chain(self.log_derivative_lhs_and_rhs(cs, challenge_index))
     }
 
     /// L_i(x1,...,xa) - l_i which evaluates to zero on every row
@@ -198,8 +205,14 @@ impl<F: PrimeField> Arguments<F> {
     }
 
     /// collect the lhs and rhs of log-derivative relations from all lookup arguments
-    pub fn log_derivative_lhs_and_rhs(&self, cs: &ConstraintSystem<F>) -> Vec<Expression<F>> {
-        let challenge_index = if self.has_vector_lookup { 1 } else { 0 };
+    ///
+    /// `challenge_index` is `r2` if special soundness protocol has 3 rounds (vector lookup or
+    /// permutation argument), `r1` otherwise
+    pub fn log_derivative_lhs_and_rhs(
+        &self,
+        cs: &ConstraintSystem<F>,
+        challenge_index: usize,
+    ) -> Vec<Expression<F>> {
         (0..self.num_lookups())
             .flat_map(|lookup_index| {
                 let (lhs, rhs) = self.log_derivative_expr(cs, lookup_index, challenge_index);
@@ -12,14 +12,20 @@
 use std::{cell::OnceCell, iter, num::NonZeroUsize, time::Instant};
 
 use count_to_non_zero::*;
-use halo2_proofs::arithmetic::CurveAffine;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{Any, Column},
+};
 use itertools::Itertools;
 use rayon::prelude::*;
 use serde::Serialize;
 use some_to_err::*;
 use tracing::{debug, error, info, info_span, instrument, warn};
 
-use self::permutation::PermutationData;
+use self::{
+    columns::{FixedColumns, Selectors},
+    permutation::PermutationData,
+};
 use crate::{
     commitment::CommitmentKey,
     concat_vec,
@@ -33,7 +39,6 @@ use crate::{
         expression::{HomogeneousExpression, QueryIndexContext},
         graph_evaluator::GraphEvaluator,
         grouped_poly::GroupedPoly,
-        sparse::SparseMatrix,
         Expression,
     },
     poseidon::{AbsorbInRO, ROTrait},
@@ -41,6 +46,7 @@ use crate::{
     util::{concatenate_with_padding, fe_to_fe},
 };
 
+pub mod columns;
 pub mod eval;
 pub mod lookup;
 pub mod permutation;
@@ -129,8 +135,10 @@ pub struct PlonkStructure<F: PrimeField> {
     pub(crate) k: usize,
     /// Instance columns lengths
     pub(crate) num_io: Box<[usize]>,
-    pub(crate) selectors: Vec<Vec<bool>>,
-    pub(crate) fixed_columns: Vec<Vec<F>>,
+    /// Bit-packed selectors, see [`columns::Selectors`]
+    pub(crate) selectors: Selectors,
+    /// Fixed columns, compressed if it pays off, see [`columns::FixedColumns`]
+    pub(crate) fixed_columns: FixedColumns<F>,
 
     pub(crate) num_advice_columns: usize,
 
@@ -154,6 +162,9 @@ pub struct PlonkStructure<F: PrimeField> {
 
     pub(crate) permutation_data: PermutationData,
     pub(crate) lookup_arguments: Option<lookup::Arguments<F>>,
+    /// Copy constraints between advice cells folded as log-derivative argument, see
+    /// [`permutation::Arguments`]
+    pub(crate) permutation_arguments: Option<permutation::Arguments>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -166,6 +177,7 @@ pub struct PlonkInstance<C: CurveAffine> {
     /// name them as r1, r2, r3.
     /// r1: compress vector lookup, e.g. (a_1, a_2, a_3) -> a_1 + r1*a_2 + r1^2*a_3
     /// r2: challenge to calculate h and g in log-derivative relation
+    /// in case of permutation argument r1 & r2 are `beta` & `gamma`, see [`permutation::Arguments`]
     /// r3: combine all custom gates (P_i) and lookup relations (L_i), e.g.:
     /// (P_1, P_2, L_1, L_2) -> P_1 + r3*P_2 + r3^2*L_1 + r3^3*L_2
     pub(crate) challenges: Vec<C::ScalarExt>,
@@ -183,6 +195,11 @@ impl<F: PrimeField> PlonkWitness<F> {
             W: round_sizes.iter().map(|sz| vec![F::ZERO; *sz]).collect(),
         }
     }
+
+    /// Number of field elements in all rounds
+    pub fn size(&self) -> usize {
+        self.W.iter().map(Vec::len).sum()
+    }
 }
 
 // TODO #31 docs
@@ -281,8 +298,16 @@ impl<F: PrimeField> PlonkStructure<F> {
 
     /// return the number of variables to be folded
     /// each lookup argument will add 5 variables (l,t,m,h,g)
+    /// each advice column of permutation argument will add 2 variables (h,g)
     pub fn num_fold_vars(&self) -> usize {
-        self.num_advice_columns + 5 * self.num_lookups()
+        self.num_advice_columns + 5 * self.num_lookups() + 2 * self.num_permutation_columns()
+    }
+
+    pub fn num_permutation_columns(&self) -> usize {
+        self.permutation_arguments
+            .as_ref()
+            .map(permutation::Arguments::num_columns)
+            .unwrap_or_default()
     }
 
     pub fn num_lookups(&self) -> usize {
@@ -316,6 +341,7 @@ impl<F: PrimeField> PlonkStructure<F> {
         let data = PlonkEvalDomain {
             num_advice: self.num_advice_columns,
             num_lookup: self.num_lookups(),
+            num_permutation: self.num_permutation_columns(),
             challenges: &U.challenges,
             selectors: &self.selectors,
             fixed: &self.fixed_columns,
@@ -382,16 +408,14 @@ impl<F: PrimeField> PlonkStructure<F> {
                 .collect::<Vec<_>>()
         };
 
-        if self.has_vector_lookup() {
-            let hs = gather_vectors(&W[2], 0);
-            let gs = gather_vectors(&W[2], 1);
-            check_is_zero(&hs, &gs)
-        } else if self.num_lookups() > 0 {
-            let hs = gather_vectors(&W[1], 0);
-            let gs = gather_vectors(&W[1], 1);
-            check_is_zero(&hs, &gs)
-        } else {
-            true
+        // (h, g) are placed in the last round
+        match W.last() {
+            Some(W_last) if self.num_lookups() > 0 => {
+                let hs = gather_vectors(W_last, 0);
+                let gs = gather_vectors(W_last, 1);
+                check_is_zero(&hs, &gs)
+            }
+            _ => true,
         }
     }
 
@@ -607,18 +631,26 @@ impl<F: PrimeField> PlonkStructure<F> {
             .absorb_point(&C1)
             .squeeze::<C::ScalarExt>(NUM_CHALLENGE_BITS);
 
+        if self.lookup_arguments.is_none() && self.permutation_arguments.is_none() {
+            return Err(SpsError::LackOfLookupArguments);
+        }
+
         // round 2
         let lookup_coeff = self
             .lookup_arguments
             .as_ref()
             .map(|la| la.evaluate_coefficient_1(self, advice, r1))
-            .transpose()?
-            .ok_or(SpsError::LackOfLookupArguments)?;
+            .transpose()?;
 
-        let W2 = concatenate_with_padding(
-            &concat_vec!(&lookup_coeff.ls, &lookup_coeff.ts, &lookup_coeff.ms),
-            k_power_of_2,
-        );
+        let W2 = lookup_coeff
+            .as_ref()
+            .map(|lookup_coeff| {
+                concatenate_with_padding(
+                    &concat_vec!(&lookup_coeff.ls, &lookup_coeff.ts, &lookup_coeff.ms),
+                    k_power_of_2,
+                )
+            })
+            .unwrap_or_default();
         let C2 = {
             let _s = info_span!("lookup_commit").entered();
             ck.commit(&W2).map_err(|err| SpsError::WrongCommitmentSize {
@@ -631,12 +663,19 @@ impl<F: PrimeField> PlonkStructure<F> {
             .squeeze::<C::ScalarExt>(NUM_CHALLENGE_BITS);
 
         // round 3
-        let lookup_coeff = lookup_coeff.evaluate_coefficient_2(r2);
+        let mut W3 = lookup_coeff
+            .map(|lookup_coeff| {
+                let lookup_coeff = lookup_coeff.evaluate_coefficient_2(r2);
+                concatenate_with_padding(
+                    &concat_vec!(&lookup_coeff.hs, &lookup_coeff.gs),
+                    k_power_of_2,
+                )
+            })
+            .unwrap_or_default();
 
-        let W3 = concatenate_with_padding(
-            &concat_vec!(&lookup_coeff.hs, &lookup_coeff.gs),
-            k_power_of_2,
-        );
+        if let Some(arg) = self.permutation_arguments.as_ref() {
+            W3.extend(arg.evaluate_h_g(self.k, &self.fixed_columns, advice, r1, r2));
+        }
 
         let C3 = {
             let _s = info_span!("lookup_commit").entered();
@@ -661,9 +700,40 @@ impl<F: PrimeField> PlonkStructure<F> {
         })
     }
 
-    pub fn permutation_matrix(&self) -> SparseMatrix<F> {
-        self.permutation_data
-            .matrix(self.k, &self.num_io, self.num_advice_columns)
+    /// Check copy constraints of (folded) witness `W`
+    ///
+    /// Relations of helpers `(h, g)` are part of custom gates, so here it remains to check their
+    /// linear part, see [`permutation::Arguments`], and copy constraints of instance cells, see
+    /// [`PermutationData::is_sat_instance_cells`]. Without the argument copy constraints of all
+    /// cells are checked directly, see [`PermutationData::is_sat_cells`]
+    ///
+    /// `permutation_data` is passed separately, because folding schemes may exclude copy
+    /// constraints of instance columns that they do not fold
+    ///
+    /// - `get_instance`: value of instance column at `(column, row)`
+    /// - `W`: witness of all rounds, advice columns are placed at the beginning of the first one
+    pub(crate) fn is_sat_permutation(
+        &self,
+        permutation_data: &PermutationData,
+        get_instance: impl Sync + Fn(usize, usize) -> F,
+        W: &[Vec<F>],
+    ) -> bool {
+        let nrow = 1 << self.k;
+
+        let get_cell = |column: &Column<Any>, row: usize| match column.column_type() {
+            Any::Instance => get_instance(column.index(), row),
+            Any::Advice(_) => W[0][column.index() * nrow + row],
+            Any::Fixed => unreachable!("'fixed column' can't be a part of permutation"),
+        };
+
+        match (self.permutation_arguments.as_ref(), W.last()) {
+            (Some(arg), Some(W_last)) => {
+                arg.is_sat_log_derivative(self.k, &W_last[2 * self.num_lookups() * nrow..])
+                    && permutation_data.is_sat_instance_cells(&self.num_io, get_cell)
+            }
+            (Some(_), None) => false,
+            (None, _) => permutation_data.is_sat_cells(&self.num_io, get_cell),
+        }
     }
 }
 
@@ -687,6 +757,7 @@ pub(crate) fn get_evaluate_witness_fn<'link, F: PrimeField>(
     let eval_domain = PlonkEvalDomain {
         num_advice: S.num_advice_columns,
         num_lookup: S.num_lookups(),
+        num_permutation: S.num_permutation_columns(),
         selectors: &S.selectors,
         fixed: &S.fixed_columns,
         challenges: trace.get_challenges(),
@@ -711,7 +782,7 @@ pub(crate) fn get_evaluate_witness_fn<'link, F: PrimeField>(
         }
 
         let gate_index = index / total_row;
-        let row_index = index & total_row;
+        let row_index = index % total_row;
 
         evaluators[gate_index].evaluate(&eval_domain, row_index)
     }
@@ -887,4 +958,36 @@ pub(crate) mod test_eval_witness {
                 assert_eq!(v, Ok(Field::ZERO));
             });
     }
+
+    /// Row zero is intact, so only evaluation of other rows catches the broken witness
+    #[test]
+    fn broken_row() {
+        let runner = CircuitRunner::<Field, _>::new(
+            12,
+            poseidon_circuit::TestPoseidonCircuit::<_, 50>::default(),
+            vec![],
+        );
+
+        let S = runner.try_collect_plonk_structure().unwrap();
+
+        let mut witness = runner.try_collect_witness().unwrap();
+        for column in witness.iter_mut() {
+            column[1] += Field::ONE;
+        }
+
+        let PlonkTrace { u, w } = S
+            .run_sps_protocol(
+                &CommitmentKey::<Curve>::setup(15, b"k"),
+                &[],
+                &witness,
+                &mut RO::new(PoseidonSpec::new(R_F1, R_P1)),
+            )
+            .unwrap();
+
+        assert!(
+            super::iter_evaluate_witness::<Field>(&S, &PlonkTrace { u, w })
+                .any(|v| v != Ok(Field::ZERO)),
+            "should fail: row 1 of each advice column is broken"
+        );
+    }
 }
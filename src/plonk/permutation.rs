@@ -1,14 +1,18 @@
 //! Adapted from halo2/halo2_proofs/src/plonk/permutation/keygen.rs
-use std::{cmp::Ordering, collections::HashSet, iter};
+use std::{cmp::Ordering, iter};
 
 use halo2_proofs::{
-    halo2curves::ff::PrimeField,
-    plonk::{permutation::Argument, Any, Column, Error},
+    halo2curves::ff::{BatchInvert, Field, PrimeField},
+    plonk::{permutation::Argument, Any, Column, ConstraintSystem, Error},
+    poly::Rotation,
 };
+use itertools::Itertools;
+use rayon::prelude::*;
 use serde::{Serialize, Serializer};
 use tracing::*;
 
-use crate::polynomial::sparse::SparseMatrix;
+use super::columns::{ColumnsView, FixedColumns};
+use crate::polynomial::{Expression, Query};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Assembly {
@@ -130,21 +134,88 @@ impl From<&Assembly> for PermutationData {
 }
 
 impl PermutationData {
-    pub fn matrix<F: PrimeField>(
+    /// Check copy constraints of instance cells
+    ///
+    /// Copy constraints between advice cells are enforced by folding, see [`Arguments`]. For each
+    /// instance cell it remains to check `Z[c] = Z[σ(c)]`: together with [`Arguments`] it covers
+    /// whole cycle. These relations are linear in `Z`, so they are preserved by folding and are
+    /// checked on the accumulator directly.
+    ///
+    /// # Parameters
+    ///
+    /// - `num_io`: instance columns lengths, rows of instance columns beyond it are not part of
+    ///   the argument
+    /// - `get_cell`: value of `Z` at `(column, row)`, called only for instance & advice columns
+    #[instrument(name = "permutation_instance_cells", skip_all)]
+    pub(crate) fn is_sat_instance_cells<F: PrimeField>(
         &self,
-        k_table_size: usize,
         num_io: &[usize],
-        num_advice_columns: usize,
-    ) -> SparseMatrix<F> {
-        construct_permutation_matrix(
-            k_table_size,
+        get_cell: impl Sync + Fn(&Column<Any>, usize) -> F,
+    ) -> bool {
+        self.is_sat_columns(
             num_io,
-            &self.columns,
-            num_advice_columns,
-            &self.mapping,
+            |column| column.column_type().eq(&Any::Instance),
+            get_cell,
         )
     }
 
+    /// Check copy constraints of all cells
+    ///
+    /// Used when copy constraints of advice cells are not folded, see
+    /// [`Arguments::configure_by_default`]. For each cell it checks `Z[c] = Z[σ(c)]`, these
+    /// relations are linear in `Z`, so they are preserved by folding and are checked on the
+    /// accumulator directly.
+    ///
+    /// Parameters are the same as for [`PermutationData::is_sat_instance_cells`]
+    #[instrument(name = "permutation_cells", skip_all)]
+    pub(crate) fn is_sat_cells<F: PrimeField>(
+        &self,
+        num_io: &[usize],
+        get_cell: impl Sync + Fn(&Column<Any>, usize) -> F,
+    ) -> bool {
+        self.is_sat_columns(num_io, |_| true, get_cell)
+    }
+
+    fn is_sat_columns<F: PrimeField>(
+        &self,
+        num_io: &[usize],
+        filter: impl Sync + Fn(&Column<Any>) -> bool,
+        get_cell: impl Sync + Fn(&Column<Any>, usize) -> F,
+    ) -> bool {
+        self.columns
+            .par_iter()
+            .zip_eq(self.mapping.par_iter())
+            .filter(|(column, _)| filter(column))
+            .all(|(column, mapping)| {
+                let rows_count = match column.column_type() {
+                    Any::Instance => num_io.get(column.index()).copied().unwrap_or_default(),
+                    _ => mapping.len(),
+                };
+
+                mapping
+                    .iter()
+                    .take(rows_count)
+                    .enumerate()
+                    .all(|(row, (next_column, next_row))| {
+                        get_cell(column, row) == get_cell(&self.columns[*next_column], *next_row)
+                    })
+            })
+    }
+
+    /// Copy of `self` with all instance cells cut out of cycles, see
+    /// [`PermutationData::rm_copy_constraints`]
+    fn advice_cycles(&self) -> Self {
+        let instance_columns = self
+            .columns
+            .iter()
+            .filter(|column| column.column_type().eq(&Any::Instance))
+            .map(|column| column.index())
+            .collect::<Vec<_>>();
+
+        self.clone()
+            .rm_copy_constraints(instance_columns.into_iter())
+    }
+
     #[instrument(level = "debug", skip_all)]
     /// Removes copy constraints for specific instance columns from the permutation mapping.
     ///
@@ -243,6 +314,244 @@ impl PermutationData {
     }
 }
 
+/// Unique `id(c)` of advice cell
+fn id<F: PrimeField>(advice_column: usize, row: usize, num_rows: usize) -> F {
+    F::from((advice_column * num_rows + row) as u64)
+}
+
+/// Copy constraints between advice cells as a log-derivative argument, folded together with
+/// custom gates
+///
+/// Each advice cell `c` gets a unique `id(c) = column * 2^k + row`. All copy constraints
+/// `Z[c] = Z[σ(c)]` hold iff the multisets `{(Z[c], id(c))}` and `{(Z[c], id(σ(c)))}` are equal.
+/// For this, the last round of special soundness protocol commits two inverse helpers per cell:
+///
+/// - `h(c) = 1 / (Z[c] + beta * id(c) + gamma)`
+/// - `g(c) = 1 / (Z[c] + beta * id(σ(c)) + gamma)`
+///
+/// where `beta` & `gamma` are `r1` & `r2`, see [`super::PlonkInstance::challenges`].
+///
+/// The relations `h * (Z + beta * id + gamma) - 1` & `g * (Z + beta * id(σ) + gamma) - 1` are
+/// part of custom gates and are folded with them. The remaining `Σ_c h(c) - g(c) = 0` is linear,
+/// so it is preserved by folding and is checked on the accumulator only.
+///
+/// Instance cells are cut out of cycles, their copy constraints are checked by
+/// [`PermutationData::is_sat_instance_cells`]
+///
+/// The argument is opt-in, see [`Arguments::configure_by_default`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Arguments {
+    /// Indexes of advice columns in permutation
+    pub(crate) advice_columns: Box<[usize]>,
+    /// Index of fixed column with row numbers, it's followed by fixed columns with `id(σ(c))` of
+    /// each of `advice_columns`
+    pub(crate) fixed_offset: usize,
+}
+
+impl Arguments {
+    /// Add fixed columns of the argument to `cs`
+    ///
+    /// Must be called right after [`halo2_proofs::plonk::Circuit::configure`], when all columns
+    /// of permutation are known. `None` if there are no advice columns in permutation
+    pub(crate) fn configure<F: Field>(cs: &mut ConstraintSystem<F>) -> Option<Self> {
+        let advice_columns = cs
+            .permutation
+            .columns
+            .iter()
+            .filter(|column| matches!(column.column_type(), Any::Advice(_)))
+            .map(|column| column.index())
+            .sorted()
+            .collect::<Box<[_]>>();
+
+        if advice_columns.is_empty() {
+            return None;
+        }
+
+        let fixed_offset = cs.num_fixed_columns();
+        for _ in 0..=advice_columns.len() {
+            cs.fixed_column();
+        }
+
+        Some(Self {
+            advice_columns,
+            fixed_offset,
+        })
+    }
+
+    /// [`Arguments::configure`] only with `fold-copy-constraints` feature
+    ///
+    /// Without the argument copy constraints of all cells are checked on the accumulator directly,
+    /// see [`PermutationData::is_sat_cells`]. It doesn't cost extra witness columns & SPS round,
+    /// but is left to the decider. To opt in for one circuit only, see
+    /// [`crate::table::CircuitRunner::with_folded_copy_constraints`]
+    pub(crate) fn configure_by_default<F: Field>(cs: &mut ConstraintSystem<F>) -> Option<Self> {
+        if cfg!(feature = "fold-copy-constraints") {
+            Self::configure(cs)
+        } else {
+            None
+        }
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.advice_columns.len()
+    }
+
+    /// Values of fixed columns added by [`Arguments::configure`]: row numbers & `id(σ(c))` of
+    /// each of advice columns
+    pub(crate) fn fixed_columns<F: PrimeField>(
+        &self,
+        k_table_size: usize,
+        permutation_data: &PermutationData,
+    ) -> impl Iterator<Item = (usize, Vec<F>)> {
+        let num_rows = 1 << k_table_size;
+        let PermutationData { columns, mapping } = permutation_data.advice_cycles();
+
+        let sigma_ids = self
+            .advice_columns
+            .iter()
+            .map(|advice_column| {
+                let position = columns
+                    .iter()
+                    .position(|column| {
+                        matches!(column.column_type(), Any::Advice(_))
+                            && column.index().eq(advice_column)
+                    })
+                    .expect("advice column from permutation");
+
+                mapping[position]
+                    .iter()
+                    .map(|(next_column, next_row)| {
+                        let next_column = columns[*next_column];
+                        match next_column.column_type() {
+                            Any::Advice(_) => id(next_column.index(), *next_row, num_rows),
+                            Any::Instance => unreachable!("instance cells are cut out"),
+                            Any::Fixed => {
+                                unreachable!("'fixed column' can't be a part of permutation")
+                            }
+                        }
+                    })
+                    .collect::<Vec<F>>()
+            })
+            .collect::<Vec<_>>();
+
+        let fixed_offset = self.fixed_offset;
+        iter::once((0..num_rows).map(|row| F::from(row as u64)).collect())
+            .chain(sigma_ids)
+            .enumerate()
+            .map(move |(index, column)| (fixed_offset + index, column))
+    }
+
+    /// `h * (Z + beta * id + gamma) - 1` & `g * (Z + beta * id(σ) + gamma) - 1` of each of advice
+    /// columns
+    ///
+    /// Helpers `(h, g)` of each column follow each other after lookup variables, see
+    /// [`crate::polynomial::expression::QueryIndexContext`]
+    pub fn to_expressions<F: PrimeField>(
+        &self,
+        cs: &ConstraintSystem<F>,
+        k_table_size: usize,
+    ) -> Vec<Expression<F>> {
+        let num_rows = 1 << k_table_size;
+        let query = |index: usize| {
+            Expression::Polynomial(Query {
+                index,
+                rotation: Rotation(0),
+            })
+        };
+
+        let fixed_offset = cs.num_selectors + self.fixed_offset;
+        let advice_offset = cs.num_selectors + cs.num_fixed_columns();
+        let helpers_offset = advice_offset + cs.num_advice_columns() + 5 * cs.lookups().len();
+
+        let [beta, gamma] = [0, 1].map(Expression::Challenge);
+
+        self.advice_columns
+            .iter()
+            .enumerate()
+            .flat_map(|(index, advice_column)| {
+                let z = query(advice_offset + advice_column);
+                let id = query(fixed_offset)
+                    + Expression::Constant(F::from((advice_column * num_rows) as u64));
+                let sigma_id = query(fixed_offset + 1 + index);
+                let [h, g] = [0, 1].map(|sub_index| query(helpers_offset + 2 * index + sub_index));
+
+                [
+                    h * (z.clone() + beta.clone() * id + gamma.clone())
+                        - Expression::Constant(F::ONE),
+                    g * (z + beta.clone() * sigma_id + gamma.clone())
+                        - Expression::Constant(F::ONE),
+                ]
+            })
+            .collect()
+    }
+
+    /// Evaluate helpers `(h, g)` of each of advice columns, columns are placed one after another
+    /// in the order of [`Arguments::to_expressions`]
+    #[instrument(name = "permutation_helpers", skip_all)]
+    pub(crate) fn evaluate_h_g<F: PrimeField>(
+        &self,
+        k_table_size: usize,
+        fixed: &FixedColumns<F>,
+        advice: &[Vec<F>],
+        beta: F,
+        gamma: F,
+    ) -> Vec<F> {
+        let num_rows = 1 << k_table_size;
+
+        self.advice_columns
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(index, advice_column)| {
+                let z = |row: usize| {
+                    advice[*advice_column].get(row).copied().unwrap_or(F::ZERO) + gamma
+                };
+
+                let mut h = (0..num_rows)
+                    .map(|row| z(row) + beta * id::<F>(*advice_column, row, num_rows))
+                    .collect::<Vec<_>>();
+                let mut g = (0..num_rows)
+                    .map(|row| {
+                        let sigma_id = fixed
+                            .get_cell(self.fixed_offset + 1 + index, row)
+                            .expect("fixed columns of permutation argument are present");
+                        z(row) + beta * sigma_id
+                    })
+                    .collect::<Vec<_>>();
+
+                h.iter_mut().batch_invert();
+                g.iter_mut().batch_invert();
+
+                h.into_iter().chain(g)
+            })
+            .collect()
+    }
+
+    /// Check `Σ_c h(c) - g(c) = 0`
+    ///
+    /// - `helpers`: part of the last round witness, that starts with helpers of the argument
+    pub(crate) fn is_sat_log_derivative<F: PrimeField>(
+        &self,
+        k_table_size: usize,
+        helpers: &[F],
+    ) -> bool {
+        helpers
+            .par_chunks(1 << k_table_size)
+            .take(2 * self.num_columns())
+            .enumerate()
+            .map(|(index, column)| {
+                let sum = column.iter().sum::<F>();
+                if index % 2 == 0 {
+                    sum
+                } else {
+                    -sum
+                }
+            })
+            .sum::<F>()
+            .is_zero()
+            .into()
+    }
+}
+
 impl Serialize for PermutationData {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -286,121 +595,29 @@ impl Serialize for PermutationData {
     }
 }
 
-/// Constructs a sparse permutation matrix `P` of size `N * N` from copy constraints.
-///
-/// The function accounts for the changes due to folding, which affects the values of advice/instance
-/// columns while keeping fixed column values unchanged. Consequently, fixed columns are not allowed
-/// in copy constraints.
-///
-/// Assume we have 1 instance column and `n` advice columns, with a total of `r` rows. Note that the
-/// instance column contains only `num_io` items:
-///
-/// `N = num_io + r * n`
-///
-/// Let `(i_1, ..., i_{io})` represent the values of the instance column, and
-/// `(x_1, ..., x_{n * r})` be the concatenated values of the advice columns. Define the vector:
-///
-/// `Z = (i_1, ..., i_{io}, x_1, ..., x_{n * r})`
-///
-/// The goal is to find a permutation matrix `P` such that the copy constraints satisfy:
-///
-/// `P * Z - Z = 0`
-///
-/// This relation remains invariant under the folding scheme.
-#[instrument(name = "permutation", skip_all)]
-pub(crate) fn construct_permutation_matrix<F: PrimeField>(
-    k_table_size: usize,
-    num_io: &[usize],
-    perm_columns: &[Column<Any>],
-    num_advice: usize,
-    permutation_mapping: &[Vec<(usize, usize)>],
-) -> SparseMatrix<F> {
-    let num_rows = 1 << k_table_size;
-
-    // Lengths of each row of the matrix
-    let rows_len = num_io
-        .iter()
-        .copied()
-        .chain(iter::repeat(num_rows).take(num_advice))
-        .collect::<Box<[_]>>();
-
-    let to_flat_column_offset = |column: Column<Any>| -> usize {
-        match column.column_type() {
-            Any::Instance => column.index(),
-            Any::Advice(_) => num_io.len() + column.index(),
-            Any::Fixed => unreachable!("'fixed column' can't be a part of permutation"),
-        }
-    };
-
-    let to_flat_index = |column: Column<Any>, row: usize| -> usize {
-        rows_len
-            .iter()
-            .take(to_flat_column_offset(column))
-            .sum::<usize>()
-            + row
-    };
-
-    let flated_rows = rows_len.iter().sum();
-    let mut columns_not_in_perm = HashSet::<usize>::from_iter(0..num_io.len() + num_advice);
-
-    let mut sparse_matrix_p = Vec::with_capacity(flated_rows);
-    for (left_col, mapping_vec) in permutation_mapping.iter().enumerate() {
-        let left_col = perm_columns[left_col];
-        columns_not_in_perm.remove(&to_flat_column_offset(left_col));
-
-        let instance_rows_count = match left_col.column_type() {
-            Any::Instance => num_io.get(left_col.index()),
-            _ => None,
-        };
-
-        for (left_row, (cycle_col, cycle_row)) in mapping_vec.iter().enumerate() {
-            if matches!(
-                instance_rows_count,
-                Some(count) if left_row >= *count
-            ) {
-                continue;
-            }
-
-            let right_col = perm_columns[*cycle_col];
-            columns_not_in_perm.remove(&to_flat_column_offset(right_col));
-
-            let left_z_idx = to_flat_index(left_col, left_row);
-            let right_z_idx = to_flat_index(right_col, *cycle_row);
-
-            sparse_matrix_p.push((left_z_idx, right_z_idx, F::ONE));
-        }
-    }
-
-    columns_not_in_perm.into_iter().for_each(|column_offset| {
-        let col_offset = rows_len.iter().take(column_offset).sum::<usize>();
-
-        for row in 0..num_rows {
-            let z_idx = col_offset + row;
-            sparse_matrix_p.push((z_idx, z_idx, F::ONE));
-        }
-    });
-
-    sparse_matrix_p
-}
-
 #[cfg(test)]
 mod test {
     use std::{
-        iter,
+        array, iter,
         mem::{self, MaybeUninit},
         ptr,
     };
 
     use halo2_proofs::{
         arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
         halo2curves::pasta::Fq,
-        plonk::{Advice, ColumnType},
+        plonk::{Advice, Circuit, ColumnType, Instance},
     };
     use itertools::Itertools;
-    use tracing::*;
 
     use super::*;
-    use crate::polynomial::sparse;
+    use crate::{
+        commitment,
+        halo2curves::bn256,
+        poseidon::{random_oracle::ROPair, PoseidonRO, ROTrait, Spec},
+        table::CircuitRunner,
+    };
 
     // Bypass the lack of a constructor for `Column`
     fn column<C: ColumnType>(index: usize, column_type: C) -> Column<C> {
@@ -443,7 +660,6 @@ mod test {
         assert_eq!(fixed_col.column_type(), &Any::Fixed);
     }
 
-    const K_TABLE_SIZE: u32 = 3;
     const NUM_ROWS: usize = 8;
     const NUM_INSTANCES: usize = 10;
     const NUM_ADVICES: usize = 10;
@@ -465,25 +681,14 @@ mod test {
     }
 
     fn check(permutation_data: &PermutationData, vec_Z: &[Fq]) -> bool {
-        sparse::matrix_multiply(
-            &permutation_data.matrix(
-                K_TABLE_SIZE as usize,
-                &iter::repeat(NUM_ROWS).take(NUM_INSTANCES).collect_vec(),
-                NUM_ADVICES,
-            ),
-            vec_Z,
-        )
-        .into_iter()
-        .zip_eq(vec_Z)
-        .enumerate()
-        .map(|(index, (y, z))| {
-            let diff = y - z;
-
-            warn!("mismatch at {index} with: {y:?} - {z:?} = {diff:?}");
+        let rows_len = iter::repeat(NUM_ROWS)
+            .take(NUM_INSTANCES + NUM_ADVICES)
+            .collect_vec();
 
-            diff
-        })
-        .all(|f| f.is_zero_vartime())
+        permutation_data.is_sat_instance_cells(
+            &iter::repeat(NUM_ROWS).take(NUM_INSTANCES).collect_vec(),
+            |column, row| vec_Z[to_flat_index(*column, row, &rows_len)],
+        )
     }
 
     fn random_Z() -> Vec<Fq> {
@@ -629,4 +834,148 @@ mod test {
             "should work: new copy constraint is work"
         );
     }
+
+    #[derive(Clone, Debug)]
+    struct CopyConfig {
+        advice: [Column<Advice>; 2],
+        instance: Column<Instance>,
+    }
+
+    /// Copies `value` between two advice cells and to the instance column
+    #[derive(Default)]
+    struct CopyCircuit {
+        value: bn256::Fr,
+    }
+
+    impl Circuit<bn256::Fr> for CopyCircuit {
+        type Config = CopyConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let advice = array::from_fn(|_| {
+                let column = meta.advice_column();
+                meta.enable_equality(column);
+                column
+            });
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            CopyConfig { advice, instance }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            let cell = layouter.assign_region(
+                || "copy",
+                |mut region| {
+                    region
+                        .assign_advice(|| "a", config.advice[0], 0, || Value::known(self.value))?
+                        .copy_advice(|| "b", &mut region, config.advice[1], 1)
+                },
+            )?;
+
+            layouter.constrain_instance(cell.cell(), config.instance, 0)
+        }
+    }
+
+    /// Without the argument copy constraints of all cells are checked on the witness directly
+    #[cfg(not(feature = "fold-copy-constraints"))]
+    #[tracing_test::traced_test]
+    #[test]
+    fn unfolded_copy_constraints() {
+        const K: u32 = 4;
+
+        let value = bn256::Fr::from(7);
+        let instances = vec![vec![value]];
+
+        let runner = CircuitRunner::new(K, CopyCircuit { value }, instances.clone());
+        let S = runner.try_collect_plonk_structure().unwrap();
+
+        assert!(S.permutation_arguments.is_none());
+        assert_eq!(S.num_fold_vars(), S.num_advice_columns);
+
+        let get_instance = |column: usize, row: usize| instances[column][row];
+
+        let W = vec![runner.try_collect_witness().unwrap().concat()];
+        assert!(S.is_sat_permutation(&S.permutation_data, get_instance, &W));
+        assert!(
+            !S.is_sat_permutation(&S.permutation_data, |_, _| value.double(), &W),
+            "should fail: instance cell is not equal to its advice copy"
+        );
+
+        let mut broken_W = W.clone();
+        broken_W[0][(1 << K) + 1] += bn256::Fr::ONE;
+        assert!(
+            !S.is_sat_permutation(&S.permutation_data, get_instance, &broken_W),
+            "should fail: copy constraint between advice cells is broken"
+        );
+    }
+
+    #[tracing_test::traced_test]
+    #[test]
+    fn folded_argument() {
+        const K: u32 = 4;
+        type RO = <PoseidonRO<3, 2> as ROPair<bn256::Fq>>::OffCircuit;
+        let ro = || RO::new(Spec::<bn256::Fq, 3, 2>::new(4, 3));
+
+        let value = bn256::Fr::from(7);
+        let instances = vec![vec![value]];
+
+        let runner = CircuitRunner::new(K, CopyCircuit { value }, instances.clone())
+            .with_folded_copy_constraints();
+        let S = runner.try_collect_plonk_structure().unwrap();
+        let ck = commitment::setup_smallest_key::<bn256::G1Affine>(
+            K,
+            &runner.cs,
+            runner.permutation.as_ref(),
+            b"permutation",
+        );
+
+        let arg = S.permutation_arguments.as_ref().unwrap();
+        assert_eq!(arg.num_columns(), 2);
+
+        let prove = |advice: &[Vec<bn256::Fr>]| {
+            S.run_sps_protocol(&ck, &instances, advice, &mut ro())
+                .unwrap()
+        };
+        let get_instance = |column: usize, row: usize| instances[column][row];
+
+        let witness = runner.try_collect_witness().unwrap();
+        let honest = prove(&witness);
+
+        S.is_sat(&ck, &mut ro(), &honest.u, &honest.w).unwrap();
+        assert!(S.is_sat_permutation(&S.permutation_data, get_instance, &honest.w.W));
+        assert!(
+            !S.is_sat_permutation(&S.permutation_data, |_, _| value.double(), &honest.w.W),
+            "should fail: instance cell is not equal to its advice copy"
+        );
+
+        let mut broken_witness = witness.clone();
+        broken_witness[1][1] += bn256::Fr::ONE;
+        let mut broken = prove(&broken_witness);
+
+        // helpers are honest, so folded relations hold & only the sum fails
+        S.is_sat(&ck, &mut ro(), &broken.u, &broken.w).unwrap();
+        assert!(
+            !S.is_sat_permutation(&S.permutation_data, get_instance, &broken.w.W),
+            "should fail: copy constraint between advice cells is broken"
+        );
+
+        // with `g = h` the sum holds, but folded relations don't
+        let num_rows = 1 << K;
+        let helpers = broken.w.W.last_mut().unwrap();
+        for column in helpers.chunks_mut(2 * num_rows) {
+            let (h, g) = column.split_at_mut(num_rows);
+            g.copy_from_slice(h);
+        }
+        assert!(arg.is_sat_log_derivative(K as usize, helpers));
+        assert!(S.is_sat(&ck, &mut ro(), &broken.u, &broken.w).is_err());
+    }
 }
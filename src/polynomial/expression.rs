@@ -42,6 +42,7 @@ pub struct QueryIndexContext {
     pub num_advice: usize,
     pub num_challenges: usize,
     pub num_lookups: usize,
+    pub num_permutation_columns: usize,
 }
 impl<F: PrimeField> From<&PlonkStructure<F>> for QueryIndexContext {
     fn from(S: &PlonkStructure<F>) -> Self {
@@ -51,13 +52,14 @@ impl<F: PrimeField> From<&PlonkStructure<F>> for QueryIndexContext {
             num_selectors: S.selectors.len(),
             num_challenges: S.num_challenges,
             num_lookups: S.num_lookups(),
+            num_permutation_columns: S.num_permutation_columns(),
         }
     }
 }
 
 impl QueryIndexContext {
     pub fn num_fold_vars(self) -> usize {
-        self.num_advice + self.num_lookups * 5
+        self.num_advice + self.num_lookups * 5 + self.num_permutation_columns * 2
     }
 
     pub fn shift_advice_index(self, advice_poly_index: usize) -> usize {
@@ -67,6 +69,10 @@ impl QueryIndexContext {
     pub fn shift_lookup_index(self, lookup_poly_index: usize) -> usize {
         lookup_poly_index + self.num_fold_vars()
     }
+
+    pub fn shift_permutation_index(self, permutation_poly_index: usize) -> usize {
+        permutation_poly_index + self.num_fold_vars()
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize)]
@@ -81,6 +87,7 @@ pub enum QueryType {
     Fixed,
     Advice,
     Lookup,
+    Permutation,
 }
 
 impl Query {
@@ -95,6 +102,14 @@ impl Query {
             < ctx.num_selectors + ctx.num_fixed + ctx.num_advice + (5 * ctx.num_lookups)
         {
             QueryType::Lookup
+        } else if self.index
+            < ctx.num_selectors
+                + ctx.num_fixed
+                + ctx.num_advice
+                + (5 * ctx.num_lookups)
+                + (2 * ctx.num_permutation_columns)
+        {
+            QueryType::Permutation
         } else {
             unreachable!("unknown index {} in {ctx:?}", self.index)
         }
@@ -365,7 +380,7 @@ impl<F: PrimeField> Expression<F> {
             Polynomial(polynomial) => HomogeneousExpression {
                 expr: Polynomial(*polynomial),
                 degree: match polynomial.subtype(ctx) {
-                    QueryType::Advice | QueryType::Lookup => 1,
+                    QueryType::Advice | QueryType::Lookup | QueryType::Permutation => 1,
                     _other => 0,
                 },
             },
@@ -432,7 +447,7 @@ impl<F: PrimeField> Expression<F> {
         self.evaluate(
             &|_| 0,
             &|poly| match poly.subtype(ctx) {
-                QueryType::Advice | QueryType::Lookup => 1,
+                QueryType::Advice | QueryType::Lookup | QueryType::Permutation => 1,
                 _other => 0,
             },
             &|_| 1,
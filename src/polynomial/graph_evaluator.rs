@@ -45,7 +45,10 @@ use super::Expression;
 /// It is an adaptation for our needs of the [code from
 /// halo2](https://github.com/privacy-scaling-explorations/halo2/blob/main/halo2_backend/src/plonk/evaluation.rs#L200)
 use crate::ff::PrimeField;
-use crate::plonk::eval::{Error as EvalError, GetDataForEval};
+use crate::plonk::{
+    columns::ColumnsView,
+    eval::{Error as EvalError, GetDataForEval},
+};
 
 /// Return the index in the polynomial of size `isize` after rotation `rot`.
 fn get_rotation_idx(idx: usize, rot: i32, num_row: usize) -> usize {
@@ -101,18 +104,19 @@ impl Calculation {
             match value {
                 ValueSource::Constant(id) => Ok(constants[*id]),
                 ValueSource::Intermediate(id) => Ok(intermediates[*id]),
-                ValueSource::Fixed { index, rotation } => eval_getter
-                    .get_fixed()
-                    .as_ref()
-                    .get(*index)
-                    .ok_or(EvalError::ColumnVariableIndexOutOfBoundary {
-                        column_index: *index,
-                    })?
-                    .get(rotations[*rotation])
-                    .cloned()
-                    .ok_or(EvalError::RowIndexOutOfBoundary {
-                        row_index: rotations[*rotation],
-                    }),
+                ValueSource::Fixed { index, rotation } => {
+                    let fixed = eval_getter.get_fixed();
+                    if *index >= fixed.columns_count() {
+                        return Err(EvalError::ColumnVariableIndexOutOfBoundary {
+                            column_index: *index,
+                        });
+                    }
+                    fixed.get_cell(*index, rotations[*rotation]).ok_or(
+                        EvalError::RowIndexOutOfBoundary {
+                            row_index: rotations[*rotation],
+                        },
+                    )
+                }
                 ValueSource::Poly { index, rotation } => {
                     Ok(eval_getter.eval_column_var(rotations[*rotation], *index)?)
                 }
@@ -417,11 +421,11 @@ mod tests {
             &self.challenges
         }
 
-        fn get_selectors(&self) -> &impl AsRef<[Vec<bool>]> {
+        fn get_selectors(&self) -> &impl ColumnsView<bool> {
             &self.selectors
         }
 
-        fn get_fixed(&self) -> &impl AsRef<[Vec<F>]> {
+        fn get_fixed(&self) -> &impl ColumnsView<F> {
             &self.fixed
         }
 
@@ -107,6 +107,10 @@ impl<F: PrimeField> GroupedPoly<F> {
                         index: ctx.shift_lookup_index(poly.index),
                         rotation: poly.rotation,
                     }))),
+                    QueryType::Permutation => terms.push(Some(Expression::Polynomial(Query {
+                        index: ctx.shift_permutation_index(poly.index),
+                        rotation: poly.rotation,
+                    }))),
                     _other => (),
                 }
 
@@ -1,11 +1,19 @@
+pub mod poseidon2;
 pub mod poseidon_circuit;
 pub mod poseidon_hash;
 pub mod random_oracle;
 mod spec;
+pub mod sponge;
+pub mod sponge_circuit;
 
+pub use poseidon2::{Poseidon2RO, Poseidon2Spec};
 pub use poseidon_hash::PoseidonHash;
 pub use random_oracle::*;
 pub use spec::Spec;
+pub use sponge::{
+    IOPattern, PoseidonSponge, PoseidonSpongeHash, SpongeOp, RO_ACC_DOMAIN, RO_NARK_DOMAIN,
+};
+pub use sponge_circuit::{PoseidonSpongeChip, PoseidonSpongeHashChip};
 
 use crate::ff::{FromUniformBytes, PrimeField, PrimeFieldBits};
 
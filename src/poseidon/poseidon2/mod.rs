@@ -0,0 +1,32 @@
+//! Poseidon2 permutation (<https://eprint.iacr.org/2023/323>) as a random oracle
+//!
+//! Compared to Poseidon, internal rounds use the cheap matrix `J + diag(d)` instead of MDS, so
+//! off-circuit permutation is faster. The sponge mode is the same as in [`super::PoseidonHash`],
+//! so [`Poseidon2RO`] can replace [`super::PoseidonRO`] wherever [`super::ROPair`] is expected.
+//!
+//! Supported widths are `T` equal to 2, 3 or multiple of 4 with `RATE = T - 1`.
+
+pub mod poseidon2_circuit;
+pub mod poseidon2_hash;
+mod spec;
+
+pub use poseidon2_circuit::Poseidon2Chip;
+pub use poseidon2_hash::Poseidon2Hash;
+pub use spec::Poseidon2Spec;
+
+use super::ROPair;
+use crate::ff::{FromUniformBytes, PrimeField, PrimeFieldBits};
+
+pub struct Poseidon2RO<const T: usize, const RATE: usize>;
+
+impl<const T: usize, const RATE: usize, F: serde::Serialize + PrimeField> ROPair<F>
+    for Poseidon2RO<T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Args = Poseidon2Spec<F, T, RATE>;
+    type Config = crate::main_gate::MainGateConfig<T>;
+
+    type OnCircuit = Poseidon2Chip<F, T, RATE>;
+    type OffCircuit = Poseidon2Hash<F, T, RATE>;
+}
@@ -0,0 +1,401 @@
+use std::num::NonZeroUsize;
+
+use halo2_proofs::{circuit::Value, plonk::Error};
+use tracing::*;
+
+use super::Poseidon2Spec;
+use crate::{
+    constants::MAX_BITS,
+    ff::{FromUniformBytes, PrimeFieldBits},
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx, WrapValue},
+    poseidon::ROCircuitTrait,
+};
+
+/// On-circuit version of [`super::Poseidon2Hash`] on top of [`MainGate`]
+///
+/// Each row of the main gate calculates one element of the next state. Between rounds the state
+/// is kept with the constants of the next round already added, so every round takes `T` rows:
+/// the s-box is applied by `q_5` (only for the first element in internal rounds), the matrix row
+/// is placed into `q_5` & `q_1` and `rc` holds the constant of the next round.
+///
+/// In total the permutation takes `T * (1 + r_f + r_p)` rows and the absorption of a chunk
+/// takes up to `T` rows.
+pub struct Poseidon2Chip<F: PrimeFieldBits, const T: usize, const RATE: usize> {
+    main_gate: MainGate<F, T>,
+    spec: Poseidon2Spec<F, T, RATE>,
+    domain: Option<&'static [u8]>,
+    buf: Vec<WrapValue<F>>,
+}
+
+impl<F: PrimeFieldBits + FromUniformBytes<64>, const T: usize, const RATE: usize> ROCircuitTrait<F>
+    for Poseidon2Chip<F, T, RATE>
+{
+    type Args = Poseidon2Spec<F, T, RATE>;
+    type Config = MainGateConfig<T>;
+
+    fn new(config: Self::Config, spec: Self::Args) -> Self {
+        Self {
+            main_gate: MainGate::new(config),
+            spec,
+            domain: None,
+            buf: Vec::new(),
+        }
+    }
+
+    fn absorb_base(&mut self, base: WrapValue<F>) -> &mut Self {
+        self.update(&[base])
+    }
+
+    fn absorb_point(&mut self, point: [WrapValue<F>; 2]) -> &mut Self {
+        self.update(&point)
+    }
+
+    fn inspect(&mut self, scan: impl FnOnce(&[F])) -> &mut Self
+    where
+        F: Sized,
+    {
+        if let Some(buf) = self
+            .buf
+            .iter()
+            .map(|b| b.value().unwrap())
+            .collect::<Option<Vec<_>>>()
+        {
+            scan(&buf)
+        }
+        self
+    }
+
+    fn squeeze_n_bits(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        num_bits: NonZeroUsize,
+    ) -> Result<Vec<AssignedBit<F>>, Error> {
+        let val = self.squeeze(ctx)?;
+        let res = self.main_gate.le_num_to_bits(ctx, val, MAX_BITS)?;
+        if res.len() >= num_bits.get() {
+            Ok(res[..num_bits.get()].to_vec())
+        } else {
+            Ok(res)
+        }
+    }
+
+    fn squeeze(&mut self, ctx: &mut RegionCtx<'_, F>) -> Result<AssignedValue<F>, Error> {
+        self.squeeze(ctx)
+    }
+}
+
+impl<F: PrimeFieldBits, const T: usize, const RATE: usize> Poseidon2Chip<F, T, RATE> {
+    pub fn new(config: MainGateConfig<T>, spec: Poseidon2Spec<F, T, RATE>) -> Self {
+        Self {
+            main_gate: MainGate::new(config),
+            spec,
+            domain: None,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Binds the hash to the `domain`, see [`Poseidon2Spec::initial_state_with_domain`]
+    pub fn with_domain(mut self, domain: &'static [u8]) -> Self {
+        self.domain = Some(domain);
+        self
+    }
+
+    pub fn update(&mut self, inputs: &[WrapValue<F>]) -> &mut Self {
+        self.buf.extend_from_slice(inputs);
+        self
+    }
+
+    /// Assigns the row `out = sum(q_1[j] * s[j]) + sum(q_5[j] * s[j]^5) + input + rc`
+    ///
+    /// Only the state elements with non-zero coefficients are copied into the row
+    fn assign_row(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>; T],
+        (q_1, q_5): ([F; T], [F; T]),
+        input: Option<&WrapValue<F>>,
+        rc: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        let config = self.main_gate.config();
+        let pow5 = |v: Value<F>| {
+            let v2 = v * v;
+            v2 * v2 * v
+        };
+
+        let mut out = Value::known(rc);
+
+        for (j, s) in state.iter().enumerate() {
+            if q_1[j].is_zero_vartime() && q_5[j].is_zero_vartime() {
+                continue;
+            }
+
+            let si = ctx.assign_advice(|| "state", config.state[j], s.value().copied())?;
+            ctx.constrain_equal(s.cell(), si.cell())?;
+
+            ctx.assign_fixed(|| "q_1", config.q_1[j], q_1[j])?;
+            ctx.assign_fixed(|| "q_5", config.q_5[j], q_5[j])?;
+
+            let s = s.value().copied();
+            out = out + s * Value::known(q_1[j]) + pow5(s) * Value::known(q_5[j]);
+        }
+
+        if let Some(input) = input {
+            let assigned = ctx.assign_advice(|| "input", config.input, input.value())?;
+            if let WrapValue::Assigned(input) = input {
+                ctx.constrain_equal(input.cell(), assigned.cell())?;
+            }
+            ctx.assign_fixed(|| "q_i", config.q_i, F::ONE)?;
+            out = out + input.value();
+        }
+
+        ctx.assign_fixed(|| "rc", config.rc, rc)?;
+        ctx.assign_fixed(|| "q_o", config.q_o, -F::ONE)?;
+        let out = ctx.assign_advice(|| "out", config.out, out)?;
+
+        ctx.next();
+        Ok(out)
+    }
+
+    /// Adds `chunk` & the padding to `state[1..]`
+    fn absorb_chunk(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: [AssignedValue<F>; T],
+        chunk: &[WrapValue<F>],
+    ) -> Result<[AssignedValue<F>; T], Error> {
+        assert!(chunk.len() <= RATE);
+
+        let mut next_state = state.clone();
+        for (i, next) in next_state.iter_mut().enumerate().skip(1) {
+            let input = chunk.get(i - 1);
+            let padding = if i == 1 + chunk.len() {
+                F::ONE
+            } else {
+                F::ZERO
+            };
+
+            if input.is_none() && padding.is_zero_vartime() {
+                continue;
+            }
+
+            let mut q_1 = [F::ZERO; T];
+            q_1[i] = F::ONE;
+            *next = self.assign_row(ctx, &state, (q_1, [F::ZERO; T]), input, padding)?;
+        }
+
+        Ok(next_state)
+    }
+
+    pub fn permutation(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>; T],
+    ) -> Result<[AssignedValue<F>; T], Error> {
+        let round_constants = self.spec.round_constants();
+        let next_constants =
+            |round: usize| round_constants.get(round).copied().unwrap_or([F::ZERO; T]);
+
+        // initial external matrix & constants of the first round
+        let first_constants = next_constants(0);
+        let mut state = self.assign_rows(ctx, state, |i| {
+            (
+                self.spec.external_mds()[i],
+                [F::ZERO; T],
+                first_constants[i],
+            )
+        })?;
+
+        for round in 0..round_constants.len() {
+            let mds = self.spec.round_mds(round);
+            let is_internal = self.spec.is_internal_round(round);
+            let constants = next_constants(round + 1);
+
+            state = self.assign_rows(ctx, &state, |i| {
+                let (mut q_1, mut q_5) = ([F::ZERO; T], mds[i]);
+                if is_internal {
+                    q_1[1..].copy_from_slice(&mds[i][1..]);
+                    q_5[1..].fill(F::ZERO);
+                }
+                (q_1, q_5, constants[i])
+            })?;
+        }
+
+        Ok(state)
+    }
+
+    /// Assigns `T` rows, `coefficients(i)` returns `(q_1, q_5, rc)` of the `i`-th row
+    fn assign_rows(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>; T],
+        coefficients: impl Fn(usize) -> ([F; T], [F; T], F),
+    ) -> Result<[AssignedValue<F>; T], Error> {
+        let next_state = (0..T)
+            .map(|i| {
+                let (q_1, q_5, rc) = coefficients(i);
+                self.assign_row(ctx, state, (q_1, q_5), None, rc)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(next_state
+            .try_into()
+            .expect("Unreachable, because collected `T` elements"))
+    }
+
+    pub fn squeeze(&mut self, ctx: &mut RegionCtx<'_, F>) -> Result<AssignedValue<F>, Error> {
+        if let Some(buf) = self
+            .buf
+            .iter()
+            .map(|val| val.value().unwrap())
+            .collect::<Option<Vec<F>>>()
+        {
+            debug!("On circuit input of hash: {buf:?}");
+        }
+
+        let initial_state = match self.domain {
+            Some(domain) => self.spec.initial_state_with_domain(self.buf.len(), domain),
+            None => self.spec.initial_state(),
+        };
+
+        let mut state: [AssignedValue<F>; T] = initial_state
+            .into_iter()
+            .map(|c| self.main_gate.assign_constant(ctx, c))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .expect("Unreachable, because initial state has `T` elements");
+
+        for chunk in self.buf.chunks(RATE) {
+            state = self.absorb_chunk(ctx, state, chunk)?;
+            state = self.permutation(ctx, &state)?;
+        }
+
+        if self.buf.len() % RATE == 0 {
+            state = self.absorb_chunk(ctx, state, &[])?;
+            state = self.permutation(ctx, &state)?;
+        }
+
+        Ok(state[1].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, Column, ConstraintSystem, Instance},
+    };
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        halo2curves::pasta::Fp,
+        poseidon::{poseidon2::Poseidon2Hash, ROTrait},
+        run_mock_prover_test,
+    };
+
+    const R_F: usize = 8;
+    const R_P: usize = 56;
+    const K: u32 = 12;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<const T: usize> {
+        config: MainGateConfig<T>,
+        instance: Column<Instance>,
+    }
+
+    /// Hashes `inputs`, the first half of them is absorbed as assigned cells
+    struct TestCircuit<const T: usize, const RATE: usize> {
+        inputs: Vec<Fp>,
+        num_bits: NonZeroUsize,
+    }
+
+    impl<const T: usize, const RATE: usize> Circuit<Fp> for TestCircuit<T, RATE> {
+        type Config = TestCircuitConfig<T>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Self::Config {
+                config: MainGate::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<Fp, T>::new(config.config.clone());
+            let mut chip =
+                Poseidon2Chip::<Fp, T, RATE>::new(config.config, Poseidon2Spec::new(R_F, R_P));
+
+            let output = layouter.assign_region(
+                || "poseidon2 hash",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+
+                    let (assigned, unassigned) = self.inputs.split_at(self.inputs.len() / 2);
+                    for input in assigned {
+                        let input = main_gate.assign_value(ctx, Value::known(*input))?;
+                        chip.absorb_base(input.into());
+                    }
+                    chip.absorb_iter(unassigned.iter().map(|input| Value::known(*input)));
+
+                    let bits = chip.squeeze_n_bits(ctx, self.num_bits)?;
+                    main_gate.le_bits_to_num(ctx, &bits)
+                },
+            )?;
+            layouter.constrain_instance(output.cell(), config.instance, 0)?;
+
+            Ok(())
+        }
+    }
+
+    fn check<const T: usize, const RATE: usize>(len: usize) {
+        let inputs = (0..len).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let num_bits = NonZeroUsize::new(128).unwrap();
+
+        let expected = Poseidon2Hash::<Fp, T, RATE>::new(Poseidon2Spec::new(R_F, R_P))
+            .absorb_field_iter(inputs.iter().copied())
+            .squeeze::<Fp>(num_bits);
+
+        run_mock_prover_test!(
+            K,
+            TestCircuit::<T, RATE> { inputs, num_bits },
+            vec![vec![expected]]
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn matches_off_circuit() {
+        check::<3, 2>(5);
+        check::<3, 2>(4);
+        check::<4, 3>(0);
+        check::<4, 3>(7);
+    }
+
+    #[test]
+    fn wrong_output() {
+        let inputs = (0..5).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let num_bits = NonZeroUsize::new(128).unwrap();
+
+        let wrong = Poseidon2Hash::<Fp, 3, 2>::new(Poseidon2Spec::new(R_F, R_P))
+            .absorb_field_iter(inputs.iter().skip(1).copied())
+            .squeeze::<Fp>(num_bits);
+
+        let prover = halo2_proofs::dev::MockProver::run(
+            K,
+            &TestCircuit::<3, 2> { inputs, num_bits },
+            vec![vec![wrong]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::num::NonZeroUsize;
+
+use halo2_proofs::arithmetic::CurveAffine;
+use tracing::*;
+
+use super::Poseidon2Spec;
+use crate::{
+    ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
+    poseidon::{ROConstantsTrait, ROTrait},
+    util::{self, bits_to_fe_le, fe_to_bits_le},
+};
+
+impl<F: PrimeField, const T: usize, const RATE: usize> ROConstantsTrait
+    for Poseidon2Spec<F, T, RATE>
+{
+    fn new(r_f: usize, r_p: usize) -> Self {
+        Poseidon2Spec::new(r_f, r_p)
+    }
+}
+
+/// Off-circuit sponge over the Poseidon2 permutation
+///
+/// The sponge mode is the same as in [`crate::poseidon::PoseidonHash`]: inputs are added to
+/// `state[1..]` by chunks of `RATE`, the chunk is padded by one, the exact input is followed by
+/// an empty padded chunk and `state[1]` is the output
+#[derive(Clone, Debug)]
+pub struct Poseidon2Hash<F: PrimeField, const T: usize, const RATE: usize> {
+    spec: Poseidon2Spec<F, T, RATE>,
+    domain: Option<&'static [u8]>,
+    buf: Vec<F>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> ROTrait<F> for Poseidon2Hash<F, T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Constants = Poseidon2Spec<F, T, RATE>;
+
+    fn new(constants: Self::Constants) -> Self {
+        Self {
+            spec: constants,
+            domain: None,
+            buf: Vec::new(),
+        }
+    }
+
+    fn absorb_field(&mut self, base: F) -> &mut Self {
+        self.update(&[base]);
+        self
+    }
+
+    fn absorb_point<C: CurveAffine>(&mut self, point: &C) -> &mut Self {
+        let encoded = point.coordinates().map(|coordinates| {
+            [coordinates.x(), coordinates.y()]
+                .into_iter()
+                .map(|v| util::fe_to_fe(v).unwrap())
+                .collect::<Vec<_>>()
+        });
+        if bool::from(encoded.is_some()) {
+            self.update(&encoded.unwrap())
+        } else {
+            self.update(&[F::ZERO, F::ZERO]) // C is infinity
+        }
+
+        self
+    }
+
+    fn inspect(&mut self, inspect: impl FnOnce(&[F])) -> &mut Self {
+        inspect(&self.buf);
+        self
+    }
+
+    #[instrument(skip_all)]
+    fn squeeze<D: PrimeField>(&mut self, num_bits: NonZeroUsize) -> D {
+        self.output::<D>(num_bits)
+    }
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> Poseidon2Hash<F, T, RATE> {
+    /// Binds the hash to the `domain`, see [`Poseidon2Spec::initial_state_with_domain`]
+    pub fn with_domain(mut self, domain: &'static [u8]) -> Self {
+        self.domain = Some(domain);
+        self
+    }
+
+    fn update(&mut self, elements: &[F]) {
+        self.buf.extend_from_slice(elements);
+    }
+
+    pub fn digest<F1: PrimeField>(
+        spec: Poseidon2Spec<F, T, RATE>,
+        elements: &[F],
+        num_bits: NonZeroUsize,
+    ) -> F1 {
+        let mut s = Self {
+            spec,
+            domain: None,
+            buf: elements.to_vec(),
+        };
+        s.output(num_bits)
+    }
+
+    pub fn output<F1: PrimeField>(&self, num_bits: NonZeroUsize) -> F1 {
+        debug!("Off circuit input of hash: {:?}", self.buf);
+
+        let mut state = match self.domain {
+            Some(domain) => self.spec.initial_state_with_domain(self.buf.len(), domain),
+            None => self.spec.initial_state(),
+        };
+
+        for chunk in self.buf.chunks(RATE) {
+            Self::absorb_chunk(&mut state, chunk);
+            self.spec.permute(&mut state);
+        }
+        if self.buf.len() % RATE == 0 {
+            Self::absorb_chunk(&mut state, &[]);
+            self.spec.permute(&mut state);
+        }
+
+        let mut bits = fe_to_bits_le(&state[1]);
+        if bits.len() < num_bits.get() {
+            bits.resize(num_bits.get(), false);
+        }
+        bits_to_fe_le(bits[..num_bits.get()].to_vec())
+    }
+
+    fn absorb_chunk(state: &mut [F; T], chunk: &[F]) {
+        assert!(chunk.len() <= RATE);
+
+        for (s, input) in state.iter_mut().skip(1).zip(chunk.iter()) {
+            *s += input;
+        }
+        if chunk.len() < RATE {
+            state[1 + chunk.len()] += F::ONE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ff::Field,
+        halo2curves::pasta::{Fp, Fq},
+    };
+
+    const T: usize = 3;
+    const RATE: usize = 2;
+    const R_F: usize = 8;
+    const R_P: usize = 56;
+
+    type PH = Poseidon2Hash<Fp, T, RATE>;
+
+    fn digest(input: &[Fp]) -> Fq {
+        PH::digest(
+            Poseidon2Spec::new(R_F, R_P),
+            input,
+            NonZeroUsize::new(128).unwrap(),
+        )
+    }
+
+    #[test]
+    fn squeeze_matches_digest() {
+        let input = (0..5).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+
+        let output = PH::new(Poseidon2Spec::new(R_F, R_P))
+            .absorb_field_iter(input.iter().copied())
+            .squeeze::<Fq>(NonZeroUsize::new(128).unwrap());
+
+        assert_eq!(output, digest(&input));
+        assert!(output.to_repr().as_ref()[16..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn padding() {
+        // the padding distinguishes inputs with trailing zeros & inputs of `RATE` length
+        let outputs = [
+            digest(&[]),
+            digest(&[Fp::ZERO]),
+            digest(&[Fp::ZERO, Fp::ZERO]),
+            digest(&[Fp::ONE]),
+            digest(&[Fp::ONE, Fp::ZERO]),
+        ];
+
+        for (i, lhs) in outputs.iter().enumerate() {
+            for rhs in outputs.iter().skip(i + 1) {
+                assert_ne!(lhs, rhs);
+            }
+        }
+    }
+}
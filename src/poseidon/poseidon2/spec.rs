@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use crate::{ff::PrimeField, poseidon::IOPattern, util::fe_from_big};
+
+/// `M4` block of the external matrix from the Poseidon2 paper (section 5.1)
+const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+/// Parameters of the Poseidon2 permutation with the width `T`
+///
+/// - The external matrix `M_E` is `circ(2, 1)` & `circ(2, 1, 1)` for `T` equal to 2 & 3,
+///   `M4` for `T = 4` and `circ(2 * M4, M4, ..., M4)` for `T = 4 * k`
+/// - The internal matrix `M_I` is `J + diag(d)`, where `J` is all-ones matrix. The diagonal is
+///   `(1, 2)` & `(1, 1, 2)` for `T` equal to 2 & 3, for bigger `T` it's sampled from the
+///   same Grain LFSR as round constants, only the invertibility of `M_I` is checked, so it
+///   can be replaced by [`Poseidon2Spec::with_internal_diagonal`]
+/// - The round constants are sampled by the Grain LFSR of the Poseidon paper in the order of
+///   rounds: `T` per external round & one per internal round
+///
+/// The constants generation follows the paper, the permutation is checked against the test
+/// vector of the reference implementation for BN254 with `T = 3`, `r_f = 8` & `r_p = 56`.
+#[derive(Clone, Debug)]
+pub struct Poseidon2Spec<F: PrimeField, const T: usize, const RATE: usize> {
+    r_f: usize,
+    r_p: usize,
+    external_mds: [[F; T]; T],
+    internal_diagonal: [F; T],
+    round_constants: Vec<[F; T]>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> Poseidon2Spec<F, T, RATE> {
+    /// `r_f` - number of external (full) rounds, must be even
+    /// `r_p` - number of internal (partial) rounds
+    pub fn new(r_f: usize, r_p: usize) -> Self {
+        assert!(T == 2 || T == 3 || T % 4 == 0, "unsupported width {T}");
+        assert_eq!(RATE + 1, T, "only `RATE = T - 1` is supported");
+        assert!(
+            r_f > 0 && r_f % 2 == 0,
+            "number of external rounds must be even"
+        );
+
+        let mut grain = Grain::new(F::NUM_BITS, T, r_f, r_p);
+
+        let round_constants = (0..r_f + r_p)
+            .map(|round| {
+                if is_internal_round(r_f, r_p, round) {
+                    let mut constants = [F::ZERO; T];
+                    constants[0] = grain.next_field_element();
+                    constants
+                } else {
+                    std::array::from_fn(|_| grain.next_field_element())
+                }
+            })
+            .collect();
+
+        let internal_diagonal = match T {
+            2 => std::array::from_fn(|i| F::from(i as u64 + 1)),
+            3 => std::array::from_fn(|i| if i == 2 { F::from(2) } else { F::ONE }),
+            _ => loop {
+                let diagonal = std::array::from_fn(|_| grain.next_field_element());
+                if is_invertible_internal_diagonal(&diagonal) {
+                    break diagonal;
+                }
+            },
+        };
+
+        Self {
+            r_f,
+            r_p,
+            external_mds: external_mds(),
+            internal_diagonal,
+            round_constants,
+        }
+    }
+
+    /// Replaces the diagonal of the internal matrix `M_I = J + diag(diagonal)`
+    ///
+    /// # Panics
+    ///
+    /// If `M_I` isn't invertible
+    pub fn with_internal_diagonal(mut self, diagonal: [F; T]) -> Self {
+        assert!(
+            is_invertible_internal_diagonal(&diagonal),
+            "internal matrix must be invertible"
+        );
+        self.internal_diagonal = diagonal;
+        self
+    }
+
+    pub fn r_f(&self) -> usize {
+        self.r_f
+    }
+
+    pub fn r_p(&self) -> usize {
+        self.r_p
+    }
+
+    pub fn external_mds(&self) -> &[[F; T]; T] {
+        &self.external_mds
+    }
+
+    pub fn internal_mds(&self) -> [[F; T]; T] {
+        std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                if i == j {
+                    F::ONE + self.internal_diagonal[i]
+                } else {
+                    F::ONE
+                }
+            })
+        })
+    }
+
+    /// Matrix applied after the `round`
+    pub fn round_mds(&self, round: usize) -> [[F; T]; T] {
+        if self.is_internal_round(round) {
+            self.internal_mds()
+        } else {
+            self.external_mds
+        }
+    }
+
+    /// Constants of each round, internal rounds have non-zero constant at the first position only
+    pub fn round_constants(&self) -> &[[F; T]] {
+        &self.round_constants
+    }
+
+    pub fn is_internal_round(&self, round: usize) -> bool {
+        is_internal_round(self.r_f, self.r_p, round)
+    }
+
+    /// Initial state of the sponge, same as in [`crate::poseidon::PoseidonHash`]
+    pub fn initial_state(&self) -> [F; T] {
+        let mut state = [F::ZERO; T];
+        state[0] = F::from_u128(1 << 64);
+        state
+    }
+
+    /// Initial state bound to the `domain`: the capacity element is the [`IOPattern::tag`] of
+    /// [`IOPattern::digest`] of `len` elements
+    pub fn initial_state_with_domain(&self, len: usize, domain: &[u8]) -> [F; T] {
+        let mut state = [F::ZERO; T];
+        state[0] = IOPattern::digest(len).tag(domain);
+        state
+    }
+
+    /// Poseidon2 permutation: the initial external matrix, then `r_f / 2` external rounds,
+    /// `r_p` internal rounds & `r_f / 2` external rounds. Each round adds its constants,
+    /// applies the `x^5` s-box (to the first element only for internal rounds) & the matrix
+    pub fn permute(&self, state: &mut [F; T]) {
+        let pow5 = |v: F| v.square().square() * v;
+        let apply_mds = |mds: &[[F; T]; T], state: &[F; T]| -> [F; T] {
+            std::array::from_fn(|i| {
+                mds[i]
+                    .iter()
+                    .zip(state.iter())
+                    .fold(F::ZERO, |acc, (m, s)| acc + *m * s)
+            })
+        };
+
+        *state = apply_mds(&self.external_mds, state);
+
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s += c;
+            }
+
+            if self.is_internal_round(round) {
+                state[0] = pow5(state[0]);
+            } else {
+                state.iter_mut().for_each(|s| *s = pow5(*s));
+            }
+
+            *state = apply_mds(&self.round_mds(round), state);
+        }
+    }
+}
+
+fn is_internal_round(r_f: usize, r_p: usize, round: usize) -> bool {
+    (r_f / 2..r_f / 2 + r_p).contains(&round)
+}
+
+/// `J + diag(d)` is invertible iff all `d_i != 0` & `1 + sum(1 / d_i) != 0`
+fn is_invertible_internal_diagonal<F: PrimeField>(diagonal: &[F]) -> bool {
+    diagonal
+        .iter()
+        .map(|d| Option::<F>::from(d.invert()))
+        .sum::<Option<F>>()
+        .is_some_and(|sum| sum != -F::ONE)
+}
+
+fn external_mds<F: PrimeField, const T: usize>() -> [[F; T]; T] {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| match T {
+            2 | 3 if i == j => F::from(2),
+            2 | 3 => F::ONE,
+            4 => F::from(M4[i][j]),
+            _ if i / 4 == j / 4 => F::from(2 * M4[i % 4][j % 4]),
+            _ => F::from(M4[i % 4][j % 4]),
+        })
+    })
+}
+
+/// Grain LFSR from the Poseidon paper (appendix F), initialized by the instance parameters
+struct Grain {
+    state: VecDeque<bool>,
+}
+
+impl Grain {
+    fn new(num_bits: u32, t: usize, r_f: usize, r_p: usize) -> Self {
+        let mut state = VecDeque::with_capacity(80);
+        let mut push = |value: u64, len: usize| {
+            state.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        };
+
+        push(1, 2); // prime field
+        push(0, 4); // `x^alpha` s-box
+        push(num_bits as u64, 12);
+        push(t as u64, 12);
+        push(r_f as u64, 10);
+        push(r_p as u64, 10);
+        push((1 << 30) - 1, 30);
+
+        let mut grain = Self { state };
+        for _ in 0..160 {
+            grain.next_bit();
+        }
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    /// Each pair of bits is taken as `(keep, bit)`
+    fn next_filtered_bit(&mut self) -> bool {
+        loop {
+            let keep = self.next_bit();
+            let bit = self.next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Big-endian `F::NUM_BITS` bits, values outside of the field are rejected
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let value = (0..F::NUM_BITS).fold(BigUint::default(), |acc, _| {
+                (acc << 1u32) + u8::from(self.next_filtered_bit())
+            });
+            if let Some(fe) = fe_from_big(value) {
+                return fe;
+            }
+        }
+    }
+}
+
+impl<F: Serialize + PrimeField, const T: usize, const RATE: usize> Serialize
+    for Poseidon2Spec<F, T, RATE>
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        #[derive(Serialize)]
+        struct SerializableSpec<'s, F: Serialize> {
+            r_f: usize,
+            r_p: usize,
+            external_mds: Vec<&'s [F]>,
+            internal_diagonal: &'s [F],
+            round_constants: Vec<&'s [F]>,
+        }
+
+        SerializableSpec {
+            r_f: self.r_f,
+            r_p: self.r_p,
+            external_mds: self.external_mds.iter().map(|row| row.as_slice()).collect(),
+            internal_diagonal: self.internal_diagonal.as_slice(),
+            round_constants: self
+                .round_constants
+                .iter()
+                .map(|constants| constants.as_slice())
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ff::Field, halo2curves::bn256::Fr};
+
+    fn determinant<const T: usize>(mut m: [[Fr; T]; T]) -> Fr {
+        let mut det = Fr::ONE;
+        for col in 0..T {
+            let Some(pivot) = (col..T).find(|row| !bool::from(m[*row][col].is_zero())) else {
+                return Fr::ZERO;
+            };
+            if pivot != col {
+                m.swap(pivot, col);
+                det = -det;
+            }
+            det *= m[col][col];
+            let inv = m[col][col].invert().unwrap();
+            let (top, bottom) = m.split_at_mut(col + 1);
+            for row in bottom {
+                let factor = row[col] * inv;
+                for (value, pivot_value) in row.iter_mut().zip(top[col].iter()) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+        det
+    }
+
+    fn check<const T: usize, const RATE: usize>() {
+        let spec = Poseidon2Spec::<Fr, T, RATE>::new(8, 56);
+
+        assert_ne!(determinant(*spec.external_mds()), Fr::ZERO);
+        assert_ne!(determinant(spec.internal_mds()), Fr::ZERO);
+
+        assert_eq!(spec.round_constants().len(), 8 + 56);
+        for (round, constants) in spec.round_constants().iter().enumerate() {
+            let zeros = constants.iter().filter(|c| bool::from(c.is_zero())).count();
+            if spec.is_internal_round(round) {
+                assert_eq!(zeros, T - 1);
+            } else {
+                assert_eq!(zeros, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn matrices() {
+        check::<2, 1>();
+        check::<3, 2>();
+        check::<4, 3>();
+        check::<8, 7>();
+    }
+
+    #[test]
+    fn deterministic() {
+        let lhs = Poseidon2Spec::<Fr, 4, 3>::new(8, 56);
+        let rhs = Poseidon2Spec::<Fr, 4, 3>::new(8, 56);
+        assert_eq!(lhs.round_constants(), rhs.round_constants());
+        assert_eq!(lhs.internal_mds(), rhs.internal_mds());
+
+        let other = Poseidon2Spec::<Fr, 4, 3>::new(8, 57);
+        assert_ne!(lhs.round_constants()[0], other.round_constants()[0]);
+    }
+
+    /// Test vector of the reference implementation: `POSEIDON2_BN256_PARAMS`, permutation of
+    /// `[0, 1, 2]` (<https://github.com/HorizenLabs/poseidon2>)
+    #[test]
+    fn known_answer_bn256_t3() {
+        let from_hex = |hex: &str| {
+            fe_from_big::<Fr>(BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()).unwrap()
+        };
+
+        let mut state = [0u64, 1, 2].map(Fr::from);
+        Poseidon2Spec::<Fr, 3, 2>::new(8, 56).permute(&mut state);
+
+        assert_eq!(
+            state,
+            [
+                "0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033",
+                "303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570",
+                "1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8",
+            ]
+            .map(from_hex)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn singular_internal_matrix() {
+        // 1 + 1/(-2) + 1/(-2) = 0
+        let _ = Poseidon2Spec::<Fr, 2, 1>::new(8, 56).with_internal_diagonal([-Fr::from(2); 2]);
+    }
+}
@@ -113,7 +113,6 @@ impl<F: PrimeField + PrimeFieldBits, const T: usize, const RATE: usize> Poseidon
         state: &[AssignedValue<F>; T],
     ) -> Result<AssignedValue<F>, Error> {
         assert!(inputs.len() <= RATE);
-        let s_val = state[state_idx].value().copied();
 
         // TODO: add copy constraint
         let inputs = std::iter::once(Value::known(F::ZERO))
@@ -122,14 +121,23 @@ impl<F: PrimeField + PrimeFieldBits, const T: usize, const RATE: usize> Poseidon
             .chain(std::iter::repeat(Value::known(F::ZERO)))
             .take(T)
             .collect::<Vec<_>>();
-        let input_val = inputs[state_idx];
+        self.pre_round_with(ctx, Some(inputs[state_idx]), state_idx, state)
+    }
+
+    /// Adds the first round constant & `input` (if any) to the `state_idx` element of `state`
+    fn pre_round_with(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        input: Option<Value<F>>,
+        state_idx: usize,
+        state: &[AssignedValue<F>; T],
+    ) -> Result<AssignedValue<F>, Error> {
+        let s_val = state[state_idx].value().copied();
 
         let constants = self.spec.constants().start();
         let pre_constants = constants[0];
         let rc_val = pre_constants[state_idx];
 
-        let out_val = s_val + input_val + Value::known(rc_val);
-
         let si = ctx.assign_advice(
             || "first round: state",
             self.main_gate.config().state[state_idx],
@@ -137,17 +145,22 @@ impl<F: PrimeField + PrimeFieldBits, const T: usize, const RATE: usize> Poseidon
         )?;
         ctx.constrain_equal(state[state_idx].cell(), si.cell())?;
 
-        ctx.assign_advice(
-            || "pre_round: input",
-            self.main_gate.config().input,
-            input_val,
-        )?;
+        let mut out_val = s_val + Value::known(rc_val);
+        if let Some(input_val) = input {
+            ctx.assign_advice(
+                || "pre_round: input",
+                self.main_gate.config().input,
+                input_val,
+            )?;
+            ctx.assign_fixed(|| "pre_round: q_i", self.main_gate.config().q_i, F::ONE)?;
+            out_val = out_val + input_val;
+        }
+
         ctx.assign_fixed(
             || "pre_round: q_1",
             self.main_gate.config().q_1[state_idx],
             F::ONE,
         )?;
-        ctx.assign_fixed(|| "pre_round: q_i", self.main_gate.config().q_i, F::ONE)?;
         ctx.assign_fixed(|| "pre_round: q_o", self.main_gate.config().q_o, -F::ONE)?;
         ctx.assign_fixed(|| "pre_round: rc", self.main_gate.config().rc, rc_val)?;
         let out = ctx.assign_advice(|| "pre_round: out", self.main_gate.config().out, out_val)?;
@@ -331,6 +344,28 @@ impl<F: PrimeField + PrimeFieldBits, const T: usize, const RATE: usize> Poseidon
             state.push(si);
         }
 
+        self.rounds(ctx, state)
+    }
+
+    /// Poseidon permutation of `state` without any inputs & padding
+    pub fn permute(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>; T],
+    ) -> Result<[AssignedValue<F>; T], Error> {
+        let state = (0..T)
+            .map(|i| self.pre_round_with(ctx, None, i, state))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.rounds(ctx, state)
+    }
+
+    /// Rounds of the permutation after [`PoseidonChip::pre_round`]
+    fn rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        mut state: Vec<AssignedValue<F>>,
+    ) -> Result<[AssignedValue<F>; T], Error> {
         let r_f = self.spec.r_f() / 2;
         let r_p = self.spec.constants().partial().len();
 
@@ -35,31 +35,26 @@ impl<F: PrimeField + FromUniformBytes<64>, const T: usize, const RATE: usize> St
         self.inner[0] = pow5(&self.inner[0]) + *constant;
     }
 
-    fn pre_round(&mut self, inputs: &[F], pre_constants: &[F; T]) {
+    /// Adds `inputs` to `inner[1..]` & pads them by one, if there is space left
+    fn absorb_with_padding(&mut self, inputs: &[F]) {
         assert!(RATE == T - 1);
         assert!(inputs.len() <= RATE);
 
-        self.inner[0] += pre_constants[0];
         self.inner
             .iter_mut()
-            .zip(pre_constants.iter())
             .skip(1)
             .zip(inputs)
-            .for_each(|((state, constant), input)| {
-                *state = *state + *input + *constant;
-            });
+            .for_each(|(state, input)| *state += input);
+        if inputs.len() < RATE {
+            self.inner[1 + inputs.len()] += F::ONE;
+        }
+    }
+
+    fn add_constants(&mut self, constants: &[F; T]) {
         self.inner
             .iter_mut()
-            .zip(pre_constants.iter())
-            .skip(1 + inputs.len())
-            .enumerate()
-            .for_each(|(idx, (state, constant))| {
-                *state = if idx == 0 {
-                    *state + F::ONE + *constant
-                } else {
-                    *state + *constant
-                };
-            });
+            .zip(constants.iter())
+            .for_each(|(state, constant)| *state += constant);
     }
 
     fn apply_mds(&mut self, mds: &[[F; T]; T]) {
@@ -94,6 +89,39 @@ impl<F: PrimeField + FromUniformBytes<64>, const T: usize, const RATE: usize> St
         .try_into()
         .unwrap();
     }
+
+    fn permute(&mut self, spec: &Spec<F, T, RATE>) {
+        let r_f = spec.r_f() / 2;
+        let mds = spec.mds_matrices().mds().rows();
+        let pre_sparse_mds = spec.mds_matrices().pre_sparse_mds().rows();
+        let sparse_matrices = spec.mds_matrices().sparse_matrices();
+
+        // First half of the full rounds
+        let constants = spec.constants().start();
+        self.add_constants(&constants[0]);
+        for constants in constants.iter().skip(1).take(r_f - 1) {
+            self.sbox_full(constants);
+            self.apply_mds(&mds);
+        }
+        self.sbox_full(constants.last().unwrap());
+        self.apply_mds(&pre_sparse_mds);
+
+        // Partial rounds
+        let constants = spec.constants().partial();
+        for (constant, sparse_mds) in constants.iter().zip(sparse_matrices.iter()) {
+            self.sbox_part(constant);
+            self.apply_sparse_mds(sparse_mds);
+        }
+
+        // Second half of the full rounds
+        let constants = spec.constants().end();
+        for constants in constants.iter() {
+            self.sbox_full(constants);
+            self.apply_mds(&mds);
+        }
+        self.sbox_full(&[F::ZERO; T]);
+        self.apply_mds(&mds);
+    }
 }
 
 impl<F, const T: usize, const RATE: usize> ROConstantsTrait for Spec<F, T, RATE>
@@ -204,39 +232,23 @@ where
     }
 
     fn permutation(&mut self, inputs: &[F]) {
-        let r_f = self.spec.r_f() / 2;
-        let mds = self.spec.mds_matrices().mds().rows();
-        let pre_sparse_mds = self.spec.mds_matrices().pre_sparse_mds().rows();
-        let sparse_matrices = self.spec.mds_matrices().sparse_matrices();
-
-        // First half of the full rounds
-        let constants = self.spec.constants().start();
-        self.state.pre_round(inputs, &constants[0]);
-        for constants in constants.iter().skip(1).take(r_f - 1) {
-            self.state.sbox_full(constants);
-            self.state.apply_mds(&mds);
-        }
-        self.state.sbox_full(constants.last().unwrap());
-        self.state.apply_mds(&pre_sparse_mds);
-
-        // Partial rounds
-        let constants = self.spec.constants().partial();
-        for (constant, sparse_mds) in constants.iter().zip(sparse_matrices.iter()) {
-            self.state.sbox_part(constant);
-            self.state.apply_sparse_mds(sparse_mds);
-        }
-
-        // Second half of the full rounds
-        let constants = self.spec.constants().end();
-        for constants in constants.iter() {
-            self.state.sbox_full(constants);
-            self.state.apply_mds(&mds);
-        }
-        self.state.sbox_full(&[F::ZERO; T]);
-        self.state.apply_mds(&mds);
+        self.state.absorb_with_padding(inputs);
+        self.state.permute(&self.spec);
     }
 }
 
+/// Poseidon permutation of `state` without any inputs & padding
+pub(crate) fn permute<F, const T: usize, const RATE: usize>(
+    spec: &Spec<F, T, RATE>,
+    state: &mut [F; T],
+) where
+    F: PrimeField + FromUniformBytes<64>,
+{
+    let mut wrapped = State::<F, T, RATE>::new(*state);
+    wrapped.permute(spec);
+    *state = wrapped.inner;
+}
+
 #[cfg(test)]
 mod tests {
     use tracing_test::traced_test;
@@ -0,0 +1,452 @@
+//! Duplex sponge over the Poseidon permutation with the SAFE API (<https://eprint.iacr.org/2023/522>)
+//!
+//! [`super::PoseidonHash`] hashes the whole buffer at once & always starts from the same state,
+//! so two transcripts with the same absorbed elements give the same challenge. Here every
+//! sponge is bound to its [`IOPattern`] & domain separator: both are hashed into the capacity
+//! element, so sponges with different patterns or domains start from different states.
+//!
+//! [`PoseidonSpongeHash`] puts the sponge behind [`super::ROTrait`], so `ro_nark` & `ro_acc` of
+//! the folding schemes can be bound to [`RO_NARK_DOMAIN`] & [`RO_ACC_DOMAIN`]: equal transcripts
+//! of NARK & accumulation rounds give different challenges.
+//!
+//! The sequence of [`PoseidonSponge::absorb`] & [`PoseidonSponge::squeeze`] calls must follow
+//! the pattern, consecutive calls of the same kind are aggregated, so `absorb(2), absorb(1)`
+//! matches `Absorb(3)`. [`PoseidonSponge::finish`] checks that the whole pattern was used.
+//!
+//! The on-circuit mirror is [`super::sponge_circuit::PoseidonSpongeChip`].
+
+use std::{collections::VecDeque, num::NonZeroUsize};
+
+use halo2_proofs::arithmetic::CurveAffine;
+use sha3::{Digest, Sha3_256};
+
+use super::{poseidon_hash, ROTrait, Spec};
+use crate::{
+    ff::{FromUniformBytes, PrimeField, PrimeFieldBits},
+    util::{self, bits_to_fe_le, fe_to_bits_le},
+};
+
+/// Domain separator of the NARK rounds transcript (`ro_nark`)
+pub const RO_NARK_DOMAIN: &[u8] = b"ro_nark";
+
+/// Domain separator of the accumulation transcript (`ro_acc`)
+pub const RO_ACC_DOMAIN: &[u8] = b"ro_acc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpongeOp {
+    Absorb(u32),
+    Squeeze(u32),
+}
+
+impl SpongeOp {
+    /// Encoding of the SAFE spec: the highest bit is set for absorb
+    fn encode(&self) -> u32 {
+        match self {
+            Self::Absorb(len) => 0x8000_0000 | len,
+            Self::Squeeze(len) => *len,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Sponge call {actual:?} doesn't match IO pattern, expected {expected:?}")]
+    UnexpectedOp {
+        expected: Option<SpongeOp>,
+        actual: SpongeOp,
+    },
+    #[error("IO pattern isn't finished, {remaining:?} left")]
+    NotFinished { remaining: Vec<SpongeOp> },
+    #[error(transparent)]
+    Halo2(#[from] halo2_proofs::plonk::Error),
+}
+
+/// Sequence of sponge calls, consecutive operations of the same kind are aggregated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IOPattern(Vec<SpongeOp>);
+
+impl IOPattern {
+    /// # Panics
+    ///
+    /// If the length of any operation doesn't fit into 31 bits
+    pub fn new(ops: impl IntoIterator<Item = SpongeOp>) -> Self {
+        let mut aggregated: Vec<SpongeOp> = vec![];
+
+        for op in ops {
+            match (aggregated.last_mut(), op) {
+                (_, SpongeOp::Absorb(0) | SpongeOp::Squeeze(0)) => {}
+                (Some(SpongeOp::Absorb(last)), SpongeOp::Absorb(len))
+                | (Some(SpongeOp::Squeeze(last)), SpongeOp::Squeeze(len)) => {
+                    *last += len;
+                }
+                (_, op) => aggregated.push(op),
+            }
+        }
+
+        assert!(
+            aggregated.iter().all(|op| match op {
+                SpongeOp::Absorb(len) | SpongeOp::Squeeze(len) => *len < 0x8000_0000,
+            }),
+            "length of sponge operation must fit into 31 bits"
+        );
+
+        Self(aggregated)
+    }
+
+    /// Pattern of a digest: absorb `len` elements & squeeze one
+    pub fn digest(len: usize) -> Self {
+        Self::new([SpongeOp::Absorb(len as u32), SpongeOp::Squeeze(1)])
+    }
+
+    pub fn ops(&self) -> &[SpongeOp] {
+        &self.0
+    }
+
+    /// Initial value of the capacity element: the first 128 bits of SHA3-256 over the encoded
+    /// pattern followed by the `domain` separator
+    pub fn tag<F: PrimeField>(&self, domain: &[u8]) -> F {
+        let mut hasher = Sha3_256::new();
+        for op in &self.0 {
+            hasher.update(op.encode().to_be_bytes());
+        }
+        hasher.update(domain);
+
+        let hash = hasher.finalize();
+        F::from_u128(u128::from_be_bytes(
+            hash[..16].try_into().expect("SHA3-256 output is 32 bytes"),
+        ))
+    }
+}
+
+/// Remaining part of the [`IOPattern`]
+#[derive(Debug, Clone)]
+pub(crate) struct PatternTracker {
+    remaining: VecDeque<SpongeOp>,
+}
+
+impl PatternTracker {
+    pub(crate) fn new(pattern: &IOPattern) -> Self {
+        Self {
+            remaining: pattern.ops().iter().copied().collect(),
+        }
+    }
+
+    pub(crate) fn consume(&mut self, actual: SpongeOp) -> Result<(), Error> {
+        let done = match (self.remaining.front_mut(), actual) {
+            (_, SpongeOp::Absorb(0) | SpongeOp::Squeeze(0)) => return Ok(()),
+            (Some(SpongeOp::Absorb(left)), SpongeOp::Absorb(len))
+            | (Some(SpongeOp::Squeeze(left)), SpongeOp::Squeeze(len))
+                if len <= *left =>
+            {
+                *left -= len;
+                *left == 0
+            }
+            (expected, actual) => {
+                return Err(Error::UnexpectedOp {
+                    expected: expected.copied(),
+                    actual,
+                })
+            }
+        };
+
+        if done {
+            self.remaining.pop_front();
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        if self.remaining.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::NotFinished {
+                remaining: self.remaining.into(),
+            })
+        }
+    }
+}
+
+/// Off-circuit duplex sponge, see [module-level](self) docs
+///
+/// `state[0]` is the capacity element, `state[1..]` is the rate part
+#[derive(Clone, Debug)]
+pub struct PoseidonSponge<F, const T: usize, const RATE: usize>
+where
+    F: PrimeField + FromUniformBytes<64>,
+{
+    spec: Spec<F, T, RATE>,
+    state: [F; T],
+    absorb_pos: usize,
+    squeeze_pos: usize,
+    pattern: PatternTracker,
+}
+
+impl<F, const T: usize, const RATE: usize> PoseidonSponge<F, T, RATE>
+where
+    F: PrimeField + FromUniformBytes<64>,
+{
+    pub fn new(spec: Spec<F, T, RATE>, pattern: &IOPattern, domain: &[u8]) -> Self {
+        assert_eq!(RATE + 1, T, "only `RATE = T - 1` is supported");
+
+        let mut state = [F::ZERO; T];
+        state[0] = pattern.tag(domain);
+
+        Self {
+            spec,
+            state,
+            absorb_pos: 0,
+            squeeze_pos: RATE,
+            pattern: PatternTracker::new(pattern),
+        }
+    }
+
+    pub fn absorb(&mut self, inputs: &[F]) -> Result<&mut Self, Error> {
+        self.pattern
+            .consume(SpongeOp::Absorb(inputs.len() as u32))?;
+
+        for input in inputs {
+            if self.absorb_pos == RATE {
+                poseidon_hash::permute(&self.spec, &mut self.state);
+                self.absorb_pos = 0;
+            }
+            self.state[1 + self.absorb_pos] += input;
+            self.absorb_pos += 1;
+        }
+        self.squeeze_pos = RATE;
+
+        Ok(self)
+    }
+
+    pub fn squeeze(&mut self, len: usize) -> Result<Vec<F>, Error> {
+        self.pattern.consume(SpongeOp::Squeeze(len as u32))?;
+
+        Ok((0..len)
+            .map(|_| {
+                if self.squeeze_pos == RATE {
+                    poseidon_hash::permute(&self.spec, &mut self.state);
+                    self.squeeze_pos = 0;
+                    self.absorb_pos = 0;
+                }
+                self.squeeze_pos += 1;
+                self.state[self.squeeze_pos]
+            })
+            .collect())
+    }
+
+    /// Checks that all calls of the [`IOPattern`] were made
+    pub fn finish(self) -> Result<(), Error> {
+        self.pattern.finish()
+    }
+}
+
+/// [`ROTrait`] on top of [`PoseidonSponge`] bound to a domain
+///
+/// As in [`super::PoseidonHash`] inputs are buffered & each squeeze hashes the whole buffer, but
+/// by a new sponge with [`IOPattern::digest`] & the domain of [`PoseidonSpongeHash::with_domain`]
+#[derive(Clone, Debug)]
+pub struct PoseidonSpongeHash<F, const T: usize, const RATE: usize>
+where
+    F: PrimeField + FromUniformBytes<64>,
+{
+    spec: Spec<F, T, RATE>,
+    domain: &'static [u8],
+    buf: Vec<F>,
+}
+
+impl<F, const T: usize, const RATE: usize> PoseidonSpongeHash<F, T, RATE>
+where
+    F: PrimeField + FromUniformBytes<64>,
+{
+    /// Replaces the domain separator, empty by default
+    pub fn with_domain(mut self, domain: &'static [u8]) -> Self {
+        self.domain = domain;
+        self
+    }
+}
+
+impl<F, const T: usize, const RATE: usize> ROTrait<F> for PoseidonSpongeHash<F, T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Constants = Spec<F, T, RATE>;
+
+    fn new(constants: Self::Constants) -> Self {
+        Self {
+            spec: constants,
+            domain: &[],
+            buf: Vec::new(),
+        }
+    }
+
+    fn absorb_field(&mut self, base: F) -> &mut Self {
+        self.buf.push(base);
+        self
+    }
+
+    fn absorb_point<C: CurveAffine>(&mut self, point: &C) -> &mut Self {
+        let encoded = point.coordinates().map(|coordinates| {
+            [coordinates.x(), coordinates.y()]
+                .into_iter()
+                .map(|v| util::fe_to_fe(v).unwrap())
+                .collect::<Vec<_>>()
+        });
+        if bool::from(encoded.is_some()) {
+            self.buf.extend(encoded.unwrap())
+        } else {
+            self.buf.extend([F::ZERO, F::ZERO]) // C is infinity
+        }
+
+        self
+    }
+
+    fn inspect(&mut self, inspect: impl FnOnce(&[F])) -> &mut Self {
+        inspect(&self.buf);
+        self
+    }
+
+    fn squeeze<D: PrimeField>(&mut self, num_bits: NonZeroUsize) -> D {
+        let output = PoseidonSponge::new(
+            self.spec.clone(),
+            &IOPattern::digest(self.buf.len()),
+            self.domain,
+        )
+        .absorb(&self.buf)
+        .and_then(|sponge| sponge.squeeze(1))
+        .expect("Unreachable, because the pattern is built from the buffer")[0];
+
+        let mut bits = fe_to_bits_le(&output);
+        if bits.len() < num_bits.get() {
+            bits.resize(num_bits.get(), false);
+        }
+        bits_to_fe_le(bits[..num_bits.get()].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ff::Field, halo2curves::pasta::Fp};
+
+    const T: usize = 3;
+    const RATE: usize = 2;
+    const R_F: usize = 8;
+    const R_P: usize = 56;
+
+    fn sponge(pattern: &IOPattern, domain: &[u8]) -> PoseidonSponge<Fp, T, RATE> {
+        PoseidonSponge::new(Spec::new(R_F, R_P), pattern, domain)
+    }
+
+    fn inputs(len: u64) -> Vec<Fp> {
+        (0..len).map(Fp::from).collect()
+    }
+
+    #[test]
+    fn pattern_aggregation() {
+        let pattern = IOPattern::new([
+            SpongeOp::Absorb(2),
+            SpongeOp::Absorb(3),
+            SpongeOp::Squeeze(0),
+            SpongeOp::Squeeze(1),
+            SpongeOp::Absorb(1),
+        ]);
+
+        assert_eq!(
+            pattern.ops(),
+            &[
+                SpongeOp::Absorb(5),
+                SpongeOp::Squeeze(1),
+                SpongeOp::Absorb(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn domain_separation() {
+        let pattern = IOPattern::new([SpongeOp::Absorb(3), SpongeOp::Squeeze(1)]);
+        let other_pattern = IOPattern::new([SpongeOp::Absorb(3), SpongeOp::Squeeze(2)]);
+
+        let output = |pattern: &IOPattern, domain: &[u8]| {
+            let mut sponge = sponge(pattern, domain);
+            sponge.absorb(&inputs(3)).unwrap();
+            sponge.squeeze(1).unwrap()[0]
+        };
+
+        let nark = output(&pattern, b"ro_nark");
+        assert_eq!(nark, output(&pattern, b"ro_nark"));
+        assert_ne!(nark, output(&pattern, b"ro_acc"));
+        assert_ne!(nark, output(&other_pattern, b"ro_nark"));
+    }
+
+    #[test]
+    fn ro_nark_ro_acc_separation() {
+        let squeeze = |domain: &'static [u8]| {
+            PoseidonSpongeHash::<Fp, T, RATE>::new(Spec::new(R_F, R_P))
+                .with_domain(domain)
+                .absorb_field_iter(inputs(5).into_iter())
+                .squeeze::<Fp>(NonZeroUsize::new(128).unwrap())
+        };
+
+        let nark = squeeze(RO_NARK_DOMAIN);
+        assert_eq!(nark, squeeze(RO_NARK_DOMAIN));
+        assert_ne!(nark, squeeze(RO_ACC_DOMAIN));
+    }
+
+    #[test]
+    fn duplex() {
+        let pattern = IOPattern::new([
+            SpongeOp::Absorb(3),
+            SpongeOp::Squeeze(5),
+            SpongeOp::Absorb(1),
+            SpongeOp::Squeeze(1),
+        ]);
+
+        let mut sponge = sponge(&pattern, b"test");
+        sponge.absorb(&inputs(2)).unwrap();
+        sponge.absorb(&inputs(1)).unwrap();
+
+        let squeezed = sponge.squeeze(5).unwrap();
+        for (i, lhs) in squeezed.iter().enumerate() {
+            for rhs in squeezed.iter().skip(i + 1) {
+                assert_ne!(lhs, rhs);
+            }
+        }
+
+        sponge.absorb(&[Fp::ONE]).unwrap();
+        let after_absorb = sponge.squeeze(1).unwrap();
+        assert!(!squeezed.contains(&after_absorb[0]));
+
+        sponge.finish().unwrap();
+    }
+
+    #[test]
+    fn pattern_violation() {
+        let pattern = IOPattern::new([SpongeOp::Absorb(2), SpongeOp::Squeeze(1)]);
+
+        let mut sponge = sponge(&pattern, b"test");
+        assert!(matches!(
+            sponge.squeeze(1),
+            Err(Error::UnexpectedOp {
+                expected: Some(SpongeOp::Absorb(2)),
+                actual: SpongeOp::Squeeze(1),
+            })
+        ));
+        assert!(matches!(
+            sponge.absorb(&inputs(3)),
+            Err(Error::UnexpectedOp { .. })
+        ));
+
+        sponge.absorb(&inputs(2)).unwrap();
+        assert!(matches!(
+            sponge.clone().finish(),
+            Err(Error::NotFinished { .. })
+        ));
+
+        sponge.squeeze(1).unwrap();
+        assert!(matches!(
+            sponge.squeeze(1),
+            Err(Error::UnexpectedOp { expected: None, .. })
+        ));
+        sponge.finish().unwrap();
+    }
+}
@@ -0,0 +1,472 @@
+//! On-circuit mirror of [`super::sponge::PoseidonSponge`]
+
+use std::num::NonZeroUsize;
+
+use halo2_proofs::plonk::Error as Halo2PlonkError;
+use tracing::error;
+
+use super::{
+    poseidon_circuit::PoseidonChip,
+    sponge::{Error, IOPattern, PatternTracker, SpongeOp},
+    ROCircuitTrait, Spec,
+};
+use crate::{
+    constants::MAX_BITS,
+    ff::{FromUniformBytes, PrimeFieldBits},
+    main_gate::{AssignedBit, AssignedValue, MainGate, MainGateConfig, RegionCtx, WrapValue},
+};
+
+/// Duplex sponge on top of [`PoseidonChip::permute`], see [`super::sponge`] docs
+///
+/// Unlike [`PoseidonChip`], inputs of [`WrapValue::Assigned`] are copy-constrained. Each absorbed
+/// element takes one row & each permutation takes `T * (1 + r_f + r_p)` rows.
+pub struct PoseidonSpongeChip<F, const T: usize, const RATE: usize>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    main_gate: MainGate<F, T>,
+    chip: PoseidonChip<F, T, RATE>,
+    state: [AssignedValue<F>; T],
+    absorb_pos: usize,
+    squeeze_pos: usize,
+    pattern: PatternTracker,
+}
+
+impl<F, const T: usize, const RATE: usize> PoseidonSpongeChip<F, T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// Assigns the initial state, the capacity element is fixed to [`IOPattern::tag`]
+    pub fn new(
+        ctx: &mut RegionCtx<'_, F>,
+        config: MainGateConfig<T>,
+        spec: Spec<F, T, RATE>,
+        pattern: &IOPattern,
+        domain: &[u8],
+    ) -> Result<Self, Error> {
+        assert_eq!(RATE + 1, T, "only `RATE = T - 1` is supported");
+
+        let main_gate = MainGate::new(config.clone());
+
+        let state = (0..T)
+            .map(|i| {
+                let value = if i == 0 { pattern.tag(domain) } else { F::ZERO };
+                main_gate.assign_constant(ctx, value)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .expect("Unreachable, because collected `T` elements");
+
+        Ok(Self {
+            main_gate,
+            chip: PoseidonChip::new(config, spec),
+            state,
+            absorb_pos: 0,
+            squeeze_pos: RATE,
+            pattern: PatternTracker::new(pattern),
+        })
+    }
+
+    pub fn absorb(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        inputs: &[WrapValue<F>],
+    ) -> Result<&mut Self, Error> {
+        self.pattern
+            .consume(SpongeOp::Absorb(inputs.len() as u32))?;
+
+        for input in inputs {
+            if self.absorb_pos == RATE {
+                self.state = self.chip.permute(ctx, &self.state)?;
+                self.absorb_pos = 0;
+            }
+
+            let idx = 1 + self.absorb_pos;
+            self.absorb_pos += 1;
+
+            if matches!(input, WrapValue::Zero) {
+                continue;
+            }
+
+            let mut q_1 = vec![F::ZERO; T];
+            q_1[idx] = F::ONE;
+            let mut state = vec![WrapValue::Zero; T];
+            state[idx] = WrapValue::Assigned(self.state[idx].clone());
+
+            let sum = self.state[idx].value().copied() + input.value();
+            self.state[idx] = self.main_gate.apply_with_input(
+                ctx,
+                (Some(q_1), None, Some(state)),
+                (Some(F::ONE), Some(input.clone())),
+                (-F::ONE, sum.into()),
+            )?;
+        }
+        self.squeeze_pos = RATE;
+
+        Ok(self)
+    }
+
+    pub fn squeeze(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        len: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        self.pattern.consume(SpongeOp::Squeeze(len as u32))?;
+
+        let mut output = Vec::with_capacity(len);
+        for _ in 0..len {
+            if self.squeeze_pos == RATE {
+                self.state = self.chip.permute(ctx, &self.state)?;
+                self.squeeze_pos = 0;
+                self.absorb_pos = 0;
+            }
+            self.squeeze_pos += 1;
+            output.push(self.state[self.squeeze_pos].clone());
+        }
+
+        Ok(output)
+    }
+
+    /// Checks that all calls of the [`IOPattern`] were made
+    pub fn finish(self) -> Result<(), Error> {
+        self.pattern.finish()
+    }
+}
+
+/// On-circuit mirror of [`super::sponge::PoseidonSpongeHash`]
+///
+/// Each squeeze assigns a new [`PoseidonSpongeChip`] over the whole buffer
+pub struct PoseidonSpongeHashChip<F, const T: usize, const RATE: usize>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    config: MainGateConfig<T>,
+    spec: Spec<F, T, RATE>,
+    domain: &'static [u8],
+    buf: Vec<WrapValue<F>>,
+}
+
+impl<F, const T: usize, const RATE: usize> PoseidonSpongeHashChip<F, T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    /// Replaces the domain separator, empty by default
+    pub fn with_domain(mut self, domain: &'static [u8]) -> Self {
+        self.domain = domain;
+        self
+    }
+}
+
+impl<F, const T: usize, const RATE: usize> ROCircuitTrait<F> for PoseidonSpongeHashChip<F, T, RATE>
+where
+    F: PrimeFieldBits + FromUniformBytes<64>,
+{
+    type Args = Spec<F, T, RATE>;
+    type Config = MainGateConfig<T>;
+
+    fn new(config: Self::Config, spec: Self::Args) -> Self {
+        Self {
+            config,
+            spec,
+            domain: &[],
+            buf: Vec::new(),
+        }
+    }
+
+    fn absorb_base(&mut self, base: WrapValue<F>) -> &mut Self {
+        self.buf.push(base);
+        self
+    }
+
+    fn absorb_point(&mut self, point: [WrapValue<F>; 2]) -> &mut Self {
+        self.buf.extend(point);
+        self
+    }
+
+    fn inspect(&mut self, scan: impl FnOnce(&[F])) -> &mut Self {
+        if let Some(buf) = self
+            .buf
+            .iter()
+            .map(|b| b.value().unwrap())
+            .collect::<Option<Vec<_>>>()
+        {
+            scan(&buf)
+        }
+        self
+    }
+
+    fn squeeze_n_bits(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        num_bits: NonZeroUsize,
+    ) -> Result<Vec<AssignedBit<F>>, Halo2PlonkError> {
+        let val = self.squeeze(ctx)?;
+        let res = MainGate::new(self.config.clone()).le_num_to_bits(ctx, val, MAX_BITS)?;
+        if res.len() >= num_bits.get() {
+            Ok(res[..num_bits.get()].to_vec())
+        } else {
+            Ok(res)
+        }
+    }
+
+    fn squeeze(&mut self, ctx: &mut RegionCtx<'_, F>) -> Result<AssignedValue<F>, Halo2PlonkError> {
+        let mut squeeze = || -> Result<Vec<AssignedValue<F>>, Error> {
+            PoseidonSpongeChip::new(
+                ctx,
+                self.config.clone(),
+                self.spec.clone(),
+                &IOPattern::digest(self.buf.len()),
+                self.domain,
+            )?
+            .absorb(ctx, &self.buf)?
+            .squeeze(ctx, 1)
+        };
+
+        let mut output = squeeze().map_err(|err| {
+            error!("while sponge squeeze: {err:?}");
+            Halo2PlonkError::Synthesis
+        })?;
+
+        Ok(output.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Circuit, Column, ConstraintSystem, Error as Halo2PlonkError, Instance},
+    };
+    use tracing::error;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{halo2curves::pasta::Fp, poseidon::sponge::PoseidonSponge, run_mock_prover_test};
+
+    const T: usize = 3;
+    const RATE: usize = 2;
+    const R_F: usize = 8;
+    const R_P: usize = 56;
+    const K: u32 = 12;
+
+    const DOMAIN: &[u8] = b"test";
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        config: MainGateConfig<T>,
+        instance: Column<Instance>,
+    }
+
+    fn pattern() -> IOPattern {
+        IOPattern::new([
+            SpongeOp::Absorb(3),
+            SpongeOp::Squeeze(3),
+            SpongeOp::Absorb(2),
+            SpongeOp::Squeeze(1),
+        ])
+    }
+
+    /// Follows [`pattern`], the first absorb takes assigned cells, the second one - values
+    struct TestCircuit {
+        first: Vec<Fp>,
+        second: Vec<Fp>,
+    }
+
+    impl Circuit<Fp> for TestCircuit {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Self::Config {
+                config: MainGate::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Halo2PlonkError> {
+            let output = layouter.assign_region(
+                || "sponge",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let main_gate = MainGate::<Fp, T>::new(config.config.clone());
+
+                    let first = self
+                        .first
+                        .iter()
+                        .map(|v| Ok(main_gate.assign_value(ctx, Value::known(*v))?.into()))
+                        .collect::<Result<Vec<WrapValue<Fp>>, Halo2PlonkError>>()?;
+                    let second = self
+                        .second
+                        .iter()
+                        .map(|v| Value::known(*v).into())
+                        .collect::<Vec<WrapValue<Fp>>>();
+
+                    let mut run = || -> Result<Vec<AssignedValue<Fp>>, Error> {
+                        let mut sponge = PoseidonSpongeChip::new(
+                            ctx,
+                            config.config.clone(),
+                            Spec::new(R_F, R_P),
+                            &pattern(),
+                            DOMAIN,
+                        )?;
+
+                        let mut output = sponge.absorb(ctx, &first)?.squeeze(ctx, 3)?;
+                        output.extend(sponge.absorb(ctx, &second)?.squeeze(ctx, 1)?);
+                        sponge.finish()?;
+
+                        Ok(output)
+                    };
+
+                    run().map_err(|err| {
+                        error!("while sponge: {err:?}");
+                        Halo2PlonkError::Synthesis
+                    })
+                },
+            )?;
+
+            for (row, cell) in output.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn off_circuit(first: &[Fp], second: &[Fp]) -> Vec<Fp> {
+        let mut sponge =
+            PoseidonSponge::<Fp, T, RATE>::new(Spec::new(R_F, R_P), &pattern(), DOMAIN);
+
+        let mut output = sponge.absorb(first).unwrap().squeeze(3).unwrap();
+        output.extend(sponge.absorb(second).unwrap().squeeze(1).unwrap());
+        sponge.finish().unwrap();
+
+        output
+    }
+
+    #[traced_test]
+    #[test]
+    fn matches_off_circuit() {
+        let first = vec![Fp::from(1), Fp::from(2), Fp::from(3)];
+        let second = vec![Fp::from(4), Fp::from(5)];
+
+        let expected = off_circuit(&first, &second);
+        run_mock_prover_test!(K, TestCircuit { first, second }, vec![expected]);
+    }
+
+    #[test]
+    fn wrong_input() {
+        let first = vec![Fp::from(1), Fp::from(2), Fp::from(3)];
+        let second = vec![Fp::from(4), Fp::from(5)];
+
+        let expected = off_circuit(&first, &second);
+
+        let circuit = TestCircuit {
+            first: vec![Fp::from(1), Fp::from(2), Fp::from(4)],
+            second,
+        };
+        let prover = halo2_proofs::dev::MockProver::run(K, &circuit, vec![expected]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Squeezes [`PoseidonSpongeHashChip`] bound to `domain` twice: after `first` & after `second`
+    struct HashTestCircuit {
+        domain: &'static [u8],
+        first: Vec<Fp>,
+        second: Vec<Fp>,
+    }
+
+    impl Circuit<Fp> for HashTestCircuit {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            TestCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Halo2PlonkError> {
+            let output = layouter.assign_region(
+                || "sponge hash",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let mut chip =
+                        PoseidonSpongeHashChip::new(config.config.clone(), Spec::new(R_F, R_P))
+                            .with_domain(self.domain);
+
+                    let first = chip
+                        .absorb_iter(self.first.iter().map(|v| Value::known(*v)))
+                        .squeeze(ctx)?;
+                    let second = chip
+                        .absorb_iter(self.second.iter().map(|v| Value::known(*v)))
+                        .squeeze(ctx)?;
+
+                    Ok([first, second])
+                },
+            )?;
+
+            for (row, cell) in output.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hash_matches_off_circuit() {
+        use crate::{
+            ff::PrimeField,
+            poseidon::{PoseidonSpongeHash, ROTrait, RO_ACC_DOMAIN, RO_NARK_DOMAIN},
+        };
+
+        let first = vec![Fp::from(1), Fp::from(2), Fp::from(3)];
+        let second = vec![Fp::from(4)];
+
+        for domain in [RO_NARK_DOMAIN, RO_ACC_DOMAIN] {
+            let mut ro =
+                PoseidonSpongeHash::<Fp, T, RATE>::new(Spec::new(R_F, R_P)).with_domain(domain);
+            let num_bits = NonZeroUsize::new(Fp::NUM_BITS as usize).unwrap();
+
+            let expected = vec![
+                ro.absorb_field_iter(first.iter().copied())
+                    .squeeze::<Fp>(num_bits),
+                ro.absorb_field_iter(second.iter().copied())
+                    .squeeze::<Fp>(num_bits),
+            ];
+
+            let circuit = HashTestCircuit {
+                domain,
+                first: first.clone(),
+                second: second.clone(),
+            };
+            let prover = halo2_proofs::dev::MockProver::run(K, &circuit, vec![expected]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn pattern_violation() {
+        let circuit = TestCircuit {
+            first: vec![Fp::from(1), Fp::from(2)],
+            second: vec![Fp::from(4), Fp::from(5)],
+        };
+        assert!(halo2_proofs::dev::MockProver::run(K, &circuit, vec![vec![]]).is_err());
+    }
+}
@@ -6,13 +6,16 @@ use halo2_proofs::{
 };
 use tracing::*;
 
-use crate::{ff::PrimeField, plonk};
+use crate::{
+    ff::PrimeField,
+    plonk::{self, columns::Selectors},
+};
 
 pub struct CircuitData<F: PrimeField> {
     pub(crate) k: u32,
     pub(crate) num_io: Box<[usize]>,
     pub(crate) fixed: Vec<Vec<Assigned<F>>>,
-    pub(crate) selector: Vec<Vec<bool>>,
+    pub(crate) selector: Selectors,
     pub(crate) permutation: plonk::permutation::Assembly,
 }
 
@@ -34,8 +37,10 @@ impl<F: PrimeField> Assignment<F> for CircuitData<F> {
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
-        self.selector[selector.index()][row] = true;
-        Ok(())
+        self.selector
+            .get_mut(selector.index())
+            .and_then(|column| column.enable(row))
+            .ok_or(Error::BoundsFailure)
     }
 
     fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
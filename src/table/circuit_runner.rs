@@ -1,10 +1,22 @@
+use std::marker::PhantomData;
+
 use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, FloorPlanner};
 use tracing::*;
 
-use super::{circuit_data::CircuitData, ConstraintSystemMetainfo, WitnessCollector};
+use super::{
+    circuit_data::CircuitData,
+    circuit_stats::{CircuitStats, LayoutMeter},
+    ConstraintSystemMetainfo, WitnessCollector,
+};
 use crate::{
+    commitment,
     ff::PrimeField,
-    plonk::{self, permutation::PermutationData, PlonkStructure},
+    plonk::{
+        self,
+        columns::{FixedColumns, Selectors},
+        permutation::PermutationData,
+        PlonkStructure,
+    },
     util::batch_invert_assigned,
 };
 
@@ -17,19 +29,34 @@ pub struct CircuitRunner<F: PrimeField, CT: Circuit<F>> {
     pub(crate) cs: ConstraintSystem<F>,
     pub(crate) config: CT::Config,
     pub(crate) instances: Vec<Vec<F>>,
+    pub(crate) permutation: Option<plonk::permutation::Arguments>,
 }
 
 impl<F: PrimeField, CT: Circuit<F>> CircuitRunner<F, CT> {
+    /// Copy constraints are folded only with `fold-copy-constraints` feature, see
+    /// [`CircuitRunner::with_folded_copy_constraints`]
     pub fn new(k: u32, circuit: CT, instances: Vec<Vec<F>>) -> Self {
         let mut cs = ConstraintSystem::default();
+        let config = CT::configure(&mut cs);
+        let permutation = plonk::permutation::Arguments::configure_by_default(&mut cs);
 
         CircuitRunner {
-            config: CT::configure(&mut cs),
+            config,
             k,
             circuit,
             cs,
             instances,
+            permutation,
+        }
+    }
+
+    /// Fold copy constraints of advice cells of this circuit regardless of
+    /// `fold-copy-constraints` feature, see [`plonk::permutation::Arguments`]
+    pub fn with_folded_copy_constraints(mut self) -> Self {
+        if self.permutation.is_none() {
+            self.permutation = plonk::permutation::Arguments::configure(&mut self.cs);
         }
+        self
     }
 
     #[instrument(name = "circuit_collect_plonk_struct", skip_all)]
@@ -41,7 +68,7 @@ impl<F: PrimeField, CT: Circuit<F>> CircuitRunner<F, CT> {
             gates,
             custom_gates_lookup_compressed,
             ..
-        } = ConstraintSystemMetainfo::build(self.k as usize, &self.cs);
+        } = ConstraintSystemMetainfo::build(self.k as usize, &self.cs, self.permutation.as_ref());
         debug!("meta info is ready");
 
         debug!("start preprocessing");
@@ -64,19 +91,118 @@ impl<F: PrimeField, CT: Circuit<F>> CircuitRunner<F, CT> {
             gates,
             permutation_data,
             lookup_arguments: plonk::lookup::Arguments::compress_from(&self.cs),
+            permutation_arguments: self.permutation.clone(),
         })
     }
 
+    /// Collect [`PlonkStructure`] without synthesis: preprocessed columns are zeroed & the
+    /// permutation is empty
+    ///
+    /// All sizes match the real structure, so it's enough to size folding accumulators even if
+    /// the circuit does not fit into `k` of this runner
+    pub(crate) fn collect_plonk_structure_shape(&self) -> PlonkStructure<F> {
+        let ConstraintSystemMetainfo {
+            num_challenges,
+            round_sizes,
+            gates,
+            custom_gates_lookup_compressed,
+        } = ConstraintSystemMetainfo::build(self.k as usize, &self.cs, self.permutation.as_ref());
+
+        PlonkStructure {
+            k: self.k as usize,
+            num_io: self.instances.iter().map(|l| l.len()).collect(),
+            selectors: Selectors::new(self.cs.num_selectors, 1 << self.k),
+            fixed_columns: FixedColumns::zeroed(self.cs.num_fixed_columns(), 1 << self.k),
+            num_advice_columns: self.cs.num_advice_columns(),
+            num_challenges,
+            round_sizes,
+            custom_gates_lookup_compressed,
+            gates,
+            permutation_data: PermutationData::default(),
+            lookup_arguments: plonk::lookup::Arguments::compress_from(&self.cs),
+            permutation_arguments: self.permutation.clone(),
+        }
+    }
+
     #[instrument(name = "circuit_collect_witness", skip_all)]
     pub fn try_collect_witness(&self) -> Result<Witness<F>, Error> {
-        let mut witness = WitnessCollector {
-            instances: self.instances.clone(),
-            advice: vec![vec![F::ZERO.into(); 1 << self.k]; self.cs.num_advice_columns()],
-        };
+        collect_witness(
+            self.k,
+            self.cs.num_advice_columns(),
+            &self.circuit,
+            self.config.clone(),
+            self.instances.clone(),
+        )
+    }
 
-        CT::FloorPlanner::synthesize(&mut witness, &self.circuit, self.config.clone(), vec![])?;
+    /// Layout of this circuit, to collect the witness of its other instances without configuring
+    /// the circuit again, see [`CircuitLayout::try_collect_witness`]
+    pub fn layout(&self) -> CircuitLayout<F, CT::Config> {
+        CircuitLayout {
+            k: self.k,
+            num_advice_columns: self.cs.num_advice_columns(),
+            config: self.config.clone(),
+            _p: PhantomData,
+        }
+    }
 
-        Ok(batch_invert_assigned(&witness.advice))
+    /// Synthesize the circuit without witness and collect its size & folding cost
+    ///
+    /// The layout is measured without table boundaries, so [`CircuitStats::min_k_table_size`]
+    /// is correct even if `k` of this runner is too small
+    #[instrument(name = "circuit_collect_stats", skip_all)]
+    pub fn try_collect_stats(&self) -> Result<CircuitStats, Error> {
+        let mut meter = LayoutMeter::new(self.instances.iter().map(Vec::len).collect());
+        CT::FloorPlanner::synthesize(&mut meter, &self.circuit, self.config.clone(), vec![])?;
+
+        let ConstraintSystemMetainfo {
+            num_challenges,
+            round_sizes,
+            gates,
+            custom_gates_lookup_compressed,
+        } = ConstraintSystemMetainfo::build(self.k as usize, &self.cs, self.permutation.as_ref());
+        let lookup_arguments = plonk::lookup::Arguments::compress_from(&self.cs);
+        let num_lookups = lookup_arguments
+            .as_ref()
+            .map(|arg| arg.lookup_polys.len())
+            .unwrap_or_default();
+        let folding_degree = custom_gates_lookup_compressed.grouped().len();
+
+        Ok(CircuitStats {
+            k_table_size: self.k,
+            used_rows: meter.used_rows,
+            min_k_table_size: CircuitStats::min_k_for_rows(
+                meter.used_rows,
+                self.cs.blinding_factors() + 1,
+            ),
+            num_io: meter.num_io,
+            num_advice_columns: self.cs.num_advice_columns(),
+            num_fixed_columns: self.cs.num_fixed_columns(),
+            num_selectors: self.cs.num_selectors,
+            copy_constraints_count: meter.copy_constraints_count,
+            enabled_selectors_count: meter.enabled_selectors_count,
+            num_custom_gates: self
+                .cs
+                .gates()
+                .iter()
+                .map(|gate| gate.polynomials().len())
+                .sum(),
+            num_lookups,
+            has_vector_lookup: lookup_arguments
+                .as_ref()
+                .is_some_and(|arg| arg.has_vector_lookup),
+            folding_degree,
+            cross_terms_count: folding_degree.saturating_sub(1),
+            protogalaxy_evaluations_count: (gates.len() << self.k).next_power_of_two(),
+            num_challenges,
+            sps_rounds: round_sizes.len(),
+            witness_size: round_sizes.iter().sum(),
+            commitment_key_size: commitment::smallest_key_size(
+                self.k,
+                &self.cs,
+                self.permutation.as_ref(),
+            ),
+        })
     }
 
     fn try_collect_preprocessing(&self) -> Result<PreprocessingData<F>, Error> {
@@ -86,7 +212,7 @@ impl<F: PrimeField, CT: Circuit<F>> CircuitRunner<F, CT> {
             k: self.k,
             num_io: self.instances.iter().map(|i| i.len()).collect(),
             fixed: vec![vec![F::ZERO.into(); nrow]; self.cs.num_fixed_columns()],
-            selector: vec![vec![false; nrow]; self.cs.num_selectors],
+            selector: Selectors::new(self.cs.num_selectors, nrow),
             permutation: plonk::permutation::Assembly::new(nrow, &self.cs.permutation),
         };
 
@@ -97,16 +223,76 @@ impl<F: PrimeField, CT: Circuit<F>> CircuitRunner<F, CT> {
             vec![],
         )?;
 
+        let permutation_data = PermutationData::from(&circuit_data.permutation);
+
+        let mut fixed = batch_invert_assigned(&circuit_data.fixed);
+        if let Some(arg) = self.permutation.as_ref() {
+            for (index, column) in arg.fixed_columns(self.k as usize, &permutation_data) {
+                fixed[index] = column;
+            }
+        }
+
         Ok(PreprocessingData {
-            permutation_data: PermutationData::from(&circuit_data.permutation),
-            fixed_columns: batch_invert_assigned(&circuit_data.fixed),
+            permutation_data,
+            fixed_columns: FixedColumns::compress(fixed),
             selectors: circuit_data.selector,
         })
     }
 }
 
+/// Configured layout of a circuit: everything besides the circuit itself that is needed to
+/// collect its witness
+///
+/// Taken from [`CircuitRunner::layout`] once, e.g. in public params, and reused for each step
+#[derive(Debug, Clone)]
+pub struct CircuitLayout<F: PrimeField, Config: Clone> {
+    k: u32,
+    num_advice_columns: usize,
+    config: Config,
+    _p: PhantomData<F>,
+}
+
+impl<F: PrimeField, Config: Clone> CircuitLayout<F, Config> {
+    pub fn k_table_size(&self) -> u32 {
+        self.k
+    }
+
+    /// Same as [`CircuitRunner::try_collect_witness`], but [`Circuit::configure`] is not called
+    #[instrument(name = "circuit_collect_witness_with_layout", skip_all)]
+    pub fn try_collect_witness<CT: Circuit<F, Config = Config>>(
+        &self,
+        circuit: &CT,
+        instances: Vec<Vec<F>>,
+    ) -> Result<Witness<F>, Error> {
+        collect_witness(
+            self.k,
+            self.num_advice_columns,
+            circuit,
+            self.config.clone(),
+            instances,
+        )
+    }
+}
+
+fn collect_witness<F: PrimeField, CT: Circuit<F>>(
+    k: u32,
+    num_advice_columns: usize,
+    circuit: &CT,
+    config: CT::Config,
+    instances: Vec<Vec<F>>,
+) -> Result<Witness<F>, Error> {
+    let mut witness = WitnessCollector {
+        instances,
+        advice: vec![vec![F::ZERO.into(); 1 << k]; num_advice_columns],
+    };
+
+    CT::FloorPlanner::synthesize(&mut witness, circuit, config, vec![])?;
+
+    Ok(batch_invert_assigned(&witness.advice))
+}
+
 struct PreprocessingData<F: PrimeField> {
     pub(crate) permutation_data: PermutationData,
-    pub(crate) fixed_columns: Vec<Vec<F>>,
-    pub(crate) selectors: Vec<Vec<bool>>,
+    pub(crate) fixed_columns: FixedColumns<F>,
+    pub(crate) selectors: Selectors,
 }
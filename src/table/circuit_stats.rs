@@ -0,0 +1,280 @@
+use std::fmt;
+
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Challenge, Column, Error, Fixed, Instance, Selector,
+    },
+};
+
+use crate::ff::PrimeField;
+
+/// [`Assignment`] that doesn't store anything, but measures how many rows the layout takes
+///
+/// It does not check any boundaries (except instance columns), so the circuit can be synthesized
+/// with any `k` and the real size can be found out from [`LayoutMeter::used_rows`]
+pub(crate) struct LayoutMeter {
+    pub(crate) num_io: Box<[usize]>,
+    /// Index of the last row touched by any assignment plus one
+    pub(crate) used_rows: usize,
+    pub(crate) copy_constraints_count: usize,
+    pub(crate) enabled_selectors_count: usize,
+}
+
+impl LayoutMeter {
+    pub fn new(num_io: Box<[usize]>) -> Self {
+        Self {
+            num_io,
+            used_rows: 0,
+            copy_constraints_count: 0,
+            enabled_selectors_count: 0,
+        }
+    }
+
+    fn touch(&mut self, row: usize) {
+        self.used_rows = self.used_rows.max(row + 1);
+    }
+}
+
+impl<F: PrimeField> Assignment<F> for LayoutMeter {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn exit_region(&mut self) {
+        // Do nothing; we don't care about regions in this context.
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _: A,
+        _selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        self.enabled_selectors_count += 1;
+        Ok(())
+    }
+
+    fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Do nothing
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.num_io
+            .get(column.index())
+            .filter(|len| &row < *len)
+            .map(|_| Value::unknown())
+            .ok_or(Error::BoundsFailure)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        _column: Column<Advice>,
+        row: usize,
+        _to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        _column: Column<Fixed>,
+        row: usize,
+        _to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        // Instance rows are not part of the table layout
+        if !matches!(left_column.column_type(), Any::Instance) {
+            self.touch(left_row);
+        }
+        if !matches!(right_column.column_type(), Any::Instance) {
+            self.touch(right_row);
+        }
+        self.copy_constraints_count += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Filling up to the end of table does not take new rows
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn query_advice(&self, _column: Column<Advice>, _row: usize) -> Result<F, Error> {
+        Err(Error::Synthesis)
+    }
+
+    fn query_fixed(&self, _column: Column<Fixed>, _row: usize) -> Result<F, Error> {
+        Err(Error::Synthesis)
+    }
+}
+
+/// Structured report about the size & folding cost of a circuit
+///
+/// Collected by [`super::CircuitRunner::try_collect_stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStats {
+    /// `k` used for synthesis, `2^k` is the table size
+    pub k_table_size: u32,
+    /// Rows actually used by the layout
+    pub used_rows: usize,
+    /// Minimal `k` such that the layout fits into the table, see [`CircuitStats::min_k_for_rows`]
+    pub min_k_table_size: u32,
+
+    /// Instance columns lengths
+    pub num_io: Box<[usize]>,
+    pub num_advice_columns: usize,
+    pub num_fixed_columns: usize,
+    pub num_selectors: usize,
+    pub copy_constraints_count: usize,
+    pub enabled_selectors_count: usize,
+
+    /// Count of custom gates polynomials, without lookup
+    pub num_custom_gates: usize,
+    pub num_lookups: usize,
+    pub has_vector_lookup: bool,
+
+    /// Degree of the compressed gates, see [`crate::plonk::PlonkStructure::get_degree_for_folding`]
+    pub folding_degree: usize,
+    /// Count of cross terms committed per fold by sangria
+    pub cross_terms_count: usize,
+    /// Count of polynomial evaluations (with padding) per fold by protogalaxy
+    pub protogalaxy_evaluations_count: usize,
+
+    /// Count of challenges generated by SPS
+    pub num_challenges: usize,
+    /// Count of SPS prover rounds (witness commitments)
+    pub sps_rounds: usize,
+    /// Total size of the witness over all SPS rounds
+    pub witness_size: usize,
+
+    /// `k` for [`crate::commitment::CommitmentKey::setup`] enough to commit any part of trace
+    pub commitment_key_size: usize,
+}
+
+impl CircuitStats {
+    /// Minimal `k` such that `used_rows` plus rows reserved by halo2 fit into `2^k` rows
+    pub fn min_k_for_rows(used_rows: usize, reserved_rows: usize) -> u32 {
+        (used_rows + reserved_rows)
+            .max(1)
+            .next_power_of_two()
+            .trailing_zeros()
+    }
+
+    /// Whether the layout fits into the table for the `k` used for synthesis
+    pub fn is_fit(&self) -> bool {
+        self.min_k_table_size <= self.k_table_size
+    }
+
+    /// Find the minimal `k` at which the circuit fits into the table, starting from `lower_bound`
+    ///
+    /// The layout of a step folding circuit may depend on `k` itself, so `collect` is called
+    /// again with [`CircuitStats::min_k_table_size`] until the layout fits into the table for
+    /// which it was measured. The layout only grows together with `k`, so the first fitting `k`
+    /// is the minimal one
+    pub fn find_min_k<E>(
+        lower_bound: u32,
+        mut collect: impl FnMut(u32) -> Result<Self, E>,
+    ) -> Result<Self, E> {
+        let mut stats = collect(lower_bound)?;
+        while !stats.is_fit() {
+            stats = collect(stats.min_k_table_size)?;
+        }
+        Ok(stats)
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "k: {} (min {}), used rows: {}",
+            self.k_table_size, self.min_k_table_size, self.used_rows
+        )?;
+        writeln!(
+            f,
+            "columns: advice {}, fixed {}, selectors {}, instance {:?}",
+            self.num_advice_columns, self.num_fixed_columns, self.num_selectors, self.num_io
+        )?;
+        writeln!(
+            f,
+            "gates: {}, lookups: {}{}, copy constraints: {}",
+            self.num_custom_gates,
+            self.num_lookups,
+            if self.has_vector_lookup {
+                " (vector)"
+            } else {
+                ""
+            },
+            self.copy_constraints_count
+        )?;
+        writeln!(
+            f,
+            "folding degree: {}, cross terms: {}, protogalaxy evaluations: {}",
+            self.folding_degree, self.cross_terms_count, self.protogalaxy_evaluations_count
+        )?;
+        write!(
+            f,
+            "sps: {} rounds, {} challenges, witness size {}, commitment key size {}",
+            self.sps_rounds, self.num_challenges, self.witness_size, self.commitment_key_size
+        )
+    }
+}
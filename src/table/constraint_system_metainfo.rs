@@ -3,7 +3,7 @@ use tracing::*;
 
 use crate::{
     ff::PrimeField,
-    plonk::{lookup, CompressedGates},
+    plonk::{lookup, permutation, CompressedGates},
     polynomial::{expression::QueryIndexContext, Expression},
 };
 
@@ -17,26 +17,27 @@ pub(crate) struct ConstraintSystemMetainfo<F: PrimeField> {
 impl<F: PrimeField> ConstraintSystemMetainfo<F> {
     /// The separation of this function from circuit_info is to remove dependency on [`PlonkStructure`]
     /// it is used to kickstart the Folding Circuit initialization
+    ///
+    /// `permutation` is the result of [`permutation::Arguments::configure`] called on `cs`
     pub(crate) fn build(
         k_table_size: usize,
         cs: &ConstraintSystem<F>,
+        permutation: Option<&permutation::Arguments>,
     ) -> ConstraintSystemMetainfo<F> {
         let num_gates: usize = cs.gates().iter().map(|gate| gate.polynomials().len()).sum();
         info!("start build constraint system metainfo with {num_gates} custom gates");
 
-        let (num_lookups, has_vector_lookup, lookup_exprs) = lookup::Arguments::compress_from(cs)
+        let lookup_arguments = lookup::Arguments::compress_from(cs);
+        let (num_lookups, has_vector_lookup) = lookup_arguments
             .as_ref()
-            .map(|arg| {
-                (
-                    arg.lookup_polys.len(),
-                    arg.has_vector_lookup,
-                    arg.to_expressions(cs).collect(),
-                )
-            })
-            .unwrap_or((0, false, vec![]));
+            .map(|arg| (arg.lookup_polys.len(), arg.has_vector_lookup))
+            .unwrap_or((0, false));
+        let num_permutation_columns = permutation
+            .map(permutation::Arguments::num_columns)
+            .unwrap_or_default();
 
         debug!(
-            "num lookups: {num_lookups} & {}",
+            "num lookups: {num_lookups} & {}, num permutation columns: {num_permutation_columns}",
             if has_vector_lookup {
                 "with vector lookup"
             } else {
@@ -44,12 +45,23 @@ impl<F: PrimeField> ConstraintSystemMetainfo<F> {
             }
         );
 
+        // vector lookup & permutation argument need two challenges before their last round
+        let is_three_rounds = has_vector_lookup || num_permutation_columns > 0;
+
+        let lookup_exprs = lookup_arguments
+            .iter()
+            .flat_map(|arg| arg.to_expressions(cs, if is_three_rounds { 1 } else { 0 }));
+        let permutation_exprs = permutation
+            .iter()
+            .flat_map(|arg| arg.to_expressions(cs, k_table_size));
+
         let gates = cs
             .gates()
             .iter()
             .flat_map(|gate| gate.polynomials().iter())
             .map(|expr| Expression::from_halo2_expr(expr, cs.num_selectors, cs.num_fixed_columns()))
             .chain(lookup_exprs)
+            .chain(permutation_exprs)
             .collect::<Vec<_>>();
 
         // we have at most 3 prover rounds
@@ -57,14 +69,15 @@ impl<F: PrimeField> ConstraintSystemMetainfo<F> {
 
         let mut round_sizes = Vec::new();
 
-        if has_vector_lookup {
+        if is_three_rounds {
             round_sizes.extend([
                 // advice columns
                 cs.num_advice_columns() * nrow,
                 // (l_i, t_i, m_i), see [`lookup.rs::Arguments::log_derivative_expr`]
                 3 * num_lookups * nrow,
                 // (h_i, g_i), see [`lookup.rs::Arguments::log_derivative_expr`]
-                2 * num_lookups * nrow,
+                // & (h_j, g_j), see [`permutation::Arguments`]
+                2 * (num_lookups + num_permutation_columns) * nrow,
             ]);
         } else if num_lookups > 0 {
             round_sizes.extend([
@@ -85,7 +98,8 @@ impl<F: PrimeField> ConstraintSystemMetainfo<F> {
             num_fixed: cs.num_fixed_columns(),
             num_advice: cs.num_advice_columns(),
             num_lookups,
-            num_challenges: if has_vector_lookup {
+            num_permutation_columns,
+            num_challenges: if is_three_rounds {
                 2
             } else if num_lookups > 0 {
                 1
@@ -14,10 +14,12 @@
 
 mod circuit_data;
 mod circuit_runner;
+mod circuit_stats;
 mod constraint_system_metainfo;
 mod witness_data;
 
-pub use circuit_runner::{CircuitRunner, Witness};
+pub use circuit_runner::{CircuitLayout, CircuitRunner, Witness};
+pub use circuit_stats::CircuitStats;
 pub(crate) use constraint_system_metainfo::ConstraintSystemMetainfo;
 pub(crate) use witness_data::WitnessCollector;
 
@@ -100,3 +100,92 @@ fn test_assembly() -> Result<(), Error> {
     // table.printstd();
     Ok(())
 }
+
+#[traced_test]
+#[test]
+fn test_collect_witness_with_layout() -> Result<(), Error> {
+    use crate::halo2curves::pasta::Fp;
+
+    const K: u32 = 4;
+    let runner = CircuitRunner::<Fp, _>::new(
+        K,
+        TestCircuit::new((1..10u64).map(Fp::from).collect(), Fp::ONE),
+        vec![vec![Fp::from(45)]],
+    );
+    let layout = runner.layout();
+    assert_eq!(layout.k_table_size(), K);
+
+    // same layout, other witness
+    let circuit = TestCircuit::new((2..11u64).map(Fp::from).collect(), Fp::ONE);
+    let instances = vec![vec![Fp::from(54)]];
+
+    assert_eq!(
+        layout.try_collect_witness(&circuit, instances.clone())?,
+        CircuitRunner::<Fp, _>::new(K, circuit, instances).try_collect_witness()?
+    );
+
+    Ok(())
+}
+
+#[traced_test]
+#[test]
+fn test_collect_stats() -> Result<(), Error> {
+    use crate::halo2curves::pasta::Fp;
+
+    const K: u32 = 4;
+    let inputs = (1..10).map(|i| Fp::from(i as u64)).collect();
+    let circuit = TestCircuit::new(inputs, Fp::ONE);
+    let public_inputs = vec![Fp::from_str_vartime("45").unwrap()];
+
+    let runner = CircuitRunner::<Fp, _>::new(K, circuit, vec![public_inputs]);
+    let S = runner.try_collect_plonk_structure()?;
+    let stats = runner.try_collect_stats()?;
+
+    assert_eq!(stats.k_table_size, K);
+    assert_eq!(stats.num_io, S.num_io);
+    assert_eq!(stats.num_advice_columns, S.num_advice_columns);
+    assert_eq!(stats.num_fixed_columns, S.fixed_columns.len());
+    assert_eq!(stats.num_lookups, 0);
+    assert_eq!(stats.folding_degree, S.get_degree_for_folding());
+    assert_eq!(stats.sps_rounds, S.round_sizes.len());
+    assert!(stats.used_rows > 0);
+    assert!(stats.copy_constraints_count > 0);
+    assert!(stats.is_fit());
+
+    Ok(())
+}
+
+#[traced_test]
+#[test]
+fn test_find_min_k() -> Result<(), Error> {
+    use crate::halo2curves::pasta::Fp;
+
+    let inputs: Vec<Fp> = (1..10).map(|i| Fp::from(i as u64)).collect();
+    let public_inputs = vec![Fp::from_str_vartime("45").unwrap()];
+
+    let stats = CircuitStats::find_min_k(1, |k| {
+        CircuitRunner::<Fp, _>::new(
+            k,
+            TestCircuit::new(inputs.clone(), Fp::ONE),
+            vec![public_inputs.clone()],
+        )
+        .try_collect_stats()
+    })?;
+
+    assert!(stats.is_fit());
+    assert_eq!(stats.k_table_size, stats.min_k_table_size);
+
+    let runner = CircuitRunner::<Fp, _>::new(
+        stats.k_table_size,
+        TestCircuit::new(inputs, Fp::ONE),
+        vec![public_inputs],
+    );
+    let S = runner.try_collect_plonk_structure()?;
+    let shape = runner.collect_plonk_structure_shape();
+
+    assert_eq!(shape.fixed_columns.len(), S.fixed_columns.len());
+    assert_eq!(shape.selectors.len(), S.selectors.len());
+    assert_eq!(shape.get_degree_for_folding(), S.get_degree_for_folding());
+
+    Ok(())
+}
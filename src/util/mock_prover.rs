@@ -7,9 +7,10 @@ use halo2_proofs::{
     halo2curves::ff::{FromUniformBytes, PrimeField},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error as PlonkError},
 };
+use rand_core::RngCore;
 use tracing::error;
 
-use crate::ivc::StepCircuit;
+use crate::ivc::{step_circuit::NativeStep, StepCircuit};
 
 #[derive(Debug, thiserror::Error)]
 pub enum VerifyFailure<const A: usize, F: PrimeField> {
@@ -20,14 +21,16 @@ pub enum VerifyFailure<const A: usize, F: PrimeField> {
 struct StepCircuitWrapper<'sc, const A: usize, F: PrimeField, SC: StepCircuit<A, F>> {
     z_i: [F; A],
     step_circuit: &'sc SC,
+    witness: &'sc SC::StepWitness,
     last_z_out: Cell<[Value<F>; A]>,
 }
 
 impl<'sc, const A: usize, F: PrimeField, SC: StepCircuit<A, F>> StepCircuitWrapper<'sc, A, F, SC> {
-    fn new(z_i: [F; A], step_circuit: &'sc SC) -> Self {
+    fn new(z_i: [F; A], step_circuit: &'sc SC, witness: &'sc SC::StepWitness) -> Self {
         Self {
             z_i,
             step_circuit,
+            witness,
             last_z_out: Cell::new([Value::unknown(); A]),
         }
     }
@@ -94,6 +97,7 @@ impl<const A: usize, F: PrimeField, SC: StepCircuit<A, F>> Circuit<F>
                 config.step_circuit_config,
                 &mut layouter,
                 &z_i.try_into().unwrap(),
+                self.witness,
             )
             .map_err(|err| {
                 error!("error while synthesize_step in MockProver: {err:?}");
@@ -137,6 +141,9 @@ impl<const A: usize, F: Field> MockProver<'_, A, F> {
     /// Runs the step circuit with the provided initial input and returns
     /// a `MockProver` instance containing the resulting outputs.
     ///
+    /// The step is synthesized on the default [`StepCircuit::StepWitness`], see
+    /// [`MockProver::run_with_witness`] to pass another one.
+    ///
     /// # Arguments
     ///
     /// * `k_table_size` - The size of the circuit's table (2^k).
@@ -159,8 +166,29 @@ impl<const A: usize, F: Field> MockProver<'_, A, F> {
     ) -> Result<Self, PlonkError>
     where
         F: FromUniformBytes<64> + Ord,
+        SC::StepWitness: Default,
     {
-        let circuit = StepCircuitWrapper::new(z_i, step_circuit);
+        Self::run_with_witness(
+            k_table_size,
+            step_circuit,
+            &SC::StepWitness::default(),
+            instance,
+            z_i,
+        )
+    }
+
+    /// Same as [`MockProver::run`], but the step is synthesized on `witness`
+    pub fn run_with_witness<SC: StepCircuit<A, F>>(
+        k_table_size: u32,
+        step_circuit: &SC,
+        witness: &SC::StepWitness,
+        instance: Vec<Vec<F>>,
+        z_i: [F; A],
+    ) -> Result<Self, PlonkError>
+    where
+        F: FromUniformBytes<64> + Ord,
+    {
+        let circuit = StepCircuitWrapper::new(z_i, step_circuit, witness);
         let mock_prover = Halo2MockProver::run(k_table_size, &circuit, instance)?;
 
         Ok(Self {
@@ -202,3 +230,59 @@ impl<const A: usize, F: Field> MockProver<'_, A, F> {
         Ok(())
     }
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum NativeStepCheckFailure<const A: usize, F: PrimeField> {
+    #[error("While mock prover run on input {z_in:?}: {err:?}")]
+    Run { z_in: [F; A], err: PlonkError },
+    #[error("While verify on input {z_in:?}: {err:?}")]
+    Verify {
+        z_in: [F; A],
+        err: VerifyFailure<A, F>,
+    },
+}
+
+/// Checks that [`NativeStep::native_step`] matches the output of synthesized
+/// [`StepCircuit::synthesize_step`] on `inputs_count` random inputs & the same `witness`
+///
+/// Each input is run by [`MockProver`], so the step circuit must be satisfied too
+///
+/// # Examples
+///
+/// ```
+/// use sirius::{ivc::step_circuit::trivial, util::mock_prover::check_native_step};
+/// use halo2_proofs::halo2curves::pasta::Fq;
+///
+/// check_native_step(
+///     10,
+///     &trivial::Circuit::<10, Fq>::default(),
+///     &(),
+///     vec![],
+///     4,
+///     rand::thread_rng(),
+/// )
+/// .unwrap();
+/// ```
+pub fn check_native_step<const A: usize, F, SC>(
+    k_table_size: u32,
+    step_circuit: &SC,
+    witness: &SC::StepWitness,
+    instance: Vec<Vec<F>>,
+    inputs_count: usize,
+    mut rng: impl RngCore,
+) -> Result<(), NativeStepCheckFailure<A, F>>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    SC: StepCircuit<A, F> + NativeStep<A, F, SC::StepWitness>,
+{
+    for _ in 0..inputs_count {
+        let z_in = std::array::from_fn(|_| F::random(&mut rng));
+
+        MockProver::run_with_witness(k_table_size, step_circuit, witness, instance.clone(), z_in)
+            .map_err(|err| NativeStepCheckFailure::Run { z_in, err })?
+            .verify(step_circuit.native_step(&z_in, witness))
+            .map_err(|err| NativeStepCheckFailure::Verify { z_in, err })?;
+    }
+
+    Ok(())
+}